@@ -1,150 +1,220 @@
+pub mod decoder;
+pub mod dialect;
 pub mod kernel_code;
+pub mod kernel_descriptor;
 pub mod kernel_args;
+#[cfg(any(test, feature = "llvm"))]
+mod listing;
+#[cfg(feature = "llvm")]
+pub mod llvm;
+pub mod metadata;
+pub mod msgpack;
+pub mod notes;
+pub mod offload_bundle;
+pub mod operand;
+pub mod semantics;
+pub mod target;
+pub mod yaml;
 
 use std::io;
-use std::ffi::CString;
+use std::fmt;
 use std::convert::TryFrom;
-use llvm_sys::disassembler::{LLVMCreateDisasmCPU, LLVMDisasmInstruction, LLVMDisasmDispose};
+use byteorder::{LE, ByteOrder};
 
-use self::kernel_args::{extract_kernel_args, KernelArgs};
+use self::metadata::{extract_metadata, KernelMetadata};
 use self::kernel_code::KernelCode;
+use self::kernel_descriptor::{KernelDescriptor, KERNEL_DESCRIPTOR_SIZE};
+use self::target::{Target, Generation};
+
+pub use self::operand::Operand;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub opcode: String,
+    pub operands: Vec<Operand>,
+    /* Byte offset from the kernel entry point */
+    pub offset: usize,
+    /* Encoded size in bytes, including the trailing literal constant if present */
+    pub size: usize
+}
 
-pub type Instruction = (String, Vec<Operand>);
-
-#[derive(Debug, PartialEq)]
-pub enum Operand {
-    SReg(usize),
-    VReg(usize),
-    SRegs(usize, usize),
-    VRegs(usize, usize),
-    Lit(i32),
-    VCC,
-    Offset(i32),
-    Keyseq(String)
+impl Instruction {
+    /* Words that do not decode, such as alignment padding or constant data,
+     * are kept as .long pseudo-instructions so that offsets remain intact */
+    pub fn raw_data(raw: &[u8], offset: usize) -> Instruction {
+        let size = std::cmp::min(raw.len(), 4);
+        let mut word = [0u8; 4];
+        word[..size].copy_from_slice(&raw[..size]);
+        Instruction { opcode: ".long".to_owned(), operands: vec![Operand::Lit(LE::read_u32(&word) as i32)], offset, size }
+    }
 }
 
-pub type Disassembly = (KernelCode, KernelArgs, Vec<Instruction>);
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.opcode.as_str(), self.operands.as_slice()) {
+            (".long", [Operand::Lit(word)]) => write!(f, ".long {:#010x}", *word as u32),
+            (opcode, []) => write!(f, "{}", opcode),
+            (opcode, operands) => write!(f, "{} {}",
+                opcode, operands.iter().map(|op| op.to_string()).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
 
-pub fn disassemble(bin: elf::File) -> io::Result<Disassembly> {
-    let mut pgm_data = bin
-        .get_section(".text")
-        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "missing .text section"))?
-        .data.to_owned();
+#[derive(Debug)]
+pub struct Disassembly {
+    pub name: String,
+    pub kcode: KernelCode,
+    pub metadata: KernelMetadata,
+    pub instructions: Vec<Instruction>,
+    /* Encoded instructions, Instruction::offset indexes into it */
+    pub code: Vec<u8>
+}
 
-    if pgm_data.len() < 256 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData,
-            "program text must be at least 256 bytes long (the size of AMDKernelCodeT struct)"));
-    }
+/* Code object v2 marks kernel entry points with a dedicated symbol type
+ * https://llvm.org/docs/AMDGPUUsage.html#symbols */
+const STT_AMDGPU_HSA_KERNEL: elf::types::SymbolType = elf::types::SymbolType(10);
+
+pub fn disassemble(bin: elf::File, target: &Target) -> io::Result<Vec<Disassembly>> {
+    let text = bin
+        .get_section(".text")
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "missing .text section"))?;
 
     let pgm_note = bin
         .get_section(".note")
         .ok_or(io::Error::new(io::ErrorKind::InvalidData, "missing .note section with OpenCL metadata"))?;
 
-    let args = extract_kernel_args(&pgm_note.data);
+    let mut symbols = read_symbols(&bin)?;
+    symbols.sort_by_key(|sym| sym.value);
+
+    /* Code object v2 stores amd_kernel_code_t at the start of each kernel symbol,
+     * v3+ references a kernel descriptor in .rodata via a <kernel>.kd symbol */
+    let kernels_v2: Vec<&elf::types::Symbol> = symbols.iter()
+        .filter(|sym| sym.symtype == STT_AMDGPU_HSA_KERNEL)
+        .collect();
 
-    let (amd_kernel_code_raw, instructions_raw) = pgm_data.split_at_mut(256);
+    let kernels: Vec<(String, KernelCode, Vec<Instruction>, Vec<u8>)> = if !kernels_v2.is_empty() {
+        kernels_v2.into_iter()
+            .map(|sym| load_kernel_v2(text, &symbols, sym, target))
+            .collect::<io::Result<_>>()?
+    }
+    else {
+        symbols.iter()
+            .filter(|sym| sym.name.ends_with(".kd"))
+            .map(|kd_sym| load_kernel_v3(&bin, text, &symbols, kd_sym, target))
+            .collect::<io::Result<_>>()?
+    };
 
-    let code_obj = KernelCode::try_from(amd_kernel_code_raw as &[u8])?;
-    let instructions = disassemble_instructions(instructions_raw)?;
+    if kernels.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no kernel symbols found in the symbol table"));
+    }
 
-    Ok((code_obj, args, instructions))
+    let mut metadata = extract_metadata(&pgm_note.data, target)?;
+
+    kernels.into_iter()
+        .map(|(name, kcode, instructions, code)| {
+            let metadata_idx = metadata.kernels.iter().position(|k| k.name == name)
+                .ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("missing metadata for kernel {}", name)))?;
+            let metadata = metadata.kernels.remove(metadata_idx);
+            Ok(Disassembly { name, kcode, metadata, instructions, code })
+        })
+        .collect()
 }
 
-fn disassemble_instructions(instructions_raw: &mut [u8]) -> io::Result<Vec<Instruction>> {
-    unsafe {
-        llvm_sys::target::LLVM_InitializeAllTargetInfos();
-        llvm_sys::target::LLVM_InitializeAllTargetMCs();
-        llvm_sys::target::LLVM_InitializeAllDisassemblers();
-    }
+fn read_symbols(bin: &elf::File) -> io::Result<Vec<elf::types::Symbol>> {
+    let symtab = bin.get_section(".symtab")
+        .or(bin.get_section(".dynsym"))
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "missing symbol table"))?;
 
-    let asmctx = unsafe {
-        LLVMCreateDisasmCPU(
-            CString::new("amdgcn--amdhsa")?.as_ptr(),
-            CString::new("gfx900")?.as_ptr(),
-            std::ptr::null_mut(), 0, None, None)
-    };
+    bin.get_symbols(symtab)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("unable to read symbols: {:?}", e)))
+}
 
-    let mut instructions: Vec<Instruction> = Vec::new();
-
-    let mut pos = 0;
-    let mut instr_buf = [0u8; 256];
-    while pos < instructions_raw.len() {
-        unsafe {
-            pos += LLVMDisasmInstruction(asmctx,
-                instructions_raw.as_mut_ptr().offset(pos as isize),
-                (instructions_raw.len() - pos) as u64, 
-                0,
-                instr_buf.as_mut_ptr() as *mut i8,
-                256);
-        }
-        let tab_skip = 1;
-        let string_end = instr_buf.iter().position(|&r| r == 0).unwrap();
-        let instr_raw = std::str::from_utf8(&instr_buf[tab_skip..string_end]).unwrap();
+fn symbol_data<'a>(section: &'a elf::Section, sym: &elf::types::Symbol) -> io::Result<&'a [u8]> {
+    let start = sym.value.wrapping_sub(section.shdr.addr) as usize;
+    let end = start.saturating_add(sym.size as usize);
 
-        instructions.push(parse_instruction(instr_raw));
-    }
+    section.data.get(start..end).ok_or(io::Error::new(io::ErrorKind::InvalidData,
+        format!("symbol {} does not fit in section {}", sym.name, section.shdr.name)))
+}
 
-    unsafe {
-        LLVMDisasmDispose(asmctx);
+/* Code ends at the symbol size, or, when the size is not set, at the next
+ * symbol in the section or the end of the section */
+fn code_data<'a>(text: &'a elf::Section, symbols: &[elf::types::Symbol], sym: &elf::types::Symbol) -> io::Result<&'a [u8]> {
+    if sym.size != 0 {
+        return symbol_data(text, sym);
     }
-
-    Ok(instructions)
+    let section_end = text.shdr.addr + text.data.len() as u64;
+    let end = symbols.iter()
+        .filter(|other| other.shndx == sym.shndx && other.value > sym.value)
+        .map(|other| other.value)
+        .min()
+        .map_or(section_end, |next| std::cmp::min(next, section_end));
+
+    let start = sym.value.wrapping_sub(text.shdr.addr) as usize;
+    let end = end.wrapping_sub(text.shdr.addr) as usize;
+    text.data.get(start..end).ok_or(io::Error::new(io::ErrorKind::InvalidData,
+        format!("symbol {} does not fit in section {}", sym.name, text.shdr.name)))
 }
 
-fn parse_instruction(instr: &str) -> Instruction {
-    let instr_ops: Vec<&str> = instr.splitn(2, ' ').collect();
-    let instr_name = instr_ops[0].to_owned();
+fn load_kernel_v2(text: &elf::Section, symbols: &[elf::types::Symbol], sym: &elf::types::Symbol, target: &Target) -> io::Result<(String, KernelCode, Vec<Instruction>, Vec<u8>)> {
+    /* The symbol spans the AMDKernelCodeT struct followed by the kernel's instructions */
+    let pgm_data = code_data(text, symbols, sym)?;
 
-    if instr_ops.len() == 1 {
-        (instr_name, Vec::new())
+    if pgm_data.len() < 256 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+            format!("kernel {} must be at least 256 bytes long (the size of AMDKernelCodeT struct)", sym.name)));
     }
-    else if instr_name == "s_waitcnt" {
-        (instr_name, vec![Operand::Keyseq(instr_ops[1].to_owned())])
+    let kcode = KernelCode::try_from(&pgm_data[..256])?;
+
+    if !target.matches_version(kcode.machine_version_major, kcode.machine_version_minor, kcode.machine_version_stepping) {
+        eprintln!("Warning: kernel {} was compiled for gfx{}.{}.{}, but is being disassembled for {}",
+            sym.name, kcode.machine_version_major, kcode.machine_version_minor, kcode.machine_version_stepping, target);
     }
-    else {
-        let operands = instr_ops[1]
-            .replace(",", "").split(" ").map(Operand::from).collect();
-        (instr_name, operands)
+
+    let entry = kcode.kernel_code_entry_byte_offset as usize;
+    if entry < 256 || entry > pgm_data.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+            format!("kernel {} has an invalid entry offset {}", sym.name, entry)));
     }
+    let instructions = disassemble_instructions(&pgm_data[entry..], target)?;
+
+    Ok((sym.name.to_owned(), kcode, instructions, pgm_data[entry..].to_vec()))
 }
 
-impl <'a> From<&'a str> for Operand {
-    fn from(operand: &'a str) -> Self {
-        if operand == "vcc" {
-            return Operand::VCC;
-        }
-        if operand.starts_with("offset:") {
-            return Operand::Offset(i32::from_str_radix(&operand[7..], 10).unwrap());
-        }
-        /* Hexadecimal literal */
-        if operand.len() > 2 && &operand[0..2] == "0x" {
-            return Operand::Lit(i32::from_str_radix(&operand[2..], 16).unwrap());
-        }
+fn load_kernel_v3(bin: &elf::File, text: &elf::Section, symbols: &[elf::types::Symbol], kd_sym: &elf::types::Symbol, target: &Target) -> io::Result<(String, KernelCode, Vec<Instruction>, Vec<u8>)> {
+    let name = &kd_sym.name[..kd_sym.name.len() - 3];
 
-        let prefix_char = operand.chars().nth(0).unwrap();
+    let kd_section = bin.sections.get(kd_sym.shndx as usize)
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("invalid section index for {}", kd_sym.name)))?;
+    let kd_raw = symbol_data(kd_section, kd_sym)?;
+    if kd_raw.len() != KERNEL_DESCRIPTOR_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+            format!("kernel descriptor {} must be {} bytes long", kd_sym.name, KERNEL_DESCRIPTOR_SIZE)));
+    }
+    let kd = KernelDescriptor::try_from(kd_raw)?;
 
-        /* Decimal literal */
-        if prefix_char.is_digit(10) || prefix_char == '-' {
-            return Operand::Lit(i32::from_str_radix(operand, 10).unwrap());
-        }
-        /* Catch-all for non-GPR operands */
-        if prefix_char != 's' && prefix_char != 'v' {
-            return Operand::Keyseq(operand.to_string());
-        }
-        match operand[1..].parse::<usize>() {
-            Ok(i) =>
-                /* Single register reference (s0, v1) */
-                if prefix_char == 's' { Operand::SReg(i) }
-                else                  { Operand::VReg(i) }
-            _ => {
-                /* Register range (s[2:3], v[8:9]) */
-                let sides: Vec<&str> = operand[2..operand.len() - 1].split(':').collect();
-                let from = sides[0].parse::<usize>().unwrap();
-                let to = sides[1].parse::<usize>().unwrap();
-
-                if prefix_char == 's' { Operand::SRegs(from, to) }
-                else                  { Operand::VRegs(from, to) }
-            }
-        }
+    /* The entry offset is relative to the descriptor and is only resolved in linked
+     * code objects, so the instructions are located through the kernel's own symbol */
+    let code_sym = symbols.iter()
+        .find(|sym| sym.name == name && sym.symtype == elf::types::STT_FUNC)
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("missing code symbol for kernel {}", name)))?;
+    let pgm_data = code_data(text, symbols, code_sym)?;
+    let instructions = disassemble_instructions(pgm_data, target)?;
+
+    Ok((name.to_owned(), KernelCode::from(kd), instructions, pgm_data.to_vec()))
+}
+
+fn disassemble_instructions(instructions_raw: &[u8], target: &Target) -> io::Result<Vec<Instruction>> {
+    match target.generation {
+        /* gfx906 and later add fmac, dot product and MFMA instructions the built-in decoder does not know */
+        #[cfg(feature = "llvm")]
+        Generation::GFX9 if ["gfx906", "gfx908", "gfx90a"].contains(&target.processor.as_str()) =>
+            llvm::disassemble_instructions(instructions_raw, target),
+        Generation::GFX9 => decoder::decode_instructions(instructions_raw),
+        #[cfg(feature = "llvm")]
+        _ => llvm::disassemble_instructions(instructions_raw, target),
+        #[cfg(not(feature = "llvm"))]
+        _ => Err(io::Error::new(io::ErrorKind::Other, format!(
+            "the built-in decoder only supports GFX9, rebuild with --features llvm to disassemble {} code", target)))
     }
 }
@@ -0,0 +1,865 @@
+/* Built-in decoder for GFX9 (Vega) instruction encodings
+ * https://developer.amd.com/wp-content/resources/Vega_Shader_ISA_28July2017.pdf
+ *
+ * Operands are produced in the same order and spelling as the LLVM disassembler,
+ * so the rest of the pipeline does not depend on which decoder has been used. */
+
+pub mod opcodes;
+
+use std::io;
+use byteorder::{LE, ByteOrder};
+
+use super::{Instruction, Operand};
+use super::operand::{SpecialReg, OutputModifier, SdwaOperand, SdwaSel, DstUnused, DppCtrl, Waitcnt, SendMsg, Hwreg};
+use self::opcodes::{Opcode, Vop3Opcode, SRC_MODS, CLAMP, OMOD, OP_SEL, SDST};
+
+/* src0 codes of VOP1, VOP2 and VOPC that select the SDWA and DPP forms */
+const SDWA: u32 = 249;
+const DPP: u32 = 250;
+
+pub fn decode_instructions(instructions_raw: &[u8]) -> io::Result<Vec<Instruction>> {
+    let mut instructions: Vec<Instruction> = Vec::new();
+
+    let mut pos = 0;
+    while pos < instructions_raw.len() {
+        let instruction = match decode_instruction(&instructions_raw[pos..]) {
+            Some((opcode, operands, size)) => Instruction { opcode, operands, offset: pos, size },
+            None => Instruction::raw_data(&instructions_raw[pos..], pos)
+        };
+        pos += instruction.size;
+        instructions.push(instruction);
+    }
+
+    Ok(instructions)
+}
+
+/* Returns the mnemonic, operands, and encoded size of the instruction at the start of raw */
+pub fn decode_instruction(raw: &[u8]) -> Option<(String, Vec<Operand>, usize)> {
+    let mut decoder = Decoder { raw, size: 4, literal: None, int16: false, suffix: None };
+    let w0 = decoder.dword(0)?;
+
+    let (opcode, operands) = match w0 >> 23 {
+        0x17d => decoder.sop1(w0),
+        0x17e => decoder.sopc(w0),
+        0x17f => decoder.sopp(w0),
+        _ if w0 >> 28 == 0b1011 => decoder.sopk(w0),
+        _ if w0 >> 30 == 0b10 => decoder.sop2(w0),
+        _ => match w0 >> 26 {
+            0b110000 => decoder.smem(w0),
+            0b110100 => decoder.vop3(w0),
+            0b110110 => decoder.ds(w0),
+            0b110111 => decoder.flat(w0),
+            0b111000 => decoder.mubuf(w0),
+            0b111010 => decoder.mtbuf(w0),
+            _ if w0 >> 25 == 0b0111111 => decoder.vop1(w0),
+            _ if w0 >> 25 == 0b0111110 => decoder.vopc(w0),
+            _ if w0 >> 31 == 0 => decoder.vop2(w0),
+            _ => None
+        }
+    }?;
+
+    let opcode = match decoder.suffix {
+        Some(suffix) => format!("{}_{}", opcode.trim_end_matches("_e32"), suffix),
+        None => opcode.to_owned()
+    };
+    Some((opcode, operands, decoder.size))
+}
+
+fn bits(word: u32, lo: u32, len: u32) -> u32 {
+    (word >> lo) & ((1 << len) - 1)
+}
+
+fn find(table: &'static [Opcode], op: u32) -> Option<&'static Opcode> {
+    table.binary_search_by_key(&op, |&(code, _, _)| code as u32).ok().map(|idx| &table[idx])
+}
+
+fn find_vop3(op: u32) -> Option<&'static Vop3Opcode> {
+    opcodes::VOP3.binary_search_by_key(&op, |&(code, _, _, _)| code as u32).ok().map(|idx| &opcodes::VOP3[idx])
+}
+
+struct Decoder<'a> {
+    raw: &'a [u8],
+    /* Size of the encoding decoded so far, grows when a literal constant is read */
+    size: usize,
+    literal: Option<u32>,
+    /* 16-bit integer instructions read floating-point inline constants as half-precision bits */
+    int16: bool,
+    /* Set for the SDWA and DPP forms, which are named after the _e32 instruction */
+    suffix: Option<&'static str>
+}
+
+impl<'a> Decoder<'a> {
+    fn dword(&self, idx: usize) -> Option<u32> {
+        self.raw.get(idx * 4..idx * 4 + 4).map(LE::read_u32)
+    }
+
+    /* Encodings with a 32-bit base are followed by at most one literal dword */
+    fn literal(&mut self) -> Option<u32> {
+        if self.literal.is_none() {
+            self.literal = Some(self.dword(self.size / 4)?);
+            self.size += 4;
+        }
+        self.literal
+    }
+
+    /* Scalar (8-bit) or vector (9-bit) source operand */
+    fn src(&mut self, code: u32, dwords: u8) -> Option<Operand> {
+        let special = |reg| Some(Operand::Special(reg));
+        let float = |value| Some(Operand::Float(value));
+        const F16_CONSTANTS: [i32; 9] = [0x3800, 0xb800, 0x3c00, 0xbc00, 0x4000, 0xc000, 0x4400, 0xc400, 0x3118];
+        match code {
+            240..=248 if self.int16 => Some(Operand::Lit(F16_CONSTANTS[code as usize - 240])),
+            0..=127 => sreg(code, dwords),
+            128..=192 => Some(Operand::Lit(code as i32 - 128)),
+            193..=208 => Some(Operand::Lit(192 - code as i32)),
+            235 => special(SpecialReg::SharedBase),
+            236 => special(SpecialReg::SharedLimit),
+            237 => special(SpecialReg::PrivateBase),
+            238 => special(SpecialReg::PrivateLimit),
+            239 => special(SpecialReg::PopsExitingWaveId),
+            240 => float(0.5),
+            241 => float(-0.5),
+            242 => float(1.0),
+            243 => float(-1.0),
+            244 => float(2.0),
+            245 => float(-2.0),
+            246 => float(4.0),
+            247 => float(-4.0),
+            /* 1/(2*pi) */
+            248 => float(0.159_154_94),
+            251 => special(SpecialReg::VCCZ),
+            252 => special(SpecialReg::ExecZ),
+            253 => special(SpecialReg::SCC),
+            255 => self.literal().map(|lit| Operand::Lit(lit as i32)),
+            256..=511 => vreg(code - 256, dwords),
+            _ => None
+        }
+    }
+
+    fn sop2(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [dst, src0, src1, _]) = find(opcodes::SOP2, bits(w0, 23, 7))?;
+        let mut ops = Vec::new();
+        if dst > 0 {
+            ops.push(sreg(bits(w0, 16, 7), dst)?);
+        }
+        ops.push(self.src(bits(w0, 0, 8), src0)?);
+        ops.push(self.src(bits(w0, 8, 8), src1)?);
+        Some((name, ops))
+    }
+
+    fn sop1(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [dst, src0, _, _]) = find(opcodes::SOP1, bits(w0, 8, 8))?;
+        let mut ops = Vec::new();
+        if dst > 0 {
+            ops.push(sreg(bits(w0, 16, 7), dst)?);
+        }
+        if src0 > 0 {
+            ops.push(self.src(bits(w0, 0, 8), src0)?);
+        }
+        Some((name, ops))
+    }
+
+    fn sopc(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [_, src0, src1, _]) = find(opcodes::SOPC, bits(w0, 16, 7))?;
+        let ops = match name {
+            /* The second operand is the index mode rather than a source */
+            "s_set_gpr_idx_on" => vec![self.src(bits(w0, 0, 8), src0)?, match bits(w0, 8, 8) {
+                mode @ 0..=15 => gpr_idx(mode),
+                mode => Operand::Lit(mode as i32)
+            }],
+            _ => vec![self.src(bits(w0, 0, 8), src0)?, self.src(bits(w0, 8, 8), src1)?]
+        };
+        Some((name, ops))
+    }
+
+    fn sopk(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [dst, _, _, _]) = find(opcodes::SOPK, bits(w0, 23, 5))?;
+        let sdst = bits(w0, 16, 7);
+        let simm16 = bits(w0, 0, 16);
+
+        let ops = match name {
+            "s_getreg_b32" => vec![sreg(sdst, dst)?, hwreg(simm16)],
+            "s_setreg_b32" => vec![hwreg(simm16), sreg(sdst, dst)?],
+            "s_setreg_imm32_b32" => vec![hwreg(simm16), Operand::Lit(self.literal()? as i32)],
+            _ => vec![sreg(sdst, dst)?, Operand::Lit(simm16 as i32)]
+        };
+        Some((name, ops))
+    }
+
+    fn sopp(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, _) = find(opcodes::SOPP, bits(w0, 16, 7))?;
+        let simm16 = bits(w0, 0, 16);
+
+        let ops = match name {
+            "s_endpgm" if simm16 == 0 => vec![],
+            "s_waitcnt" => vec![waitcnt(simm16)],
+            "s_sendmsg" | "s_sendmsghalt" => vec![sendmsg(simm16)],
+            "s_set_gpr_idx_mode" if simm16 <= 0xf => vec![gpr_idx(simm16)],
+            "s_wakeup" | "s_barrier" | "s_icache_inv" | "s_ttracedata" |
+            "s_endpgm_saved" | "s_set_gpr_idx_off" | "s_endpgm_ordered_ps_done" =>
+                if simm16 == 0 { vec![] } else { return None },
+            _ => vec![Operand::Lit(simm16 as i32)]
+        };
+        Some((name, ops))
+    }
+
+    fn smem(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [sdata, sbase, _, _]) = find(opcodes::SMEM, bits(w0, 18, 8))?;
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        let mut ops = Vec::new();
+        if name.starts_with("s_atc_probe") {
+            ops.push(Operand::Lit(bits(w0, 6, 7) as i32));
+        }
+        else if sdata > 0 {
+            ops.push(sreg(bits(w0, 6, 7), sdata)?);
+        }
+        if sbase > 0 {
+            ops.push(sreg(bits(w0, 0, 6) * 2, sbase)?);
+            ops.push(match bits(w0, 17, 1) {
+                1 => Operand::Lit(bits(w1, 0, 20) as i32),
+                _ => sreg(bits(w1, 0, 7), 1)?
+            });
+        }
+        /* Only loads, stores and atomics take the GLC bit */
+        if bits(w0, 16, 1) == 1 && sdata > 0 && sbase > 0 {
+            ops.push(Operand::Glc);
+        }
+        Some((name, ops))
+    }
+
+    fn vop2(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let op = bits(w0, 25, 6);
+        let &(_, name, widths) = find(opcodes::VOP2, op)?;
+        self.int16 = int16_operands(name);
+        let vdst = vreg(bits(w0, 17, 8), widths[0])?;
+        let (mut srcs, mut modifiers) = self.vop_sources(w0, name, widths, op + 0x100)?;
+        let vsrc1 = srcs.pop()?;
+        let src0 = srcs.pop()?;
+
+        let mut ops = match name {
+            "v_madmk_f32" | "v_madmk_f16" => vec![vdst, src0, Operand::Lit(self.literal()? as i32), vsrc1],
+            "v_madak_f32" | "v_madak_f16" => vec![vdst, src0, vsrc1, Operand::Lit(self.literal()? as i32)],
+            "v_cndmask_b32_e32" => vec![vdst, src0, vsrc1, Operand::VCC],
+            "v_addc_co_u32_e32" | "v_subb_co_u32_e32" | "v_subbrev_co_u32_e32" =>
+                vec![vdst, Operand::VCC, src0, vsrc1, Operand::VCC],
+            "v_add_co_u32_e32" | "v_sub_co_u32_e32" | "v_subrev_co_u32_e32" =>
+                vec![vdst, Operand::VCC, src0, vsrc1],
+            _ => vec![vdst, src0, vsrc1]
+        };
+        ops.append(&mut modifiers);
+        Some((name, ops))
+    }
+
+    fn vop1(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let op = bits(w0, 9, 8);
+        let &(_, name, widths) = find(opcodes::VOP1, op)?;
+        self.int16 = int16_operands(name);
+        let mut ops = Vec::new();
+        if widths[0] > 0 {
+            ops.push(match name {
+                "v_readfirstlane_b32" => sreg(bits(w0, 17, 8), widths[0])?,
+                _ => vreg(bits(w0, 17, 8), widths[0])?
+            });
+        }
+        if name == "v_swap_b32" {
+            /* Both operands are swapped in place, so the source must be a VGPR */
+            ops.push(vreg(bits(w0, 0, 8), widths[1])?);
+        }
+        else {
+            let (mut srcs, mut modifiers) = self.vop_sources(w0, name, widths, op + 0x140)?;
+            ops.append(&mut srcs);
+            ops.append(&mut modifiers);
+        }
+        Some((name, ops))
+    }
+
+    fn vopc(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let op = bits(w0, 17, 8);
+        let &(_, name, widths) = find(opcodes::VOPC, op)?;
+        self.int16 = int16_operands(name);
+        let (mut srcs, mut modifiers) = self.vop_sources(w0, name, widths, op)?;
+        /* The SDWA form can write the result to a scalar register pair instead of vcc */
+        let sdst = match (self.suffix, self.dword(1)) {
+            (Some("sdwa"), Some(w1)) if bits(w1, 15, 1) == 1 => sreg(bits(w1, 8, 7), 2)?,
+            _ => Operand::VCC
+        };
+        let mut ops = vec![sdst];
+        ops.append(&mut srcs);
+        ops.append(&mut modifiers);
+        Some((name, ops))
+    }
+
+    /* Sources of VOP1, VOP2 and VOPC, followed by the modifiers of the SDWA and DPP forms.
+     * These forms take the real src0 from a second dword and exist only for instructions
+     * with 32-bit or narrower operands; vop3_op is the opcode of the VOP3 form,
+     * which tells which source and output modifiers are supported */
+    fn vop_sources(&mut self, w0: u32, name: &'static str, widths: [u8; 4], vop3_op: u32) -> Option<(Vec<Operand>, Vec<Operand>)> {
+        let code = bits(w0, 0, 9);
+        let vsrc1 = bits(w0, 9, 8);
+        let src_count = widths[1..3].iter().filter(|&&w| w > 0).count();
+        if code != SDWA && code != DPP {
+            let mut srcs = Vec::new();
+            if src_count > 0 {
+                srcs.push(self.src(code, widths[1])?);
+            }
+            if src_count > 1 {
+                srcs.push(vreg(vsrc1, widths[2])?);
+            }
+            return Some((srcs, Vec::new()));
+        }
+
+        /* VOPC writes a scalar mask, VOPC has no DPP form and v_mac no SDWA form on GFX9 */
+        let vopc = vop3_op < 0x100;
+        let narrow = widths[1..].iter().all(|&w| w <= 1) && (vopc || widths[0] <= 1);
+        if src_count == 0 || !narrow || name == "v_readfirstlane_b32" ||
+            name.starts_with("v_madmk") || name.starts_with("v_madak") ||
+            (code == SDWA && name.starts_with("v_mac")) || (code == DPP && vopc) {
+            return None;
+        }
+        let modifiers = find_vop3(vop3_op).map_or(0, |&(_, _, _, modifiers)| modifiers);
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        match code {
+            SDWA => self.sdwa(w1, name, vsrc1, src_count, modifiers, vopc),
+            _ => self.dpp(w1, name, vsrc1, src_count, modifiers)
+        }
+    }
+
+    fn sdwa(&mut self, w1: u32, name: &'static str, vsrc1: u32, src_count: usize, modifiers: u8, vopc: bool) -> Option<(Vec<Operand>, Vec<Operand>)> {
+        self.suffix = Some("sdwa");
+        let sel = |field| match field {
+            0..=3 => Some(SdwaSel::Byte(field as u8)),
+            4 | 5 => Some(SdwaSel::Word(field as u8 - 4)),
+            6 => Some(SdwaSel::Dword),
+            _ => None
+        };
+        let mut srcs = Vec::new();
+        let mut sels = Vec::new();
+        for idx in 0..src_count {
+            let (code, field) = match idx {
+                0 => (bits(w1, 0, 8), bits(w1, 16, 8)),
+                _ => (vsrc1, bits(w1, 24, 8))
+            };
+            let (sext, neg, abs) = (bits(field, 3, 1), bits(field, 4, 1), bits(field, 5, 1));
+            /* Floating-point sources can be negated and made absolute, integer ones sign-extended */
+            let float = float_source(name, idx, modifiers);
+            if (sext != 0 && float) || ((neg | abs) != 0 && !float) {
+                return None;
+            }
+            /* Scalar sources cannot be literal constants */
+            let src = match bits(field, 7, 1) {
+                1 if code != 255 => self.src(code, 1)?,
+                1 => return None,
+                _ => vreg(code, 1)?
+            };
+            let src = if sext == 1 { Operand::Sext(Box::new(src)) } else { src };
+            let src = if abs == 1 { Operand::Abs(Box::new(src)) } else { src };
+            let src = if neg == 1 { Operand::Neg(Box::new(src)) } else { src };
+            srcs.push(src);
+            let operand = if idx == 0 { SdwaOperand::Src0 } else { SdwaOperand::Src1 };
+            sels.push(Operand::SdwaSel(operand, sel(bits(field, 0, 3))?));
+        }
+        /* Bit 30 is unused, the other src1 fields must be clear without a second source */
+        if src_count == 1 && (bits(w1, 24, 6) != 0 || bits(w1, 31, 1) != 0) {
+            return None;
+        }
+
+        /* The destination fields of VOPC hold the scalar destination */
+        let mut ops = Vec::new();
+        if !vopc {
+            let omod = bits(w1, 14, 2);
+            if omod != 0 && (modifiers & OMOD == 0 || !float_result(name)) {
+                return None;
+            }
+            if bits(w1, 13, 1) == 1 {
+                ops.push(Operand::Clamp);
+            }
+            ops.extend(output_modifier(omod));
+            ops.push(Operand::SdwaSel(SdwaOperand::Dst, sel(bits(w1, 8, 3))?));
+            ops.push(Operand::DstUnused(match bits(w1, 11, 2) {
+                0 => DstUnused::Pad,
+                1 => DstUnused::Sext,
+                2 => DstUnused::Preserve,
+                _ => return None
+            }));
+        }
+        ops.append(&mut sels);
+        Some((srcs, ops))
+    }
+
+    fn dpp(&mut self, w1: u32, name: &'static str, vsrc1: u32, src_count: usize, modifiers: u8) -> Option<(Vec<Operand>, Vec<Operand>)> {
+        self.suffix = Some("dpp");
+        let ctrl = bits(w1, 8, 9);
+        let ctrl = match ctrl {
+            0x000..=0x0ff => DppCtrl::QuadPerm([bits(ctrl, 0, 2) as u8, bits(ctrl, 2, 2) as u8, bits(ctrl, 4, 2) as u8, bits(ctrl, 6, 2) as u8]),
+            0x101..=0x10f => DppCtrl::RowShl(bits(ctrl, 0, 4) as u8),
+            0x111..=0x11f => DppCtrl::RowShr(bits(ctrl, 0, 4) as u8),
+            0x121..=0x12f => DppCtrl::RowRor(bits(ctrl, 0, 4) as u8),
+            0x130 => DppCtrl::WaveShl,
+            0x134 => DppCtrl::WaveRol,
+            0x138 => DppCtrl::WaveShr,
+            0x13c => DppCtrl::WaveRor,
+            0x140 => DppCtrl::RowMirror,
+            0x141 => DppCtrl::RowHalfMirror,
+            0x142 => DppCtrl::RowBcast15,
+            0x143 => DppCtrl::RowBcast31,
+            _ => return None
+        };
+        if src_count == 1 && bits(w1, 22, 2) != 0 {
+            return None;
+        }
+
+        let mut srcs = Vec::new();
+        for (idx, &code) in [bits(w1, 0, 8), vsrc1][..src_count].iter().enumerate() {
+            let (neg, abs) = (bits(w1, 20 + 2 * idx as u32, 1), bits(w1, 21 + 2 * idx as u32, 1));
+            let src = vreg(code, 1)?;
+            srcs.push(match name {
+                /* v_cndmask ignores the modifier bits, integer sources of instructions
+                 * with floating-point modifiers read the neg bit as sext */
+                "v_cndmask_b32_e32" => src,
+                _ if float_source(name, idx, modifiers) => {
+                    let src = if abs == 1 { Operand::Abs(Box::new(src)) } else { src };
+                    if neg == 1 { Operand::Neg(Box::new(src)) } else { src }
+                },
+                _ if modifiers & SRC_MODS != 0 && neg == 1 => Operand::Sext(Box::new(src)),
+                _ if modifiers & SRC_MODS == 0 && (neg | abs) != 0 => return None,
+                _ => src
+            });
+        }
+
+        let mut ops = vec![Operand::Dpp(ctrl), Operand::RowMask(bits(w1, 28, 4) as u8), Operand::BankMask(bits(w1, 24, 4) as u8)];
+        if bits(w1, 19, 1) == 1 {
+            ops.push(Operand::BoundCtrl);
+        }
+        Some((srcs, ops))
+    }
+
+    fn vop3(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let op = bits(w0, 16, 10);
+        let &(_, name, widths, modifiers) = find_vop3(op)?;
+        self.int16 = int16_operands(name);
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        let vdst = bits(w0, 0, 8);
+        let (abs, op_sel) = if modifiers & SDST != 0 { (0, 0) } else { (bits(w0, 8, 3), bits(w0, 11, 4)) };
+        let clamp = bits(w0, 15, 1);
+        let omod = bits(w1, 27, 2);
+        let neg = bits(w1, 29, 3);
+
+        /* The source modifiers of v_cndmask do not extend to the condition mask */
+        let src_count = widths[1..].iter().filter(|&&w| w > 0).count();
+        let mod_srcs = if name == "v_cndmask_b32_e64" { 2 } else { src_count };
+        let mod_mask = (1 << mod_srcs) - 1;
+
+        if (neg | abs) != 0 && (modifiers & SRC_MODS == 0 || (neg | abs) & !mod_mask != 0) ||
+            (clamp != 0 && modifiers & CLAMP == 0) ||
+            (omod != 0 && modifiers & OMOD == 0) {
+            return None;
+        }
+        /* Stray op_sel bits are ignored by instructions without 16-bit operands */
+        let op_sel = if modifiers & OP_SEL == 0 { 0 } else { op_sel };
+
+        let mut ops = Vec::new();
+        if widths[0] > 0 {
+            /* Promoted VOPC instructions (opcodes below 256) write a scalar mask */
+            ops.push(match name {
+                "v_readlane_b32" => sreg(vdst, widths[0])?,
+                _ if op < 256 => sreg(vdst, widths[0])?,
+                _ => vreg(vdst, widths[0])?
+            });
+        }
+        if modifiers & SDST != 0 {
+            ops.push(sreg(bits(w0, 8, 7), 2)?);
+        }
+        for (idx, &width) in widths[1..].iter().enumerate().filter(|&(_, &w)| w > 0) {
+            let code = bits(w1, 9 * idx as u32, 9);
+            /* v_mad_u32_u16 and v_mad_i32_i16 accumulate into a 32-bit addend */
+            if idx == 2 && (name == "v_mad_u32_u16" || name == "v_mad_i32_i16") {
+                self.int16 = false;
+            }
+            if code == 255 {
+                /* Literal constants cannot be used with VOP3 on GFX9 */
+                return None;
+            }
+            let src = self.src(code, width)?;
+            let src = if bits(abs, idx as u32, 1) == 1 { Operand::Abs(Box::new(src)) } else { src };
+            let src = if bits(neg, idx as u32, 1) == 1 { Operand::Neg(Box::new(src)) } else { src };
+            ops.push(src);
+        }
+
+        if op_sel != 0 {
+            let mut sel: Vec<u8> = (0..src_count as u32).map(|i| bits(op_sel, i, 1) as u8).collect();
+            sel.push(bits(op_sel, 3, 1) as u8);
+            ops.push(Operand::OpSel(sel));
+        }
+        if clamp != 0 {
+            ops.push(Operand::Clamp);
+        }
+        ops.extend(output_modifier(omod));
+        Some((name, ops))
+    }
+
+    fn ds(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [dst, addr, data0, data1]) = find(opcodes::DS, bits(w0, 17, 8))?;
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        let mut ops = Vec::new();
+        if dst > 0 { ops.push(vreg(bits(w1, 24, 8), dst)?); }
+        if addr > 0 { ops.push(vreg(bits(w1, 0, 8), addr)?); }
+        if data0 > 0 { ops.push(vreg(bits(w1, 8, 8), data0)?); }
+        if data1 > 0 { ops.push(vreg(bits(w1, 16, 8), data1)?); }
+
+        let (offset0, offset1) = (bits(w0, 0, 8), bits(w0, 8, 8));
+        if name.contains("read2") || name.contains("write2") || name.contains("wrxchg2") {
+            if offset0 != 0 { ops.push(Operand::Offset0(offset0 as u8)); }
+            if offset1 != 0 { ops.push(Operand::Offset1(offset1 as u8)); }
+        }
+        else if bits(w0, 0, 16) != 0 {
+            ops.push(Operand::Offset(bits(w0, 0, 16) as i32));
+        }
+        if bits(w0, 16, 1) == 1 {
+            ops.push(Operand::Gds);
+        }
+        Some((name, ops))
+    }
+
+    fn flat(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let segment = bits(w0, 14, 2);
+        let table = match segment {
+            0 => opcodes::FLAT,
+            1 => opcodes::SCRATCH,
+            2 => opcodes::GLOBAL,
+            _ => return None
+        };
+        let &(_, name, [dst, addr, data, _]) = find(table, bits(w0, 18, 7))?;
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        let glc = bits(w0, 16, 1) == 1;
+        let saddr = bits(w1, 16, 7);
+        /* FLAT instructions have no scalar address, the LDS bit is unused on GFX9 */
+        if (segment == 0 && saddr != 0) || bits(w0, 13, 1) == 1 {
+            return None;
+        }
+
+        let mut ops = Vec::new();
+        if dst > 0 {
+            ops.push(vreg(bits(w1, 24, 8), dst)?);
+        }
+        else if glc && name.contains("_atomic_") {
+            /* Returning atomics; compare-and-swap returns half of its data operand */
+            let ret = if name.contains("_cmpswap") { data / 2 } else { data };
+            ops.push(vreg(bits(w1, 24, 8), ret)?);
+        }
+        match (segment, saddr) {
+            (0, _) | (_, 0x7f) => ops.push(vreg(bits(w1, 0, 8), addr)?),
+            (2, _) => ops.push(vreg(bits(w1, 0, 8), 1)?),
+            _ => ops.push(Operand::Off)
+        }
+        if data > 0 {
+            ops.push(vreg(bits(w1, 8, 8), data)?);
+        }
+        match (segment, saddr) {
+            (0, _) => (),
+            (_, 0x7f) => ops.push(Operand::Off),
+            (2, _) => ops.push(sreg(saddr, 2)?),
+            _ => ops.push(sreg(saddr, 1)?)
+        }
+
+        /* FLAT offsets are unsigned, GLOBAL and SCRATCH ones are 13-bit signed */
+        let offset = match segment {
+            0 => bits(w0, 0, 13) as i32,
+            _ => ((bits(w0, 0, 13) << 19) as i32) >> 19
+        };
+        if offset != 0 {
+            ops.push(Operand::Offset(offset));
+        }
+        if glc {
+            ops.push(Operand::Glc);
+        }
+        if bits(w0, 17, 1) == 1 {
+            ops.push(Operand::Slc);
+        }
+        Some((name, ops))
+    }
+
+    fn mubuf(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [vdata, _, _, _]) = find(opcodes::MUBUF, bits(w0, 18, 7))?;
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        let mut ops = self.buffer_operands(w0, w1, vdata)?;
+        ops.append(&mut buffer_flags(w0));
+        if bits(w0, 14, 1) == 1 { ops.push(Operand::Glc); }
+        if bits(w0, 17, 1) == 1 { ops.push(Operand::Slc); }
+        if bits(w0, 16, 1) == 1 { ops.push(Operand::Lds); }
+        /* Atomics and LDS loads cannot return texture fault status */
+        if bits(w1, 23, 1) == 1 && bits(w0, 16, 1) == 0 && !name.contains("_atomic_") { ops.push(Operand::Tfe); }
+        Some((name, ops))
+    }
+
+    fn mtbuf(&mut self, w0: u32) -> Option<(&'static str, Vec<Operand>)> {
+        let &(_, name, [vdata, _, _, _]) = find(opcodes::MTBUF, bits(w0, 15, 4))?;
+        let w1 = self.dword(1)?;
+        self.size = 8;
+
+        let mut ops = self.buffer_operands(w0, w1, vdata)?;
+        if let Some(format) = buffer_format(bits(w0, 19, 4), bits(w0, 23, 3)) {
+            ops.push(format);
+        }
+        ops.append(&mut buffer_flags(w0));
+        if bits(w0, 14, 1) == 1 { ops.push(Operand::Glc); }
+        if bits(w1, 22, 1) == 1 { ops.push(Operand::Slc); }
+        if bits(w1, 23, 1) == 1 { ops.push(Operand::Tfe); }
+        Some((name, ops))
+    }
+
+    /* vdata, vaddr, srsrc, soffset */
+    fn buffer_operands(&mut self, w0: u32, w1: u32, vdata: u8) -> Option<Vec<Operand>> {
+        let vaddr = bits(w1, 0, 8);
+        let vaddr = match (bits(w0, 13, 1), bits(w0, 12, 1)) {
+            (1, 1) => vreg(vaddr, 2)?,
+            (0, 0) => Operand::Off,
+            _ => vreg(vaddr, 1)?
+        };
+        let soffset = match bits(w1, 24, 8) {
+            255 => return None,
+            code => self.src(code, 1)?
+        };
+        Some(vec![vreg(bits(w1, 8, 8), vdata)?, vaddr, sreg(bits(w1, 16, 5) * 4, 4)?, soffset])
+    }
+}
+
+fn int16_operands(name: &str) -> bool {
+    let name = name.trim_end_matches("_e32").trim_end_matches("_e64");
+    let int16 = name.ends_with("_i16") || name.ends_with("_u16") || name.ends_with("_b16");
+    /* These take 16-bit values in 32-bit operands */
+    int16 && !name.starts_with("v_sat_pk") && name != "v_sad_u16"
+}
+
+/* SDWA output modifiers apply only to instructions with a floating-point result,
+ * whose type comes first in the mnemonic (v_cvt_u32_f32 returns an integer) */
+fn float_result(name: &str) -> bool {
+    let is_type = |t: &&str| t.len() > 1 && "fiub".contains(&t[..1]) && t[1..].chars().all(|c| c.is_ascii_digit());
+    name.split('_').skip(1).find(is_type).map_or(false, |t| t.starts_with('f'))
+}
+
+/* v_ldexp and v_cmp_class take an integer second source, v_cndmask selects between integers */
+fn float_source(name: &str, idx: usize, modifiers: u8) -> bool {
+    let int_src1 = name.starts_with("v_ldexp") || name.starts_with("v_cmp_class") || name.starts_with("v_cmpx_class");
+    modifiers & SRC_MODS != 0 && name != "v_cndmask_b32_e32" && !(idx == 1 && int_src1)
+}
+
+fn output_modifier(omod: u32) -> Option<Operand> {
+    match omod {
+        1 => Some(Operand::Omod(OutputModifier::Mul2)),
+        2 => Some(Operand::Omod(OutputModifier::Mul4)),
+        3 => Some(Operand::Omod(OutputModifier::Div2)),
+        _ => None
+    }
+}
+
+fn vreg(idx: u32, dwords: u8) -> Option<Operand> {
+    let last = idx + dwords as u32 - 1;
+    match dwords {
+        _ if last > 255 => None,
+        1 => Some(Operand::VReg(idx as usize)),
+        _ => Some(Operand::VRegs(idx as usize, last as usize))
+    }
+}
+
+fn sreg(idx: u32, dwords: u8) -> Option<Operand> {
+    /* Register tuples are aligned to 2 (64-bit) or 4 dwords, lower bits are ignored
+     * for SGPRs and trap temporaries but not for the special registers */
+    let aligned = match dwords {
+        1 => idx,
+        2 => idx & !1,
+        _ => idx & !3
+    };
+    if aligned != idx && (aligned >= 124 || (102..108).contains(&aligned)) {
+        return None;
+    }
+    let idx = aligned;
+    let last = idx + dwords as u32 - 1;
+    let special = |reg| Some(Operand::Special(reg));
+    match (idx, dwords) {
+        (0..=101, 1) => Some(Operand::SReg(idx as usize)),
+        (0..=101, _) => Some(Operand::SRegs(idx as usize, last as usize)),
+        (102, 1) => special(SpecialReg::FlatScratchLo),
+        (103, 1) => special(SpecialReg::FlatScratchHi),
+        (102, 2) => special(SpecialReg::FlatScratch),
+        (104, 1) => special(SpecialReg::XnackMaskLo),
+        (105, 1) => special(SpecialReg::XnackMaskHi),
+        (104, 2) => special(SpecialReg::XnackMask),
+        (106, 1) => special(SpecialReg::VCCLo),
+        (107, 1) => special(SpecialReg::VCCHi),
+        (106, 2) => Some(Operand::VCC),
+        (108..=123, 1) => Some(Operand::TReg(idx as usize - 108)),
+        (108..=123, _) if last <= 123 => Some(Operand::TRegs(idx as usize - 108, last as usize - 108)),
+        (124, 1) => special(SpecialReg::M0),
+        (126, 1) => special(SpecialReg::ExecLo),
+        (127, 1) => special(SpecialReg::ExecHi),
+        (126, 2) => special(SpecialReg::Exec),
+        _ => None
+    }
+}
+
+/* s_waitcnt lists only the counters that are waited on, or all of them if none is */
+fn waitcnt(simm16: u32) -> Operand {
+    let vmcnt = bits(simm16, 0, 4) | (bits(simm16, 14, 2) << 4);
+    let expcnt = bits(simm16, 4, 3);
+    let lgkmcnt = bits(simm16, 8, 4);
+
+    let all_max = vmcnt == 63 && expcnt == 7 && lgkmcnt == 15;
+    let counter = |value: u32, max: u32| if all_max || value != max { Some(value as u8) } else { None };
+
+    Operand::Waitcnt(Waitcnt {
+        vmcnt: counter(vmcnt, 63),
+        expcnt: counter(expcnt, 7),
+        lgkmcnt: counter(lgkmcnt, 15),
+        vscnt: None
+    })
+}
+
+fn sendmsg(simm16: u32) -> Operand {
+    match SendMsg::from_simm16(simm16 as u16) {
+        Some(msg) => Operand::SendMsg(msg),
+        None => Operand::Lit(simm16 as i32)
+    }
+}
+
+fn hwreg(simm16: u32) -> Operand {
+    Operand::Hwreg(Hwreg::from_simm16(simm16 as u16))
+}
+
+fn gpr_idx(simm16: u32) -> Operand {
+    let modes: Vec<&str> = ["SRC0", "SRC1", "SRC2", "DST"].iter().enumerate()
+        .filter(|&(i, _)| bits(simm16, i as u32, 1) == 1)
+        .map(|(_, &mode)| mode)
+        .collect();
+    Operand::Keyseq(format!("gpr_idx({})", modes.join(",")))
+}
+
+fn buffer_flags(w0: u32) -> Vec<Operand> {
+    let mut flags = Vec::new();
+    if bits(w0, 13, 1) == 1 { flags.push(Operand::Idxen); }
+    if bits(w0, 12, 1) == 1 { flags.push(Operand::Offen); }
+    if bits(w0, 0, 12) != 0 { flags.push(Operand::Offset(bits(w0, 0, 12) as i32)); }
+    flags
+}
+
+/* The default format (BUF_DATA_FORMAT_8, BUF_NUM_FORMAT_UNORM) is omitted */
+fn buffer_format(dfmt: u32, nfmt: u32) -> Option<Operand> {
+    const DATA_FORMATS: [&str; 16] = [
+        "INVALID", "8", "16", "8_8", "32", "16_16", "10_11_11", "11_11_10",
+        "10_10_10_2", "2_10_10_10", "8_8_8_8", "32_32", "16_16_16_16", "32_32_32", "32_32_32_32", "RESERVED_15"];
+    const NUM_FORMATS: [&str; 8] = ["UNORM", "SNORM", "USCALED", "SSCALED", "UINT", "SINT", "RESERVED_6", "FLOAT"];
+
+    let mut format = Vec::new();
+    if dfmt != 1 { format.push(format!("BUF_DATA_FORMAT_{}", DATA_FORMATS[dfmt as usize])); }
+    if nfmt != 0 { format.push(format!("BUF_NUM_FORMAT_{}", NUM_FORMATS[nfmt as usize])); }
+
+    match format.is_empty() {
+        true => None,
+        false => Some(Operand::Keyseq(format!("format:[{}]", format.join(","))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_instructions, decode_instruction};
+    use crate::asm::Instruction;
+    use crate::asm::listing::parse_instruction;
+    #[cfg(feature = "llvm")]
+    use crate::asm::llvm;
+    #[cfg(feature = "llvm")]
+    use crate::asm::target::Target;
+
+    /* One instruction per line: dwords in hex followed by the LLVM listing as a comment */
+    const FIXTURES: &str = include_str!("../../tests/fixtures/gfx9.txt");
+
+    /* (line, encoded instruction, expected listing) */
+    fn fixtures() -> impl Iterator<Item = (&'static str, Vec<u8>, &'static str)> {
+        FIXTURES.lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, '#');
+                let (encoding, listing) = (parts.next().unwrap().trim(), parts.next().unwrap_or("").trim());
+                if encoding.is_empty() {
+                    return None;
+                }
+                let raw: Vec<u8> = encoding.split_whitespace()
+                    .map(|dw| u32::from_str_radix(dw, 16).unwrap())
+                    .flat_map(|dw| (0..4).map(move |byte| (dw >> (byte * 8)) as u8))
+                    .collect();
+                Some((line, raw, listing))
+            })
+    }
+
+    #[test]
+    fn matches_fixtures() {
+        let mismatches: Vec<String> = fixtures()
+            .filter_map(|(line, raw, listing)| {
+                let (opcode, operands) = parse_instruction(listing);
+                let expected = vec![Instruction { opcode, operands, offset: 0, size: raw.len() }];
+                match decode_instructions(&raw) {
+                    Ok(ref native) if *native == expected => None,
+                    native => Some(format!("{}\n  native:   {:?}\n  expected: {:?}", line, native, expected))
+                }
+            })
+            .collect();
+
+        assert!(mismatches.is_empty(), "{} mismatches:\n{}", mismatches.len(), mismatches.join("\n"));
+    }
+
+    #[test]
+    fn rejects_invalid_extensions() {
+        let encodings: [[u32; 2]; 9] = [
+            /* v_mac_f32 has no SDWA form */
+            [0x2c1450f9, 0x06060614],
+            /* VOPC has no DPP form */
+            [0x7d9450fa, 0xff00e414],
+            /* Integer sources take sext, not neg */
+            [0x681450f9, 0x06160614],
+            /* Floating-point sources take neg, not sext */
+            [0x021450f9, 0x060e0614],
+            /* No output modifier for an integer result */
+            [0x7e140ef9, 0x00064614],
+            /* Literal constants cannot be scalar sources */
+            [0x021450f9, 0x068606ff],
+            /* src1 fields of a VOP1 instruction */
+            [0x7e1402f9, 0x06060614],
+            /* Unused dpp_ctrl value */
+            [0x7e1402fa, 0xff013114],
+            /* Integer instructions take no DPP modifiers */
+            [0x681450fa, 0xff10e414]
+        ];
+        for dwords in encodings.iter() {
+            let raw: Vec<u8> = dwords.iter().flat_map(|&dw| (0..4).map(move |byte| (dw >> (byte * 8)) as u8)).collect();
+            assert_eq!(decode_instruction(&raw), None, "{:08x} {:08x}", dwords[0], dwords[1]);
+        }
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn matches_llvm() {
+        let target = Target::from_processor("gfx900").unwrap();
+
+        let mismatches: Vec<String> = fixtures()
+            .filter_map(|(line, raw, _)| {
+                let native = decode_instructions(&raw);
+                let llvm = llvm::disassemble_instructions(&raw, &target).unwrap();
+                match native {
+                    Ok(ref native) if *native == llvm => None,
+                    _ => Some(format!("{}\n  native: {:?}\n  llvm:   {:?}", line, native, llvm))
+                }
+            })
+            .collect();
+
+        assert!(mismatches.is_empty(), "{} mismatches:\n{}", mismatches.len(), mismatches.join("\n"));
+    }
+}
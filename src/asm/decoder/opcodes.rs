@@ -0,0 +1,1440 @@
+/* GFX9 opcode tables, cross-checked against the LLVM AMDGPU disassembler.
+ *
+ * Each entry is (opcode, mnemonic, operand widths in dwords), where the meaning of
+ * the width slots depends on the encoding:
+ *   SOP*, VOP*  [dst, src0, src1, src2]
+ *   SMEM        [sdata, sbase]
+ *   DS          [vdst, addr, data0, data1]
+ *   FLAT        [vdst, addr, data]
+ *   MUBUF/MTBUF [vdata]
+ * A width of 0 means the operand is not present. Tables are sorted by opcode. */
+
+pub type Opcode = (u16, &'static str, [u8; 4]);
+
+/* VOP3 entries also list the modifiers accepted by the instruction */
+pub type Vop3Opcode = (u16, &'static str, [u8; 4], u8);
+
+/* neg and abs on each source operand */
+pub const SRC_MODS: u8 = 1 << 0;
+pub const CLAMP: u8 = 1 << 1;
+pub const OMOD: u8 = 1 << 2;
+pub const OP_SEL: u8 = 1 << 3;
+/* VOP3b: a scalar carry-out or condition destination replaces abs and op_sel */
+pub const SDST: u8 = 1 << 4;
+
+pub const SOP2: &[Opcode] = &[
+    (0, "s_add_u32", [1, 1, 1, 0]),
+    (1, "s_sub_u32", [1, 1, 1, 0]),
+    (2, "s_add_i32", [1, 1, 1, 0]),
+    (3, "s_sub_i32", [1, 1, 1, 0]),
+    (4, "s_addc_u32", [1, 1, 1, 0]),
+    (5, "s_subb_u32", [1, 1, 1, 0]),
+    (6, "s_min_i32", [1, 1, 1, 0]),
+    (7, "s_min_u32", [1, 1, 1, 0]),
+    (8, "s_max_i32", [1, 1, 1, 0]),
+    (9, "s_max_u32", [1, 1, 1, 0]),
+    (10, "s_cselect_b32", [1, 1, 1, 0]),
+    (11, "s_cselect_b64", [2, 2, 2, 0]),
+    (12, "s_and_b32", [1, 1, 1, 0]),
+    (13, "s_and_b64", [2, 2, 2, 0]),
+    (14, "s_or_b32", [1, 1, 1, 0]),
+    (15, "s_or_b64", [2, 2, 2, 0]),
+    (16, "s_xor_b32", [1, 1, 1, 0]),
+    (17, "s_xor_b64", [2, 2, 2, 0]),
+    (18, "s_andn2_b32", [1, 1, 1, 0]),
+    (19, "s_andn2_b64", [2, 2, 2, 0]),
+    (20, "s_orn2_b32", [1, 1, 1, 0]),
+    (21, "s_orn2_b64", [2, 2, 2, 0]),
+    (22, "s_nand_b32", [1, 1, 1, 0]),
+    (23, "s_nand_b64", [2, 2, 2, 0]),
+    (24, "s_nor_b32", [1, 1, 1, 0]),
+    (25, "s_nor_b64", [2, 2, 2, 0]),
+    (26, "s_xnor_b32", [1, 1, 1, 0]),
+    (27, "s_xnor_b64", [2, 2, 2, 0]),
+    (28, "s_lshl_b32", [1, 1, 1, 0]),
+    (29, "s_lshl_b64", [2, 2, 1, 0]),
+    (30, "s_lshr_b32", [1, 1, 1, 0]),
+    (31, "s_lshr_b64", [2, 2, 1, 0]),
+    (32, "s_ashr_i32", [1, 1, 1, 0]),
+    (33, "s_ashr_i64", [2, 2, 1, 0]),
+    (34, "s_bfm_b32", [1, 1, 1, 0]),
+    (35, "s_bfm_b64", [2, 1, 1, 0]),
+    (36, "s_mul_i32", [1, 1, 1, 0]),
+    (37, "s_bfe_u32", [1, 1, 1, 0]),
+    (38, "s_bfe_i32", [1, 1, 1, 0]),
+    (39, "s_bfe_u64", [2, 2, 1, 0]),
+    (40, "s_bfe_i64", [2, 2, 1, 0]),
+    (41, "s_cbranch_g_fork", [0, 2, 2, 0]),
+    (42, "s_absdiff_i32", [1, 1, 1, 0]),
+    (43, "s_rfe_restore_b64", [0, 2, 1, 0]),
+    (44, "s_mul_hi_u32", [1, 1, 1, 0]),
+    (45, "s_mul_hi_i32", [1, 1, 1, 0]),
+    (46, "s_lshl1_add_u32", [1, 1, 1, 0]),
+    (47, "s_lshl2_add_u32", [1, 1, 1, 0]),
+    (48, "s_lshl3_add_u32", [1, 1, 1, 0]),
+    (49, "s_lshl4_add_u32", [1, 1, 1, 0]),
+    (50, "s_pack_ll_b32_b16", [1, 1, 1, 0]),
+    (51, "s_pack_lh_b32_b16", [1, 1, 1, 0]),
+    (52, "s_pack_hh_b32_b16", [1, 1, 1, 0]),
+];
+
+pub const SOP1: &[Opcode] = &[
+    (0, "s_mov_b32", [1, 1, 0, 0]),
+    (1, "s_mov_b64", [2, 2, 0, 0]),
+    (2, "s_cmov_b32", [1, 1, 0, 0]),
+    (3, "s_cmov_b64", [2, 2, 0, 0]),
+    (4, "s_not_b32", [1, 1, 0, 0]),
+    (5, "s_not_b64", [2, 2, 0, 0]),
+    (6, "s_wqm_b32", [1, 1, 0, 0]),
+    (7, "s_wqm_b64", [2, 2, 0, 0]),
+    (8, "s_brev_b32", [1, 1, 0, 0]),
+    (9, "s_brev_b64", [2, 2, 0, 0]),
+    (10, "s_bcnt0_i32_b32", [1, 1, 0, 0]),
+    (11, "s_bcnt0_i32_b64", [1, 2, 0, 0]),
+    (12, "s_bcnt1_i32_b32", [1, 1, 0, 0]),
+    (13, "s_bcnt1_i32_b64", [1, 2, 0, 0]),
+    (14, "s_ff0_i32_b32", [1, 1, 0, 0]),
+    (15, "s_ff0_i32_b64", [1, 2, 0, 0]),
+    (16, "s_ff1_i32_b32", [1, 1, 0, 0]),
+    (17, "s_ff1_i32_b64", [1, 2, 0, 0]),
+    (18, "s_flbit_i32_b32", [1, 1, 0, 0]),
+    (19, "s_flbit_i32_b64", [1, 2, 0, 0]),
+    (20, "s_flbit_i32", [1, 1, 0, 0]),
+    (21, "s_flbit_i32_i64", [1, 2, 0, 0]),
+    (22, "s_sext_i32_i8", [1, 1, 0, 0]),
+    (23, "s_sext_i32_i16", [1, 1, 0, 0]),
+    (24, "s_bitset0_b32", [1, 1, 0, 0]),
+    (25, "s_bitset0_b64", [2, 1, 0, 0]),
+    (26, "s_bitset1_b32", [1, 1, 0, 0]),
+    (27, "s_bitset1_b64", [2, 1, 0, 0]),
+    (28, "s_getpc_b64", [2, 0, 0, 0]),
+    (29, "s_setpc_b64", [0, 2, 0, 0]),
+    (30, "s_swappc_b64", [2, 2, 0, 0]),
+    (31, "s_rfe_b64", [0, 2, 0, 0]),
+    (32, "s_and_saveexec_b64", [2, 2, 0, 0]),
+    (33, "s_or_saveexec_b64", [2, 2, 0, 0]),
+    (34, "s_xor_saveexec_b64", [2, 2, 0, 0]),
+    (35, "s_andn2_saveexec_b64", [2, 2, 0, 0]),
+    (36, "s_orn2_saveexec_b64", [2, 2, 0, 0]),
+    (37, "s_nand_saveexec_b64", [2, 2, 0, 0]),
+    (38, "s_nor_saveexec_b64", [2, 2, 0, 0]),
+    (39, "s_xnor_saveexec_b64", [2, 2, 0, 0]),
+    (40, "s_quadmask_b32", [1, 1, 0, 0]),
+    (41, "s_quadmask_b64", [2, 2, 0, 0]),
+    (42, "s_movrels_b32", [1, 1, 0, 0]),
+    (43, "s_movrels_b64", [2, 2, 0, 0]),
+    (44, "s_movreld_b32", [1, 1, 0, 0]),
+    (45, "s_movreld_b64", [2, 2, 0, 0]),
+    (46, "s_cbranch_join", [0, 1, 0, 0]),
+    (48, "s_abs_i32", [1, 1, 0, 0]),
+    (50, "s_set_gpr_idx_idx", [0, 1, 0, 0]),
+    (51, "s_andn1_saveexec_b64", [2, 2, 0, 0]),
+    (52, "s_orn1_saveexec_b64", [2, 2, 0, 0]),
+    (53, "s_andn1_wrexec_b64", [2, 2, 0, 0]),
+    (54, "s_andn2_wrexec_b64", [2, 2, 0, 0]),
+    (55, "s_bitreplicate_b64_b32", [2, 1, 0, 0]),
+];
+
+pub const SOPK: &[Opcode] = &[
+    (0, "s_movk_i32", [1, 0, 0, 0]),
+    (1, "s_cmovk_i32", [1, 0, 0, 0]),
+    (2, "s_cmpk_eq_i32", [1, 0, 0, 0]),
+    (3, "s_cmpk_lg_i32", [1, 0, 0, 0]),
+    (4, "s_cmpk_gt_i32", [1, 0, 0, 0]),
+    (5, "s_cmpk_ge_i32", [1, 0, 0, 0]),
+    (6, "s_cmpk_lt_i32", [1, 0, 0, 0]),
+    (7, "s_cmpk_le_i32", [1, 0, 0, 0]),
+    (8, "s_cmpk_eq_u32", [1, 0, 0, 0]),
+    (9, "s_cmpk_lg_u32", [1, 0, 0, 0]),
+    (10, "s_cmpk_gt_u32", [1, 0, 0, 0]),
+    (11, "s_cmpk_ge_u32", [1, 0, 0, 0]),
+    (12, "s_cmpk_lt_u32", [1, 0, 0, 0]),
+    (13, "s_cmpk_le_u32", [1, 0, 0, 0]),
+    (14, "s_addk_i32", [1, 0, 0, 0]),
+    (15, "s_mulk_i32", [1, 0, 0, 0]),
+    (16, "s_cbranch_i_fork", [2, 0, 0, 0]),
+    (17, "s_getreg_b32", [1, 0, 0, 0]),
+    (18, "s_setreg_b32", [1, 0, 0, 0]),
+    (20, "s_setreg_imm32_b32", [0, 0, 0, 0]),
+    (21, "s_call_b64", [2, 0, 0, 0]),
+];
+
+pub const SOPC: &[Opcode] = &[
+    (0, "s_cmp_eq_i32", [0, 1, 1, 0]),
+    (1, "s_cmp_lg_i32", [0, 1, 1, 0]),
+    (2, "s_cmp_gt_i32", [0, 1, 1, 0]),
+    (3, "s_cmp_ge_i32", [0, 1, 1, 0]),
+    (4, "s_cmp_lt_i32", [0, 1, 1, 0]),
+    (5, "s_cmp_le_i32", [0, 1, 1, 0]),
+    (6, "s_cmp_eq_u32", [0, 1, 1, 0]),
+    (7, "s_cmp_lg_u32", [0, 1, 1, 0]),
+    (8, "s_cmp_gt_u32", [0, 1, 1, 0]),
+    (9, "s_cmp_ge_u32", [0, 1, 1, 0]),
+    (10, "s_cmp_lt_u32", [0, 1, 1, 0]),
+    (11, "s_cmp_le_u32", [0, 1, 1, 0]),
+    (12, "s_bitcmp0_b32", [0, 1, 1, 0]),
+    (13, "s_bitcmp1_b32", [0, 1, 1, 0]),
+    (14, "s_bitcmp0_b64", [0, 2, 1, 0]),
+    (15, "s_bitcmp1_b64", [0, 2, 1, 0]),
+    (16, "s_setvskip", [0, 1, 1, 0]),
+    (17, "s_set_gpr_idx_on", [0, 1, 0, 0]),
+    (18, "s_cmp_eq_u64", [0, 2, 2, 0]),
+    (19, "s_cmp_lg_u64", [0, 2, 2, 0]),
+];
+
+pub const SOPP: &[Opcode] = &[
+    (0, "s_nop", [0, 0, 0, 0]),
+    (1, "s_endpgm", [0, 0, 0, 0]),
+    (2, "s_branch", [0, 0, 0, 0]),
+    (3, "s_wakeup", [0, 0, 0, 0]),
+    (4, "s_cbranch_scc0", [0, 0, 0, 0]),
+    (5, "s_cbranch_scc1", [0, 0, 0, 0]),
+    (6, "s_cbranch_vccz", [0, 0, 0, 0]),
+    (7, "s_cbranch_vccnz", [0, 0, 0, 0]),
+    (8, "s_cbranch_execz", [0, 0, 0, 0]),
+    (9, "s_cbranch_execnz", [0, 0, 0, 0]),
+    (10, "s_barrier", [0, 0, 0, 0]),
+    (11, "s_setkill", [0, 0, 0, 0]),
+    (12, "s_waitcnt", [0, 0, 0, 0]),
+    (13, "s_sethalt", [0, 0, 0, 0]),
+    (14, "s_sleep", [0, 0, 0, 0]),
+    (15, "s_setprio", [0, 0, 0, 0]),
+    (16, "s_sendmsg", [0, 0, 0, 0]),
+    (17, "s_sendmsghalt", [0, 0, 0, 0]),
+    (18, "s_trap", [0, 0, 0, 0]),
+    (19, "s_icache_inv", [0, 0, 0, 0]),
+    (20, "s_incperflevel", [0, 0, 0, 0]),
+    (21, "s_decperflevel", [0, 0, 0, 0]),
+    (22, "s_ttracedata", [0, 0, 0, 0]),
+    (23, "s_cbranch_cdbgsys", [0, 0, 0, 0]),
+    (24, "s_cbranch_cdbguser", [0, 0, 0, 0]),
+    (25, "s_cbranch_cdbgsys_or_user", [0, 0, 0, 0]),
+    (26, "s_cbranch_cdbgsys_and_user", [0, 0, 0, 0]),
+    (27, "s_endpgm_saved", [0, 0, 0, 0]),
+    (28, "s_set_gpr_idx_off", [0, 0, 0, 0]),
+    (29, "s_set_gpr_idx_mode", [0, 0, 0, 0]),
+    (30, "s_endpgm_ordered_ps_done", [0, 0, 0, 0]),
+];
+
+pub const SMEM: &[Opcode] = &[
+    (0, "s_load_dword", [1, 2, 0, 0]),
+    (1, "s_load_dwordx2", [2, 2, 0, 0]),
+    (2, "s_load_dwordx4", [4, 2, 0, 0]),
+    (3, "s_load_dwordx8", [8, 2, 0, 0]),
+    (4, "s_load_dwordx16", [16, 2, 0, 0]),
+    (5, "s_scratch_load_dword", [1, 2, 0, 0]),
+    (6, "s_scratch_load_dwordx2", [2, 2, 0, 0]),
+    (7, "s_scratch_load_dwordx4", [4, 2, 0, 0]),
+    (8, "s_buffer_load_dword", [1, 4, 0, 0]),
+    (9, "s_buffer_load_dwordx2", [2, 4, 0, 0]),
+    (10, "s_buffer_load_dwordx4", [4, 4, 0, 0]),
+    (11, "s_buffer_load_dwordx8", [8, 4, 0, 0]),
+    (12, "s_buffer_load_dwordx16", [16, 4, 0, 0]),
+    (16, "s_store_dword", [1, 2, 0, 0]),
+    (17, "s_store_dwordx2", [2, 2, 0, 0]),
+    (18, "s_store_dwordx4", [4, 2, 0, 0]),
+    (21, "s_scratch_store_dword", [1, 2, 0, 0]),
+    (22, "s_scratch_store_dwordx2", [2, 2, 0, 0]),
+    (23, "s_scratch_store_dwordx4", [4, 2, 0, 0]),
+    (24, "s_buffer_store_dword", [1, 4, 0, 0]),
+    (25, "s_buffer_store_dwordx2", [2, 4, 0, 0]),
+    (26, "s_buffer_store_dwordx4", [4, 4, 0, 0]),
+    (32, "s_dcache_inv", [0, 0, 0, 0]),
+    (33, "s_dcache_wb", [0, 0, 0, 0]),
+    (34, "s_dcache_inv_vol", [0, 0, 0, 0]),
+    (35, "s_dcache_wb_vol", [0, 0, 0, 0]),
+    (36, "s_memtime", [2, 0, 0, 0]),
+    (37, "s_memrealtime", [2, 0, 0, 0]),
+    (38, "s_atc_probe", [0, 2, 0, 0]),
+    (39, "s_atc_probe_buffer", [0, 4, 0, 0]),
+    (40, "s_dcache_discard", [0, 2, 0, 0]),
+    (41, "s_dcache_discard_x2", [0, 2, 0, 0]),
+    (64, "s_buffer_atomic_swap", [1, 4, 0, 0]),
+    (65, "s_buffer_atomic_cmpswap", [2, 4, 0, 0]),
+    (66, "s_buffer_atomic_add", [1, 4, 0, 0]),
+    (67, "s_buffer_atomic_sub", [1, 4, 0, 0]),
+    (68, "s_buffer_atomic_smin", [1, 4, 0, 0]),
+    (69, "s_buffer_atomic_umin", [1, 4, 0, 0]),
+    (70, "s_buffer_atomic_smax", [1, 4, 0, 0]),
+    (71, "s_buffer_atomic_umax", [1, 4, 0, 0]),
+    (72, "s_buffer_atomic_and", [1, 4, 0, 0]),
+    (73, "s_buffer_atomic_or", [1, 4, 0, 0]),
+    (74, "s_buffer_atomic_xor", [1, 4, 0, 0]),
+    (75, "s_buffer_atomic_inc", [1, 4, 0, 0]),
+    (76, "s_buffer_atomic_dec", [1, 4, 0, 0]),
+    (96, "s_buffer_atomic_swap_x2", [2, 4, 0, 0]),
+    (97, "s_buffer_atomic_cmpswap_x2", [4, 4, 0, 0]),
+    (98, "s_buffer_atomic_add_x2", [2, 4, 0, 0]),
+    (99, "s_buffer_atomic_sub_x2", [2, 4, 0, 0]),
+    (100, "s_buffer_atomic_smin_x2", [2, 4, 0, 0]),
+    (101, "s_buffer_atomic_umin_x2", [2, 4, 0, 0]),
+    (102, "s_buffer_atomic_smax_x2", [2, 4, 0, 0]),
+    (103, "s_buffer_atomic_umax_x2", [2, 4, 0, 0]),
+    (104, "s_buffer_atomic_and_x2", [2, 4, 0, 0]),
+    (105, "s_buffer_atomic_or_x2", [2, 4, 0, 0]),
+    (106, "s_buffer_atomic_xor_x2", [2, 4, 0, 0]),
+    (107, "s_buffer_atomic_inc_x2", [2, 4, 0, 0]),
+    (108, "s_buffer_atomic_dec_x2", [2, 4, 0, 0]),
+    (128, "s_atomic_swap", [1, 2, 0, 0]),
+    (129, "s_atomic_cmpswap", [2, 2, 0, 0]),
+    (130, "s_atomic_add", [1, 2, 0, 0]),
+    (131, "s_atomic_sub", [1, 2, 0, 0]),
+    (132, "s_atomic_smin", [1, 2, 0, 0]),
+    (133, "s_atomic_umin", [1, 2, 0, 0]),
+    (134, "s_atomic_smax", [1, 2, 0, 0]),
+    (135, "s_atomic_umax", [1, 2, 0, 0]),
+    (136, "s_atomic_and", [1, 2, 0, 0]),
+    (137, "s_atomic_or", [1, 2, 0, 0]),
+    (138, "s_atomic_xor", [1, 2, 0, 0]),
+    (139, "s_atomic_inc", [1, 2, 0, 0]),
+    (140, "s_atomic_dec", [1, 2, 0, 0]),
+    (160, "s_atomic_swap_x2", [2, 2, 0, 0]),
+    (161, "s_atomic_cmpswap_x2", [4, 2, 0, 0]),
+    (162, "s_atomic_add_x2", [2, 2, 0, 0]),
+    (163, "s_atomic_sub_x2", [2, 2, 0, 0]),
+    (164, "s_atomic_smin_x2", [2, 2, 0, 0]),
+    (165, "s_atomic_umin_x2", [2, 2, 0, 0]),
+    (166, "s_atomic_smax_x2", [2, 2, 0, 0]),
+    (167, "s_atomic_umax_x2", [2, 2, 0, 0]),
+    (168, "s_atomic_and_x2", [2, 2, 0, 0]),
+    (169, "s_atomic_or_x2", [2, 2, 0, 0]),
+    (170, "s_atomic_xor_x2", [2, 2, 0, 0]),
+    (171, "s_atomic_inc_x2", [2, 2, 0, 0]),
+    (172, "s_atomic_dec_x2", [2, 2, 0, 0]),
+];
+
+pub const VOP2: &[Opcode] = &[
+    (0, "v_cndmask_b32_e32", [1, 1, 1, 0]),
+    (1, "v_add_f32_e32", [1, 1, 1, 0]),
+    (2, "v_sub_f32_e32", [1, 1, 1, 0]),
+    (3, "v_subrev_f32_e32", [1, 1, 1, 0]),
+    (4, "v_mul_legacy_f32_e32", [1, 1, 1, 0]),
+    (5, "v_mul_f32_e32", [1, 1, 1, 0]),
+    (6, "v_mul_i32_i24_e32", [1, 1, 1, 0]),
+    (7, "v_mul_hi_i32_i24_e32", [1, 1, 1, 0]),
+    (8, "v_mul_u32_u24_e32", [1, 1, 1, 0]),
+    (9, "v_mul_hi_u32_u24_e32", [1, 1, 1, 0]),
+    (10, "v_min_f32_e32", [1, 1, 1, 0]),
+    (11, "v_max_f32_e32", [1, 1, 1, 0]),
+    (12, "v_min_i32_e32", [1, 1, 1, 0]),
+    (13, "v_max_i32_e32", [1, 1, 1, 0]),
+    (14, "v_min_u32_e32", [1, 1, 1, 0]),
+    (15, "v_max_u32_e32", [1, 1, 1, 0]),
+    (16, "v_lshrrev_b32_e32", [1, 1, 1, 0]),
+    (17, "v_ashrrev_i32_e32", [1, 1, 1, 0]),
+    (18, "v_lshlrev_b32_e32", [1, 1, 1, 0]),
+    (19, "v_and_b32_e32", [1, 1, 1, 0]),
+    (20, "v_or_b32_e32", [1, 1, 1, 0]),
+    (21, "v_xor_b32_e32", [1, 1, 1, 0]),
+    (22, "v_mac_f32_e32", [1, 1, 1, 0]),
+    (23, "v_madmk_f32", [1, 1, 1, 0]),
+    (24, "v_madak_f32", [1, 1, 1, 0]),
+    (25, "v_add_co_u32_e32", [1, 1, 1, 0]),
+    (26, "v_sub_co_u32_e32", [1, 1, 1, 0]),
+    (27, "v_subrev_co_u32_e32", [1, 1, 1, 0]),
+    (28, "v_addc_co_u32_e32", [1, 1, 1, 0]),
+    (29, "v_subb_co_u32_e32", [1, 1, 1, 0]),
+    (30, "v_subbrev_co_u32_e32", [1, 1, 1, 0]),
+    (31, "v_add_f16_e32", [1, 1, 1, 0]),
+    (32, "v_sub_f16_e32", [1, 1, 1, 0]),
+    (33, "v_subrev_f16_e32", [1, 1, 1, 0]),
+    (34, "v_mul_f16_e32", [1, 1, 1, 0]),
+    (35, "v_mac_f16_e32", [1, 1, 1, 0]),
+    (36, "v_madmk_f16", [1, 1, 1, 0]),
+    (37, "v_madak_f16", [1, 1, 1, 0]),
+    (38, "v_add_u16_e32", [1, 1, 1, 0]),
+    (39, "v_sub_u16_e32", [1, 1, 1, 0]),
+    (40, "v_subrev_u16_e32", [1, 1, 1, 0]),
+    (41, "v_mul_lo_u16_e32", [1, 1, 1, 0]),
+    (42, "v_lshlrev_b16_e32", [1, 1, 1, 0]),
+    (43, "v_lshrrev_b16_e32", [1, 1, 1, 0]),
+    (44, "v_ashrrev_i16_e32", [1, 1, 1, 0]),
+    (45, "v_max_f16_e32", [1, 1, 1, 0]),
+    (46, "v_min_f16_e32", [1, 1, 1, 0]),
+    (47, "v_max_u16_e32", [1, 1, 1, 0]),
+    (48, "v_max_i16_e32", [1, 1, 1, 0]),
+    (49, "v_min_u16_e32", [1, 1, 1, 0]),
+    (50, "v_min_i16_e32", [1, 1, 1, 0]),
+    (51, "v_ldexp_f16_e32", [1, 1, 1, 0]),
+    (52, "v_add_u32_e32", [1, 1, 1, 0]),
+    (53, "v_sub_u32_e32", [1, 1, 1, 0]),
+    (54, "v_subrev_u32_e32", [1, 1, 1, 0]),
+    (63, "v_sqrt_f64_e32", [2, 2, 0, 0]),
+];
+
+pub const VOP1: &[Opcode] = &[
+    (0, "v_nop", [0, 0, 0, 0]),
+    (1, "v_mov_b32_e32", [1, 1, 0, 0]),
+    (2, "v_readfirstlane_b32", [1, 1, 0, 0]),
+    (3, "v_cvt_i32_f64_e32", [1, 2, 0, 0]),
+    (4, "v_cvt_f64_i32_e32", [2, 1, 0, 0]),
+    (5, "v_cvt_f32_i32_e32", [1, 1, 0, 0]),
+    (6, "v_cvt_f32_u32_e32", [1, 1, 0, 0]),
+    (7, "v_cvt_u32_f32_e32", [1, 1, 0, 0]),
+    (8, "v_cvt_i32_f32_e32", [1, 1, 0, 0]),
+    (10, "v_cvt_f16_f32_e32", [1, 1, 0, 0]),
+    (11, "v_cvt_f32_f16_e32", [1, 1, 0, 0]),
+    (12, "v_cvt_rpi_i32_f32_e32", [1, 1, 0, 0]),
+    (13, "v_cvt_flr_i32_f32_e32", [1, 1, 0, 0]),
+    (14, "v_cvt_off_f32_i4_e32", [1, 1, 0, 0]),
+    (15, "v_cvt_f32_f64_e32", [1, 2, 0, 0]),
+    (16, "v_cvt_f64_f32_e32", [2, 1, 0, 0]),
+    (17, "v_cvt_f32_ubyte0_e32", [1, 1, 0, 0]),
+    (18, "v_cvt_f32_ubyte1_e32", [1, 1, 0, 0]),
+    (19, "v_cvt_f32_ubyte2_e32", [1, 1, 0, 0]),
+    (20, "v_cvt_f32_ubyte3_e32", [1, 1, 0, 0]),
+    (21, "v_cvt_u32_f64_e32", [1, 2, 0, 0]),
+    (22, "v_cvt_f64_u32_e32", [2, 1, 0, 0]),
+    (23, "v_trunc_f64_e32", [2, 2, 0, 0]),
+    (24, "v_ceil_f64_e32", [2, 2, 0, 0]),
+    (25, "v_rndne_f64_e32", [2, 2, 0, 0]),
+    (26, "v_floor_f64_e32", [2, 2, 0, 0]),
+    (27, "v_fract_f32_e32", [1, 1, 0, 0]),
+    (28, "v_trunc_f32_e32", [1, 1, 0, 0]),
+    (29, "v_ceil_f32_e32", [1, 1, 0, 0]),
+    (30, "v_rndne_f32_e32", [1, 1, 0, 0]),
+    (31, "v_floor_f32_e32", [1, 1, 0, 0]),
+    (32, "v_exp_f32_e32", [1, 1, 0, 0]),
+    (33, "v_log_f32_e32", [1, 1, 0, 0]),
+    (34, "v_rcp_f32_e32", [1, 1, 0, 0]),
+    (35, "v_rcp_iflag_f32_e32", [1, 1, 0, 0]),
+    (36, "v_rsq_f32_e32", [1, 1, 0, 0]),
+    (37, "v_rcp_f64_e32", [2, 2, 0, 0]),
+    (38, "v_rsq_f64_e32", [2, 2, 0, 0]),
+    (39, "v_sqrt_f32_e32", [1, 1, 0, 0]),
+    (40, "v_sqrt_f64_e32", [2, 2, 0, 0]),
+    (41, "v_sin_f32_e32", [1, 1, 0, 0]),
+    (42, "v_cos_f32_e32", [1, 1, 0, 0]),
+    (43, "v_not_b32_e32", [1, 1, 0, 0]),
+    (44, "v_bfrev_b32_e32", [1, 1, 0, 0]),
+    (45, "v_ffbh_u32_e32", [1, 1, 0, 0]),
+    (46, "v_ffbl_b32_e32", [1, 1, 0, 0]),
+    (47, "v_ffbh_i32_e32", [1, 1, 0, 0]),
+    (48, "v_frexp_exp_i32_f64_e32", [1, 2, 0, 0]),
+    (49, "v_frexp_mant_f64_e32", [2, 2, 0, 0]),
+    (50, "v_fract_f64_e32", [2, 2, 0, 0]),
+    (51, "v_frexp_exp_i32_f32_e32", [1, 1, 0, 0]),
+    (52, "v_frexp_mant_f32_e32", [1, 1, 0, 0]),
+    (53, "v_clrexcp", [0, 0, 0, 0]),
+    (55, "v_screen_partition_4se_b32_e32", [1, 1, 0, 0]),
+    (57, "v_cvt_f16_u16_e32", [1, 1, 0, 0]),
+    (58, "v_cvt_f16_i16_e32", [1, 1, 0, 0]),
+    (59, "v_cvt_u16_f16_e32", [1, 1, 0, 0]),
+    (60, "v_cvt_i16_f16_e32", [1, 1, 0, 0]),
+    (61, "v_rcp_f16_e32", [1, 1, 0, 0]),
+    (62, "v_sqrt_f16_e32", [1, 1, 0, 0]),
+    (63, "v_rsq_f16_e32", [1, 1, 0, 0]),
+    (64, "v_log_f16_e32", [1, 1, 0, 0]),
+    (65, "v_exp_f16_e32", [1, 1, 0, 0]),
+    (66, "v_frexp_mant_f16_e32", [1, 1, 0, 0]),
+    (67, "v_frexp_exp_i16_f16_e32", [1, 1, 0, 0]),
+    (68, "v_floor_f16_e32", [1, 1, 0, 0]),
+    (69, "v_ceil_f16_e32", [1, 1, 0, 0]),
+    (70, "v_trunc_f16_e32", [1, 1, 0, 0]),
+    (71, "v_rndne_f16_e32", [1, 1, 0, 0]),
+    (72, "v_fract_f16_e32", [1, 1, 0, 0]),
+    (73, "v_sin_f16_e32", [1, 1, 0, 0]),
+    (74, "v_cos_f16_e32", [1, 1, 0, 0]),
+    (75, "v_exp_legacy_f32_e32", [1, 1, 0, 0]),
+    (76, "v_log_legacy_f32_e32", [1, 1, 0, 0]),
+    (77, "v_cvt_norm_i16_f16_e32", [1, 1, 0, 0]),
+    (78, "v_cvt_norm_u16_f16_e32", [1, 1, 0, 0]),
+    (79, "v_sat_pk_u8_i16_e32", [1, 1, 0, 0]),
+    (81, "v_swap_b32", [1, 1, 0, 0]),
+];
+
+pub const VOPC: &[Opcode] = &[
+    (16, "v_cmp_class_f32_e32", [2, 1, 1, 0]),
+    (17, "v_cmpx_class_f32_e32", [2, 1, 1, 0]),
+    (18, "v_cmp_class_f64_e32", [2, 2, 1, 0]),
+    (19, "v_cmpx_class_f64_e32", [2, 2, 1, 0]),
+    (20, "v_cmp_class_f16_e32", [2, 1, 1, 0]),
+    (21, "v_cmpx_class_f16_e32", [2, 1, 1, 0]),
+    (32, "v_cmp_f_f16_e32", [2, 1, 1, 0]),
+    (33, "v_cmp_lt_f16_e32", [2, 1, 1, 0]),
+    (34, "v_cmp_eq_f16_e32", [2, 1, 1, 0]),
+    (35, "v_cmp_le_f16_e32", [2, 1, 1, 0]),
+    (36, "v_cmp_gt_f16_e32", [2, 1, 1, 0]),
+    (37, "v_cmp_lg_f16_e32", [2, 1, 1, 0]),
+    (38, "v_cmp_ge_f16_e32", [2, 1, 1, 0]),
+    (39, "v_cmp_o_f16_e32", [2, 1, 1, 0]),
+    (40, "v_cmp_u_f16_e32", [2, 1, 1, 0]),
+    (41, "v_cmp_nge_f16_e32", [2, 1, 1, 0]),
+    (42, "v_cmp_nlg_f16_e32", [2, 1, 1, 0]),
+    (43, "v_cmp_ngt_f16_e32", [2, 1, 1, 0]),
+    (44, "v_cmp_nle_f16_e32", [2, 1, 1, 0]),
+    (45, "v_cmp_neq_f16_e32", [2, 1, 1, 0]),
+    (46, "v_cmp_nlt_f16_e32", [2, 1, 1, 0]),
+    (47, "v_cmp_tru_f16_e32", [2, 1, 1, 0]),
+    (48, "v_cmpx_f_f16_e32", [2, 1, 1, 0]),
+    (49, "v_cmpx_lt_f16_e32", [2, 1, 1, 0]),
+    (50, "v_cmpx_eq_f16_e32", [2, 1, 1, 0]),
+    (51, "v_cmpx_le_f16_e32", [2, 1, 1, 0]),
+    (52, "v_cmpx_gt_f16_e32", [2, 1, 1, 0]),
+    (53, "v_cmpx_lg_f16_e32", [2, 1, 1, 0]),
+    (54, "v_cmpx_ge_f16_e32", [2, 1, 1, 0]),
+    (55, "v_cmpx_o_f16_e32", [2, 1, 1, 0]),
+    (56, "v_cmpx_u_f16_e32", [2, 1, 1, 0]),
+    (57, "v_cmpx_nge_f16_e32", [2, 1, 1, 0]),
+    (58, "v_cmpx_nlg_f16_e32", [2, 1, 1, 0]),
+    (59, "v_cmpx_ngt_f16_e32", [2, 1, 1, 0]),
+    (60, "v_cmpx_nle_f16_e32", [2, 1, 1, 0]),
+    (61, "v_cmpx_neq_f16_e32", [2, 1, 1, 0]),
+    (62, "v_cmpx_nlt_f16_e32", [2, 1, 1, 0]),
+    (63, "v_cmpx_tru_f16_e32", [2, 1, 1, 0]),
+    (64, "v_cmp_f_f32_e32", [2, 1, 1, 0]),
+    (65, "v_cmp_lt_f32_e32", [2, 1, 1, 0]),
+    (66, "v_cmp_eq_f32_e32", [2, 1, 1, 0]),
+    (67, "v_cmp_le_f32_e32", [2, 1, 1, 0]),
+    (68, "v_cmp_gt_f32_e32", [2, 1, 1, 0]),
+    (69, "v_cmp_lg_f32_e32", [2, 1, 1, 0]),
+    (70, "v_cmp_ge_f32_e32", [2, 1, 1, 0]),
+    (71, "v_cmp_o_f32_e32", [2, 1, 1, 0]),
+    (72, "v_cmp_u_f32_e32", [2, 1, 1, 0]),
+    (73, "v_cmp_nge_f32_e32", [2, 1, 1, 0]),
+    (74, "v_cmp_nlg_f32_e32", [2, 1, 1, 0]),
+    (75, "v_cmp_ngt_f32_e32", [2, 1, 1, 0]),
+    (76, "v_cmp_nle_f32_e32", [2, 1, 1, 0]),
+    (77, "v_cmp_neq_f32_e32", [2, 1, 1, 0]),
+    (78, "v_cmp_nlt_f32_e32", [2, 1, 1, 0]),
+    (79, "v_cmp_tru_f32_e32", [2, 1, 1, 0]),
+    (80, "v_cmpx_f_f32_e32", [2, 1, 1, 0]),
+    (81, "v_cmpx_lt_f32_e32", [2, 1, 1, 0]),
+    (82, "v_cmpx_eq_f32_e32", [2, 1, 1, 0]),
+    (83, "v_cmpx_le_f32_e32", [2, 1, 1, 0]),
+    (84, "v_cmpx_gt_f32_e32", [2, 1, 1, 0]),
+    (85, "v_cmpx_lg_f32_e32", [2, 1, 1, 0]),
+    (86, "v_cmpx_ge_f32_e32", [2, 1, 1, 0]),
+    (87, "v_cmpx_o_f32_e32", [2, 1, 1, 0]),
+    (88, "v_cmpx_u_f32_e32", [2, 1, 1, 0]),
+    (89, "v_cmpx_nge_f32_e32", [2, 1, 1, 0]),
+    (90, "v_cmpx_nlg_f32_e32", [2, 1, 1, 0]),
+    (91, "v_cmpx_ngt_f32_e32", [2, 1, 1, 0]),
+    (92, "v_cmpx_nle_f32_e32", [2, 1, 1, 0]),
+    (93, "v_cmpx_neq_f32_e32", [2, 1, 1, 0]),
+    (94, "v_cmpx_nlt_f32_e32", [2, 1, 1, 0]),
+    (95, "v_cmpx_tru_f32_e32", [2, 1, 1, 0]),
+    (96, "v_cmp_f_f64_e32", [2, 2, 2, 0]),
+    (97, "v_cmp_lt_f64_e32", [2, 2, 2, 0]),
+    (98, "v_cmp_eq_f64_e32", [2, 2, 2, 0]),
+    (99, "v_cmp_le_f64_e32", [2, 2, 2, 0]),
+    (100, "v_cmp_gt_f64_e32", [2, 2, 2, 0]),
+    (101, "v_cmp_lg_f64_e32", [2, 2, 2, 0]),
+    (102, "v_cmp_ge_f64_e32", [2, 2, 2, 0]),
+    (103, "v_cmp_o_f64_e32", [2, 2, 2, 0]),
+    (104, "v_cmp_u_f64_e32", [2, 2, 2, 0]),
+    (105, "v_cmp_nge_f64_e32", [2, 2, 2, 0]),
+    (106, "v_cmp_nlg_f64_e32", [2, 2, 2, 0]),
+    (107, "v_cmp_ngt_f64_e32", [2, 2, 2, 0]),
+    (108, "v_cmp_nle_f64_e32", [2, 2, 2, 0]),
+    (109, "v_cmp_neq_f64_e32", [2, 2, 2, 0]),
+    (110, "v_cmp_nlt_f64_e32", [2, 2, 2, 0]),
+    (111, "v_cmp_tru_f64_e32", [2, 2, 2, 0]),
+    (112, "v_cmpx_f_f64_e32", [2, 2, 2, 0]),
+    (113, "v_cmpx_lt_f64_e32", [2, 2, 2, 0]),
+    (114, "v_cmpx_eq_f64_e32", [2, 2, 2, 0]),
+    (115, "v_cmpx_le_f64_e32", [2, 2, 2, 0]),
+    (116, "v_cmpx_gt_f64_e32", [2, 2, 2, 0]),
+    (117, "v_cmpx_lg_f64_e32", [2, 2, 2, 0]),
+    (118, "v_cmpx_ge_f64_e32", [2, 2, 2, 0]),
+    (119, "v_cmpx_o_f64_e32", [2, 2, 2, 0]),
+    (120, "v_cmpx_u_f64_e32", [2, 2, 2, 0]),
+    (121, "v_cmpx_nge_f64_e32", [2, 2, 2, 0]),
+    (122, "v_cmpx_nlg_f64_e32", [2, 2, 2, 0]),
+    (123, "v_cmpx_ngt_f64_e32", [2, 2, 2, 0]),
+    (124, "v_cmpx_nle_f64_e32", [2, 2, 2, 0]),
+    (125, "v_cmpx_neq_f64_e32", [2, 2, 2, 0]),
+    (126, "v_cmpx_nlt_f64_e32", [2, 2, 2, 0]),
+    (127, "v_cmpx_tru_f64_e32", [2, 2, 2, 0]),
+    (160, "v_cmp_f_i16_e32", [2, 1, 1, 0]),
+    (161, "v_cmp_lt_i16_e32", [2, 1, 1, 0]),
+    (162, "v_cmp_eq_i16_e32", [2, 1, 1, 0]),
+    (163, "v_cmp_le_i16_e32", [2, 1, 1, 0]),
+    (164, "v_cmp_gt_i16_e32", [2, 1, 1, 0]),
+    (165, "v_cmp_ne_i16_e32", [2, 1, 1, 0]),
+    (166, "v_cmp_ge_i16_e32", [2, 1, 1, 0]),
+    (167, "v_cmp_t_i16_e32", [2, 1, 1, 0]),
+    (168, "v_cmp_f_u16_e32", [2, 1, 1, 0]),
+    (169, "v_cmp_lt_u16_e32", [2, 1, 1, 0]),
+    (170, "v_cmp_eq_u16_e32", [2, 1, 1, 0]),
+    (171, "v_cmp_le_u16_e32", [2, 1, 1, 0]),
+    (172, "v_cmp_gt_u16_e32", [2, 1, 1, 0]),
+    (173, "v_cmp_ne_u16_e32", [2, 1, 1, 0]),
+    (174, "v_cmp_ge_u16_e32", [2, 1, 1, 0]),
+    (175, "v_cmp_t_u16_e32", [2, 1, 1, 0]),
+    (176, "v_cmpx_f_i16_e32", [2, 1, 1, 0]),
+    (177, "v_cmpx_lt_i16_e32", [2, 1, 1, 0]),
+    (178, "v_cmpx_eq_i16_e32", [2, 1, 1, 0]),
+    (179, "v_cmpx_le_i16_e32", [2, 1, 1, 0]),
+    (180, "v_cmpx_gt_i16_e32", [2, 1, 1, 0]),
+    (181, "v_cmpx_ne_i16_e32", [2, 1, 1, 0]),
+    (182, "v_cmpx_ge_i16_e32", [2, 1, 1, 0]),
+    (183, "v_cmpx_t_i16_e32", [2, 1, 1, 0]),
+    (184, "v_cmpx_f_u16_e32", [2, 1, 1, 0]),
+    (185, "v_cmpx_lt_u16_e32", [2, 1, 1, 0]),
+    (186, "v_cmpx_eq_u16_e32", [2, 1, 1, 0]),
+    (187, "v_cmpx_le_u16_e32", [2, 1, 1, 0]),
+    (188, "v_cmpx_gt_u16_e32", [2, 1, 1, 0]),
+    (189, "v_cmpx_ne_u16_e32", [2, 1, 1, 0]),
+    (190, "v_cmpx_ge_u16_e32", [2, 1, 1, 0]),
+    (191, "v_cmpx_t_u16_e32", [2, 1, 1, 0]),
+    (192, "v_cmp_f_i32_e32", [2, 1, 1, 0]),
+    (193, "v_cmp_lt_i32_e32", [2, 1, 1, 0]),
+    (194, "v_cmp_eq_i32_e32", [2, 1, 1, 0]),
+    (195, "v_cmp_le_i32_e32", [2, 1, 1, 0]),
+    (196, "v_cmp_gt_i32_e32", [2, 1, 1, 0]),
+    (197, "v_cmp_ne_i32_e32", [2, 1, 1, 0]),
+    (198, "v_cmp_ge_i32_e32", [2, 1, 1, 0]),
+    (199, "v_cmp_t_i32_e32", [2, 1, 1, 0]),
+    (200, "v_cmp_f_u32_e32", [2, 1, 1, 0]),
+    (201, "v_cmp_lt_u32_e32", [2, 1, 1, 0]),
+    (202, "v_cmp_eq_u32_e32", [2, 1, 1, 0]),
+    (203, "v_cmp_le_u32_e32", [2, 1, 1, 0]),
+    (204, "v_cmp_gt_u32_e32", [2, 1, 1, 0]),
+    (205, "v_cmp_ne_u32_e32", [2, 1, 1, 0]),
+    (206, "v_cmp_ge_u32_e32", [2, 1, 1, 0]),
+    (207, "v_cmp_t_u32_e32", [2, 1, 1, 0]),
+    (208, "v_cmpx_f_i32_e32", [2, 1, 1, 0]),
+    (209, "v_cmpx_lt_i32_e32", [2, 1, 1, 0]),
+    (210, "v_cmpx_eq_i32_e32", [2, 1, 1, 0]),
+    (211, "v_cmpx_le_i32_e32", [2, 1, 1, 0]),
+    (212, "v_cmpx_gt_i32_e32", [2, 1, 1, 0]),
+    (213, "v_cmpx_ne_i32_e32", [2, 1, 1, 0]),
+    (214, "v_cmpx_ge_i32_e32", [2, 1, 1, 0]),
+    (215, "v_cmpx_t_i32_e32", [2, 1, 1, 0]),
+    (216, "v_cmpx_f_u32_e32", [2, 1, 1, 0]),
+    (217, "v_cmpx_lt_u32_e32", [2, 1, 1, 0]),
+    (218, "v_cmpx_eq_u32_e32", [2, 1, 1, 0]),
+    (219, "v_cmpx_le_u32_e32", [2, 1, 1, 0]),
+    (220, "v_cmpx_gt_u32_e32", [2, 1, 1, 0]),
+    (221, "v_cmpx_ne_u32_e32", [2, 1, 1, 0]),
+    (222, "v_cmpx_ge_u32_e32", [2, 1, 1, 0]),
+    (223, "v_cmpx_t_u32_e32", [2, 1, 1, 0]),
+    (224, "v_cmp_f_i64_e32", [2, 2, 2, 0]),
+    (225, "v_cmp_lt_i64_e32", [2, 2, 2, 0]),
+    (226, "v_cmp_eq_i64_e32", [2, 2, 2, 0]),
+    (227, "v_cmp_le_i64_e32", [2, 2, 2, 0]),
+    (228, "v_cmp_gt_i64_e32", [2, 2, 2, 0]),
+    (229, "v_cmp_ne_i64_e32", [2, 2, 2, 0]),
+    (230, "v_cmp_ge_i64_e32", [2, 2, 2, 0]),
+    (231, "v_cmp_t_i64_e32", [2, 2, 2, 0]),
+    (232, "v_cmp_f_u64_e32", [2, 2, 2, 0]),
+    (233, "v_cmp_lt_u64_e32", [2, 2, 2, 0]),
+    (234, "v_cmp_eq_u64_e32", [2, 2, 2, 0]),
+    (235, "v_cmp_le_u64_e32", [2, 2, 2, 0]),
+    (236, "v_cmp_gt_u64_e32", [2, 2, 2, 0]),
+    (237, "v_cmp_ne_u64_e32", [2, 2, 2, 0]),
+    (238, "v_cmp_ge_u64_e32", [2, 2, 2, 0]),
+    (239, "v_cmp_t_u64_e32", [2, 2, 2, 0]),
+    (240, "v_cmpx_f_i64_e32", [2, 2, 2, 0]),
+    (241, "v_cmpx_lt_i64_e32", [2, 2, 2, 0]),
+    (242, "v_cmpx_eq_i64_e32", [2, 2, 2, 0]),
+    (243, "v_cmpx_le_i64_e32", [2, 2, 2, 0]),
+    (244, "v_cmpx_gt_i64_e32", [2, 2, 2, 0]),
+    (245, "v_cmpx_ne_i64_e32", [2, 2, 2, 0]),
+    (246, "v_cmpx_ge_i64_e32", [2, 2, 2, 0]),
+    (247, "v_cmpx_t_i64_e32", [2, 2, 2, 0]),
+    (248, "v_cmpx_f_u64_e32", [2, 2, 2, 0]),
+    (249, "v_cmpx_lt_u64_e32", [2, 2, 2, 0]),
+    (250, "v_cmpx_eq_u64_e32", [2, 2, 2, 0]),
+    (251, "v_cmpx_le_u64_e32", [2, 2, 2, 0]),
+    (252, "v_cmpx_gt_u64_e32", [2, 2, 2, 0]),
+    (253, "v_cmpx_ne_u64_e32", [2, 2, 2, 0]),
+    (254, "v_cmpx_ge_u64_e32", [2, 2, 2, 0]),
+    (255, "v_cmpx_t_u64_e32", [2, 2, 2, 0]),
+];
+
+pub const VOP3: &[Vop3Opcode] = &[
+    (16, "v_cmp_class_f32_e64", [2, 1, 1, 0], SRC_MODS),
+    (17, "v_cmpx_class_f32_e64", [2, 1, 1, 0], SRC_MODS),
+    (18, "v_cmp_class_f64_e64", [2, 2, 1, 0], SRC_MODS),
+    (19, "v_cmpx_class_f64_e64", [2, 2, 1, 0], SRC_MODS),
+    (20, "v_cmp_class_f16_e64", [2, 1, 1, 0], SRC_MODS),
+    (21, "v_cmpx_class_f16_e64", [2, 1, 1, 0], SRC_MODS),
+    (32, "v_cmp_f_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (33, "v_cmp_lt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (34, "v_cmp_eq_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (35, "v_cmp_le_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (36, "v_cmp_gt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (37, "v_cmp_lg_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (38, "v_cmp_ge_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (39, "v_cmp_o_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (40, "v_cmp_u_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (41, "v_cmp_nge_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (42, "v_cmp_nlg_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (43, "v_cmp_ngt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (44, "v_cmp_nle_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (45, "v_cmp_neq_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (46, "v_cmp_nlt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (47, "v_cmp_tru_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (48, "v_cmpx_f_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (49, "v_cmpx_lt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (50, "v_cmpx_eq_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (51, "v_cmpx_le_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (52, "v_cmpx_gt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (53, "v_cmpx_lg_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (54, "v_cmpx_ge_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (55, "v_cmpx_o_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (56, "v_cmpx_u_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (57, "v_cmpx_nge_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (58, "v_cmpx_nlg_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (59, "v_cmpx_ngt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (60, "v_cmpx_nle_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (61, "v_cmpx_neq_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (62, "v_cmpx_nlt_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (63, "v_cmpx_tru_f16_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (64, "v_cmp_f_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (65, "v_cmp_lt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (66, "v_cmp_eq_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (67, "v_cmp_le_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (68, "v_cmp_gt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (69, "v_cmp_lg_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (70, "v_cmp_ge_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (71, "v_cmp_o_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (72, "v_cmp_u_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (73, "v_cmp_nge_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (74, "v_cmp_nlg_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (75, "v_cmp_ngt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (76, "v_cmp_nle_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (77, "v_cmp_neq_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (78, "v_cmp_nlt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (79, "v_cmp_tru_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (80, "v_cmpx_f_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (81, "v_cmpx_lt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (82, "v_cmpx_eq_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (83, "v_cmpx_le_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (84, "v_cmpx_gt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (85, "v_cmpx_lg_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (86, "v_cmpx_ge_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (87, "v_cmpx_o_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (88, "v_cmpx_u_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (89, "v_cmpx_nge_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (90, "v_cmpx_nlg_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (91, "v_cmpx_ngt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (92, "v_cmpx_nle_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (93, "v_cmpx_neq_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (94, "v_cmpx_nlt_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (95, "v_cmpx_tru_f32_e64", [2, 1, 1, 0], SRC_MODS | CLAMP),
+    (96, "v_cmp_f_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (97, "v_cmp_lt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (98, "v_cmp_eq_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (99, "v_cmp_le_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (100, "v_cmp_gt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (101, "v_cmp_lg_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (102, "v_cmp_ge_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (103, "v_cmp_o_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (104, "v_cmp_u_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (105, "v_cmp_nge_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (106, "v_cmp_nlg_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (107, "v_cmp_ngt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (108, "v_cmp_nle_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (109, "v_cmp_neq_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (110, "v_cmp_nlt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (111, "v_cmp_tru_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (112, "v_cmpx_f_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (113, "v_cmpx_lt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (114, "v_cmpx_eq_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (115, "v_cmpx_le_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (116, "v_cmpx_gt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (117, "v_cmpx_lg_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (118, "v_cmpx_ge_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (119, "v_cmpx_o_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (120, "v_cmpx_u_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (121, "v_cmpx_nge_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (122, "v_cmpx_nlg_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (123, "v_cmpx_ngt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (124, "v_cmpx_nle_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (125, "v_cmpx_neq_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (126, "v_cmpx_nlt_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (127, "v_cmpx_tru_f64_e64", [2, 2, 2, 0], SRC_MODS | CLAMP),
+    (160, "v_cmp_f_i16_e64", [2, 1, 1, 0], 0),
+    (161, "v_cmp_lt_i16_e64", [2, 1, 1, 0], 0),
+    (162, "v_cmp_eq_i16_e64", [2, 1, 1, 0], 0),
+    (163, "v_cmp_le_i16_e64", [2, 1, 1, 0], 0),
+    (164, "v_cmp_gt_i16_e64", [2, 1, 1, 0], 0),
+    (165, "v_cmp_ne_i16_e64", [2, 1, 1, 0], 0),
+    (166, "v_cmp_ge_i16_e64", [2, 1, 1, 0], 0),
+    (167, "v_cmp_t_i16_e64", [2, 1, 1, 0], 0),
+    (168, "v_cmp_f_u16_e64", [2, 1, 1, 0], 0),
+    (169, "v_cmp_lt_u16_e64", [2, 1, 1, 0], 0),
+    (170, "v_cmp_eq_u16_e64", [2, 1, 1, 0], 0),
+    (171, "v_cmp_le_u16_e64", [2, 1, 1, 0], 0),
+    (172, "v_cmp_gt_u16_e64", [2, 1, 1, 0], 0),
+    (173, "v_cmp_ne_u16_e64", [2, 1, 1, 0], 0),
+    (174, "v_cmp_ge_u16_e64", [2, 1, 1, 0], 0),
+    (175, "v_cmp_t_u16_e64", [2, 1, 1, 0], 0),
+    (176, "v_cmpx_f_i16_e64", [2, 1, 1, 0], 0),
+    (177, "v_cmpx_lt_i16_e64", [2, 1, 1, 0], 0),
+    (178, "v_cmpx_eq_i16_e64", [2, 1, 1, 0], 0),
+    (179, "v_cmpx_le_i16_e64", [2, 1, 1, 0], 0),
+    (180, "v_cmpx_gt_i16_e64", [2, 1, 1, 0], 0),
+    (181, "v_cmpx_ne_i16_e64", [2, 1, 1, 0], 0),
+    (182, "v_cmpx_ge_i16_e64", [2, 1, 1, 0], 0),
+    (183, "v_cmpx_t_i16_e64", [2, 1, 1, 0], 0),
+    (184, "v_cmpx_f_u16_e64", [2, 1, 1, 0], 0),
+    (185, "v_cmpx_lt_u16_e64", [2, 1, 1, 0], 0),
+    (186, "v_cmpx_eq_u16_e64", [2, 1, 1, 0], 0),
+    (187, "v_cmpx_le_u16_e64", [2, 1, 1, 0], 0),
+    (188, "v_cmpx_gt_u16_e64", [2, 1, 1, 0], 0),
+    (189, "v_cmpx_ne_u16_e64", [2, 1, 1, 0], 0),
+    (190, "v_cmpx_ge_u16_e64", [2, 1, 1, 0], 0),
+    (191, "v_cmpx_t_u16_e64", [2, 1, 1, 0], 0),
+    (192, "v_cmp_f_i32_e64", [2, 1, 1, 0], 0),
+    (193, "v_cmp_lt_i32_e64", [2, 1, 1, 0], 0),
+    (194, "v_cmp_eq_i32_e64", [2, 1, 1, 0], 0),
+    (195, "v_cmp_le_i32_e64", [2, 1, 1, 0], 0),
+    (196, "v_cmp_gt_i32_e64", [2, 1, 1, 0], 0),
+    (197, "v_cmp_ne_i32_e64", [2, 1, 1, 0], 0),
+    (198, "v_cmp_ge_i32_e64", [2, 1, 1, 0], 0),
+    (199, "v_cmp_t_i32_e64", [2, 1, 1, 0], 0),
+    (200, "v_cmp_f_u32_e64", [2, 1, 1, 0], 0),
+    (201, "v_cmp_lt_u32_e64", [2, 1, 1, 0], 0),
+    (202, "v_cmp_eq_u32_e64", [2, 1, 1, 0], 0),
+    (203, "v_cmp_le_u32_e64", [2, 1, 1, 0], 0),
+    (204, "v_cmp_gt_u32_e64", [2, 1, 1, 0], 0),
+    (205, "v_cmp_ne_u32_e64", [2, 1, 1, 0], 0),
+    (206, "v_cmp_ge_u32_e64", [2, 1, 1, 0], 0),
+    (207, "v_cmp_t_u32_e64", [2, 1, 1, 0], 0),
+    (208, "v_cmpx_f_i32_e64", [2, 1, 1, 0], 0),
+    (209, "v_cmpx_lt_i32_e64", [2, 1, 1, 0], 0),
+    (210, "v_cmpx_eq_i32_e64", [2, 1, 1, 0], 0),
+    (211, "v_cmpx_le_i32_e64", [2, 1, 1, 0], 0),
+    (212, "v_cmpx_gt_i32_e64", [2, 1, 1, 0], 0),
+    (213, "v_cmpx_ne_i32_e64", [2, 1, 1, 0], 0),
+    (214, "v_cmpx_ge_i32_e64", [2, 1, 1, 0], 0),
+    (215, "v_cmpx_t_i32_e64", [2, 1, 1, 0], 0),
+    (216, "v_cmpx_f_u32_e64", [2, 1, 1, 0], 0),
+    (217, "v_cmpx_lt_u32_e64", [2, 1, 1, 0], 0),
+    (218, "v_cmpx_eq_u32_e64", [2, 1, 1, 0], 0),
+    (219, "v_cmpx_le_u32_e64", [2, 1, 1, 0], 0),
+    (220, "v_cmpx_gt_u32_e64", [2, 1, 1, 0], 0),
+    (221, "v_cmpx_ne_u32_e64", [2, 1, 1, 0], 0),
+    (222, "v_cmpx_ge_u32_e64", [2, 1, 1, 0], 0),
+    (223, "v_cmpx_t_u32_e64", [2, 1, 1, 0], 0),
+    (224, "v_cmp_f_i64_e64", [2, 2, 2, 0], 0),
+    (225, "v_cmp_lt_i64_e64", [2, 2, 2, 0], 0),
+    (226, "v_cmp_eq_i64_e64", [2, 2, 2, 0], 0),
+    (227, "v_cmp_le_i64_e64", [2, 2, 2, 0], 0),
+    (228, "v_cmp_gt_i64_e64", [2, 2, 2, 0], 0),
+    (229, "v_cmp_ne_i64_e64", [2, 2, 2, 0], 0),
+    (230, "v_cmp_ge_i64_e64", [2, 2, 2, 0], 0),
+    (231, "v_cmp_t_i64_e64", [2, 2, 2, 0], 0),
+    (232, "v_cmp_f_u64_e64", [2, 2, 2, 0], 0),
+    (233, "v_cmp_lt_u64_e64", [2, 2, 2, 0], 0),
+    (234, "v_cmp_eq_u64_e64", [2, 2, 2, 0], 0),
+    (235, "v_cmp_le_u64_e64", [2, 2, 2, 0], 0),
+    (236, "v_cmp_gt_u64_e64", [2, 2, 2, 0], 0),
+    (237, "v_cmp_ne_u64_e64", [2, 2, 2, 0], 0),
+    (238, "v_cmp_ge_u64_e64", [2, 2, 2, 0], 0),
+    (239, "v_cmp_t_u64_e64", [2, 2, 2, 0], 0),
+    (240, "v_cmpx_f_i64_e64", [2, 2, 2, 0], 0),
+    (241, "v_cmpx_lt_i64_e64", [2, 2, 2, 0], 0),
+    (242, "v_cmpx_eq_i64_e64", [2, 2, 2, 0], 0),
+    (243, "v_cmpx_le_i64_e64", [2, 2, 2, 0], 0),
+    (244, "v_cmpx_gt_i64_e64", [2, 2, 2, 0], 0),
+    (245, "v_cmpx_ne_i64_e64", [2, 2, 2, 0], 0),
+    (246, "v_cmpx_ge_i64_e64", [2, 2, 2, 0], 0),
+    (247, "v_cmpx_t_i64_e64", [2, 2, 2, 0], 0),
+    (248, "v_cmpx_f_u64_e64", [2, 2, 2, 0], 0),
+    (249, "v_cmpx_lt_u64_e64", [2, 2, 2, 0], 0),
+    (250, "v_cmpx_eq_u64_e64", [2, 2, 2, 0], 0),
+    (251, "v_cmpx_le_u64_e64", [2, 2, 2, 0], 0),
+    (252, "v_cmpx_gt_u64_e64", [2, 2, 2, 0], 0),
+    (253, "v_cmpx_ne_u64_e64", [2, 2, 2, 0], 0),
+    (254, "v_cmpx_ge_u64_e64", [2, 2, 2, 0], 0),
+    (255, "v_cmpx_t_u64_e64", [2, 2, 2, 0], 0),
+    (256, "v_cndmask_b32_e64", [1, 1, 1, 2], SRC_MODS),
+    (257, "v_add_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (258, "v_sub_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (259, "v_subrev_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (260, "v_mul_legacy_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (261, "v_mul_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (262, "v_mul_i32_i24_e64", [1, 1, 1, 0], CLAMP),
+    (263, "v_mul_hi_i32_i24_e64", [1, 1, 1, 0], 0),
+    (264, "v_mul_u32_u24_e64", [1, 1, 1, 0], CLAMP),
+    (265, "v_mul_hi_u32_u24_e64", [1, 1, 1, 0], 0),
+    (266, "v_min_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (267, "v_max_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (268, "v_min_i32_e64", [1, 1, 1, 0], 0),
+    (269, "v_max_i32_e64", [1, 1, 1, 0], 0),
+    (270, "v_min_u32_e64", [1, 1, 1, 0], 0),
+    (271, "v_max_u32_e64", [1, 1, 1, 0], 0),
+    (272, "v_lshrrev_b32_e64", [1, 1, 1, 0], 0),
+    (273, "v_ashrrev_i32_e64", [1, 1, 1, 0], 0),
+    (274, "v_lshlrev_b32_e64", [1, 1, 1, 0], 0),
+    (275, "v_and_b32_e64", [1, 1, 1, 0], 0),
+    (276, "v_or_b32_e64", [1, 1, 1, 0], 0),
+    (277, "v_xor_b32_e64", [1, 1, 1, 0], 0),
+    (278, "v_mac_f32_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (281, "v_add_co_u32_e64", [1, 1, 1, 0], CLAMP | SDST),
+    (282, "v_sub_co_u32_e64", [1, 1, 1, 0], CLAMP | SDST),
+    (283, "v_subrev_co_u32_e64", [1, 1, 1, 0], CLAMP | SDST),
+    (284, "v_addc_co_u32_e64", [1, 1, 1, 2], CLAMP | SDST),
+    (285, "v_subb_co_u32_e64", [1, 1, 1, 2], CLAMP | SDST),
+    (286, "v_subbrev_co_u32_e64", [1, 1, 1, 2], CLAMP | SDST),
+    (287, "v_add_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (288, "v_sub_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (289, "v_subrev_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (290, "v_mul_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (291, "v_mac_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (294, "v_add_u16_e64", [1, 1, 1, 0], CLAMP),
+    (295, "v_sub_u16_e64", [1, 1, 1, 0], CLAMP),
+    (296, "v_subrev_u16_e64", [1, 1, 1, 0], CLAMP),
+    (297, "v_mul_lo_u16_e64", [1, 1, 1, 0], 0),
+    (298, "v_lshlrev_b16_e64", [1, 1, 1, 0], 0),
+    (299, "v_lshrrev_b16_e64", [1, 1, 1, 0], 0),
+    (300, "v_ashrrev_i16_e64", [1, 1, 1, 0], 0),
+    (301, "v_max_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (302, "v_min_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (303, "v_max_u16_e64", [1, 1, 1, 0], 0),
+    (304, "v_max_i16_e64", [1, 1, 1, 0], 0),
+    (305, "v_min_u16_e64", [1, 1, 1, 0], 0),
+    (306, "v_min_i16_e64", [1, 1, 1, 0], 0),
+    (307, "v_ldexp_f16_e64", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (308, "v_add_u32_e64", [1, 1, 1, 0], CLAMP),
+    (309, "v_sub_u32_e64", [1, 1, 1, 0], CLAMP),
+    (310, "v_subrev_u32_e64", [1, 1, 1, 0], CLAMP),
+    (320, "v_nop", [0, 0, 0, 0], 0),
+    (321, "v_mov_b32_e64", [1, 1, 0, 0], 0),
+    (323, "v_cvt_i32_f64_e64", [1, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (324, "v_cvt_f64_i32_e64", [2, 1, 0, 0], CLAMP | OMOD),
+    (325, "v_cvt_f32_i32_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (326, "v_cvt_f32_u32_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (327, "v_cvt_u32_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (328, "v_cvt_i32_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (330, "v_cvt_f16_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (331, "v_cvt_f32_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (332, "v_cvt_rpi_i32_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP),
+    (333, "v_cvt_flr_i32_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP),
+    (334, "v_cvt_off_f32_i4_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (335, "v_cvt_f32_f64_e64", [1, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (336, "v_cvt_f64_f32_e64", [2, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (337, "v_cvt_f32_ubyte0_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (338, "v_cvt_f32_ubyte1_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (339, "v_cvt_f32_ubyte2_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (340, "v_cvt_f32_ubyte3_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (341, "v_cvt_u32_f64_e64", [1, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (342, "v_cvt_f64_u32_e64", [2, 1, 0, 0], CLAMP | OMOD),
+    (343, "v_trunc_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (344, "v_ceil_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (345, "v_rndne_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (346, "v_floor_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (347, "v_fract_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (348, "v_trunc_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (349, "v_ceil_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (350, "v_rndne_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (351, "v_floor_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (352, "v_exp_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (353, "v_log_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (354, "v_rcp_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (355, "v_rcp_iflag_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (356, "v_rsq_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (357, "v_rcp_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (358, "v_rsq_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (359, "v_sqrt_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (360, "v_sqrt_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (361, "v_sin_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (362, "v_cos_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (363, "v_not_b32_e64", [1, 1, 0, 0], 0),
+    (364, "v_bfrev_b32_e64", [1, 1, 0, 0], 0),
+    (365, "v_ffbh_u32_e64", [1, 1, 0, 0], 0),
+    (366, "v_ffbl_b32_e64", [1, 1, 0, 0], 0),
+    (367, "v_ffbh_i32_e64", [1, 1, 0, 0], 0),
+    (368, "v_frexp_exp_i32_f64_e64", [1, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (369, "v_frexp_mant_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (370, "v_fract_f64_e64", [2, 2, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (371, "v_frexp_exp_i32_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP),
+    (372, "v_frexp_mant_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (373, "v_clrexcp", [0, 0, 0, 0], 0),
+    (375, "v_screen_partition_4se_b32_e64", [1, 1, 0, 0], 0),
+    (377, "v_cvt_f16_u16_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (378, "v_cvt_f16_i16_e64", [1, 1, 0, 0], CLAMP | OMOD),
+    (379, "v_cvt_u16_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (380, "v_cvt_i16_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (381, "v_rcp_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (382, "v_sqrt_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (383, "v_rsq_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (384, "v_log_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (385, "v_exp_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (386, "v_frexp_mant_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (387, "v_frexp_exp_i16_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (388, "v_floor_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (389, "v_ceil_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (390, "v_trunc_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (391, "v_rndne_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (392, "v_fract_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (393, "v_sin_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (394, "v_cos_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (395, "v_exp_legacy_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (396, "v_log_legacy_f32_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (397, "v_cvt_norm_i16_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (398, "v_cvt_norm_u16_f16_e64", [1, 1, 0, 0], SRC_MODS | CLAMP | OMOD),
+    (399, "v_sat_pk_u8_i16_e64", [1, 1, 0, 0], 0),
+    (448, "v_mad_legacy_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (449, "v_mad_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (450, "v_mad_i32_i24", [1, 1, 1, 1], CLAMP),
+    (451, "v_mad_u32_u24", [1, 1, 1, 1], CLAMP),
+    (452, "v_cubeid_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (453, "v_cubesc_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (454, "v_cubetc_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (455, "v_cubema_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (456, "v_bfe_u32", [1, 1, 1, 1], 0),
+    (457, "v_bfe_i32", [1, 1, 1, 1], 0),
+    (458, "v_bfi_b32", [1, 1, 1, 1], 0),
+    (459, "v_fma_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (460, "v_fma_f64", [2, 2, 2, 2], SRC_MODS | CLAMP | OMOD),
+    (461, "v_lerp_u8", [1, 1, 1, 1], 0),
+    (462, "v_alignbit_b32", [1, 1, 1, 1], 0),
+    (463, "v_alignbyte_b32", [1, 1, 1, 1], 0),
+    (464, "v_min3_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (465, "v_min3_i32", [1, 1, 1, 1], 0),
+    (466, "v_min3_u32", [1, 1, 1, 1], 0),
+    (467, "v_max3_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (468, "v_max3_i32", [1, 1, 1, 1], 0),
+    (469, "v_max3_u32", [1, 1, 1, 1], 0),
+    (470, "v_med3_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (471, "v_med3_i32", [1, 1, 1, 1], 0),
+    (472, "v_med3_u32", [1, 1, 1, 1], 0),
+    (473, "v_sad_u8", [1, 1, 1, 1], CLAMP),
+    (474, "v_sad_hi_u8", [1, 1, 1, 1], CLAMP),
+    (475, "v_sad_u16", [1, 1, 1, 1], CLAMP),
+    (476, "v_sad_u32", [1, 1, 1, 1], CLAMP),
+    (477, "v_cvt_pk_u8_f32", [1, 1, 1, 1], SRC_MODS | CLAMP),
+    (478, "v_div_fixup_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (479, "v_div_fixup_f64", [2, 2, 2, 2], SRC_MODS | CLAMP | OMOD),
+    (480, "v_div_scale_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD | SDST),
+    (481, "v_div_scale_f64", [2, 2, 2, 2], SRC_MODS | CLAMP | OMOD | SDST),
+    (482, "v_div_fmas_f32", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (483, "v_div_fmas_f64", [2, 2, 2, 2], SRC_MODS | CLAMP | OMOD),
+    (484, "v_msad_u8", [1, 1, 1, 1], CLAMP),
+    (485, "v_qsad_pk_u16_u8", [2, 2, 1, 2], CLAMP),
+    (486, "v_mqsad_pk_u16_u8", [2, 2, 1, 2], CLAMP),
+    (487, "v_mqsad_u32_u8", [4, 2, 1, 4], CLAMP),
+    (488, "v_mad_u64_u32", [2, 1, 1, 2], CLAMP | SDST),
+    (489, "v_mad_i64_i32", [2, 1, 1, 2], CLAMP | SDST),
+    (490, "v_mad_legacy_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (491, "v_mad_legacy_u16", [1, 1, 1, 1], CLAMP),
+    (492, "v_mad_legacy_i16", [1, 1, 1, 1], CLAMP),
+    (493, "v_perm_b32", [1, 1, 1, 1], 0),
+    (494, "v_fma_legacy_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (495, "v_div_fixup_legacy_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OMOD),
+    (496, "v_cvt_pkaccum_u8_f32", [1, 1, 1, 0], SRC_MODS | CLAMP),
+    (497, "v_mad_u32_u16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (498, "v_mad_i32_i16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (499, "v_xad_u32", [1, 1, 1, 1], 0),
+    (500, "v_min3_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OP_SEL),
+    (501, "v_min3_i16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (502, "v_min3_u16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (503, "v_max3_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OP_SEL),
+    (504, "v_max3_i16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (505, "v_max3_u16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (506, "v_med3_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OP_SEL),
+    (507, "v_med3_i16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (508, "v_med3_u16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (509, "v_lshl_add_u32", [1, 1, 1, 1], 0),
+    (510, "v_add_lshl_u32", [1, 1, 1, 1], 0),
+    (511, "v_add3_u32", [1, 1, 1, 1], 0),
+    (512, "v_lshl_or_b32", [1, 1, 1, 1], 0),
+    (513, "v_and_or_b32", [1, 1, 1, 1], 0),
+    (514, "v_or3_b32", [1, 1, 1, 1], 0),
+    (515, "v_mad_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OP_SEL),
+    (516, "v_mad_u16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (517, "v_mad_i16", [1, 1, 1, 1], CLAMP | OP_SEL),
+    (518, "v_fma_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OP_SEL),
+    (519, "v_div_fixup_f16", [1, 1, 1, 1], SRC_MODS | CLAMP | OP_SEL),
+    (640, "v_add_f64", [2, 2, 2, 0], SRC_MODS | CLAMP | OMOD),
+    (641, "v_mul_f64", [2, 2, 2, 0], SRC_MODS | CLAMP | OMOD),
+    (642, "v_min_f64", [2, 2, 2, 0], SRC_MODS | CLAMP | OMOD),
+    (643, "v_max_f64", [2, 2, 2, 0], SRC_MODS | CLAMP | OMOD),
+    (644, "v_ldexp_f64", [2, 2, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (645, "v_mul_lo_u32", [1, 1, 1, 0], 0),
+    (646, "v_mul_hi_u32", [1, 1, 1, 0], 0),
+    (647, "v_mul_hi_i32", [1, 1, 1, 0], 0),
+    (648, "v_ldexp_f32", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (649, "v_readlane_b32", [1, 1, 1, 0], 0),
+    (650, "v_writelane_b32", [1, 1, 1, 0], 0),
+    (651, "v_bcnt_u32_b32", [1, 1, 1, 0], 0),
+    (652, "v_mbcnt_lo_u32_b32", [1, 1, 1, 0], 0),
+    (653, "v_mbcnt_hi_u32_b32", [1, 1, 1, 0], 0),
+    (655, "v_lshlrev_b64", [2, 1, 2, 0], 0),
+    (656, "v_lshrrev_b64", [2, 1, 2, 0], 0),
+    (657, "v_ashrrev_i64", [2, 1, 2, 0], 0),
+    (658, "v_trig_preop_f64", [2, 2, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (659, "v_bfm_b32", [1, 1, 1, 0], 0),
+    (660, "v_cvt_pknorm_i16_f32", [1, 1, 1, 0], SRC_MODS | CLAMP),
+    (661, "v_cvt_pknorm_u16_f32", [1, 1, 1, 0], SRC_MODS | CLAMP),
+    (662, "v_cvt_pkrtz_f16_f32", [1, 1, 1, 0], SRC_MODS | CLAMP | OMOD),
+    (663, "v_cvt_pk_u16_u32", [1, 1, 1, 0], 0),
+    (664, "v_cvt_pk_i16_i32", [1, 1, 1, 0], 0),
+    (665, "v_cvt_pknorm_i16_f16", [1, 1, 1, 0], SRC_MODS | CLAMP | OP_SEL),
+    (666, "v_cvt_pknorm_u16_f16", [1, 1, 1, 0], SRC_MODS | CLAMP | OP_SEL),
+    (668, "v_add_i32", [1, 1, 1, 0], CLAMP),
+    (669, "v_sub_i32", [1, 1, 1, 0], CLAMP),
+    (670, "v_add_i16", [1, 1, 1, 0], CLAMP | OP_SEL),
+    (671, "v_sub_i16", [1, 1, 1, 0], CLAMP | OP_SEL),
+    (672, "v_pack_b32_f16", [1, 1, 1, 0], SRC_MODS | CLAMP | OP_SEL),
+];
+
+pub const DS: &[Opcode] = &[
+    (0, "ds_add_u32", [0, 1, 1, 0]),
+    (1, "ds_sub_u32", [0, 1, 1, 0]),
+    (2, "ds_rsub_u32", [0, 1, 1, 0]),
+    (3, "ds_inc_u32", [0, 1, 1, 0]),
+    (4, "ds_dec_u32", [0, 1, 1, 0]),
+    (5, "ds_min_i32", [0, 1, 1, 0]),
+    (6, "ds_max_i32", [0, 1, 1, 0]),
+    (7, "ds_min_u32", [0, 1, 1, 0]),
+    (8, "ds_max_u32", [0, 1, 1, 0]),
+    (9, "ds_and_b32", [0, 1, 1, 0]),
+    (10, "ds_or_b32", [0, 1, 1, 0]),
+    (11, "ds_xor_b32", [0, 1, 1, 0]),
+    (12, "ds_mskor_b32", [0, 1, 1, 1]),
+    (13, "ds_write_b32", [0, 1, 1, 0]),
+    (14, "ds_write2_b32", [0, 1, 1, 1]),
+    (15, "ds_write2st64_b32", [0, 1, 1, 1]),
+    (16, "ds_cmpst_b32", [0, 1, 1, 1]),
+    (17, "ds_cmpst_f32", [0, 1, 1, 1]),
+    (18, "ds_min_f32", [0, 1, 1, 0]),
+    (19, "ds_max_f32", [0, 1, 1, 0]),
+    (21, "ds_add_f32", [0, 1, 1, 0]),
+    (29, "ds_write_addtid_b32", [0, 0, 1, 0]),
+    (30, "ds_write_b8", [0, 1, 1, 0]),
+    (31, "ds_write_b16", [0, 1, 1, 0]),
+    (32, "ds_add_rtn_u32", [1, 1, 1, 0]),
+    (33, "ds_sub_rtn_u32", [1, 1, 1, 0]),
+    (34, "ds_rsub_rtn_u32", [1, 1, 1, 0]),
+    (35, "ds_inc_rtn_u32", [1, 1, 1, 0]),
+    (36, "ds_dec_rtn_u32", [1, 1, 1, 0]),
+    (37, "ds_min_rtn_i32", [1, 1, 1, 0]),
+    (38, "ds_max_rtn_i32", [1, 1, 1, 0]),
+    (39, "ds_min_rtn_u32", [1, 1, 1, 0]),
+    (40, "ds_max_rtn_u32", [1, 1, 1, 0]),
+    (41, "ds_and_rtn_b32", [1, 1, 1, 0]),
+    (42, "ds_or_rtn_b32", [1, 1, 1, 0]),
+    (43, "ds_xor_rtn_b32", [1, 1, 1, 0]),
+    (44, "ds_mskor_rtn_b32", [1, 1, 1, 1]),
+    (45, "ds_wrxchg_rtn_b32", [1, 1, 1, 0]),
+    (46, "ds_wrxchg2_rtn_b32", [2, 1, 1, 1]),
+    (47, "ds_wrxchg2st64_rtn_b32", [2, 1, 1, 1]),
+    (48, "ds_cmpst_rtn_b32", [1, 1, 1, 1]),
+    (49, "ds_cmpst_rtn_f32", [1, 1, 1, 1]),
+    (50, "ds_min_rtn_f32", [1, 1, 1, 0]),
+    (51, "ds_max_rtn_f32", [1, 1, 1, 0]),
+    (52, "ds_wrap_rtn_b32", [1, 1, 1, 1]),
+    (53, "ds_add_rtn_f32", [1, 1, 1, 0]),
+    (54, "ds_read_b32", [1, 1, 0, 0]),
+    (55, "ds_read2_b32", [2, 1, 0, 0]),
+    (56, "ds_read2st64_b32", [2, 1, 0, 0]),
+    (57, "ds_read_i8", [1, 1, 0, 0]),
+    (58, "ds_read_u8", [1, 1, 0, 0]),
+    (59, "ds_read_i16", [1, 1, 0, 0]),
+    (60, "ds_read_u16", [1, 1, 0, 0]),
+    (61, "ds_swizzle_b32", [1, 1, 0, 0]),
+    (62, "ds_permute_b32", [1, 1, 1, 0]),
+    (63, "ds_bpermute_b32", [1, 1, 1, 0]),
+    (64, "ds_add_u64", [0, 1, 2, 0]),
+    (65, "ds_sub_u64", [0, 1, 2, 0]),
+    (66, "ds_rsub_u64", [0, 1, 2, 0]),
+    (67, "ds_inc_u64", [0, 1, 2, 0]),
+    (68, "ds_dec_u64", [0, 1, 2, 0]),
+    (69, "ds_min_i64", [0, 1, 2, 0]),
+    (70, "ds_max_i64", [0, 1, 2, 0]),
+    (71, "ds_min_u64", [0, 1, 2, 0]),
+    (72, "ds_max_u64", [0, 1, 2, 0]),
+    (73, "ds_and_b64", [0, 1, 2, 0]),
+    (74, "ds_or_b64", [0, 1, 2, 0]),
+    (75, "ds_xor_b64", [0, 1, 2, 0]),
+    (76, "ds_mskor_b64", [0, 1, 2, 2]),
+    (77, "ds_write_b64", [0, 1, 2, 0]),
+    (78, "ds_write2_b64", [0, 1, 2, 2]),
+    (79, "ds_write2st64_b64", [0, 1, 2, 2]),
+    (80, "ds_cmpst_b64", [0, 1, 2, 2]),
+    (81, "ds_cmpst_f64", [0, 1, 2, 2]),
+    (82, "ds_min_f64", [0, 1, 2, 0]),
+    (83, "ds_max_f64", [0, 1, 2, 0]),
+    (84, "ds_write_b8_d16_hi", [0, 1, 1, 0]),
+    (85, "ds_write_b16_d16_hi", [0, 1, 1, 0]),
+    (86, "ds_read_u8_d16", [1, 1, 0, 0]),
+    (87, "ds_read_u8_d16_hi", [1, 1, 0, 0]),
+    (88, "ds_read_i8_d16", [1, 1, 0, 0]),
+    (89, "ds_read_i8_d16_hi", [1, 1, 0, 0]),
+    (90, "ds_read_u16_d16", [1, 1, 0, 0]),
+    (91, "ds_read_u16_d16_hi", [1, 1, 0, 0]),
+    (96, "ds_add_rtn_u64", [2, 1, 2, 0]),
+    (97, "ds_sub_rtn_u64", [2, 1, 2, 0]),
+    (98, "ds_rsub_rtn_u64", [2, 1, 2, 0]),
+    (99, "ds_inc_rtn_u64", [2, 1, 2, 0]),
+    (100, "ds_dec_rtn_u64", [2, 1, 2, 0]),
+    (101, "ds_min_rtn_i64", [2, 1, 2, 0]),
+    (102, "ds_max_rtn_i64", [2, 1, 2, 0]),
+    (103, "ds_min_rtn_u64", [2, 1, 2, 0]),
+    (104, "ds_max_rtn_u64", [2, 1, 2, 0]),
+    (105, "ds_and_rtn_b64", [2, 1, 2, 0]),
+    (106, "ds_or_rtn_b64", [2, 1, 2, 0]),
+    (107, "ds_xor_rtn_b64", [2, 1, 2, 0]),
+    (108, "ds_mskor_rtn_b64", [2, 1, 2, 2]),
+    (109, "ds_wrxchg_rtn_b64", [2, 1, 2, 0]),
+    (110, "ds_wrxchg2_rtn_b64", [4, 1, 2, 2]),
+    (111, "ds_wrxchg2st64_rtn_b64", [4, 1, 2, 2]),
+    (112, "ds_cmpst_rtn_b64", [2, 1, 2, 2]),
+    (113, "ds_cmpst_rtn_f64", [2, 1, 2, 2]),
+    (114, "ds_min_rtn_f64", [2, 1, 2, 0]),
+    (115, "ds_max_rtn_f64", [2, 1, 2, 0]),
+    (118, "ds_read_b64", [2, 1, 0, 0]),
+    (119, "ds_read2_b64", [4, 1, 0, 0]),
+    (120, "ds_read2st64_b64", [4, 1, 0, 0]),
+    (126, "ds_condxchg32_rtn_b64", [2, 1, 2, 0]),
+    (128, "ds_add_src2_u32", [0, 1, 0, 0]),
+    (129, "ds_sub_src2_u32", [0, 1, 0, 0]),
+    (130, "ds_rsub_src2_u32", [0, 1, 0, 0]),
+    (131, "ds_inc_src2_u32", [0, 1, 0, 0]),
+    (132, "ds_dec_src2_u32", [0, 1, 0, 0]),
+    (133, "ds_min_src2_i32", [0, 1, 0, 0]),
+    (134, "ds_max_src2_i32", [0, 1, 0, 0]),
+    (135, "ds_min_src2_u32", [0, 1, 0, 0]),
+    (136, "ds_max_src2_u32", [0, 1, 0, 0]),
+    (137, "ds_and_src2_b32", [0, 1, 0, 0]),
+    (138, "ds_or_src2_b32", [0, 1, 0, 0]),
+    (139, "ds_xor_src2_b32", [0, 1, 0, 0]),
+    (141, "ds_write_src2_b32", [0, 1, 0, 0]),
+    (146, "ds_min_src2_f32", [0, 1, 0, 0]),
+    (147, "ds_max_src2_f32", [0, 1, 0, 0]),
+    (149, "ds_add_src2_f32", [0, 1, 0, 0]),
+    (182, "ds_read_addtid_b32", [1, 0, 0, 0]),
+    (189, "ds_consume", [1, 0, 0, 0]),
+    (190, "ds_append", [1, 0, 0, 0]),
+    (192, "ds_add_src2_u64", [0, 1, 0, 0]),
+    (193, "ds_sub_src2_u64", [0, 1, 0, 0]),
+    (194, "ds_rsub_src2_u64", [0, 1, 0, 0]),
+    (195, "ds_inc_src2_u64", [0, 1, 0, 0]),
+    (196, "ds_dec_src2_u64", [0, 1, 0, 0]),
+    (197, "ds_min_src2_i64", [0, 1, 0, 0]),
+    (198, "ds_max_src2_i64", [0, 1, 0, 0]),
+    (199, "ds_min_src2_u64", [0, 1, 0, 0]),
+    (200, "ds_max_src2_u64", [0, 1, 0, 0]),
+    (201, "ds_and_src2_b64", [0, 1, 0, 0]),
+    (202, "ds_or_src2_b64", [0, 1, 0, 0]),
+    (203, "ds_xor_src2_b64", [0, 1, 0, 0]),
+    (205, "ds_write_src2_b64", [0, 1, 0, 0]),
+    (210, "ds_min_src2_f64", [0, 1, 0, 0]),
+    (211, "ds_max_src2_f64", [0, 1, 0, 0]),
+    (222, "ds_write_b96", [0, 1, 3, 0]),
+    (223, "ds_write_b128", [0, 1, 4, 0]),
+    (254, "ds_read_b96", [3, 1, 0, 0]),
+    (255, "ds_read_b128", [4, 1, 0, 0]),
+];
+
+pub const FLAT: &[Opcode] = &[
+    (16, "flat_load_ubyte", [1, 2, 0, 0]),
+    (17, "flat_load_sbyte", [1, 2, 0, 0]),
+    (18, "flat_load_ushort", [1, 2, 0, 0]),
+    (19, "flat_load_sshort", [1, 2, 0, 0]),
+    (20, "flat_load_dword", [1, 2, 0, 0]),
+    (21, "flat_load_dwordx2", [2, 2, 0, 0]),
+    (22, "flat_load_dwordx3", [3, 2, 0, 0]),
+    (23, "flat_load_dwordx4", [4, 2, 0, 0]),
+    (24, "flat_store_byte", [0, 2, 1, 0]),
+    (25, "flat_store_byte_d16_hi", [0, 2, 1, 0]),
+    (26, "flat_store_short", [0, 2, 1, 0]),
+    (27, "flat_store_short_d16_hi", [0, 2, 1, 0]),
+    (28, "flat_store_dword", [0, 2, 1, 0]),
+    (29, "flat_store_dwordx2", [0, 2, 2, 0]),
+    (30, "flat_store_dwordx3", [0, 2, 3, 0]),
+    (31, "flat_store_dwordx4", [0, 2, 4, 0]),
+    (32, "flat_load_ubyte_d16", [1, 2, 0, 0]),
+    (33, "flat_load_ubyte_d16_hi", [1, 2, 0, 0]),
+    (34, "flat_load_sbyte_d16", [1, 2, 0, 0]),
+    (35, "flat_load_sbyte_d16_hi", [1, 2, 0, 0]),
+    (36, "flat_load_short_d16", [1, 2, 0, 0]),
+    (37, "flat_load_short_d16_hi", [1, 2, 0, 0]),
+    (64, "flat_atomic_swap", [0, 2, 1, 0]),
+    (65, "flat_atomic_cmpswap", [0, 2, 2, 0]),
+    (66, "flat_atomic_add", [0, 2, 1, 0]),
+    (67, "flat_atomic_sub", [0, 2, 1, 0]),
+    (68, "flat_atomic_smin", [0, 2, 1, 0]),
+    (69, "flat_atomic_umin", [0, 2, 1, 0]),
+    (70, "flat_atomic_smax", [0, 2, 1, 0]),
+    (71, "flat_atomic_umax", [0, 2, 1, 0]),
+    (72, "flat_atomic_and", [0, 2, 1, 0]),
+    (73, "flat_atomic_or", [0, 2, 1, 0]),
+    (74, "flat_atomic_xor", [0, 2, 1, 0]),
+    (75, "flat_atomic_inc", [0, 2, 1, 0]),
+    (76, "flat_atomic_dec", [0, 2, 1, 0]),
+    (96, "flat_atomic_swap_x2", [0, 2, 2, 0]),
+    (97, "flat_atomic_cmpswap_x2", [0, 2, 4, 0]),
+    (98, "flat_atomic_add_x2", [0, 2, 2, 0]),
+    (99, "flat_atomic_sub_x2", [0, 2, 2, 0]),
+    (100, "flat_atomic_smin_x2", [0, 2, 2, 0]),
+    (101, "flat_atomic_umin_x2", [0, 2, 2, 0]),
+    (102, "flat_atomic_smax_x2", [0, 2, 2, 0]),
+    (103, "flat_atomic_umax_x2", [0, 2, 2, 0]),
+    (104, "flat_atomic_and_x2", [0, 2, 2, 0]),
+    (105, "flat_atomic_or_x2", [0, 2, 2, 0]),
+    (106, "flat_atomic_xor_x2", [0, 2, 2, 0]),
+    (107, "flat_atomic_inc_x2", [0, 2, 2, 0]),
+    (108, "flat_atomic_dec_x2", [0, 2, 2, 0]),
+];
+
+pub const GLOBAL: &[Opcode] = &[
+    (16, "global_load_ubyte", [1, 2, 0, 0]),
+    (17, "global_load_sbyte", [1, 2, 0, 0]),
+    (18, "global_load_ushort", [1, 2, 0, 0]),
+    (19, "global_load_sshort", [1, 2, 0, 0]),
+    (20, "global_load_dword", [1, 2, 0, 0]),
+    (21, "global_load_dwordx2", [2, 2, 0, 0]),
+    (22, "global_load_dwordx3", [3, 2, 0, 0]),
+    (23, "global_load_dwordx4", [4, 2, 0, 0]),
+    (24, "global_store_byte", [0, 2, 1, 0]),
+    (25, "global_store_byte_d16_hi", [0, 2, 1, 0]),
+    (26, "global_store_short", [0, 2, 1, 0]),
+    (27, "global_store_short_d16_hi", [0, 2, 1, 0]),
+    (28, "global_store_dword", [0, 2, 1, 0]),
+    (29, "global_store_dwordx2", [0, 2, 2, 0]),
+    (30, "global_store_dwordx3", [0, 2, 3, 0]),
+    (31, "global_store_dwordx4", [0, 2, 4, 0]),
+    (32, "global_load_ubyte_d16", [1, 2, 0, 0]),
+    (33, "global_load_ubyte_d16_hi", [1, 2, 0, 0]),
+    (34, "global_load_sbyte_d16", [1, 2, 0, 0]),
+    (35, "global_load_sbyte_d16_hi", [1, 2, 0, 0]),
+    (36, "global_load_short_d16", [1, 2, 0, 0]),
+    (37, "global_load_short_d16_hi", [1, 2, 0, 0]),
+    (64, "global_atomic_swap", [0, 2, 1, 0]),
+    (65, "global_atomic_cmpswap", [0, 2, 2, 0]),
+    (66, "global_atomic_add", [0, 2, 1, 0]),
+    (67, "global_atomic_sub", [0, 2, 1, 0]),
+    (68, "global_atomic_smin", [0, 2, 1, 0]),
+    (69, "global_atomic_umin", [0, 2, 1, 0]),
+    (70, "global_atomic_smax", [0, 2, 1, 0]),
+    (71, "global_atomic_umax", [0, 2, 1, 0]),
+    (72, "global_atomic_and", [0, 2, 1, 0]),
+    (73, "global_atomic_or", [0, 2, 1, 0]),
+    (74, "global_atomic_xor", [0, 2, 1, 0]),
+    (75, "global_atomic_inc", [0, 2, 1, 0]),
+    (76, "global_atomic_dec", [0, 2, 1, 0]),
+    (96, "global_atomic_swap_x2", [0, 2, 2, 0]),
+    (97, "global_atomic_cmpswap_x2", [0, 2, 4, 0]),
+    (98, "global_atomic_add_x2", [0, 2, 2, 0]),
+    (99, "global_atomic_sub_x2", [0, 2, 2, 0]),
+    (100, "global_atomic_smin_x2", [0, 2, 2, 0]),
+    (101, "global_atomic_umin_x2", [0, 2, 2, 0]),
+    (102, "global_atomic_smax_x2", [0, 2, 2, 0]),
+    (103, "global_atomic_umax_x2", [0, 2, 2, 0]),
+    (104, "global_atomic_and_x2", [0, 2, 2, 0]),
+    (105, "global_atomic_or_x2", [0, 2, 2, 0]),
+    (106, "global_atomic_xor_x2", [0, 2, 2, 0]),
+    (107, "global_atomic_inc_x2", [0, 2, 2, 0]),
+    (108, "global_atomic_dec_x2", [0, 2, 2, 0]),
+];
+
+pub const SCRATCH: &[Opcode] = &[
+    (16, "scratch_load_ubyte", [1, 1, 0, 0]),
+    (17, "scratch_load_sbyte", [1, 1, 0, 0]),
+    (18, "scratch_load_ushort", [1, 1, 0, 0]),
+    (19, "scratch_load_sshort", [1, 1, 0, 0]),
+    (20, "scratch_load_dword", [1, 1, 0, 0]),
+    (21, "scratch_load_dwordx2", [2, 1, 0, 0]),
+    (22, "scratch_load_dwordx3", [3, 1, 0, 0]),
+    (23, "scratch_load_dwordx4", [4, 1, 0, 0]),
+    (24, "scratch_store_byte", [0, 1, 1, 0]),
+    (25, "scratch_store_byte_d16_hi", [0, 1, 1, 0]),
+    (26, "scratch_store_short", [0, 1, 1, 0]),
+    (27, "scratch_store_short_d16_hi", [0, 1, 1, 0]),
+    (28, "scratch_store_dword", [0, 1, 1, 0]),
+    (29, "scratch_store_dwordx2", [0, 1, 2, 0]),
+    (30, "scratch_store_dwordx3", [0, 1, 3, 0]),
+    (31, "scratch_store_dwordx4", [0, 1, 4, 0]),
+    (32, "scratch_load_ubyte_d16", [1, 1, 0, 0]),
+    (33, "scratch_load_ubyte_d16_hi", [1, 1, 0, 0]),
+    (34, "scratch_load_sbyte_d16", [1, 1, 0, 0]),
+    (35, "scratch_load_sbyte_d16_hi", [1, 1, 0, 0]),
+    (36, "scratch_load_short_d16", [1, 1, 0, 0]),
+    (37, "scratch_load_short_d16_hi", [1, 1, 0, 0]),
+];
+
+pub const MUBUF: &[Opcode] = &[
+    (0, "buffer_load_format_x", [1, 0, 0, 0]),
+    (1, "buffer_load_format_xy", [2, 0, 0, 0]),
+    (2, "buffer_load_format_xyz", [3, 0, 0, 0]),
+    (3, "buffer_load_format_xyzw", [4, 0, 0, 0]),
+    (4, "buffer_store_format_x", [1, 0, 0, 0]),
+    (5, "buffer_store_format_xy", [2, 0, 0, 0]),
+    (6, "buffer_store_format_xyz", [3, 0, 0, 0]),
+    (7, "buffer_store_format_xyzw", [4, 0, 0, 0]),
+    (8, "buffer_load_format_d16_x", [1, 0, 0, 0]),
+    (9, "buffer_load_format_d16_xy", [1, 0, 0, 0]),
+    (10, "buffer_load_format_d16_xyz", [2, 0, 0, 0]),
+    (11, "buffer_load_format_d16_xyzw", [2, 0, 0, 0]),
+    (12, "buffer_store_format_d16_x", [1, 0, 0, 0]),
+    (13, "buffer_store_format_d16_xy", [1, 0, 0, 0]),
+    (14, "buffer_store_format_d16_xyz", [2, 0, 0, 0]),
+    (15, "buffer_store_format_d16_xyzw", [2, 0, 0, 0]),
+    (16, "buffer_load_ubyte", [1, 0, 0, 0]),
+    (17, "buffer_load_sbyte", [1, 0, 0, 0]),
+    (18, "buffer_load_ushort", [1, 0, 0, 0]),
+    (19, "buffer_load_sshort", [1, 0, 0, 0]),
+    (20, "buffer_load_dword", [1, 0, 0, 0]),
+    (21, "buffer_load_dwordx2", [2, 0, 0, 0]),
+    (22, "buffer_load_dwordx3", [3, 0, 0, 0]),
+    (23, "buffer_load_dwordx4", [4, 0, 0, 0]),
+    (24, "buffer_store_byte", [1, 0, 0, 0]),
+    (25, "buffer_store_byte_d16_hi", [1, 0, 0, 0]),
+    (26, "buffer_store_short", [1, 0, 0, 0]),
+    (27, "buffer_store_short_d16_hi", [1, 0, 0, 0]),
+    (28, "buffer_store_dword", [1, 0, 0, 0]),
+    (29, "buffer_store_dwordx2", [2, 0, 0, 0]),
+    (30, "buffer_store_dwordx3", [3, 0, 0, 0]),
+    (31, "buffer_store_dwordx4", [4, 0, 0, 0]),
+    (32, "buffer_load_ubyte_d16", [1, 0, 0, 0]),
+    (33, "buffer_load_ubyte_d16_hi", [1, 0, 0, 0]),
+    (34, "buffer_load_sbyte_d16", [1, 0, 0, 0]),
+    (35, "buffer_load_sbyte_d16_hi", [1, 0, 0, 0]),
+    (36, "buffer_load_short_d16", [1, 0, 0, 0]),
+    (37, "buffer_load_short_d16_hi", [1, 0, 0, 0]),
+    (38, "buffer_load_format_d16_hi_x", [1, 0, 0, 0]),
+    (39, "buffer_store_format_d16_hi_x", [1, 0, 0, 0]),
+    (64, "buffer_atomic_swap", [1, 0, 0, 0]),
+    (65, "buffer_atomic_cmpswap", [2, 0, 0, 0]),
+    (66, "buffer_atomic_add", [1, 0, 0, 0]),
+    (67, "buffer_atomic_sub", [1, 0, 0, 0]),
+    (68, "buffer_atomic_smin", [1, 0, 0, 0]),
+    (69, "buffer_atomic_umin", [1, 0, 0, 0]),
+    (70, "buffer_atomic_smax", [1, 0, 0, 0]),
+    (71, "buffer_atomic_umax", [1, 0, 0, 0]),
+    (72, "buffer_atomic_and", [1, 0, 0, 0]),
+    (73, "buffer_atomic_or", [1, 0, 0, 0]),
+    (74, "buffer_atomic_xor", [1, 0, 0, 0]),
+    (75, "buffer_atomic_inc", [1, 0, 0, 0]),
+    (76, "buffer_atomic_dec", [1, 0, 0, 0]),
+    (96, "buffer_atomic_swap_x2", [2, 0, 0, 0]),
+    (97, "buffer_atomic_cmpswap_x2", [4, 0, 0, 0]),
+    (98, "buffer_atomic_add_x2", [2, 0, 0, 0]),
+    (99, "buffer_atomic_sub_x2", [2, 0, 0, 0]),
+    (100, "buffer_atomic_smin_x2", [2, 0, 0, 0]),
+    (101, "buffer_atomic_umin_x2", [2, 0, 0, 0]),
+    (102, "buffer_atomic_smax_x2", [2, 0, 0, 0]),
+    (103, "buffer_atomic_umax_x2", [2, 0, 0, 0]),
+    (104, "buffer_atomic_and_x2", [2, 0, 0, 0]),
+    (105, "buffer_atomic_or_x2", [2, 0, 0, 0]),
+    (106, "buffer_atomic_xor_x2", [2, 0, 0, 0]),
+    (107, "buffer_atomic_inc_x2", [2, 0, 0, 0]),
+    (108, "buffer_atomic_dec_x2", [2, 0, 0, 0]),
+];
+
+pub const MTBUF: &[Opcode] = &[
+    (0, "tbuffer_load_format_x", [1, 0, 0, 0]),
+    (1, "tbuffer_load_format_xy", [2, 0, 0, 0]),
+    (2, "tbuffer_load_format_xyz", [3, 0, 0, 0]),
+    (3, "tbuffer_load_format_xyzw", [4, 0, 0, 0]),
+    (4, "tbuffer_store_format_x", [1, 0, 0, 0]),
+    (5, "tbuffer_store_format_xy", [2, 0, 0, 0]),
+    (6, "tbuffer_store_format_xyz", [3, 0, 0, 0]),
+    (7, "tbuffer_store_format_xyzw", [4, 0, 0, 0]),
+    (8, "tbuffer_load_format_d16_x", [1, 0, 0, 0]),
+    (9, "tbuffer_load_format_d16_xy", [1, 0, 0, 0]),
+    (10, "tbuffer_load_format_d16_xyz", [2, 0, 0, 0]),
+    (11, "tbuffer_load_format_d16_xyzw", [2, 0, 0, 0]),
+    (12, "tbuffer_store_format_d16_x", [1, 0, 0, 0]),
+    (13, "tbuffer_store_format_d16_xy", [1, 0, 0, 0]),
+    (14, "tbuffer_store_format_d16_xyz", [2, 0, 0, 0]),
+    (15, "tbuffer_store_format_d16_xyzw", [2, 0, 0, 0]),
+];
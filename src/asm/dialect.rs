@@ -0,0 +1,230 @@
+/* Normalizes the instruction dialects of other generations to GFX9, which is what
+ * the semantics table and data flow analysis are written against:
+ *
+ *   GFX6-8   v_add_i32/v_add_u32 with a VCC carry-out become v_add_co_u32
+ *   GFX10    v_add_nc_u32 becomes v_add_u32, v_add_co_ci_u32 becomes v_addc_co_u32,
+ *            wave32 lane masks (vcc_lo, exec_lo) become VCC and EXEC
+ *   GFX906+  v_fmac_f32 d, a, b becomes v_fma_f32 d, a, b, d
+ *
+ * MFMA and dot product instructions have no GFX9 counterpart and are left as is.
+ *
+ * The built-in decoder only handles GFX9: code of other generations is disassembled
+ * with LLVM and needs a build with `--features llvm` (see asm::disassemble). */
+
+use super::{Instruction, Operand};
+use super::operand::SpecialReg;
+use super::kernel_code::KernelCode;
+use super::target::{Target, Generation};
+use super::decoder::opcodes;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialect {
+    pub generation: Generation,
+    /* Number of lanes in a wavefront, 32 or 64 */
+    pub wave_lanes: u32
+}
+
+impl Dialect {
+    pub fn new(target: &Target, kcode: &KernelCode) -> Dialect {
+        Dialect { generation: target.generation, wave_lanes: kcode.wave_lanes() }
+    }
+
+    pub fn normalize(&self, instrs: Vec<Instruction>) -> Vec<Instruction> {
+        instrs.into_iter().map(|instr| self.normalize_instruction(instr)).collect()
+    }
+
+    fn normalize_instruction(&self, instr: Instruction) -> Instruction {
+        let Instruction { opcode, mut operands, offset, size } = instr;
+
+        /* A wave32 lane mask occupies a single SGPR, so vcc_lo is all of VCC */
+        if self.wave_lanes == 32 {
+            for op in operands.iter_mut() {
+                match op {
+                    Operand::Special(SpecialReg::VCCLo) => *op = Operand::VCC,
+                    Operand::Special(SpecialReg::ExecLo) => *op = Operand::Special(SpecialReg::Exec),
+                    _ => ()
+                }
+            }
+        }
+
+        let (base, suffix) = split_suffix(&opcode);
+        let (base, suffix) = (base.to_owned(), suffix.to_owned());
+
+        let renamed = match self.generation {
+            Generation::GFX6 | Generation::GFX7 | Generation::GFX8 => carry_op_gfx8(&base, self.generation),
+            Generation::GFX10 | Generation::GFX11 => carry_op_gfx10(&base),
+            _ => None
+        };
+        let mut opcode = match renamed {
+            /* VOP3-only ops are printed without a suffix, which GFX9 needs when it also has a VOP2 form */
+            Some(base) if suffix.is_empty() && has_vop2_form(base) => format!("{}_e64", base),
+            Some(base) => format!("{}{}", base, suffix),
+            None => opcode
+        };
+
+        let fmac_acc = match operands.as_slice() {
+            [Operand::VReg(acc), _, _] if base == "v_fmac_f32" || base == "v_fmac_f16" => Some(*acc),
+            _ => None
+        };
+        if let Some(acc) = fmac_acc {
+            opcode = format!("v_fma_{}", &base[7..]);
+            operands.push(Operand::VReg(acc));
+        }
+
+        /* GFX10 splits off the vector store counter into s_waitcnt_vscnt null, <count> */
+        let waitcnt = match operands.as_slice() {
+            [Operand::Special(SpecialReg::Null), Operand::Waitcnt(waitcnt)] if opcode.starts_with("s_waitcnt_") => Some(*waitcnt),
+            _ => None
+        };
+        if let Some(waitcnt) = waitcnt {
+            opcode = "s_waitcnt".to_owned();
+            operands = vec![Operand::Waitcnt(waitcnt)];
+        }
+
+        let opcode = shrink_carry_op(opcode, &operands);
+        Instruction { opcode, operands, offset, size }
+    }
+}
+
+fn split_suffix(opcode: &str) -> (&str, &str) {
+    ["_e32", "_e64", "_sdwa", "_dpp"].iter()
+        .find(|suffix| opcode.ends_with(*suffix))
+        .map(|suffix| opcode.split_at(opcode.len() - suffix.len()))
+        .unwrap_or((opcode, ""))
+}
+
+fn has_vop2_form(base: &str) -> bool {
+    opcodes::VOP2.iter().any(|&(_, name, _)| split_suffix(name) == (base, "_e32"))
+}
+
+/* Before GFX9, the 32-bit add and subtract always wrote a carry-out */
+fn carry_op_gfx8(base: &str, generation: Generation) -> Option<&'static str> {
+    match (base, generation) {
+        ("v_add_i32", Generation::GFX6) | ("v_add_i32", Generation::GFX7) | ("v_add_u32", Generation::GFX8) => Some("v_add_co_u32"),
+        ("v_sub_i32", Generation::GFX6) | ("v_sub_i32", Generation::GFX7) | ("v_sub_u32", Generation::GFX8) => Some("v_sub_co_u32"),
+        ("v_subrev_i32", Generation::GFX6) | ("v_subrev_i32", Generation::GFX7) | ("v_subrev_u32", Generation::GFX8) => Some("v_subrev_co_u32"),
+        ("v_addc_u32", _) => Some("v_addc_co_u32"),
+        ("v_subb_u32", _) => Some("v_subb_co_u32"),
+        ("v_subbrev_u32", _) => Some("v_subbrev_co_u32"),
+        _ => None
+    }
+}
+
+fn carry_op_gfx10(base: &str) -> Option<&'static str> {
+    match base {
+        "v_add_nc_u32" => Some("v_add_u32"),
+        "v_sub_nc_u32" => Some("v_sub_u32"),
+        "v_subrev_nc_u32" => Some("v_subrev_u32"),
+        "v_add_nc_u16" => Some("v_add_u16"),
+        "v_sub_nc_u16" => Some("v_sub_u16"),
+        "v_add_nc_i32" => Some("v_add_i32"),
+        "v_sub_nc_i32" => Some("v_sub_i32"),
+        "v_add_nc_i16" => Some("v_add_i16"),
+        "v_sub_nc_i16" => Some("v_sub_i16"),
+        "v_add_co_ci_u32" => Some("v_addc_co_u32"),
+        "v_sub_co_ci_u32" => Some("v_subb_co_u32"),
+        "v_subrev_co_ci_u32" => Some("v_subbrev_co_u32"),
+        "v_add_co_u32" => Some("v_add_co_u32"),
+        "v_sub_co_u32" => Some("v_sub_co_u32"),
+        "v_subrev_co_u32" => Some("v_subrev_co_u32"),
+        _ => None
+    }
+}
+
+/* GFX10 only has a VOP3 encoding for v_add_co_u32, which is spelled as the GFX9
+ * VOP2 form when the carry goes through VCC and no modifiers are used */
+fn shrink_carry_op(opcode: String, ops: &[Operand]) -> String {
+    let carry_in = match opcode.as_str() {
+        "v_add_co_u32_e64" | "v_sub_co_u32_e64" | "v_subrev_co_u32_e64" => false,
+        "v_addc_co_u32_e64" | "v_subb_co_u32_e64" | "v_subbrev_co_u32_e64" => true,
+        _ => return opcode
+    };
+    let fits_vop2 = match ops {
+        [Operand::VReg(_), Operand::VCC, src0, Operand::VReg(_)] if !carry_in => plain_source(src0),
+        [Operand::VReg(_), Operand::VCC, src0, Operand::VReg(_), Operand::VCC] if carry_in => plain_source(src0),
+        _ => false
+    };
+    if fits_vop2 { format!("{}_e32", &opcode[..opcode.len() - 4]) } else { opcode }
+}
+
+fn plain_source(op: &Operand) -> bool {
+    matches!(op, Operand::SReg(_) | Operand::VReg(_) | Operand::Lit(_) | Operand::Float(_) | Operand::Special(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::operand::{self, OutputModifier};
+    use Operand::*;
+
+    fn normalize(generation: Generation, wave_lanes: u32, opcode: &str, operands: Vec<Operand>) -> Instruction {
+        let dialect = Dialect { generation, wave_lanes };
+        let instr = Instruction { opcode: opcode.to_owned(), operands, offset: 0, size: 8 };
+        dialect.normalize(vec![instr]).remove(0)
+    }
+
+    #[test]
+    fn carry_ops_before_gfx9() {
+        let ops = vec![VReg(0), VCC, VReg(1), VReg(2)];
+        let instr = normalize(Generation::GFX8, 64, "v_add_u32_e32", ops.clone());
+        assert_eq!((instr.opcode.as_str(), instr.operands), ("v_add_co_u32_e32", ops.clone()));
+        assert_eq!(normalize(Generation::GFX7, 64, "v_sub_i32_e64", ops.clone()).opcode, "v_sub_co_u32_e32");
+        assert_eq!(normalize(Generation::GFX6, 64, "v_subrev_i32_e32", ops.clone()).opcode, "v_subrev_co_u32_e32");
+        assert_eq!(normalize(Generation::GFX8, 64, "v_addc_u32_e32", vec![VReg(0), VCC, VReg(1), VReg(2), VCC]).opcode, "v_addc_co_u32_e32");
+        /* v_add_u32 only writes a carry before GFX9, and v_add_i32 is a VOP3 op on GFX8 */
+        assert_eq!(normalize(Generation::GFX9, 64, "v_add_u32_e32", ops.clone()).opcode, "v_add_u32_e32");
+        assert_eq!(normalize(Generation::GFX8, 64, "v_add_i32", ops).opcode, "v_add_i32");
+    }
+
+    #[test]
+    fn carry_ops_gfx10() {
+        let instr = normalize(Generation::GFX10, 64, "v_add_nc_u32_e32", vec![VReg(0), VReg(1), VReg(2)]);
+        assert_eq!(instr.opcode, "v_add_u32_e32");
+        /* VOP3-only encodings get the suffix of the GFX9 VOP3 form */
+        assert_eq!(normalize(Generation::GFX10, 64, "v_sub_nc_u32", vec![VReg(0), VReg(1), VReg(2)]).opcode, "v_sub_u32_e64");
+        assert_eq!(normalize(Generation::GFX10, 64, "v_add_nc_i32", vec![VReg(0), VReg(1), VReg(2)]).opcode, "v_add_i32");
+
+        /* The carry goes through VCC: the GFX9 VOP2 form */
+        let instr = normalize(Generation::GFX10, 64, "v_add_co_u32", vec![VReg(0), VCC, SReg(4), VReg(2)]);
+        assert_eq!(instr.opcode, "v_add_co_u32_e32");
+        let instr = normalize(Generation::GFX10, 64, "v_add_co_ci_u32_e32", vec![VReg(1), VCC, VReg(3), VReg(4), VCC]);
+        assert_eq!(instr.opcode, "v_addc_co_u32_e32");
+        assert_eq!(normalize(Generation::GFX10, 64, "v_sub_co_ci_u32", vec![VReg(1), VCC, VReg(3), VReg(4), VCC]).opcode, "v_subb_co_u32_e32");
+        /* A carry in SGPRs needs the VOP3 form */
+        let instr = normalize(Generation::GFX10, 64, "v_add_co_u32", vec![VReg(0), SRegs(4, 5), VReg(1), VReg(2)]);
+        assert_eq!(instr.opcode, "v_add_co_u32_e64");
+        let instr = normalize(Generation::GFX10, 64, "v_subrev_co_ci_u32", vec![VReg(1), SRegs(4, 5), VReg(3), VReg(4), SRegs(6, 7)]);
+        assert_eq!(instr.opcode, "v_subbrev_co_u32_e64");
+    }
+
+    #[test]
+    fn wave32_lane_masks() {
+        let ops = vec![Special(SpecialReg::ExecLo), Special(SpecialReg::ExecLo), Special(SpecialReg::VCCLo)];
+        let instr = normalize(Generation::GFX10, 32, "s_and_b32", ops.clone());
+        assert_eq!(instr.operands, vec![Special(SpecialReg::Exec), Special(SpecialReg::Exec), VCC]);
+        let instr = normalize(Generation::GFX10, 32, "v_cndmask_b32_e32", vec![VReg(0), VReg(1), VReg(2), Special(SpecialReg::VCCLo)]);
+        assert_eq!(instr.operands[3], VCC);
+        /* The low half of a wave64 mask is a separate register */
+        assert_eq!(normalize(Generation::GFX10, 64, "s_and_b32", ops.clone()).operands, ops);
+    }
+
+    #[test]
+    fn fmac_accumulates_into_destination() {
+        let instr = normalize(Generation::GFX9, 64, "v_fmac_f32_e32", vec![VReg(0), VReg(1), SReg(2)]);
+        assert_eq!((instr.opcode.as_str(), instr.operands), ("v_fma_f32", vec![VReg(0), VReg(1), SReg(2), VReg(0)]));
+        let instr = normalize(Generation::GFX10, 32, "v_fmac_f16_e64", vec![VReg(3), VReg(1), VReg(2)]);
+        assert_eq!((instr.opcode.as_str(), instr.operands), ("v_fma_f16", vec![VReg(3), VReg(1), VReg(2), VReg(3)]));
+        /* Output modifiers are not carried over */
+        let ops = vec![VReg(0), VReg(1), VReg(2), Omod(OutputModifier::Mul2)];
+        assert_eq!(normalize(Generation::GFX9, 64, "v_fmac_f32_e64", ops.clone()).operands, ops);
+    }
+
+    #[test]
+    fn vector_store_counter() {
+        let waitcnt = operand::Waitcnt { vscnt: Some(0), ..Default::default() };
+        let instr = normalize(Generation::GFX10, 32, "s_waitcnt_vscnt", vec![Special(SpecialReg::Null), Waitcnt(waitcnt)]);
+        assert_eq!((instr.opcode.as_str(), instr.operands), ("s_waitcnt", vec![Waitcnt(waitcnt)]));
+        let instr = normalize(Generation::GFX10, 32, "s_waitcnt_vscnt", vec![SReg(0), Waitcnt(waitcnt)]);
+        assert_eq!(instr.opcode, "s_waitcnt_vscnt");
+    }
+}
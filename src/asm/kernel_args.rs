@@ -3,8 +3,140 @@ pub struct KernelArg {
     pub name: String,
     pub size: u32,
     pub offset: u32,
+    pub align: u32,
     pub typename: Option<String>,
-    pub is_const: bool
+    pub is_const: bool,
+    pub value_kind: Option<ValueKind>,
+    pub value_type: Option<ValueType>,
+    pub address_space: Option<AddressSpace>,
+    pub access: Option<AccessQualifier>,
+    pub actual_access: Option<AccessQualifier>,
+    pub pointee_align: Option<u32>,
+    pub is_restrict: bool,
+    pub is_volatile: bool,
+    pub is_pipe: bool
+}
+
+/* Code object v2 metadata spells enumerations in CamelCase (GlobalBuffer),
+ * v3+ uses snake_case (global_buffer); both are matched in lowercase with
+ * underscores removed.
+ * https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-code-object-kernel-argument-metadata-map-table-v3 */
+fn normalize_enum(value: &str) -> String {
+    value.chars().filter(|&c| c != '_').flat_map(|c| c.to_lowercase()).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueKind {
+    ByValue,
+    GlobalBuffer,
+    DynamicSharedPointer,
+    Sampler,
+    Image,
+    Pipe,
+    Queue,
+    HiddenGlobalOffsetX,
+    HiddenGlobalOffsetY,
+    HiddenGlobalOffsetZ,
+    HiddenNone,
+    HiddenPrintfBuffer,
+    HiddenHostcallBuffer,
+    HiddenDefaultQueue,
+    HiddenCompletionAction,
+    HiddenMultigridSyncArg,
+    Other(String)
+}
+
+impl<'a> From<&'a str> for ValueKind {
+    fn from(value: &'a str) -> Self {
+        match normalize_enum(value).as_str() {
+            "byvalue" => ValueKind::ByValue,
+            "globalbuffer" => ValueKind::GlobalBuffer,
+            "dynamicsharedpointer" => ValueKind::DynamicSharedPointer,
+            "sampler" => ValueKind::Sampler,
+            "image" => ValueKind::Image,
+            "pipe" => ValueKind::Pipe,
+            "queue" => ValueKind::Queue,
+            "hiddenglobaloffsetx" => ValueKind::HiddenGlobalOffsetX,
+            "hiddenglobaloffsety" => ValueKind::HiddenGlobalOffsetY,
+            "hiddenglobaloffsetz" => ValueKind::HiddenGlobalOffsetZ,
+            "hiddennone" => ValueKind::HiddenNone,
+            "hiddenprintfbuffer" => ValueKind::HiddenPrintfBuffer,
+            "hiddenhostcallbuffer" => ValueKind::HiddenHostcallBuffer,
+            "hiddendefaultqueue" => ValueKind::HiddenDefaultQueue,
+            "hiddencompletionaction" => ValueKind::HiddenCompletionAction,
+            "hiddenmultigridsyncarg" => ValueKind::HiddenMultigridSyncArg,
+            _ => ValueKind::Other(value.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    Struct, I8, U8, I16, U16, F16, I32, U32, F32, I64, U64, F64
+}
+
+impl ValueType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match normalize_enum(value).as_str() {
+            "struct" => Some(ValueType::Struct),
+            "i8" => Some(ValueType::I8),
+            "u8" => Some(ValueType::U8),
+            "i16" => Some(ValueType::I16),
+            "u16" => Some(ValueType::U16),
+            "f16" => Some(ValueType::F16),
+            "i32" => Some(ValueType::I32),
+            "u32" => Some(ValueType::U32),
+            "f32" => Some(ValueType::F32),
+            "i64" => Some(ValueType::I64),
+            "u64" => Some(ValueType::U64),
+            "f64" => Some(ValueType::F64),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddressSpace {
+    Private,
+    Global,
+    Constant,
+    Local,
+    Generic,
+    Region
+}
+
+impl AddressSpace {
+    pub fn parse(value: &str) -> Option<Self> {
+        match normalize_enum(value).as_str() {
+            "private" => Some(AddressSpace::Private),
+            "global" => Some(AddressSpace::Global),
+            "constant" => Some(AddressSpace::Constant),
+            "local" => Some(AddressSpace::Local),
+            "generic" => Some(AddressSpace::Generic),
+            "region" => Some(AddressSpace::Region),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessQualifier {
+    Default,
+    ReadOnly,
+    WriteOnly,
+    ReadWrite
+}
+
+impl AccessQualifier {
+    pub fn parse(value: &str) -> Option<Self> {
+        match normalize_enum(value).as_str() {
+            "default" => Some(AccessQualifier::Default),
+            "readonly" => Some(AccessQualifier::ReadOnly),
+            "writeonly" => Some(AccessQualifier::WriteOnly),
+            "readwrite" => Some(AccessQualifier::ReadWrite),
+            _ => None
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -17,7 +149,7 @@ impl KernelArgs {
             .enumerate()
             .take_while(|&(_, &offset)| offset <= at_offset)
             .last()
-            .map(|(idx, offset)| (idx, ((at_offset - offset) / 2) as u8))
+            .map(|(idx, offset)| (idx, ((at_offset - offset) / 4) as u8))
     }
 
     pub fn iter(&self) -> std::slice::Iter<KernelArg> {
@@ -25,56 +157,15 @@ impl KernelArgs {
     }
 }
 
+impl From<Vec<KernelArg>> for KernelArgs {
+    fn from(args: Vec<KernelArg>) -> Self {
+        KernelArgs(args)
+    }
+}
+
 impl std::ops::Index<usize> for KernelArgs {
     type Output = KernelArg;
     fn index(&self, index: usize) -> &Self::Output {
         &self.0[index]
     }
 }
-
-pub fn extract_kernel_args(section_note: &Vec<u8>) -> KernelArgs {
-    let cl_note: Vec<u8> = section_note
-        .iter()
-        .skip_while(|&&c| c != '\n' as u8)
-        .filter(|&&c| c != 0)
-        .map(|c| c.to_owned()).collect();
-
-    let metadata = String::from_utf8_lossy(cl_note.as_slice());
-    let args_raw: Vec<Vec<String>> = metadata
-        .lines()
-        .skip_while(|l| !l.starts_with("    Args:")).skip(1)
-        .take_while(|l| !l.starts_with("    CodeProps:"))
-        .fold(Vec::new(), |mut args, l| {
-            if l.starts_with("      - ") {
-                args.push(vec![l[8..].replace(" ", "")])
-            }
-            else {
-                args.last_mut().unwrap().push(l.replace(" ", ""))
-            }
-            args
-        });
-
-    let mut offset = 0;
-
-    let args = args_raw
-        .into_iter()
-        .map(|args| {
-            let name = args.iter().find(|e| e.starts_with("Name")).map(|e| &e[5..])
-                .or(args.iter().find(|e| e.starts_with("ValueKind")).map(|e| &e[10..]))
-                .unwrap().to_string();
-            let size = args.iter().find(|e| e.starts_with("Size")).unwrap()[5..]
-                .parse::<u32>().unwrap();
-            let alignment = args.iter().find(|e| e.starts_with("Align")).unwrap()[6..]
-                .parse::<u32>().unwrap();
-            let typename = args.iter().find(|e| e.starts_with("TypeName")).map(|e| e[9..].replace("'", ""));
-            let is_const = args.iter().any(|e| e == "IsConst:true");
-
-            offset += offset % alignment;
-            offset += size;
-            KernelArg { name, size, offset: offset - size, typename, is_const }
-        })
-        .collect();
-
-    KernelArgs(args)
-}
-
@@ -39,17 +39,14 @@ use byteorder::{LE, ReadBytesExt};
 use std::convert::TryFrom;
 
 // https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-system-vgpr-work-item-id-enumeration-values-table
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum VGPRWorkItemId {
+    #[default]
     X,
     XY,
     XYZ
 }
 
-impl Default for VGPRWorkItemId {
-    fn default() -> Self { VGPRWorkItemId::X }
-}
-
 impl From<u8> for VGPRWorkItemId {
     fn from(val: u8) -> Self {
         match val {
@@ -61,18 +58,15 @@ impl From<u8> for VGPRWorkItemId {
 }
 
 // https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-floating-point-rounding-mode-enumeration-values-table
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum FPRoundMode {
+    #[default]
     NearEven,
     PlusInfinity,
     MinusInfinity,
     Zero
 }
 
-impl Default for FPRoundMode {
-    fn default() -> Self { FPRoundMode::NearEven }
-}
-
 impl From<u8> for FPRoundMode {
     fn from(val: u8) -> Self {
         match val {
@@ -85,18 +79,16 @@ impl From<u8> for FPRoundMode {
 }
 
 // https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-floating-point-denorm-mode-enumeration-values-table
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[allow(clippy::enum_variant_names)]
 pub enum FPDenormMode {
+    #[default]
     FlushSrcDst,
     FlushDst,
     FlushSrc,
     FlushNone
 }
 
-impl Default for FPDenormMode {
-    fn default() -> Self { FPDenormMode::FlushSrcDst }
-}
-
 impl From<u8> for FPDenormMode {
     fn from(val: u8) -> Self {
         match val {
@@ -136,7 +128,7 @@ pub struct PgmProperties {
     pub enable_vgpr_workitem_id: VGPRWorkItemId,
     pub enable_exception_address_watch: bool,
     pub enable_exception_memory: bool,
-    pub granulated_lds_size: u8,
+    pub granulated_lds_size: u16,
     pub enable_exception_ieee_754_fp_invalid_operation: bool,
     pub enable_exception_fp_denormal_source: bool,
     pub enable_exception_ieee_754_fp_division_by_zero: bool,
@@ -163,7 +155,10 @@ pub struct CodeProperties {
   pub is_ptr64: bool,
   pub is_dynamic_callstack: bool,
   pub is_debug_supported: bool,
-  pub is_xnack_supported: bool
+  pub is_xnack_supported: bool,
+  /* Only present in kernel descriptors (code object v3+) */
+  pub enable_wavefront_size32: bool,
+  pub uses_dynamic_stack: bool
 }
 
 macro_rules! extract_bitfields {
@@ -179,15 +174,35 @@ macro_rules! extract_bitfields {
     }
 }
 
+impl KernelCode {
+    /* wavefront_size holds log2 of the number of lanes, which is also the width of EXEC and VCC */
+    pub fn wave_lanes(&self) -> u32 {
+        match self.wavefront_size {
+            5 => 32,
+            _ => 64
+        }
+    }
+
+    /* The granulated size counts blocks of 128 dwords, it is the only one set by some assemblers */
+    pub fn lds_size(&self) -> u32 {
+        match self.workgroup_group_segment_byte_size {
+            0 => self.pgm_props.granulated_lds_size as u32 * 512,
+            size => size
+        }
+    }
+}
+
 macro_rules! get_bitfield {
     ($source:expr, from bit $shift:expr, width $width:expr) => {
-        ($source & (((1 << $width) - 1) << $shift)) >> $shift;
+        ($source & (((1 << $width) - 1) << $shift)) >> $shift
     }
 }
 
 impl <'a> TryFrom<&'a [u8]> for KernelCode {
     type Error = io::Error;
 
+    /* Fields are read in the order they are laid out */
+    #[allow(clippy::field_reassign_with_default)]
     fn try_from(buf: &[u8]) -> Result<Self, io::Error> {
         if buf.len() != 256 {
             return Err(io::Error::from(io::ErrorKind::InvalidData));
@@ -228,8 +243,21 @@ impl <'a> TryFrom<&'a [u8]> for KernelCode {
         crs.seek(SeekFrom::Current(12))?; /* 12 bytes reserved */
         obj.runtime_loader_kernel_symbol = crs.read_u64::<LE>()?;
     
+        obj.pgm_props = PgmProperties::from(compute_pgm_resource_registers);
+        obj.code_props = CodeProperties::from(code_properties);
+
+        Ok(obj)
+    }
+}
+
+/* COMPUTE_PGM_RSRC1 in the low dword, COMPUTE_PGM_RSRC2 in the high dword */
+impl From<u64> for PgmProperties {
+    #[allow(clippy::field_reassign_with_default)]
+    fn from(rsrc: u64) -> Self {
+        let mut props: PgmProperties = Default::default();
+
         extract_bitfields!(
-            [compute_pgm_resource_registers => obj.pgm_props] {
+            [rsrc => props] {
                 is_priv: bool at bit 20,
                 enable_dx10_clamp: bool at bit 21,
                 debug_mode: bool at bit 22,
@@ -238,7 +266,7 @@ impl <'a> TryFrom<&'a [u8]> for KernelCode {
                 cdbg_user: bool at bit 25,
                 fp16_ovfl: bool at bit 26,
 
-                enable_sgpr_private_segment_wavefront_offset: bool at bit 32 + 0,
+                enable_sgpr_private_segment_wavefront_offset: bool at bit 32,
                 enable_trap_handler: bool at bit 32 + 6,
                 enable_sgpr_workgroup_id_x: bool at bit 32 + 7,
                 enable_sgpr_workgroup_id_y: bool at bit 32 + 8,
@@ -256,28 +284,37 @@ impl <'a> TryFrom<&'a [u8]> for KernelCode {
             }
         );
         extract_bitfields!(
-            [compute_pgm_resource_registers => obj.pgm_props] {
+            [rsrc => props] {
                 granulated_workitem_vgpr_count: u8, from bit 0, width 6,
                 granulated_wavefront_sgpr_count: u8, from bit 6, width 4,
                 priority: u8, from bit 10, width 2,
 
                 user_sgpr_count: u8, from bit 32 + 1, width 5,
-                granulated_lds_size: u8, from bit 32 + 15, width 9
+                granulated_lds_size: u16, from bit 32 + 15, width 9
             }
         );
-        obj.pgm_props.float_round_mode_32 = FPRoundMode::from(
-            get_bitfield!(compute_pgm_resource_registers, from bit 12, width 2) as u8);
-        obj.pgm_props.float_round_mode_16_64 = FPRoundMode::from(
-            get_bitfield!(compute_pgm_resource_registers, from bit 14, width 2) as u8);
-        obj.pgm_props.float_denorm_mode_32 = FPDenormMode::from(
-            get_bitfield!(compute_pgm_resource_registers, from bit 16, width 2) as u8);
-        obj.pgm_props.float_denorm_mode_16_64 = FPDenormMode::from(
-            get_bitfield!(compute_pgm_resource_registers, from bit 18, width 2) as u8);
-        obj.pgm_props.enable_vgpr_workitem_id = VGPRWorkItemId::from(
-            get_bitfield!(compute_pgm_resource_registers, from bit 32 + 11, width 2) as u8);
+        props.float_round_mode_32 = FPRoundMode::from(
+            get_bitfield!(rsrc, from bit 12, width 2) as u8);
+        props.float_round_mode_16_64 = FPRoundMode::from(
+            get_bitfield!(rsrc, from bit 14, width 2) as u8);
+        props.float_denorm_mode_32 = FPDenormMode::from(
+            get_bitfield!(rsrc, from bit 16, width 2) as u8);
+        props.float_denorm_mode_16_64 = FPDenormMode::from(
+            get_bitfield!(rsrc, from bit 18, width 2) as u8);
+        props.enable_vgpr_workitem_id = VGPRWorkItemId::from(
+            get_bitfield!(rsrc, from bit 32 + 11, width 2) as u8);
+
+        props
+    }
+}
+
+impl From<u32> for CodeProperties {
+    #[allow(clippy::field_reassign_with_default)]
+    fn from(code_properties: u32) -> Self {
+        let mut props: CodeProperties = Default::default();
 
         extract_bitfields!(
-            [code_properties => obj.code_props] {
+            [code_properties => props] {
                 enable_sgpr_private_segment_buffer: bool at bit 0,
                 enable_sgpr_dispatch_ptr: bool at bit 1,
                 enable_sgpr_queue_ptr: bool at bit 2,
@@ -296,11 +333,11 @@ impl <'a> TryFrom<&'a [u8]> for KernelCode {
             }
         );
         extract_bitfields!(
-            [code_properties => obj.code_props] {
+            [code_properties => props] {
                 private_element_size: u8, from bit 17, width 2
             }
         );
 
-        Ok(obj)
+        props
     }
 }
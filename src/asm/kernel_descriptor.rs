@@ -0,0 +1,79 @@
+/* Kernel descriptor used by code object v3 and later in place of amd_kernel_code_t.
+ * https://llvm.org/docs/AMDGPUUsage.html#kernel-descriptor */
+#[derive(Default, Debug)]
+pub struct KernelDescriptor {
+    pub group_segment_fixed_size: u32,
+    pub private_segment_fixed_size: u32,
+    pub kernarg_size: u32,
+    pub kernel_code_entry_byte_offset: i64,
+    pub compute_pgm_rsrc3: u32,
+    pub compute_pgm_rsrc1: u32,
+    pub compute_pgm_rsrc2: u32,
+    pub kernel_code_properties: u16,
+
+    pub pgm_props: PgmProperties,
+    pub code_props: CodeProperties
+}
+
+use std::io;
+use std::io::{Cursor, Seek, SeekFrom};
+use byteorder::{LE, ReadBytesExt};
+use std::convert::TryFrom;
+
+use super::kernel_code::{KernelCode, PgmProperties, CodeProperties};
+
+pub const KERNEL_DESCRIPTOR_SIZE: usize = 64;
+
+impl <'a> TryFrom<&'a [u8]> for KernelDescriptor {
+    type Error = io::Error;
+
+    /* Mirrors the amdhsa_kernel_descriptor_t layout field by field */
+    #[allow(clippy::field_reassign_with_default)]
+    fn try_from(buf: &[u8]) -> Result<Self, io::Error> {
+        if buf.len() != KERNEL_DESCRIPTOR_SIZE {
+            return Err(io::Error::from(io::ErrorKind::InvalidData));
+        }
+        let mut crs = Cursor::new(buf);
+        let mut obj: KernelDescriptor = Default::default();
+
+        obj.group_segment_fixed_size = crs.read_u32::<LE>()?;
+        obj.private_segment_fixed_size = crs.read_u32::<LE>()?;
+        obj.kernarg_size = crs.read_u32::<LE>()?;
+        crs.seek(SeekFrom::Current(4))?; /* 4 bytes reserved */
+        obj.kernel_code_entry_byte_offset = crs.read_i64::<LE>()?;
+        crs.seek(SeekFrom::Current(20))?; /* 20 bytes reserved */
+        obj.compute_pgm_rsrc3 = crs.read_u32::<LE>()?;
+        obj.compute_pgm_rsrc1 = crs.read_u32::<LE>()?;
+        obj.compute_pgm_rsrc2 = crs.read_u32::<LE>()?;
+        obj.kernel_code_properties = crs.read_u16::<LE>()?;
+
+        obj.pgm_props = PgmProperties::from(
+            ((obj.compute_pgm_rsrc2 as u64) << 32) | obj.compute_pgm_rsrc1 as u64);
+
+        /* The first seven bits match amd_kernel_code_t's code_properties; the grid
+         * workgroup count SGPRs are no longer available */
+        obj.code_props = CodeProperties::from((obj.kernel_code_properties & 0x7f) as u32);
+        obj.code_props.enable_wavefront_size32 = (obj.kernel_code_properties & (1 << 10)) != 0;
+        obj.code_props.uses_dynamic_stack = (obj.kernel_code_properties & (1 << 11)) != 0;
+
+        Ok(obj)
+    }
+}
+
+/* Lowers the descriptor into the subset of amd_kernel_code_t fields it shares,
+ * so that later stages do not need to distinguish between the two formats */
+impl From<KernelDescriptor> for KernelCode {
+    fn from(kd: KernelDescriptor) -> Self {
+        KernelCode {
+            kernel_code_entry_byte_offset: kd.kernel_code_entry_byte_offset,
+            workitem_private_segment_byte_size: kd.private_segment_fixed_size,
+            workgroup_group_segment_byte_size: kd.group_segment_fixed_size,
+            kernarg_segment_byte_size: kd.kernarg_size as u64,
+            /* log2 of the number of lanes */
+            wavefront_size: if kd.code_props.enable_wavefront_size32 { 5 } else { 6 },
+            pgm_props: kd.pgm_props,
+            code_props: kd.code_props,
+            ..Default::default()
+        }
+    }
+}
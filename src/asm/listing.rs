@@ -0,0 +1,239 @@
+/* Parses instructions in the syntax printed by the LLVM disassembler, which is what
+ * the LLVM backend returns and what the decoder fixtures are written in */
+
+use super::Operand;
+use super::operand::{SpecialReg, OutputModifier, SdwaOperand, SdwaSel, DstUnused, DppCtrl, Waitcnt, SendMsg, Hwreg};
+
+pub fn parse_instruction(instr: &str) -> (String, Vec<Operand>) {
+    /* Drop trailing comments, e.g. register alignment warnings */
+    let instr = instr.split(';').next().unwrap_or(instr).trim();
+    let instr_ops: Vec<&str> = instr.splitn(2, ' ').collect();
+    let instr_name = instr_ops[0].to_owned();
+
+    if instr_ops.len() == 1 {
+        (instr_name, Vec::new())
+    }
+    else if instr_name == "s_waitcnt" {
+        let waitcnt = parse_waitcnt(instr_ops[1]).map(Operand::Waitcnt)
+            .unwrap_or_else(|| Operand::Keyseq(instr_ops[1].to_owned()));
+        (instr_name, vec![waitcnt])
+    }
+    else {
+        let mut operands: Vec<Operand> = split_operands(instr_ops[1]).into_iter().map(Operand::from).collect();
+        /* GFX10 sets individual counters with s_waitcnt_vscnt null, 0x0 and friends */
+        if instr_name.starts_with("s_waitcnt_") && operands.len() == 2 {
+            let count = match operands[1] { Operand::Lit(count) => Some(count as u8), _ => None };
+            let waitcnt = count.and_then(|count| parse_waitcnt(&format!("{}({})", &instr_name[10..], count)));
+            if let Some(waitcnt) = waitcnt {
+                operands[1] = Operand::Waitcnt(waitcnt);
+            }
+        }
+        (instr_name, operands)
+    }
+}
+
+/* vmcnt(0) lgkmcnt(0), with counters separated by spaces, commas or ampersands */
+fn parse_waitcnt(counters: &str) -> Option<Waitcnt> {
+    let mut waitcnt = Waitcnt::default();
+    for counter in counters.split(|c| c == ' ' || c == ',' || c == '&').filter(|c| !c.is_empty()) {
+        let open = counter.find('(')?;
+        if !counter.ends_with(')') {
+            return None;
+        }
+        let count = Some(counter[open + 1..counter.len() - 1].parse().ok()?);
+        match &counter[..open] {
+            "vmcnt" => waitcnt.vmcnt = count,
+            "expcnt" => waitcnt.expcnt = count,
+            "lgkmcnt" => waitcnt.lgkmcnt = count,
+            "vscnt" => waitcnt.vscnt = count,
+            _ => return None
+        }
+    }
+    Some(waitcnt)
+}
+
+/* Splits the arguments of sendmsg(MSG_GS, GS_OP_EMIT, 0) or hwreg(HW_REG_MODE, 0, 1) */
+fn call_args<'a>(operand: &'a str, func: &str) -> Option<Vec<&'a str>> {
+    if operand.len() > func.len() + 2 && operand.starts_with(func) && operand[func.len()..].starts_with('(') && operand.ends_with(')') {
+        Some(operand[func.len() + 1..operand.len() - 1].split(',').map(|arg| arg.trim()).collect())
+    }
+    else {
+        None
+    }
+}
+
+/* Splits on commas and spaces outside of parentheses and brackets, keeping
+ * operands such as hwreg(HW_REG_MODE, 0, 1) in one piece */
+fn split_operands(ops: &str) -> Vec<&str> {
+    let mut operands: Vec<&str> = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (idx, c) in ops.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' | ' ' if depth == 0 => {
+                if start < idx { operands.push(&ops[start..idx]); }
+                start = idx + 1;
+            },
+            _ => ()
+        }
+    }
+    if start < ops.len() {
+        operands.push(&ops[start..]);
+    }
+    operands
+}
+
+impl <'a> From<&'a str> for Operand {
+    fn from(operand: &'a str) -> Self {
+        let keyseq = || Operand::Keyseq(operand.to_string());
+        let inner = |prefix: usize, suffix: usize| Box::new(Operand::from(&operand[prefix..operand.len() - suffix]));
+
+        if let Some(flag) = flag(operand) {
+            return flag;
+        }
+        if let Some(reg) = SpecialReg::from_name(operand) {
+            return Operand::Special(reg);
+        }
+        /* Source modifiers */
+        if operand.len() > 2 && operand.starts_with('|') && operand.ends_with('|') {
+            return Operand::Abs(inner(1, 1));
+        }
+        if operand.starts_with("neg(") && operand.ends_with(')') {
+            return Operand::Neg(inner(4, 1));
+        }
+        if operand.starts_with("sext(") && operand.ends_with(')') {
+            return Operand::Sext(inner(5, 1));
+        }
+        if let Some(args) = call_args(operand, "sendmsg") {
+            return SendMsg::from_names(args[0], args.get(1).cloned(), args.get(2).cloned()).map(Operand::SendMsg).unwrap_or_else(keyseq);
+        }
+        if let Some(args) = call_args(operand, "hwreg") {
+            return Hwreg::from_names(args[0], args.get(1).cloned(), args.get(2).cloned()).map(Operand::Hwreg).unwrap_or_else(keyseq);
+        }
+        if let Some(idx) = operand.find(':').filter(|&idx| !operand[..idx].contains('[')) {
+            return modifier(&operand[..idx], &operand[idx + 1..]).unwrap_or_else(keyseq);
+        }
+        /* Hexadecimal literal, printed for 32-bit values that aren't inline constants */
+        if operand.len() > 2 && &operand[0..2] == "0x" {
+            return u32::from_str_radix(&operand[2..], 16).map(|v| Operand::Lit(v as i32)).unwrap_or_else(|_| keyseq());
+        }
+
+        let prefix_char = operand.chars().next().unwrap();
+
+        /* Integer and floating-point inline constants, or a negated operand (-v1) */
+        if prefix_char.is_ascii_digit() || prefix_char == '-' {
+            if let Ok(value) = operand.parse::<i32>() {
+                return Operand::Lit(value);
+            }
+            if let Ok(value) = operand.parse::<f32>() {
+                return Operand::Float(value);
+            }
+            if prefix_char == '-' && operand.len() > 1 {
+                return Operand::Neg(inner(1, 0));
+            }
+            return keyseq();
+        }
+        if let Some(range) = operand.strip_prefix("ttmp") {
+            return match register_range(range) {
+                Some((from, to)) if from == to => Operand::TReg(from),
+                Some((from, to)) => Operand::TRegs(from, to),
+                None => keyseq()
+            };
+        }
+        match (prefix_char, register_range(&operand[1..])) {
+            ('s', Some((from, to))) if from == to => Operand::SReg(from),
+            ('s', Some((from, to))) => Operand::SRegs(from, to),
+            ('v', Some((from, to))) if from == to => Operand::VReg(from),
+            ('v', Some((from, to))) => Operand::VRegs(from, to),
+            _ => keyseq()
+        }
+    }
+}
+
+/* Parses a single register index (4) or a range ([2:3]) */
+fn register_range(range: &str) -> Option<(usize, usize)> {
+    if let Ok(idx) = range.parse::<usize>() {
+        return Some((idx, idx));
+    }
+    if range.len() > 2 && range.starts_with('[') && range.ends_with(']') {
+        let sides: Vec<&str> = range[1..range.len() - 1].split(':').collect();
+        if let [from, to] = sides.as_slice() {
+            return Some((from.parse().ok()?, to.parse().ok()?));
+        }
+    }
+    None
+}
+
+fn flag(operand: &str) -> Option<Operand> {
+    Some(match operand {
+        "vcc" => Operand::VCC,
+        "off" => Operand::Off,
+        "clamp" => Operand::Clamp,
+        "glc" => Operand::Glc,
+        "slc" => Operand::Slc,
+        "lds" => Operand::Lds,
+        "tfe" => Operand::Tfe,
+        "gds" => Operand::Gds,
+        "idxen" => Operand::Idxen,
+        "offen" => Operand::Offen,
+        "row_mirror" => Operand::Dpp(DppCtrl::RowMirror),
+        "row_half_mirror" => Operand::Dpp(DppCtrl::RowHalfMirror),
+        _ => return None
+    })
+}
+
+/* Operands written as key:value, e.g. offset:16, dst_sel:WORD_1 or row_shl:1 */
+fn modifier(key: &str, value: &str) -> Option<Operand> {
+    let int = || -> Option<u8> {
+        match value.len() > 2 && &value[0..2] == "0x" {
+            true => u8::from_str_radix(&value[2..], 16).ok(),
+            false => value.parse().ok()
+        }
+    };
+    let list = || -> Option<Vec<u8>> {
+        if !value.starts_with('[') || !value.ends_with(']') { return None; }
+        value[1..value.len() - 1].split(',').map(|v| v.parse().ok()).collect()
+    };
+    let sdwa_sel = || match value {
+        "DWORD" => Some(SdwaSel::Dword),
+        _ if value.starts_with("BYTE_") => value[5..].parse().ok().map(SdwaSel::Byte),
+        _ if value.starts_with("WORD_") => value[5..].parse().ok().map(SdwaSel::Word),
+        _ => None
+    };
+
+    Some(match (key, value) {
+        ("offset", _) => Operand::Offset(value.parse().ok()?),
+        ("offset0", _) => Operand::Offset0(int()?),
+        ("offset1", _) => Operand::Offset1(int()?),
+        ("mul", "2") => Operand::Omod(OutputModifier::Mul2),
+        ("mul", "4") => Operand::Omod(OutputModifier::Mul4),
+        ("div", "2") => Operand::Omod(OutputModifier::Div2),
+        ("op_sel", _) => Operand::OpSel(list()?),
+        ("dst_sel", _) => Operand::SdwaSel(SdwaOperand::Dst, sdwa_sel()?),
+        ("src0_sel", _) => Operand::SdwaSel(SdwaOperand::Src0, sdwa_sel()?),
+        ("src1_sel", _) => Operand::SdwaSel(SdwaOperand::Src1, sdwa_sel()?),
+        ("dst_unused", "UNUSED_PAD") => Operand::DstUnused(DstUnused::Pad),
+        ("dst_unused", "UNUSED_SEXT") => Operand::DstUnused(DstUnused::Sext),
+        ("dst_unused", "UNUSED_PRESERVE") => Operand::DstUnused(DstUnused::Preserve),
+        ("quad_perm", _) => match list()?.as_slice() {
+            &[a, b, c, d] => Operand::Dpp(DppCtrl::QuadPerm([a, b, c, d])),
+            _ => return None
+        },
+        ("row_shl", _) => Operand::Dpp(DppCtrl::RowShl(int()?)),
+        ("row_shr", _) => Operand::Dpp(DppCtrl::RowShr(int()?)),
+        ("row_ror", _) => Operand::Dpp(DppCtrl::RowRor(int()?)),
+        ("wave_shl", "1") => Operand::Dpp(DppCtrl::WaveShl),
+        ("wave_rol", "1") => Operand::Dpp(DppCtrl::WaveRol),
+        ("wave_shr", "1") => Operand::Dpp(DppCtrl::WaveShr),
+        ("wave_ror", "1") => Operand::Dpp(DppCtrl::WaveRor),
+        ("row_bcast", "15") => Operand::Dpp(DppCtrl::RowBcast15),
+        ("row_bcast", "31") => Operand::Dpp(DppCtrl::RowBcast31),
+        ("row_mask", _) => Operand::RowMask(int()?),
+        ("bank_mask", _) => Operand::BankMask(int()?),
+        ("bound_ctrl", _) => Operand::BoundCtrl,
+        _ => return None
+    })
+}
@@ -0,0 +1,64 @@
+/* Disassembly through the LLVM AMDGPU backend, used for targets not covered by
+ * the built-in decoder and to cross-check it */
+
+use std::io;
+use std::ffi::CString;
+use llvm_sys::disassembler::{LLVMCreateDisasmCPU, LLVMDisasmInstruction, LLVMDisasmDispose};
+
+use super::Instruction;
+use super::listing::parse_instruction;
+use super::target::Target;
+
+pub fn disassemble_instructions(instructions_raw: &[u8], target: &Target) -> io::Result<Vec<Instruction>> {
+    unsafe {
+        llvm_sys::target::LLVM_InitializeAllTargetInfos();
+        llvm_sys::target::LLVM_InitializeAllTargetMCs();
+        llvm_sys::target::LLVM_InitializeAllDisassemblers();
+    }
+
+    let asmctx = unsafe {
+        LLVMCreateDisasmCPU(
+            CString::new("amdgcn--amdhsa")?.as_ptr(),
+            CString::new(target.processor.as_str())?.as_ptr(),
+            std::ptr::null_mut(), 0, None, None)
+    };
+    if asmctx.is_null() {
+        return Err(io::Error::new(io::ErrorKind::Other, format!("unable to create an LLVM disassembler for {}", target)));
+    }
+
+    let mut instructions_raw = instructions_raw.to_owned();
+    let mut instructions: Vec<Instruction> = Vec::new();
+
+    let mut pos = 0;
+    let mut instr_buf = [0u8; 256];
+    while pos < instructions_raw.len() {
+        let size = unsafe {
+            LLVMDisasmInstruction(asmctx,
+                instructions_raw.as_mut_ptr().add(pos),
+                (instructions_raw.len() - pos) as u64,
+                0,
+                instr_buf.as_mut_ptr() as *mut i8,
+                256)
+        };
+        /* The text is prefixed with a tab and NUL-terminated unless it fills the buffer */
+        let tab_skip = 1;
+        let string_end = instr_buf.iter().position(|&r| r == 0).unwrap_or(instr_buf.len());
+        let instr_raw = instr_buf.get(tab_skip..string_end).and_then(|text| std::str::from_utf8(text).ok());
+
+        let instruction = match instr_raw {
+            Some(instr_raw) if size > 0 && pos + size <= instructions_raw.len() => {
+                let (opcode, operands) = parse_instruction(instr_raw);
+                Instruction { opcode, operands, offset: pos, size }
+            },
+            _ => Instruction::raw_data(&instructions_raw[pos..], pos)
+        };
+        pos += instruction.size;
+        instructions.push(instruction);
+    }
+
+    unsafe {
+        LLVMDisasmDispose(asmctx);
+    }
+
+    Ok(instructions)
+}
@@ -0,0 +1,485 @@
+use std::fmt;
+use std::io;
+
+use super::kernel_args::{KernelArg, KernelArgs, ValueKind, ValueType, AddressSpace, AccessQualifier};
+use super::msgpack;
+use super::notes::{parse_notes, NT_AMD_AMDGPU_HSA_METADATA, NT_AMDGPU_METADATA};
+use super::target::Target;
+use super::yaml::{self, Yaml};
+
+#[derive(Debug, Default)]
+pub struct CodeObjectMetadata {
+    pub version: Vec<u32>,
+    pub printf: Vec<String>,
+    pub kernels: Vec<KernelMetadata>
+}
+
+#[derive(Debug)]
+pub struct KernelMetadata {
+    pub name: String,
+    pub symbol_name: Option<String>,
+    pub language: Option<String>,
+    pub language_version: Vec<u32>,
+    pub attrs: KernelAttrs,
+    pub args: KernelArgs,
+    pub code_props: KernelCodeProps
+}
+
+#[derive(Debug, Default)]
+pub struct KernelAttrs {
+    pub reqd_work_group_size: Option<[u32; 3]>,
+    pub work_group_size_hint: Option<[u32; 3]>,
+    pub vec_type_hint: Option<String>,
+    pub runtime_handle: Option<String>
+}
+
+#[derive(Debug, Default)]
+pub struct KernelCodeProps {
+    pub kernarg_segment_size: u64,
+    pub group_segment_fixed_size: u32,
+    pub private_segment_fixed_size: u32,
+    pub kernarg_segment_align: u32,
+    pub wavefront_size: u32,
+    pub num_sgprs: u32,
+    pub num_vgprs: u32,
+    pub max_flat_work_group_size: u32,
+    pub is_dynamic_callstack: bool,
+    pub is_xnack_enabled: bool,
+    pub num_spilled_sgprs: u32,
+    pub num_spilled_vgprs: u32
+}
+
+#[derive(Debug)]
+pub enum MetadataError {
+    MissingNote,
+    Notes(io::Error),
+    Syntax { line: usize, message: String },
+    MsgPack(io::Error),
+    MissingField { context: String, field: &'static str },
+    InvalidValue { context: String, field: &'static str, value: String }
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::MissingNote =>
+                write!(f, "no NT_AMD_AMDGPU_HSA_METADATA or NT_AMDGPU_METADATA note found"),
+            MetadataError::Notes(e) =>
+                write!(f, "malformed ELF note section: {}", e),
+            MetadataError::Syntax { line, message } =>
+                write!(f, "malformed metadata at line {}: {}", line, message),
+            MetadataError::MsgPack(e) =>
+                write!(f, "malformed MessagePack metadata: {}", e),
+            MetadataError::MissingField { context, field } =>
+                write!(f, "{}: missing required field {}", context, field),
+            MetadataError::InvalidValue { context, field, value } =>
+                write!(f, "{}: invalid value {:?} for {}", context, value, field)
+        }
+    }
+}
+
+impl From<MetadataError> for io::Error {
+    fn from(e: MetadataError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}", e))
+    }
+}
+
+/* The target supplies the XNACK mode of v3+ kernels when the metadata lacks amdhsa.target */
+pub fn extract_metadata(section_note: &[u8], target: &Target) -> Result<CodeObjectMetadata, MetadataError> {
+    let notes = parse_notes(section_note).map_err(MetadataError::Notes)?;
+
+    if let Some(note) = notes.iter().find(|n| n.name == "AMDGPU" && n.note_type == NT_AMDGPU_METADATA) {
+        let root = msgpack::decode(&note.desc).map_err(MetadataError::MsgPack)?;
+        return metadata_from_msgpack(&root, target);
+    }
+    if let Some(note) = notes.iter().find(|n| n.name == "AMD" && n.note_type == NT_AMD_AMDGPU_HSA_METADATA) {
+        let document = String::from_utf8_lossy(&note.desc);
+        let root = yaml::parse(document.trim_end_matches('\0'))
+            .map_err(|e| MetadataError::Syntax { line: e.line, message: e.message })?;
+        return metadata_from_yaml(&root);
+    }
+    Err(MetadataError::MissingNote)
+}
+
+/* Code object v2: https://llvm.org/docs/AMDGPUUsage.html#code-object-v2-metadata */
+
+fn metadata_from_yaml(root: &Yaml) -> Result<CodeObjectMetadata, MetadataError> {
+    let context = "code object metadata";
+
+    let version = match root.get("Version") {
+        Some(v) => yaml_u32_seq(v, context, "Version")?,
+        None => Vec::new()
+    };
+    let printf = match root.get("Printf").and_then(Yaml::as_seq) {
+        Some(items) => items.iter().filter_map(Yaml::as_str).map(str::to_string).collect(),
+        None => Vec::new()
+    };
+    let kernels = match root.get("Kernels").and_then(Yaml::as_seq) {
+        Some(kernels) => kernels.iter().map(kernel_from_yaml).collect::<Result<_, _>>()?,
+        None => Vec::new()
+    };
+
+    Ok(CodeObjectMetadata { version, printf, kernels })
+}
+
+fn kernel_from_yaml(kernel: &Yaml) -> Result<KernelMetadata, MetadataError> {
+    let name = kernel.get("Name").and_then(Yaml::as_str)
+        .ok_or(MetadataError::MissingField { context: "kernel".to_string(), field: "Name" })?
+        .to_string();
+    let context = format!("kernel {}", name);
+
+    let attrs = match kernel.get("Attrs") {
+        Some(attrs) => KernelAttrs {
+            reqd_work_group_size: yaml_dim3(attrs, &context, "ReqdWorkGroupSize")?,
+            work_group_size_hint: yaml_dim3(attrs, &context, "WorkGroupSizeHint")?,
+            vec_type_hint: attrs.get("VecTypeHint").and_then(Yaml::as_str).map(str::to_string),
+            runtime_handle: attrs.get("RuntimeHandle").and_then(Yaml::as_str).map(str::to_string)
+        },
+        None => Default::default()
+    };
+
+    /* v2 metadata does not record argument offsets, so they are computed from sizes and alignments */
+    let mut offset = 0;
+    let args = match kernel.get("Args").and_then(Yaml::as_seq) {
+        Some(args) => args.iter().enumerate()
+            .map(|(i, arg)| {
+                let arg = arg_from_yaml(arg, &format!("{} arg #{}", context, i), offset)?;
+                offset = arg.offset + arg.size;
+                Ok(arg)
+            })
+            .collect::<Result<Vec<KernelArg>, MetadataError>>()?,
+        None => Vec::new()
+    };
+
+    let code_props = match kernel.get("CodeProps") {
+        Some(props) => KernelCodeProps {
+            kernarg_segment_size: yaml_num(props, &context, "KernargSegmentSize")?.unwrap_or(0),
+            group_segment_fixed_size: yaml_num(props, &context, "GroupSegmentFixedSize")?.unwrap_or(0),
+            private_segment_fixed_size: yaml_num(props, &context, "PrivateSegmentFixedSize")?.unwrap_or(0),
+            kernarg_segment_align: yaml_num(props, &context, "KernargSegmentAlign")?.unwrap_or(0),
+            wavefront_size: yaml_num(props, &context, "WavefrontSize")?.unwrap_or(0),
+            num_sgprs: yaml_num(props, &context, "NumSGPRs")?.unwrap_or(0),
+            num_vgprs: yaml_num(props, &context, "NumVGPRs")?.unwrap_or(0),
+            max_flat_work_group_size: yaml_num(props, &context, "MaxFlatWorkGroupSize")?.unwrap_or(0),
+            is_dynamic_callstack: yaml_bool(props, &context, "IsDynamicCallStack")?,
+            is_xnack_enabled: yaml_bool(props, &context, "IsXNACKEnabled")?,
+            num_spilled_sgprs: yaml_num(props, &context, "NumSpilledSGPRs")?.unwrap_or(0),
+            num_spilled_vgprs: yaml_num(props, &context, "NumSpilledVGPRs")?.unwrap_or(0)
+        },
+        None => Default::default()
+    };
+
+    Ok(KernelMetadata {
+        name,
+        symbol_name: kernel.get("SymbolName").and_then(Yaml::as_str).map(str::to_string),
+        language: kernel.get("Language").and_then(Yaml::as_str).map(str::to_string),
+        language_version: match kernel.get("LanguageVersion") {
+            Some(v) => yaml_u32_seq(v, &context, "LanguageVersion")?,
+            None => Vec::new()
+        },
+        attrs,
+        args: KernelArgs::from(args),
+        code_props
+    })
+}
+
+fn arg_from_yaml(arg: &Yaml, context: &str, prev_end: u32) -> Result<KernelArg, MetadataError> {
+    let size: u32 = yaml_num(arg, context, "Size")?
+        .ok_or(MetadataError::MissingField { context: context.to_string(), field: "Size" })?;
+    let align: u32 = yaml_num(arg, context, "Align")?
+        .ok_or(MetadataError::MissingField { context: context.to_string(), field: "Align" })?;
+    if align == 0 || !align.is_power_of_two() {
+        return Err(MetadataError::InvalidValue { context: context.to_string(), field: "Align", value: align.to_string() });
+    }
+    let value_kind = arg.get("ValueKind").and_then(Yaml::as_str)
+        .map(ValueKind::from)
+        .ok_or(MetadataError::MissingField { context: context.to_string(), field: "ValueKind" })?;
+
+    /* Hidden arguments are unnamed, and are referred to by their value kind */
+    let name = arg.get("Name").and_then(Yaml::as_str).map(str::to_string)
+        .or(arg.get("ValueKind").and_then(Yaml::as_str).map(str::to_string))
+        .unwrap_or_default();
+
+    Ok(KernelArg {
+        name,
+        size,
+        offset: (prev_end + align - 1) & !(align - 1),
+        align,
+        typename: arg.get("TypeName").and_then(Yaml::as_str).map(str::to_string),
+        is_const: yaml_bool(arg, context, "IsConst")?,
+        value_kind: Some(value_kind),
+        value_type: yaml_enum(arg, context, "ValueType", ValueType::parse)?,
+        address_space: yaml_enum(arg, context, "AddrSpaceQual", AddressSpace::parse)?,
+        access: yaml_enum(arg, context, "AccQual", AccessQualifier::parse)?,
+        actual_access: yaml_enum(arg, context, "ActualAccQual", AccessQualifier::parse)?,
+        pointee_align: yaml_num(arg, context, "PointeeAlign")?,
+        is_restrict: yaml_bool(arg, context, "IsRestrict")?,
+        is_volatile: yaml_bool(arg, context, "IsVolatile")?,
+        is_pipe: yaml_bool(arg, context, "IsPipe")?
+    })
+}
+
+fn yaml_num<T: std::str::FromStr>(node: &Yaml, context: &str, field: &'static str) -> Result<Option<T>, MetadataError> {
+    match node.get(field) {
+        Some(Yaml::Scalar(s)) => s.parse::<T>().map(Some)
+            .map_err(|_| MetadataError::InvalidValue { context: context.to_string(), field, value: s.to_string() }),
+        Some(other) => Err(MetadataError::InvalidValue { context: context.to_string(), field, value: format!("{:?}", other) }),
+        None => Ok(None)
+    }
+}
+
+fn yaml_bool(node: &Yaml, context: &str, field: &'static str) -> Result<bool, MetadataError> {
+    match node.get(field).and_then(Yaml::as_str) {
+        Some("true") => Ok(true),
+        Some("false") | None => Ok(false),
+        Some(other) => Err(MetadataError::InvalidValue { context: context.to_string(), field, value: other.to_string() })
+    }
+}
+
+fn yaml_enum<T>(node: &Yaml, context: &str, field: &'static str, parse: fn(&str) -> Option<T>) -> Result<Option<T>, MetadataError> {
+    match node.get(field).and_then(Yaml::as_str) {
+        Some(value) => parse(value).map(Some)
+            .ok_or(MetadataError::InvalidValue { context: context.to_string(), field, value: value.to_string() }),
+        None => Ok(None)
+    }
+}
+
+fn yaml_u32_seq(node: &Yaml, context: &str, field: &'static str) -> Result<Vec<u32>, MetadataError> {
+    let invalid = || MetadataError::InvalidValue { context: context.to_string(), field, value: format!("{:?}", node) };
+    node.as_seq().ok_or_else(invalid)?.iter()
+        .map(|item| item.as_str().and_then(|s| s.parse::<u32>().ok()).ok_or_else(invalid))
+        .collect()
+}
+
+fn yaml_dim3(node: &Yaml, context: &str, field: &'static str) -> Result<Option<[u32; 3]>, MetadataError> {
+    match node.get(field) {
+        Some(dims) => match yaml_u32_seq(dims, context, field)?.as_slice() {
+            [x, y, z] => Ok(Some([*x, *y, *z])),
+            _ => Err(MetadataError::InvalidValue { context: context.to_string(), field, value: format!("{:?}", dims) })
+        },
+        None => Ok(None)
+    }
+}
+
+/* Code object v3+: https://llvm.org/docs/AMDGPUUsage.html#code-object-v3-metadata */
+
+fn metadata_from_msgpack(root: &msgpack::Value, target: &Target) -> Result<CodeObjectMetadata, MetadataError> {
+    let context = "code object metadata";
+
+    /* amdhsa.target is a target ID such as amdgcn-amd-amdhsa--gfx906:xnack+ (v4+) */
+    let xnack = root.get("amdhsa.target").and_then(msgpack::Value::as_str)
+        .and_then(|id| id.rsplit("--").next())
+        .and_then(Target::from_processor)
+        .and_then(|target| target.xnack)
+        .or(target.xnack)
+        .unwrap_or(false);
+
+    let version = match root.get("amdhsa.version") {
+        Some(v) => msgpack_u32_seq(v, context, "amdhsa.version")?,
+        None => Vec::new()
+    };
+    let printf = match root.get("amdhsa.printf").and_then(msgpack::Value::as_array) {
+        Some(items) => items.iter().filter_map(msgpack::Value::as_str).map(str::to_string).collect(),
+        None => Vec::new()
+    };
+    let kernels = match root.get("amdhsa.kernels").and_then(msgpack::Value::as_array) {
+        Some(kernels) => kernels.iter().map(|kernel| kernel_from_msgpack(kernel, xnack)).collect::<Result<_, _>>()?,
+        None => Vec::new()
+    };
+
+    Ok(CodeObjectMetadata { version, printf, kernels })
+}
+
+fn kernel_from_msgpack(kernel: &msgpack::Value, xnack: bool) -> Result<KernelMetadata, MetadataError> {
+    let name = kernel.get(".name").and_then(msgpack::Value::as_str)
+        .ok_or(MetadataError::MissingField { context: "kernel".to_string(), field: ".name" })?
+        .to_string();
+    let context = format!("kernel {}", name);
+
+    let attrs = KernelAttrs {
+        reqd_work_group_size: msgpack_dim3(kernel, &context, ".reqd_workgroup_size")?,
+        work_group_size_hint: msgpack_dim3(kernel, &context, ".workgroup_size_hint")?,
+        vec_type_hint: kernel.get(".vec_type_hint").and_then(msgpack::Value::as_str).map(str::to_string),
+        runtime_handle: kernel.get(".device_enqueue_symbol").and_then(msgpack::Value::as_str).map(str::to_string)
+    };
+
+    let args = match kernel.get(".args").and_then(msgpack::Value::as_array) {
+        Some(args) => args.iter().enumerate()
+            .map(|(i, arg)| arg_from_msgpack(arg, &format!("{} arg #{}", context, i)))
+            .collect::<Result<Vec<KernelArg>, MetadataError>>()?,
+        None => Vec::new()
+    };
+
+    let code_props = KernelCodeProps {
+        kernarg_segment_size: msgpack_num(kernel, &context, ".kernarg_segment_size")?.unwrap_or(0),
+        group_segment_fixed_size: msgpack_num(kernel, &context, ".group_segment_fixed_size")?.unwrap_or(0) as u32,
+        private_segment_fixed_size: msgpack_num(kernel, &context, ".private_segment_fixed_size")?.unwrap_or(0) as u32,
+        kernarg_segment_align: msgpack_num(kernel, &context, ".kernarg_segment_align")?.unwrap_or(0) as u32,
+        wavefront_size: msgpack_num(kernel, &context, ".wavefront_size")?.unwrap_or(0) as u32,
+        num_sgprs: msgpack_num(kernel, &context, ".sgpr_count")?.unwrap_or(0) as u32,
+        num_vgprs: msgpack_num(kernel, &context, ".vgpr_count")?.unwrap_or(0) as u32,
+        max_flat_work_group_size: msgpack_num(kernel, &context, ".max_flat_workgroup_size")?.unwrap_or(0) as u32,
+        is_dynamic_callstack: msgpack_bool(kernel, ".uses_dynamic_stack"),
+        is_xnack_enabled: xnack,
+        num_spilled_sgprs: msgpack_num(kernel, &context, ".sgpr_spill_count")?.unwrap_or(0) as u32,
+        num_spilled_vgprs: msgpack_num(kernel, &context, ".vgpr_spill_count")?.unwrap_or(0) as u32
+    };
+
+    Ok(KernelMetadata {
+        name,
+        symbol_name: kernel.get(".symbol").and_then(msgpack::Value::as_str).map(str::to_string),
+        language: kernel.get(".language").and_then(msgpack::Value::as_str).map(str::to_string),
+        language_version: match kernel.get(".language_version") {
+            Some(v) => msgpack_u32_seq(v, &context, ".language_version")?,
+            None => Vec::new()
+        },
+        attrs,
+        args: KernelArgs::from(args),
+        code_props
+    })
+}
+
+fn arg_from_msgpack(arg: &msgpack::Value, context: &str) -> Result<KernelArg, MetadataError> {
+    let str_field = |key: &str| arg.get(key).and_then(msgpack::Value::as_str).map(str::to_string);
+
+    let size = msgpack_num(arg, context, ".size")?
+        .ok_or(MetadataError::MissingField { context: context.to_string(), field: ".size" })? as u32;
+    let offset = msgpack_num(arg, context, ".offset")?
+        .ok_or(MetadataError::MissingField { context: context.to_string(), field: ".offset" })? as u32;
+    let value_kind_raw = str_field(".value_kind")
+        .ok_or(MetadataError::MissingField { context: context.to_string(), field: ".value_kind" })?;
+
+    /* Hidden arguments are unnamed; like in v2 metadata, fall back to the value kind (in CamelCase) */
+    let name = str_field(".name").unwrap_or(snake_to_camel_case(&value_kind_raw));
+
+    Ok(KernelArg {
+        name,
+        size,
+        offset,
+        align: natural_align(size, offset),
+        typename: str_field(".type_name"),
+        is_const: msgpack_bool(arg, ".is_const"),
+        value_kind: Some(ValueKind::from(value_kind_raw.as_str())),
+        value_type: msgpack_enum(arg, context, ".value_type", ValueType::parse)?,
+        address_space: msgpack_enum(arg, context, ".address_space", AddressSpace::parse)?,
+        access: msgpack_enum(arg, context, ".access", AccessQualifier::parse)?,
+        actual_access: msgpack_enum(arg, context, ".actual_access", AccessQualifier::parse)?,
+        pointee_align: msgpack_num(arg, context, ".pointee_align")?.map(|a| a as u32),
+        is_restrict: msgpack_bool(arg, ".is_restrict"),
+        is_volatile: msgpack_bool(arg, ".is_volatile"),
+        is_pipe: msgpack_bool(arg, ".is_pipe")
+    })
+}
+
+/* v3 metadata has no argument alignment: arguments are naturally aligned, up to
+ * the alignment of their offset */
+fn natural_align(size: u32, offset: u32) -> u32 {
+    let size_align = size.max(1).next_power_of_two();
+    match offset {
+        0 => size_align,
+        _ => size_align.min(1 << offset.trailing_zeros())
+    }
+}
+
+fn snake_to_camel_case(value: &str) -> String {
+    value.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
+
+fn msgpack_num(node: &msgpack::Value, context: &str, field: &'static str) -> Result<Option<u64>, MetadataError> {
+    match node.get(field) {
+        Some(value) => value.as_u64().map(Some)
+            .ok_or(MetadataError::InvalidValue { context: context.to_string(), field, value: format!("{:?}", value) }),
+        None => Ok(None)
+    }
+}
+
+fn msgpack_bool(node: &msgpack::Value, field: &'static str) -> bool {
+    node.get(field).and_then(msgpack::Value::as_bool).unwrap_or(false)
+}
+
+fn msgpack_enum<T>(node: &msgpack::Value, context: &str, field: &'static str, parse: fn(&str) -> Option<T>) -> Result<Option<T>, MetadataError> {
+    match node.get(field).and_then(msgpack::Value::as_str) {
+        Some(value) => parse(value).map(Some)
+            .ok_or(MetadataError::InvalidValue { context: context.to_string(), field, value: value.to_string() }),
+        None => Ok(None)
+    }
+}
+
+fn msgpack_u32_seq(node: &msgpack::Value, context: &str, field: &'static str) -> Result<Vec<u32>, MetadataError> {
+    let invalid = || MetadataError::InvalidValue { context: context.to_string(), field, value: format!("{:?}", node) };
+    node.as_array().ok_or_else(invalid)?.iter()
+        .map(|item| item.as_u64().map(|v| v as u32).ok_or_else(invalid))
+        .collect()
+}
+
+fn msgpack_dim3(node: &msgpack::Value, context: &str, field: &'static str) -> Result<Option<[u32; 3]>, MetadataError> {
+    match node.get(field) {
+        Some(dims) => match msgpack_u32_seq(dims, context, field)?.as_slice() {
+            [x, y, z] => Ok(Some([*x, *y, *z])),
+            _ => Err(MetadataError::InvalidValue { context: context.to_string(), field, value: format!("{:?}", dims) })
+        },
+        None => Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{metadata_from_msgpack, natural_align};
+    use crate::asm::msgpack::Value;
+    use crate::asm::target::Target;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        Value::Map(entries.into_iter().map(|(k, v)| (Value::Str(k.to_string()), v)).collect())
+    }
+
+    fn string(s: &str) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    fn root(target: Option<&str>) -> Value {
+        let arg = |size, offset, kind| map(vec![(".size", Value::UInt(size)), (".offset", Value::UInt(offset)), (".value_kind", string(kind))]);
+        let kernel = map(vec![
+            (".name", string("k")),
+            (".args", Value::Array(vec![arg(8, 0, "global_buffer"), arg(4, 8, "by_value"), arg(16, 16, "by_value"), arg(2, 34, "by_value")]))
+        ]);
+        let mut entries = vec![("amdhsa.kernels", Value::Array(vec![kernel]))];
+        if let Some(target) = target {
+            entries.push(("amdhsa.target", string(target)));
+        }
+        map(entries)
+    }
+
+    #[test]
+    fn natural_alignment() {
+        assert_eq!(natural_align(8, 0), 8);
+        assert_eq!(natural_align(4, 8), 4);
+        assert_eq!(natural_align(8, 4), 4);
+        assert_eq!(natural_align(12, 16), 16);
+        assert_eq!(natural_align(0, 6), 1);
+    }
+
+    #[test]
+    fn v3_arguments() {
+        let metadata = metadata_from_msgpack(&root(None), &Target::from_processor("gfx900").unwrap()).unwrap();
+        let aligns: Vec<u32> = metadata.kernels[0].args.iter().map(|arg| arg.align).collect();
+        assert_eq!(aligns, vec![8, 4, 16, 2]);
+        assert_eq!(metadata.kernels[0].args.iter().map(|arg| arg.name.as_str()).collect::<Vec<_>>(),
+                   vec!["GlobalBuffer", "ByValue", "ByValue", "ByValue"]);
+    }
+
+    #[test]
+    fn xnack() {
+        let xnack = |target_id, target: &str| metadata_from_msgpack(&root(target_id), &Target::from_processor(target).unwrap())
+            .unwrap().kernels[0].code_props.is_xnack_enabled;
+        assert!(xnack(Some("amdgcn-amd-amdhsa--gfx906:xnack+"), "gfx906"));
+        assert!(!xnack(Some("amdgcn-amd-amdhsa--gfx906:sramecc+:xnack-"), "gfx906:xnack+"));
+        assert!(xnack(Some("amdgcn-amd-amdhsa--gfx906"), "gfx906:xnack+"));
+        assert!(xnack(None, "gfx906+xnack"));
+        assert!(!xnack(None, "gfx906"));
+    }
+}
@@ -0,0 +1,261 @@
+use std::io;
+use std::io::{Cursor, Read};
+use byteorder::{BE, ReadBytesExt};
+
+/* https://github.com/msgpack/msgpack/blob/master/spec.md */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Ext(i8, Vec<u8>)
+}
+
+impl Value {
+    /* Looks up a string key in a map */
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s.as_str()),
+            _ => None
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::UInt(u) => Some(u),
+            Value::Int(i) if i >= 0 => Some(i as u64),
+            _ => None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items.as_slice()),
+            _ => None
+        }
+    }
+}
+
+/* Metadata maps are nested a few levels deep; the limit keeps malformed input
+ * from exhausting the stack */
+const MAX_DEPTH: usize = 64;
+
+pub fn decode(buf: &[u8]) -> io::Result<Value> {
+    decode_value(&mut Cursor::new(buf), 0)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn decode_value(crs: &mut Cursor<&[u8]>, depth: usize) -> io::Result<Value> {
+    let marker = crs.read_u8()?;
+
+    match marker {
+        0x00..=0x7f => Ok(Value::UInt(marker as u64)),
+        0x80..=0x8f => decode_map(crs, (marker & 0x0f) as usize, depth),
+        0x90..=0x9f => decode_array(crs, (marker & 0x0f) as usize, depth),
+        0xa0..=0xbf => decode_str(crs, (marker & 0x1f) as usize),
+        0xc0 => Ok(Value::Nil),
+        0xc2 => Ok(Value::Bool(false)),
+        0xc3 => Ok(Value::Bool(true)),
+        0xc4 => { let len = crs.read_u8()? as usize; read_bytes(crs, len).map(Value::Bin) },
+        0xc5 => { let len = crs.read_u16::<BE>()? as usize; read_bytes(crs, len).map(Value::Bin) },
+        0xc6 => { let len = crs.read_u32::<BE>()? as usize; read_bytes(crs, len).map(Value::Bin) },
+        0xc7 => { let len = crs.read_u8()? as usize; decode_ext(crs, len) },
+        0xc8 => { let len = crs.read_u16::<BE>()? as usize; decode_ext(crs, len) },
+        0xc9 => { let len = crs.read_u32::<BE>()? as usize; decode_ext(crs, len) },
+        0xca => Ok(Value::Float(crs.read_f32::<BE>()? as f64)),
+        0xcb => Ok(Value::Float(crs.read_f64::<BE>()?)),
+        0xcc => Ok(Value::UInt(crs.read_u8()? as u64)),
+        0xcd => Ok(Value::UInt(crs.read_u16::<BE>()? as u64)),
+        0xce => Ok(Value::UInt(crs.read_u32::<BE>()? as u64)),
+        0xcf => Ok(Value::UInt(crs.read_u64::<BE>()?)),
+        0xd0 => Ok(Value::Int(crs.read_i8()? as i64)),
+        0xd1 => Ok(Value::Int(crs.read_i16::<BE>()? as i64)),
+        0xd2 => Ok(Value::Int(crs.read_i32::<BE>()? as i64)),
+        0xd3 => Ok(Value::Int(crs.read_i64::<BE>()?)),
+        0xd4 => decode_ext(crs, 1),
+        0xd5 => decode_ext(crs, 2),
+        0xd6 => decode_ext(crs, 4),
+        0xd7 => decode_ext(crs, 8),
+        0xd8 => decode_ext(crs, 16),
+        0xd9 => { let len = crs.read_u8()? as usize; decode_str(crs, len) },
+        0xda => { let len = crs.read_u16::<BE>()? as usize; decode_str(crs, len) },
+        0xdb => { let len = crs.read_u32::<BE>()? as usize; decode_str(crs, len) },
+        0xdc => { let len = crs.read_u16::<BE>()? as usize; decode_array(crs, len, depth) },
+        0xdd => { let len = crs.read_u32::<BE>()? as usize; decode_array(crs, len, depth) },
+        0xde => { let len = crs.read_u16::<BE>()? as usize; decode_map(crs, len, depth) },
+        0xdf => { let len = crs.read_u32::<BE>()? as usize; decode_map(crs, len, depth) },
+        0xe0..=0xff => Ok(Value::Int(marker as i8 as i64)),
+        _ => Err(invalid(format!("invalid MessagePack marker {:#x} at offset {}", marker, crs.position() - 1)))
+    }
+}
+
+fn remaining(crs: &Cursor<&[u8]>) -> usize {
+    crs.get_ref().len().saturating_sub(crs.position() as usize)
+}
+
+fn read_bytes(crs: &mut Cursor<&[u8]>, len: usize) -> io::Result<Vec<u8>> {
+    if len > remaining(crs) {
+        return Err(invalid(format!("MessagePack object of {} bytes exceeds the input", len)));
+    }
+    let mut bytes = vec![0u8; len];
+    crs.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn decode_str(crs: &mut Cursor<&[u8]>, len: usize) -> io::Result<Value> {
+    let bytes = read_bytes(crs, len)?;
+    String::from_utf8(bytes)
+        .map(Value::Str)
+        .map_err(|e| invalid(format!("invalid UTF-8 in MessagePack string: {}", e)))
+}
+
+fn decode_ext(crs: &mut Cursor<&[u8]>, len: usize) -> io::Result<Value> {
+    let ext_type = crs.read_i8()?;
+    read_bytes(crs, len).map(|data| Value::Ext(ext_type, data))
+}
+
+fn nested(crs: &Cursor<&[u8]>, depth: usize) -> io::Result<usize> {
+    match depth < MAX_DEPTH {
+        true => Ok(depth + 1),
+        false => Err(invalid(format!("MessagePack containers nested deeper than {} levels at offset {}", MAX_DEPTH, crs.position() - 1)))
+    }
+}
+
+fn decode_array(crs: &mut Cursor<&[u8]>, len: usize, depth: usize) -> io::Result<Value> {
+    let depth = nested(crs, depth)?;
+    /* Every element takes at least one byte, which bounds the allocation */
+    if len > remaining(crs) {
+        return Err(invalid(format!("MessagePack array of {} elements exceeds the input", len)));
+    }
+    (0..len).map(|_| decode_value(crs, depth)).collect::<io::Result<_>>().map(Value::Array)
+}
+
+fn decode_map(crs: &mut Cursor<&[u8]>, len: usize, depth: usize) -> io::Result<Value> {
+    let depth = nested(crs, depth)?;
+    if len.saturating_mul(2) > remaining(crs) {
+        return Err(invalid(format!("MessagePack map of {} entries exceeds the input", len)));
+    }
+    (0..len).map(|_| Ok((decode_value(crs, depth)?, decode_value(crs, depth)?)))
+        .collect::<io::Result<_>>().map(Value::Map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, Value, MAX_DEPTH};
+
+    fn str(s: &str) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(decode(&[0x00]).unwrap(), Value::UInt(0));
+        assert_eq!(decode(&[0x7f]).unwrap(), Value::UInt(127));
+        assert_eq!(decode(&[0xe0]).unwrap(), Value::Int(-32));
+        assert_eq!(decode(&[0xff]).unwrap(), Value::Int(-1));
+        assert_eq!(decode(&[0xcc, 0xff]).unwrap(), Value::UInt(0xff));
+        assert_eq!(decode(&[0xcd, 0x12, 0x34]).unwrap(), Value::UInt(0x1234));
+        assert_eq!(decode(&[0xce, 0x12, 0x34, 0x56, 0x78]).unwrap(), Value::UInt(0x1234_5678));
+        assert_eq!(decode(&[0xcf, 0x80, 0, 0, 0, 0, 0, 0, 1]).unwrap(), Value::UInt(0x8000_0000_0000_0001));
+        assert_eq!(decode(&[0xd0, 0x80]).unwrap(), Value::Int(-128));
+        assert_eq!(decode(&[0xd1, 0xff, 0xfe]).unwrap(), Value::Int(-2));
+        assert_eq!(decode(&[0xd2, 0xff, 0xff, 0xff, 0xfd]).unwrap(), Value::Int(-3));
+        assert_eq!(decode(&[0xd3, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfc]).unwrap(), Value::Int(-4));
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(decode(&[0xc0]).unwrap(), Value::Nil);
+        assert_eq!(decode(&[0xc2]).unwrap(), Value::Bool(false));
+        assert_eq!(decode(&[0xc3]).unwrap(), Value::Bool(true));
+        assert_eq!(decode(&[0xca, 0x3f, 0xc0, 0, 0]).unwrap(), Value::Float(1.5));
+        assert_eq!(decode(&[0xcb, 0xc0, 0x04, 0, 0, 0, 0, 0, 0]).unwrap(), Value::Float(-2.5));
+    }
+
+    #[test]
+    fn strings_and_binaries() {
+        assert_eq!(decode(&[0xa3, b'a', b'b', b'c']).unwrap(), str("abc"));
+        assert_eq!(decode(&[0xd9, 2, b'h', b'i']).unwrap(), str("hi"));
+        assert_eq!(decode(&[0xda, 0, 1, b'x']).unwrap(), str("x"));
+        assert_eq!(decode(&[0xdb, 0, 0, 0, 0]).unwrap(), str(""));
+        assert_eq!(decode(&[0xc4, 2, 1, 2]).unwrap(), Value::Bin(vec![1, 2]));
+        assert_eq!(decode(&[0xc5, 0, 1, 3]).unwrap(), Value::Bin(vec![3]));
+        assert_eq!(decode(&[0xc6, 0, 0, 0, 1, 4]).unwrap(), Value::Bin(vec![4]));
+        assert!(decode(&[0xa2, 0xc3, 0x28]).is_err());
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(decode(&[0xd4, 1, 9]).unwrap(), Value::Ext(1, vec![9]));
+        assert_eq!(decode(&[0xd5, 2, 9, 8]).unwrap(), Value::Ext(2, vec![9, 8]));
+        assert_eq!(decode(&[0xd6, 0xff, 1, 2, 3, 4]).unwrap(), Value::Ext(-1, vec![1, 2, 3, 4]));
+        assert_eq!(decode(&[0xd7, 3, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap(), Value::Ext(3, vec![0; 8]));
+        assert_eq!(decode(&[&[0xd8, 4][..], &[7; 16][..]].concat()).unwrap(), Value::Ext(4, vec![7; 16]));
+        assert_eq!(decode(&[0xc7, 1, 5, 6]).unwrap(), Value::Ext(5, vec![6]));
+        assert_eq!(decode(&[0xc8, 0, 1, 5, 6]).unwrap(), Value::Ext(5, vec![6]));
+        assert_eq!(decode(&[0xc9, 0, 0, 0, 1, 5, 6]).unwrap(), Value::Ext(5, vec![6]));
+    }
+
+    #[test]
+    fn containers() {
+        assert_eq!(decode(&[0x92, 0x01, 0xc0]).unwrap(), Value::Array(vec![Value::UInt(1), Value::Nil]));
+        assert_eq!(decode(&[0xdc, 0, 1, 0xc3]).unwrap(), Value::Array(vec![Value::Bool(true)]));
+        assert_eq!(decode(&[0xdd, 0, 0, 0, 0]).unwrap(), Value::Array(vec![]));
+
+        let map = decode(&[0x82, 0xa1, b'a', 0x01, 0xa1, b'b', 0x91, 0x02]).unwrap();
+        assert_eq!(map, Value::Map(vec![(str("a"), Value::UInt(1)), (str("b"), Value::Array(vec![Value::UInt(2)]))]));
+        assert_eq!(map.get("a").and_then(Value::as_u64), Some(1));
+        assert_eq!(map.get("b").and_then(Value::as_array).map(|items| items.len()), Some(1));
+        assert_eq!(map.get("c"), None);
+        assert_eq!(decode(&[0xde, 0, 1, 0xc0, 0xc2]).unwrap(), Value::Map(vec![(Value::Nil, Value::Bool(false))]));
+        assert_eq!(decode(&[0xdf, 0, 0, 0, 0]).unwrap(), Value::Map(vec![]));
+    }
+
+    #[test]
+    fn truncated_input() {
+        let truncated: &[&[u8]] = &[
+            &[], &[0xcd, 0x12], &[0xcf, 0, 0, 0], &[0xd3], &[0xca, 0x3f], &[0xcb, 0, 0, 0, 0],
+            &[0xa3, b'a'], &[0xd9], &[0xda, 0], &[0xc4, 4, 1], &[0xc6, 0xff, 0xff, 0xff, 0xff],
+            &[0xd4, 1], &[0xd8, 1, 0], &[0xc7, 2, 1, 0],
+            &[0x92, 0x01], &[0xdd, 0xff, 0xff, 0xff, 0xff], &[0x81, 0xc0], &[0xdf, 0xff, 0xff, 0xff, 0xff, 0xc0]
+        ];
+        for buf in truncated {
+            assert!(decode(buf).is_err(), "{:x?} decoded", buf);
+        }
+        assert!(decode(&[0xc1]).is_err());
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let nested = |levels: usize| [vec![0x91; levels], vec![0xc0]].concat();
+        assert!(decode(&nested(MAX_DEPTH)).is_ok());
+        assert!(decode(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(decode(&vec![0x91; 1 << 20]).is_err());
+        assert!(decode(&vec![0x81; 1 << 20]).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use std::io;
+use std::io::{Cursor, Read};
+use byteorder::{LE, ReadBytesExt};
+
+/* https://llvm.org/docs/AMDGPUUsage.html#note-records */
+pub const NT_AMD_AMDGPU_HSA_METADATA: u32 = 10;
+pub const NT_AMDGPU_METADATA: u32 = 32;
+
+#[derive(Debug)]
+pub struct Note {
+    pub name: String,
+    pub note_type: u32,
+    pub desc: Vec<u8>
+}
+
+pub fn parse_notes(section_note: &[u8]) -> io::Result<Vec<Note>> {
+    let mut crs = Cursor::new(section_note);
+    let mut notes: Vec<Note> = Vec::new();
+
+    while (crs.position() as usize) + 12 <= section_note.len() {
+        let namesz = crs.read_u32::<LE>()? as usize;
+        let descsz = crs.read_u32::<LE>()? as usize;
+        let note_type = crs.read_u32::<LE>()?;
+
+        let exceeds_bounds = || io::Error::new(io::ErrorKind::InvalidData, "note record exceeds section bounds");
+        let remaining = section_note.len() - crs.position() as usize;
+        let aligned_namesz = align4(namesz).ok_or_else(exceeds_bounds)?;
+        if aligned_namesz.checked_add(descsz).map_or(true, |size| size > remaining) {
+            return Err(exceeds_bounds());
+        }
+
+        let mut name = vec![0u8; aligned_namesz];
+        crs.read_exact(&mut name)?;
+        name.truncate(namesz);
+        let name = String::from_utf8_lossy(&name).trim_end_matches('\0').to_string();
+
+        let mut desc = vec![0u8; descsz];
+        crs.read_exact(&mut desc)?;
+        /* The descriptor is padded to a 4-byte boundary as well */
+        let padding = std::cmp::min(align4(descsz).unwrap_or(descsz) - descsz, section_note.len() - crs.position() as usize);
+        crs.set_position(crs.position() + padding as u64);
+
+        notes.push(Note { name, note_type, desc });
+    }
+
+    Ok(notes)
+}
+
+fn align4(size: usize) -> Option<usize> {
+    size.checked_add(3).map(|size| size & !3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(namesz: u32, descsz: u32, note_type: u32, contents: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for field in [namesz, descsz, note_type].iter() {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(contents);
+        data
+    }
+
+    #[test]
+    fn padded_records() {
+        let mut section = note(4, 5, NT_AMDGPU_METADATA, b"AMD\0descr\0\0\0");
+        section.extend(note(7, 2, 3, b"AMDGPU\0\0ok"));
+        let notes = parse_notes(&section).unwrap();
+        let parsed: Vec<(&str, u32, &[u8])> = notes.iter().map(|n| (n.name.as_str(), n.note_type, n.desc.as_slice())).collect();
+        assert_eq!(parsed, vec![("AMD", NT_AMDGPU_METADATA, &b"descr"[..]), ("AMDGPU", 3, b"ok")]);
+    }
+
+    #[test]
+    fn sizes_exceeding_section() {
+        for &(namesz, descsz) in [(4, 5), (4, u32::MAX), (u32::MAX, 0), (u32::MAX - 2, u32::MAX)].iter() {
+            let err = parse_notes(&note(namesz, descsz, 1, b"AMD\0")).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
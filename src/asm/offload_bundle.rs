@@ -0,0 +1,208 @@
+use std::io;
+use std::io::{Cursor, Read};
+use byteorder::{LE, ReadBytesExt};
+
+/* https://clang.llvm.org/docs/ClangOffloadBundler.html#bundled-binary-file-layout */
+const OFFLOAD_BUNDLE_MAGIC: &[u8] = b"__CLANG_OFFLOAD_BUNDLE__";
+const COMPRESSED_BUNDLE_MAGIC: &[u8] = b"CCOB";
+
+/* HIP host executables embed device code in this section */
+const HIP_FATBIN_SECTION: &str = ".hip_fatbin";
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const EM_AMDGPU: elf::types::Machine = elf::types::Machine(224);
+
+#[derive(Debug)]
+pub struct BundleEntry {
+    pub triple: String,
+    pub code_object: Vec<u8>
+}
+
+impl BundleEntry {
+    pub fn is_amdgcn(&self) -> bool {
+        self.triple.contains("amdgcn") && !self.code_object.is_empty()
+    }
+}
+
+/* Returns the entries of every offload bundle found in the input, or an empty list
+ * if the input is already an AMDGPU code object */
+pub fn bundle_entries(input: &[u8]) -> io::Result<Vec<BundleEntry>> {
+    if input.starts_with(OFFLOAD_BUNDLE_MAGIC) || input.starts_with(COMPRESSED_BUNDLE_MAGIC) {
+        return parse_bundles(input);
+    }
+    if !input.starts_with(ELF_MAGIC) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "input is neither an ELF file nor an offload bundle"));
+    }
+
+    let bin = elf::File::open_stream(&mut Cursor::new(input))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("unable to parse ELF: {:?}", e)))?;
+    if bin.ehdr.machine == EM_AMDGPU {
+        return Ok(Vec::new());
+    }
+
+    let fatbin = bin.get_section(HIP_FATBIN_SECTION)
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData,
+            format!("host executable has no {} section with device code", HIP_FATBIN_SECTION)))?;
+    parse_bundles(&fatbin.data)
+}
+
+/* Picks the code object for the requested target (a full triple or just the processor,
+ * e.g. gfx900), or the first AMDGPU entry if none is specified */
+pub fn select_entry(entries: Vec<BundleEntry>, target: Option<&str>) -> io::Result<BundleEntry> {
+    let mut amdgcn_entries = entries.into_iter().filter(BundleEntry::is_amdgcn);
+
+    match target {
+        Some(target) => amdgcn_entries
+            .find(|e| {
+                let matches = |triple: &str| triple == target || triple.ends_with(&format!("-{}", target));
+                /* Target features follow the processor: hipv4-amdgcn-amd-amdhsa--gfx90a:xnack- */
+                let without_features = e.triple.split(':').next().unwrap_or(&e.triple);
+                matches(&e.triple) || matches(without_features)
+            })
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, format!("no offload bundle entry for target {}", target))),
+        None => amdgcn_entries
+            .next()
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, "offload bundle contains no AMDGPU code objects"))
+    }
+}
+
+/* A .hip_fatbin section holds one bundle per translation unit, each aligned
+ * to a page boundary, so we look for every occurrence of the magic string */
+fn parse_bundles(data: &[u8]) -> io::Result<Vec<BundleEntry>> {
+    let mut entries: Vec<BundleEntry> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = find_magic(data, pos) {
+        if data[start..].starts_with(COMPRESSED_BUNDLE_MAGIC) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "compressed offload bundles are not supported"));
+        }
+        let (mut bundle_entries, bundle_size) = parse_bundle(&data[start..])?;
+        entries.append(&mut bundle_entries);
+        pos = start + std::cmp::max(bundle_size, OFFLOAD_BUNDLE_MAGIC.len());
+    }
+
+    if entries.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no offload bundles found"));
+    }
+    Ok(entries)
+}
+
+fn find_magic(data: &[u8], from: usize) -> Option<usize> {
+    if from >= data.len() {
+        return None;
+    }
+    data[from..].windows(OFFLOAD_BUNDLE_MAGIC.len())
+        .position(|w| w == OFFLOAD_BUNDLE_MAGIC || w.starts_with(COMPRESSED_BUNDLE_MAGIC))
+        .map(|idx| from + idx)
+}
+
+fn parse_bundle(bundle: &[u8]) -> io::Result<(Vec<BundleEntry>, usize)> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("malformed offload bundle: {}", msg));
+
+    let mut crs = Cursor::new(bundle);
+    crs.set_position(OFFLOAD_BUNDLE_MAGIC.len() as u64);
+
+    let num_entries = crs.read_u64::<LE>()?;
+    /* Each entry header takes at least 24 bytes */
+    if num_entries > (bundle.len() / 24) as u64 {
+        return Err(invalid("too many entries"));
+    }
+
+    let mut entries: Vec<BundleEntry> = Vec::with_capacity(num_entries as usize);
+    let mut bundle_size = crs.position() as usize;
+
+    for _ in 0..num_entries {
+        let offset = crs.read_u64::<LE>()? as usize;
+        let size = crs.read_u64::<LE>()? as usize;
+        let triple_size = crs.read_u64::<LE>()? as usize;
+
+        if triple_size > bundle.len() - crs.position() as usize {
+            return Err(invalid("triple exceeds bundle bounds"));
+        }
+        let mut triple = vec![0u8; triple_size];
+        crs.read_exact(&mut triple)?;
+        let triple = String::from_utf8_lossy(&triple).to_string();
+
+        let end = offset.checked_add(size).filter(|&end| end <= bundle.len())
+            .ok_or(invalid(&format!("code object for {} exceeds bundle bounds", triple)))?;
+
+        bundle_size = std::cmp::max(bundle_size, std::cmp::max(end, crs.position() as usize));
+        entries.push(BundleEntry { triple, code_object: bundle[offset..end].to_owned() });
+    }
+
+    Ok((entries, bundle_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* An uncompressed bundle with the entries' code objects following the header */
+    fn bundle(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let header_size = OFFLOAD_BUNDLE_MAGIC.len() + 8 + entries.iter().map(|(triple, _)| 24 + triple.len()).sum::<usize>();
+        let mut data = OFFLOAD_BUNDLE_MAGIC.to_vec();
+        data.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+        let mut offset = header_size;
+        for (triple, code_object) in entries {
+            for field in [offset, code_object.len(), triple.len()].iter() {
+                data.extend_from_slice(&(*field as u64).to_le_bytes());
+            }
+            data.extend_from_slice(triple.as_bytes());
+            offset += code_object.len();
+        }
+        for (_, code_object) in entries {
+            data.extend_from_slice(code_object);
+        }
+        data
+    }
+
+    fn triples(entries: &[BundleEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.triple.as_str()).collect()
+    }
+
+    #[test]
+    fn multiple_entries() {
+        let data = bundle(&[
+            ("host-x86_64-unknown-linux-gnu", b""),
+            ("hipv4-amdgcn-amd-amdhsa--gfx900", b"gfx900"),
+            ("hipv4-amdgcn-amd-amdhsa--gfx90a:sramecc+:xnack-", b"gfx90a")
+        ]);
+        let entries = bundle_entries(&data).unwrap();
+        assert_eq!(triples(&entries), vec!["host-x86_64-unknown-linux-gnu", "hipv4-amdgcn-amd-amdhsa--gfx900", "hipv4-amdgcn-amd-amdhsa--gfx90a:sramecc+:xnack-"]);
+        assert_eq!(entries[2].code_object, b"gfx90a");
+
+        let select = |target| bundle_entries(&data).and_then(|entries| select_entry(entries, target)).map(|e| e.code_object);
+        assert_eq!(select(None).unwrap(), b"gfx900");
+        assert_eq!(select(Some("gfx90a")).unwrap(), b"gfx90a");
+        assert_eq!(select(Some("gfx90a:sramecc+:xnack-")).unwrap(), b"gfx90a");
+        assert_eq!(select(Some("hipv4-amdgcn-amd-amdhsa--gfx90a")).unwrap(), b"gfx90a");
+        assert_eq!(select(Some("gfx90")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn bundles_of_each_translation_unit() {
+        let mut data = bundle(&[("hipv4-amdgcn-amd-amdhsa--gfx900", b"first")]);
+        data.resize(4096, 0);
+        data.extend(bundle(&[("hipv4-amdgcn-amd-amdhsa--gfx900", b"second"), ("hipv4-amdgcn-amd-amdhsa--gfx906", b"third")]));
+        let entries = parse_bundles(&data).unwrap();
+        let code_objects: Vec<&[u8]> = entries.iter().map(|e| e.code_object.as_slice()).collect();
+        assert_eq!(code_objects, vec![&b"first"[..], b"second", b"third"]);
+    }
+
+    #[test]
+    fn malformed_bundles() {
+        let data = bundle(&[("hipv4-amdgcn-amd-amdhsa--gfx900", b"gfx900")]);
+        /* The header ends in the middle of the entry's triple size */
+        assert!(bundle_entries(&data[..OFFLOAD_BUNDLE_MAGIC.len() + 8 + 20]).is_err());
+        /* The code object is cut off */
+        let err = bundle_entries(&data[..data.len() - 1]).unwrap_err();
+        assert!(err.to_string().contains("exceeds bundle bounds"), "{}", err);
+
+        let mut compressed = COMPRESSED_BUNDLE_MAGIC.to_vec();
+        compressed.extend_from_slice(&[0; 32]);
+        let err = bundle_entries(&compressed).unwrap_err();
+        assert!(err.to_string().contains("compressed"), "{}", err);
+
+        assert!(bundle_entries(b"not a bundle").is_err());
+    }
+}
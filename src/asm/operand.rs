@@ -0,0 +1,322 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    SReg(usize),
+    VReg(usize),
+    SRegs(usize, usize),
+    VRegs(usize, usize),
+    /* Trap handler temporaries (ttmp0, ttmp[4:5]) */
+    TReg(usize),
+    TRegs(usize, usize),
+    VCC,
+    Special(SpecialReg),
+    /* Integer inline constant or 32-bit literal */
+    Lit(i32),
+    /* Floating-point inline constant (0.5, -4.0, 1/2pi) */
+    Float(f32),
+    /* Absent address or scalar offset of memory instructions */
+    Off,
+    Offset(i32),
+    /* Element offsets of the two addresses of DS read2 and write2 */
+    Offset0(u8),
+    Offset1(u8),
+    /* VOP3 and SDWA source modifiers, -|v1| is Neg(Abs(VReg(1))) */
+    Neg(Box<Operand>),
+    Abs(Box<Operand>),
+    Sext(Box<Operand>),
+    Clamp,
+    Omod(OutputModifier),
+    OpSel(Vec<u8>),
+    SdwaSel(SdwaOperand, SdwaSel),
+    DstUnused(DstUnused),
+    Dpp(DppCtrl),
+    RowMask(u8),
+    BankMask(u8),
+    BoundCtrl,
+    Glc,
+    Slc,
+    Lds,
+    Tfe,
+    Gds,
+    Idxen,
+    Offen,
+    Waitcnt(Waitcnt),
+    SendMsg(SendMsg),
+    Hwreg(Hwreg),
+    Keyseq(String)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecialReg {
+    VCCLo, VCCHi,
+    Exec, ExecLo, ExecHi,
+    M0,
+    FlatScratch, FlatScratchLo, FlatScratchHi,
+    XnackMask, XnackMaskLo, XnackMaskHi,
+    SharedBase, SharedLimit, PrivateBase, PrivateLimit, PopsExitingWaveId,
+    VCCZ, ExecZ, SCC,
+    Null
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputModifier {
+    Mul2, Mul4, Div2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SdwaOperand {
+    Dst, Src0, Src1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SdwaSel {
+    Byte(u8), Word(u8), Dword
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DstUnused {
+    Pad, Sext, Preserve
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DppCtrl {
+    QuadPerm([u8; 4]),
+    RowShl(u8), RowShr(u8), RowRor(u8),
+    WaveShl, WaveRol, WaveShr, WaveRor,
+    RowMirror, RowHalfMirror,
+    RowBcast15, RowBcast31
+}
+
+/* Counters an s_waitcnt waits on; None means the counter is left at its maximum.
+ * vscnt is only present on GFX10, where it's set by s_waitcnt_vscnt */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Waitcnt {
+    pub vmcnt: Option<u8>,
+    pub expcnt: Option<u8>,
+    pub lgkmcnt: Option<u8>,
+    pub vscnt: Option<u8>
+}
+
+/* https://llvm.org/docs/AMDGPUOperandSyntax.html#sendmsg */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SendMsg {
+    pub msg: u8,
+    pub op: u8,
+    pub stream: u8
+}
+
+/* https://llvm.org/docs/AMDGPUOperandSyntax.html#hwreg */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hwreg {
+    pub id: u8,
+    pub offset: u8,
+    pub size: u8
+}
+
+const MSG_NAMES: [(u8, &str); 11] = [
+    (1, "MSG_INTERRUPT"), (2, "MSG_GS"), (3, "MSG_GS_DONE"), (4, "MSG_SAVEWAVE"),
+    (5, "MSG_STALL_WAVE_GEN"), (6, "MSG_HALT_WAVES"), (7, "MSG_ORDERED_PS_DONE"),
+    (8, "MSG_EARLY_PRIM_DEALLOC"), (9, "MSG_GS_ALLOC_REQ"), (10, "MSG_GET_DOORBELL"),
+    (15, "MSG_SYSMSG")
+];
+const GS_OPS: [&str; 4] = ["GS_OP_NOP", "GS_OP_CUT", "GS_OP_EMIT", "GS_OP_EMIT_CUT"];
+const SYSMSG_OPS: [&str; 5] = ["", "SYSMSG_OP_ECC_ERR_INTERRUPT", "SYSMSG_OP_REG_RD", "SYSMSG_OP_HOST_TRAP_ACK", "SYSMSG_OP_TTRACE_PC"];
+
+const HWREG_NAMES: [(u8, &str); 17] = [
+    (1, "HW_REG_MODE"), (2, "HW_REG_STATUS"), (3, "HW_REG_TRAPSTS"), (4, "HW_REG_HW_ID"),
+    (5, "HW_REG_GPR_ALLOC"), (6, "HW_REG_LDS_ALLOC"), (7, "HW_REG_IB_STS"), (15, "HW_REG_SH_MEM_BASES"),
+    (16, "HW_REG_TBA_LO"), (17, "HW_REG_TBA_HI"), (18, "HW_REG_TMA_LO"), (19, "HW_REG_TMA_HI"),
+    (20, "HW_REG_FLAT_SCR_LO"), (21, "HW_REG_FLAT_SCR_HI"), (22, "HW_REG_XNACK_MASK"),
+    (23, "HW_REG_POPS_PACKER"), (29, "HW_REG_SHADER_CYCLES")
+];
+
+impl SendMsg {
+    /* Decodes the immediate of s_sendmsg; bits outside of the message, operation and
+     * stream fields are ignored for known messages, unknown ones are kept as raw fields
+     * only if they encode back to the same immediate */
+    pub fn from_simm16(simm16: u16) -> Option<SendMsg> {
+        let msg = SendMsg { msg: (simm16 & 0xf) as u8, op: ((simm16 >> 4) & 0x7) as u8, stream: ((simm16 >> 8) & 0x3) as u8 };
+        match msg.is_known() || simm16 & !0x37f == 0 {
+            true => Some(msg),
+            false => None
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        matches!((self.msg, self.op, self.stream), (2, 1..=3, _) | (3, 1..=3, _) | (3, 0, 0) | (15, 1..=4, 0) | (1, 0, 0) | (4..=10, 0, 0))
+    }
+
+    pub fn from_names(msg: &str, op: Option<&str>, stream: Option<&str>) -> Option<SendMsg> {
+        let msg = MSG_NAMES.iter().find(|&&(_, name)| name == msg).map(|&(id, _)| id).or_else(|| msg.parse().ok())?;
+        let op_names: &[&str] = if msg == 15 { &SYSMSG_OPS } else { &GS_OPS };
+        let op = match op {
+            Some(op) => op_names.iter().position(|&name| name == op).map(|idx| idx as u8)
+                .or_else(|| op.parse().ok())?,
+            None => 0
+        };
+        let stream = match stream {
+            Some(stream) => stream.parse().ok()?,
+            None => 0
+        };
+        Some(SendMsg { msg, op, stream })
+    }
+}
+
+impl Hwreg {
+    pub fn from_simm16(simm16: u16) -> Hwreg {
+        Hwreg { id: (simm16 & 0x3f) as u8, offset: ((simm16 >> 6) & 0x1f) as u8, size: ((simm16 >> 11) & 0x1f) as u8 + 1 }
+    }
+
+    pub fn from_names(id: &str, offset: Option<&str>, size: Option<&str>) -> Option<Hwreg> {
+        let id = HWREG_NAMES.iter().find(|&&(_, name)| name == id).map(|&(id, _)| id).or_else(|| id.parse().ok())?;
+        let offset = match offset { Some(offset) => offset.parse().ok()?, None => 0 };
+        let size = match size { Some(size) => size.parse().ok()?, None => 32 };
+        Some(Hwreg { id, offset, size })
+    }
+}
+
+const SPECIAL_REGS: [(SpecialReg, &str); 21] = [
+    (SpecialReg::VCCLo, "vcc_lo"), (SpecialReg::VCCHi, "vcc_hi"),
+    (SpecialReg::Exec, "exec"), (SpecialReg::ExecLo, "exec_lo"), (SpecialReg::ExecHi, "exec_hi"),
+    (SpecialReg::M0, "m0"),
+    (SpecialReg::FlatScratch, "flat_scratch"), (SpecialReg::FlatScratchLo, "flat_scratch_lo"), (SpecialReg::FlatScratchHi, "flat_scratch_hi"),
+    (SpecialReg::XnackMask, "xnack_mask"), (SpecialReg::XnackMaskLo, "xnack_mask_lo"), (SpecialReg::XnackMaskHi, "xnack_mask_hi"),
+    (SpecialReg::SharedBase, "src_shared_base"), (SpecialReg::SharedLimit, "src_shared_limit"),
+    (SpecialReg::PrivateBase, "src_private_base"), (SpecialReg::PrivateLimit, "src_private_limit"),
+    (SpecialReg::PopsExitingWaveId, "src_pops_exiting_wave_id"),
+    (SpecialReg::VCCZ, "src_vccz"), (SpecialReg::ExecZ, "src_execz"), (SpecialReg::SCC, "src_scc"),
+    (SpecialReg::Null, "null")
+];
+
+impl SpecialReg {
+    pub fn from_name(name: &str) -> Option<SpecialReg> {
+        SPECIAL_REGS.iter().find(|&&(_, n)| n == name).map(|&(reg, _)| reg)
+    }
+
+    pub fn name(self) -> &'static str {
+        SPECIAL_REGS.iter().find(|&&(reg, _)| reg == self).map(|&(_, n)| n).unwrap()
+    }
+}
+
+impl Operand {
+    /* Inline constants are negated with neg() to tell -1.0 from neg(1.0) */
+    fn is_constant(&self) -> bool {
+        matches!(self, Operand::Lit(_) | Operand::Float(_))
+    }
+}
+
+impl fmt::Display for SdwaSel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdwaSel::Byte(idx) => write!(f, "BYTE_{}", idx),
+            SdwaSel::Word(idx) => write!(f, "WORD_{}", idx),
+            SdwaSel::Dword => write!(f, "DWORD")
+        }
+    }
+}
+
+impl fmt::Display for DppCtrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DppCtrl::QuadPerm(p) => write!(f, "quad_perm:[{},{},{},{}]", p[0], p[1], p[2], p[3]),
+            DppCtrl::RowShl(n) => write!(f, "row_shl:{}", n),
+            DppCtrl::RowShr(n) => write!(f, "row_shr:{}", n),
+            DppCtrl::RowRor(n) => write!(f, "row_ror:{}", n),
+            DppCtrl::WaveShl => write!(f, "wave_shl:1"),
+            DppCtrl::WaveRol => write!(f, "wave_rol:1"),
+            DppCtrl::WaveShr => write!(f, "wave_shr:1"),
+            DppCtrl::WaveRor => write!(f, "wave_ror:1"),
+            DppCtrl::RowMirror => write!(f, "row_mirror"),
+            DppCtrl::RowHalfMirror => write!(f, "row_half_mirror"),
+            DppCtrl::RowBcast15 => write!(f, "row_bcast:15"),
+            DppCtrl::RowBcast31 => write!(f, "row_bcast:31")
+        }
+    }
+}
+
+impl fmt::Display for Waitcnt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counters = [("vmcnt", self.vmcnt), ("expcnt", self.expcnt), ("lgkmcnt", self.lgkmcnt), ("vscnt", self.vscnt)];
+        let waits: Vec<String> = counters.iter()
+            .filter_map(|&(name, count)| count.map(|count| format!("{}({})", name, count)))
+            .collect();
+        write!(f, "{}", waits.join(" "))
+    }
+}
+
+impl fmt::Display for SendMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = MSG_NAMES.iter().find(|&&(id, _)| id == self.msg).map(|&(_, name)| name).unwrap_or("");
+        match (self.is_known(), self.msg, self.op) {
+            (false, _, _) => write!(f, "sendmsg({}, {}, {})", self.msg, self.op, self.stream),
+            (true, 3, 0) => write!(f, "sendmsg({}, {})", name, GS_OPS[0]),
+            (true, 2, _) | (true, 3, _) => write!(f, "sendmsg({}, {}, {})", name, GS_OPS[self.op as usize], self.stream),
+            (true, 15, _) => write!(f, "sendmsg({}, {})", name, SYSMSG_OPS[self.op as usize]),
+            (true, _, _) => write!(f, "sendmsg({})", name)
+        }
+    }
+}
+
+impl fmt::Display for Hwreg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = HWREG_NAMES.iter().find(|&&(id, _)| id == self.id).map(|&(_, name)| name.to_owned())
+            .unwrap_or_else(|| self.id.to_string());
+        match (self.offset, self.size) {
+            (0, 32) => write!(f, "hwreg({})", name),
+            _ => write!(f, "hwreg({}, {}, {})", name, self.offset, self.size)
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::SReg(idx) => write!(f, "s{}", idx),
+            Operand::VReg(idx) => write!(f, "v{}", idx),
+            Operand::SRegs(from, to) => write!(f, "s[{}:{}]", from, to),
+            Operand::VRegs(from, to) => write!(f, "v[{}:{}]", from, to),
+            Operand::TReg(idx) => write!(f, "ttmp{}", idx),
+            Operand::TRegs(from, to) => write!(f, "ttmp[{}:{}]", from, to),
+            Operand::VCC => write!(f, "vcc"),
+            Operand::Special(reg) => write!(f, "{}", reg.name()),
+            Operand::Lit(value) => write!(f, "{}", value),
+            Operand::Float(value) => write!(f, "{:?}", value),
+            Operand::Off => write!(f, "off"),
+            Operand::Offset(offset) => write!(f, "offset:{}", offset),
+            Operand::Offset0(offset) => write!(f, "offset0:{}", offset),
+            Operand::Offset1(offset) => write!(f, "offset1:{}", offset),
+            Operand::Neg(op) if op.is_constant() => write!(f, "neg({})", op),
+            Operand::Neg(op) => write!(f, "-{}", op),
+            Operand::Abs(op) => write!(f, "|{}|", op),
+            Operand::Sext(op) => write!(f, "sext({})", op),
+            Operand::Clamp => write!(f, "clamp"),
+            Operand::Omod(OutputModifier::Mul2) => write!(f, "mul:2"),
+            Operand::Omod(OutputModifier::Mul4) => write!(f, "mul:4"),
+            Operand::Omod(OutputModifier::Div2) => write!(f, "div:2"),
+            Operand::OpSel(sel) => write!(f, "op_sel:[{}]",
+                sel.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(",")),
+            Operand::SdwaSel(SdwaOperand::Dst, sel) => write!(f, "dst_sel:{}", sel),
+            Operand::SdwaSel(SdwaOperand::Src0, sel) => write!(f, "src0_sel:{}", sel),
+            Operand::SdwaSel(SdwaOperand::Src1, sel) => write!(f, "src1_sel:{}", sel),
+            Operand::DstUnused(DstUnused::Pad) => write!(f, "dst_unused:UNUSED_PAD"),
+            Operand::DstUnused(DstUnused::Sext) => write!(f, "dst_unused:UNUSED_SEXT"),
+            Operand::DstUnused(DstUnused::Preserve) => write!(f, "dst_unused:UNUSED_PRESERVE"),
+            Operand::Dpp(ctrl) => write!(f, "{}", ctrl),
+            Operand::RowMask(mask) => write!(f, "row_mask:{:#x}", mask),
+            Operand::BankMask(mask) => write!(f, "bank_mask:{:#x}", mask),
+            Operand::BoundCtrl => write!(f, "bound_ctrl:0"),
+            Operand::Glc => write!(f, "glc"),
+            Operand::Slc => write!(f, "slc"),
+            Operand::Lds => write!(f, "lds"),
+            Operand::Tfe => write!(f, "tfe"),
+            Operand::Gds => write!(f, "gds"),
+            Operand::Idxen => write!(f, "idxen"),
+            Operand::Offen => write!(f, "offen"),
+            Operand::Waitcnt(waitcnt) => write!(f, "{}", waitcnt),
+            Operand::SendMsg(msg) => write!(f, "{}", msg),
+            Operand::Hwreg(hwreg) => write!(f, "{}", hwreg),
+            Operand::Keyseq(keyseq) => write!(f, "{}", keyseq)
+        }
+    }
+}
@@ -0,0 +1,443 @@
+/* Instruction semantics keyed by mnemonic: which operands are read and written,
+ * implicit register effects, operand data types, memory accesses and branches.
+ *
+ * Encodings and explicit operand widths come from the GFX9 decoder tables, the
+ * rest is derived from the mnemonic following the Vega ISA manual. */
+
+use std::collections::HashMap;
+
+use super::Operand;
+use super::decoder::opcodes::{self, Opcode, SDST};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    SOP2, SOP1, SOPK, SOPC, SOPP, SMEM,
+    VOP2, VOP1, VOPC, VOP3,
+    DS, FLAT, GLOBAL, SCRATCH, MUBUF, MTBUF
+}
+
+impl Format {
+    pub fn is_salu(self) -> bool {
+        matches!(self, Format::SOP2 | Format::SOP1 | Format::SOPK | Format::SOPC | Format::SOPP)
+    }
+
+    pub fn is_valu(self) -> bool {
+        matches!(self, Format::VOP2 | Format::VOP1 | Format::VOPC | Format::VOP3)
+    }
+}
+
+/* Registers read or written without appearing in the operand list */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImplicitReg {
+    SCC, VCC, Exec, M0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataType {
+    Int(u16), Uint(u16), Float(u16), Bits(u16)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddressSpace {
+    /* Read-only through the scalar cache (s_load_*) */
+    Constant,
+    Global, Flat, Scratch, Buffer, Lds, Gds
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryAccess {
+    Load(AddressSpace),
+    Store(AddressSpace),
+    /* Read-modify-write, the pre-op value is returned by DS _rtn ops and by others when glc is set */
+    Atomic(AddressSpace)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BranchCondition {
+    Always, SCC0, SCC1, VCCZ, VCCNZ, ExecZ, ExecNZ, Debug
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Branch {
+    /* Jump to PC + 4 + simm16 * 4 when the condition holds */
+    Direct(BranchCondition),
+    /* Jump to an address held in SGPRs (s_setpc_b64, s_rfe_b64, fork and join) */
+    Indirect,
+    /* Jump that saves the return address (s_call_b64, s_swappc_b64, s_trap) */
+    Call,
+    End
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Semantics {
+    pub format: Format,
+    /* Number of leading explicit operands written by the instruction, including
+     * the VCC carry that VOP2 and VOPC encodings spell out */
+    pub defs: usize,
+    /* The first def is also read: accumulators, conditional moves, partial writes */
+    pub tied: bool,
+    pub implicit_defs: Vec<ImplicitReg>,
+    pub implicit_uses: Vec<ImplicitReg>,
+    pub dst_type: Option<DataType>,
+    pub src_type: Option<DataType>,
+    /* Compares write SCC or a lane mask regardless of the operand type */
+    pub compare: bool,
+    pub memory: Option<MemoryAccess>,
+    pub branch: Option<Branch>
+}
+
+impl Semantics {
+    /* Non-DS atomics only write the pre-op value back when glc is set. Flat atomics
+     * gain a separate vdst operand, buffer and scalar atomics overwrite their data */
+    fn returns_atomic(&self, ops: &[Operand]) -> bool {
+        match self.memory {
+            Some(MemoryAccess::Atomic(_)) if self.format != Format::DS => ops.contains(&Operand::Glc),
+            _ => false
+        }
+    }
+
+    fn def_count(&self, ops: &[Operand]) -> usize {
+        if self.returns_atomic(ops) { self.defs + 1 } else { self.defs }
+    }
+
+    /* Register operands written by the instruction */
+    pub fn defs<'a>(&self, ops: &'a [Operand]) -> Vec<&'a Operand> {
+        ops.iter().take(self.def_count(ops)).filter_map(register).collect()
+    }
+
+    /* Register operands read by the instruction, with source modifiers stripped */
+    pub fn uses<'a>(&self, ops: &'a [Operand]) -> Vec<&'a Operand> {
+        let defs = self.def_count(ops);
+        let tied = self.tied || (self.returns_atomic(ops) && matches!(self.format, Format::SMEM | Format::MUBUF | Format::MTBUF));
+        let tied_def = if tied { ops.first() } else { None };
+        tied_def.into_iter().chain(ops.iter().skip(defs)).filter_map(register).collect()
+    }
+}
+
+fn register(op: &Operand) -> Option<&Operand> {
+    match op {
+        Operand::Neg(inner) | Operand::Abs(inner) | Operand::Sext(inner) => register(inner),
+        Operand::SReg(_) | Operand::VReg(_) | Operand::SRegs(_, _) | Operand::VRegs(_, _) |
+        Operand::TReg(_) | Operand::TRegs(_, _) | Operand::VCC | Operand::Special(_) => Some(op),
+        _ => None
+    }
+}
+
+const TABLES: [(Format, &[Opcode]); 15] = [
+    (Format::SOP2, opcodes::SOP2), (Format::SOP1, opcodes::SOP1), (Format::SOPK, opcodes::SOPK),
+    (Format::SOPC, opcodes::SOPC), (Format::SOPP, opcodes::SOPP), (Format::SMEM, opcodes::SMEM),
+    (Format::VOP2, opcodes::VOP2), (Format::VOP1, opcodes::VOP1), (Format::VOPC, opcodes::VOPC),
+    (Format::DS, opcodes::DS), (Format::FLAT, opcodes::FLAT), (Format::GLOBAL, opcodes::GLOBAL),
+    (Format::SCRATCH, opcodes::SCRATCH), (Format::MUBUF, opcodes::MUBUF), (Format::MTBUF, opcodes::MTBUF)
+];
+
+/* Encoding suffixes do not change what the instruction does */
+fn base_name(mnemonic: &str) -> &str {
+    ["_e32", "_e64", "_sdwa", "_dpp"].iter()
+        .find(|suffix| mnemonic.ends_with(*suffix))
+        .map(|suffix| &mnemonic[..mnemonic.len() - suffix.len()])
+        .unwrap_or(mnemonic)
+}
+
+type OpcodeInfo = (Format, [u8; 4], u8);
+
+/* Opcodes by mnemonic and by mnemonic without the encoding suffix, the first table listing a name wins */
+struct OpcodeIndex {
+    exact: HashMap<&'static str, OpcodeInfo>,
+    base: HashMap<&'static str, OpcodeInfo>
+}
+
+impl OpcodeIndex {
+    fn build() -> OpcodeIndex {
+        let mut index = OpcodeIndex { exact: HashMap::new(), base: HashMap::new() };
+        let opcodes = TABLES.iter()
+            .flat_map(|&(format, table)| table.iter().map(move |&(_, name, widths)| (name, (format, widths, 0))))
+            .chain(opcodes::VOP3.iter().map(|&(_, name, widths, mods)| (name, (Format::VOP3, widths, mods))));
+        for (name, info) in opcodes {
+            index.exact.entry(name).or_insert(info);
+            index.base.entry(base_name(name)).or_insert(info);
+        }
+        index
+    }
+}
+
+thread_local! {
+    static OPCODES: OpcodeIndex = OpcodeIndex::build();
+}
+
+/* Returns the encoding, operand widths and VOP3 modifiers of the mnemonic,
+ * preferring the encoding named by its suffix */
+fn find_opcode(mnemonic: &str) -> Option<OpcodeInfo> {
+    OPCODES.with(|index| index.exact.get(mnemonic).or_else(|| index.base.get(base_name(mnemonic))).cloned())
+}
+
+pub fn lookup(mnemonic: &str) -> Option<Semantics> {
+    let (format, widths, mods) = find_opcode(mnemonic)?;
+    let name = base_name(mnemonic);
+    let memory = memory_access(format, name);
+    let branch = branch(name);
+
+    let defs = match format {
+        Format::SOPC | Format::SOPP => 0,
+        Format::SOPK if name.starts_with("s_cmpk_") || name == "s_cbranch_i_fork" => 0,
+        Format::VOPC => 1,
+        /* The carry-out of VOP2 _co_ ops is written to an explicit vcc operand */
+        Format::VOP2 if name.contains("_co_") => 2,
+        Format::VOP3 if mods & SDST != 0 => 2,
+        Format::SMEM | Format::FLAT | Format::GLOBAL | Format::SCRATCH | Format::MUBUF | Format::MTBUF => match memory {
+            Some(MemoryAccess::Load(_)) => 1,
+            Some(_) => 0,
+            None if widths[0] > 0 => 1,
+            None => 0
+        },
+        _ if widths[0] > 0 => 1,
+        _ => 0
+    };
+
+    let tied = match name {
+        "s_cmov_b32" | "s_cmov_b64" | "s_cmovk_i32" | "s_addk_i32" | "s_mulk_i32" | "v_writelane_b32" => true,
+        _ if name.starts_with("s_bitset") => true,
+        _ if name.starts_with("v_mac_") || name.starts_with("v_fmac_") => true,
+        /* d16 loads preserve the other half of the destination register */
+        _ if name.contains("_d16") => matches!(memory, Some(MemoryAccess::Load(_))),
+        _ => false
+    };
+
+    let mut implicit_defs = Vec::new();
+    let mut implicit_uses = Vec::new();
+
+    if writes_scc(format, name) {
+        implicit_defs.push(ImplicitReg::SCC);
+    }
+    if reads_scc(name) {
+        implicit_uses.push(ImplicitReg::SCC);
+    }
+    if name.contains("saveexec") || name.contains("wrexec") {
+        implicit_defs.push(ImplicitReg::Exec);
+        implicit_uses.push(ImplicitReg::Exec);
+    }
+    /* v_readlane and v_writelane address a lane directly and ignore EXEC */
+    let lane_access = name == "v_readlane_b32" || name == "v_writelane_b32";
+    let vector_memory = format != Format::SMEM && memory.is_some();
+    if (format.is_valu() && !lane_access && name != "v_nop") || format == Format::DS || vector_memory {
+        implicit_uses.push(ImplicitReg::Exec);
+    }
+    if name.starts_with("v_cmpx_") {
+        implicit_defs.push(ImplicitReg::Exec);
+    }
+    if name.starts_with("v_div_fmas_") {
+        implicit_uses.push(ImplicitReg::VCC);
+    }
+    /* LDS accesses are clamped to M0 before GFX9; GWS, ordered count and addtid ops still read it */
+    if format == Format::DS || name.contains("movrel") || name.starts_with("s_sendmsg") || name == "s_ttracedata" {
+        implicit_uses.push(ImplicitReg::M0);
+    }
+    if name.starts_with("s_set_gpr_idx_") && name != "s_set_gpr_idx_off" {
+        implicit_defs.push(ImplicitReg::M0);
+        /* idx and mode replace a single field of M0 */
+        if name != "s_set_gpr_idx_on" {
+            implicit_uses.push(ImplicitReg::M0);
+        }
+    }
+    match branch {
+        Some(Branch::Direct(BranchCondition::SCC0)) | Some(Branch::Direct(BranchCondition::SCC1)) =>
+            implicit_uses.push(ImplicitReg::SCC),
+        Some(Branch::Direct(BranchCondition::VCCZ)) | Some(Branch::Direct(BranchCondition::VCCNZ)) =>
+            implicit_uses.push(ImplicitReg::VCC),
+        Some(Branch::Direct(BranchCondition::ExecZ)) | Some(Branch::Direct(BranchCondition::ExecNZ)) =>
+            implicit_uses.push(ImplicitReg::Exec),
+        _ => ()
+    }
+
+    let compare = format == Format::SOPC || format == Format::VOPC
+        || name.starts_with("s_cmpk_") || name.starts_with("v_cmp");
+    let (dst_type, src_type) = data_types(name, compare);
+
+    Some(Semantics { format, defs, tied, implicit_defs, implicit_uses, dst_type, src_type, compare, memory, branch })
+}
+
+fn writes_scc(format: Format, name: &str) -> bool {
+    match format {
+        Format::SOP2 => !(name.starts_with("s_cselect_") || name.starts_with("s_bfm_") || name.starts_with("s_mul_")
+            || name.starts_with("s_pack_") || name == "s_cbranch_g_fork" || name == "s_rfe_restore_b64"),
+        Format::SOP1 => ["s_not_", "s_wqm_", "s_bcnt", "s_quadmask_", "s_abs_"].iter().any(|p| name.starts_with(p))
+            || name.contains("saveexec") || name.contains("wrexec"),
+        Format::SOPK => name.starts_with("s_cmpk_") || name == "s_addk_i32",
+        Format::SOPC => name != "s_setvskip" && name != "s_set_gpr_idx_on",
+        _ => false
+    }
+}
+
+fn reads_scc(name: &str) -> bool {
+    name.starts_with("s_cselect_") || name.starts_with("s_cmov") || name == "s_addc_u32" || name == "s_subb_u32"
+}
+
+fn branch(name: &str) -> Option<Branch> {
+    use self::BranchCondition::*;
+    match name {
+        "s_branch" => Some(Branch::Direct(Always)),
+        "s_cbranch_scc0" => Some(Branch::Direct(SCC0)),
+        "s_cbranch_scc1" => Some(Branch::Direct(SCC1)),
+        "s_cbranch_vccz" => Some(Branch::Direct(VCCZ)),
+        "s_cbranch_vccnz" => Some(Branch::Direct(VCCNZ)),
+        "s_cbranch_execz" => Some(Branch::Direct(ExecZ)),
+        "s_cbranch_execnz" => Some(Branch::Direct(ExecNZ)),
+        _ if name.starts_with("s_cbranch_cdbg") => Some(Branch::Direct(Debug)),
+        "s_setpc_b64" | "s_rfe_b64" | "s_rfe_restore_b64" | "s_cbranch_i_fork" | "s_cbranch_g_fork" | "s_cbranch_join" =>
+            Some(Branch::Indirect),
+        "s_call_b64" | "s_swappc_b64" | "s_trap" => Some(Branch::Call),
+        _ if name.starts_with("s_endpgm") => Some(Branch::End),
+        _ => None
+    }
+}
+
+fn memory_access(format: Format, name: &str) -> Option<MemoryAccess> {
+    let space = match format {
+        Format::SMEM if name.starts_with("s_buffer_") => AddressSpace::Buffer,
+        Format::SMEM if name.starts_with("s_scratch_") => AddressSpace::Scratch,
+        Format::SMEM if name.starts_with("s_load_") => AddressSpace::Constant,
+        Format::SMEM => AddressSpace::Global,
+        Format::DS if name.starts_with("ds_gws_") || name == "ds_ordered_count" => AddressSpace::Gds,
+        Format::DS => AddressSpace::Lds,
+        Format::FLAT => AddressSpace::Flat,
+        Format::GLOBAL => AddressSpace::Global,
+        Format::SCRATCH => AddressSpace::Scratch,
+        Format::MUBUF | Format::MTBUF => AddressSpace::Buffer,
+        _ => return None
+    };
+    let has = |op: &str| name.contains(op);
+
+    if format == Format::DS {
+        return match name {
+            "ds_nop" | "ds_swizzle_b32" | "ds_permute_b32" | "ds_bpermute_b32" => None,
+            _ if has("ds_read") => Some(MemoryAccess::Load(space)),
+            _ if has("ds_write") && !has("ds_wrxchg") => Some(MemoryAccess::Store(space)),
+            _ => Some(MemoryAccess::Atomic(space))
+        };
+    }
+    if has("_load") {
+        Some(MemoryAccess::Load(space))
+    }
+    else if has("_store") {
+        Some(MemoryAccess::Store(space))
+    }
+    else if has("_atomic") {
+        Some(MemoryAccess::Atomic(space))
+    }
+    else {
+        /* Cache control (s_dcache_wb, buffer_wbinvl1) and counters (s_memtime) */
+        None
+    }
+}
+
+/* Operand types come from the mnemonic: v_cvt_f32_i32 writes f32 and reads i32,
+ * v_add_u32 reads and writes u32, buffer_load_sbyte reads a sign-extended byte */
+fn data_types(name: &str, compare: bool) -> (Option<DataType>, Option<DataType>) {
+    let types: Vec<DataType> = name.split('_').filter_map(data_type).collect();
+    let (dst, src) = match types.as_slice() {
+        /* Untyped atomics (buffer_atomic_swap_x2) operate on whole dwords */
+        [] if name.contains("_atomic_") => {
+            let ty = DataType::Bits(if name.ends_with("_x2") { 64 } else { 32 });
+            (ty, ty)
+        },
+        [] => return (None, None),
+        [ty] => (*ty, *ty),
+        [dst, src, ..] => (*dst, *src)
+    };
+    if compare { (None, Some(src)) } else { (Some(dst), Some(src)) }
+}
+
+fn data_type(component: &str) -> Option<DataType> {
+    let sized = |bits: &str| bits.parse::<u16>().ok().filter(|bits| (8..=64).contains(bits));
+    match component {
+        "ubyte" => Some(DataType::Uint(8)),
+        "sbyte" => Some(DataType::Int(8)),
+        "byte" => Some(DataType::Bits(8)),
+        "ushort" => Some(DataType::Uint(16)),
+        "sshort" => Some(DataType::Int(16)),
+        "short" => Some(DataType::Bits(16)),
+        "dword" => Some(DataType::Bits(32)),
+        _ if component.starts_with("dwordx") => component[6..].parse::<u16>().ok().map(|n| DataType::Bits(n * 32)),
+        _ if component.len() > 1 => match component.as_bytes()[0] {
+            b'i' => sized(&component[1..]).map(DataType::Int),
+            b'u' => sized(&component[1..]).map(DataType::Uint),
+            b'f' => sized(&component[1..]).map(DataType::Float),
+            b'b' => sized(&component[1..]).map(DataType::Bits),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings_by_suffix() {
+        assert_eq!(lookup("v_add_f32").unwrap().format, Format::VOP2);
+        assert_eq!(lookup("v_add_f32_e32").unwrap().format, Format::VOP2);
+        assert_eq!(lookup("v_add_f32_e64").unwrap().format, Format::VOP3);
+        assert_eq!(lookup("v_fma_f32").unwrap().format, Format::VOP3);
+        assert_eq!(lookup("v_add_f32_sdwa").unwrap().format, Format::VOP2);
+        assert_eq!(lookup("v_frobnicate_b32"), None);
+    }
+
+    #[test]
+    fn compares() {
+        let cmp = lookup("v_cmp_lt_f32_e64").unwrap();
+        assert_eq!((cmp.compare, cmp.dst_type, cmp.src_type), (true, None, Some(DataType::Float(32))));
+        assert!(!cmp.implicit_defs.contains(&ImplicitReg::Exec));
+        let cmpx = lookup("v_cmpx_eq_u32_e32").unwrap();
+        assert_eq!((cmpx.compare, cmpx.defs), (true, 1));
+        assert!(cmpx.implicit_defs.contains(&ImplicitReg::Exec));
+        let cmpk = lookup("s_cmpk_lg_i32").unwrap();
+        assert_eq!((cmpk.compare, cmpk.defs, cmpk.src_type), (true, 0, Some(DataType::Int(32))));
+        assert_eq!(cmpk.implicit_defs, vec![ImplicitReg::SCC]);
+        assert!(!lookup("v_cndmask_b32_e32").unwrap().compare);
+    }
+
+    #[test]
+    fn memory_accesses() {
+        let load = lookup("s_load_dwordx2").unwrap();
+        assert_eq!((load.memory, load.dst_type, load.defs), (Some(MemoryAccess::Load(AddressSpace::Constant)), Some(DataType::Bits(64)), 1));
+        let store = lookup("global_store_dword").unwrap();
+        assert_eq!((store.memory, store.src_type, store.defs), (Some(MemoryAccess::Store(AddressSpace::Global)), Some(DataType::Bits(32)), 0));
+        assert_eq!(lookup("flat_load_ushort").unwrap().dst_type, Some(DataType::Uint(16)));
+        assert_eq!(lookup("ds_wrxchg_rtn_b32").unwrap().memory, Some(MemoryAccess::Atomic(AddressSpace::Lds)));
+        assert_eq!(lookup("s_memtime").unwrap().memory, None);
+
+        let ops = [Operand::VReg(0), Operand::VRegs(2, 3), Operand::VReg(1), Operand::Off];
+        let atomic = lookup("global_atomic_add").unwrap();
+        assert_eq!(atomic.defs(&ops), Vec::<&Operand>::new());
+        let ops = [Operand::VReg(0), Operand::VRegs(2, 3), Operand::VReg(1), Operand::Off, Operand::Glc];
+        assert_eq!(atomic.defs(&ops), vec![&Operand::VReg(0)]);
+    }
+
+    #[test]
+    fn implicit_registers() {
+        let addc = lookup("s_addc_u32").unwrap();
+        assert_eq!((addc.implicit_defs, addc.implicit_uses), (vec![ImplicitReg::SCC], vec![ImplicitReg::SCC]));
+        let saveexec = lookup("s_and_saveexec_b64").unwrap();
+        assert!(saveexec.implicit_defs.contains(&ImplicitReg::Exec) && saveexec.implicit_uses.contains(&ImplicitReg::Exec));
+        assert_eq!(lookup("v_readlane_b32").unwrap().implicit_uses, Vec::new());
+        assert!(lookup("v_div_fmas_f32").unwrap().implicit_uses.contains(&ImplicitReg::VCC));
+
+        let branch = lookup("s_cbranch_vccnz").unwrap();
+        assert_eq!((branch.branch, branch.implicit_uses), (Some(Branch::Direct(BranchCondition::VCCNZ)), vec![ImplicitReg::VCC]));
+        assert_eq!(lookup("s_endpgm").unwrap().branch, Some(Branch::End));
+        assert_eq!(lookup("s_setpc_b64").unwrap().branch, Some(Branch::Indirect));
+    }
+
+    #[test]
+    fn operand_types() {
+        let cvt = lookup("v_cvt_f32_i32_e32").unwrap();
+        assert_eq!((cvt.dst_type, cvt.src_type), (Some(DataType::Float(32)), Some(DataType::Int(32))));
+        let sbyte = lookup("buffer_load_sbyte").unwrap();
+        assert_eq!(sbyte.dst_type, Some(DataType::Int(8)));
+        let mac = lookup("v_mac_f32_e32").unwrap();
+        assert!(mac.tied);
+        assert_eq!(mac.uses(&[Operand::VReg(0), Operand::Neg(Box::new(Operand::VReg(1))), Operand::SReg(2)]),
+            vec![&Operand::VReg(0), &Operand::VReg(1), &Operand::SReg(2)]);
+    }
+}
@@ -0,0 +1,327 @@
+use std::io;
+use std::fmt;
+use std::io::Cursor;
+use byteorder::{LE, ByteOrder, ReadBytesExt};
+
+use super::notes::{Note, parse_notes};
+
+/* https://llvm.org/docs/AMDGPUUsage.html#elf-header */
+const EF_AMDGPU_MACH: u32 = 0xff;
+const EF_AMDGPU_XNACK_V3: u32 = 0x100;
+const EF_AMDGPU_FEATURE_XNACK_V4: u32 = 0x300;
+const EF_AMDGPU_FEATURE_XNACK_ON_V4: u32 = 0x300;
+const EF_AMDGPU_FEATURE_XNACK_OFF_V4: u32 = 0x200;
+const EF_AMDGPU_SRAM_ECC_V3: u32 = 0x200;
+const EF_AMDGPU_FEATURE_SRAMECC_V4: u32 = 0xc00;
+const EF_AMDGPU_FEATURE_SRAMECC_ON_V4: u32 = 0xc00;
+const EF_AMDGPU_FEATURE_SRAMECC_OFF_V4: u32 = 0x800;
+const ELFCLASS64: u8 = 2;
+const ELFABIVERSION_AMDGPU_HSA_V3: u8 = 1;
+
+/* https://llvm.org/docs/AMDGPUUsage.html#code-object-v2-note-records */
+const NT_AMD_HSA_ISA_VERSION: u32 = 3;
+const NT_AMD_HSA_ISA_NAME: u32 = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Generation {
+    GFX6,
+    GFX7,
+    GFX8,
+    GFX9,
+    GFX10,
+    GFX11
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    /* LLVM processor name, e.g. gfx906 */
+    pub processor: String,
+    pub generation: Generation,
+    pub major: u16,
+    pub minor: u16,
+    pub stepping: u16,
+    /* Whether XNACK replay is enabled, if the code object or processor name specifies it */
+    pub xnack: Option<bool>,
+    /* Whether SRAM ECC is enabled, likewise */
+    pub sramecc: Option<bool>
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.processor)
+    }
+}
+
+impl Target {
+    /* Parses a processor name such as gfx803, gfx90a or gfx1030, followed by
+     * target features in the v4 (gfx906:xnack-) or v3 (gfx906+xnack) syntax */
+    pub fn from_processor(name: &str) -> Option<Target> {
+        let mut parts = name.split(|c| c == ':' || c == '+');
+        let processor = parts.next().unwrap_or(name).to_lowercase();
+        let features: Vec<&str> = parts.collect();
+        let feature = |name: &str| features.iter().filter_map(|feature| match feature.strip_prefix(name)? {
+            "" | "+" => Some(true),
+            "-" => Some(false),
+            _ => None
+        }).last();
+        let (xnack, sramecc) = (feature("xnack"), feature("sramecc"));
+        if !processor.starts_with("gfx") || processor.len() < 6 {
+            return None;
+        }
+        let version = &processor[3..];
+
+        let (major, minor_stepping) = version.split_at(version.len() - 2);
+        let major = major.parse::<u16>().ok()?;
+        let minor = u16::from_str_radix(&minor_stepping[..1], 16).ok()?;
+        let stepping = u16::from_str_radix(&minor_stepping[1..], 16).ok()?;
+
+        Target::from_version(major, minor, stepping).map(|target| Target { xnack: xnack.or(target.xnack), sramecc, ..target })
+    }
+
+    pub fn from_version(major: u16, minor: u16, stepping: u16) -> Option<Target> {
+        let generation = match major {
+            6 => Generation::GFX6,
+            7 => Generation::GFX7,
+            8 => Generation::GFX8,
+            9 => Generation::GFX9,
+            10 => Generation::GFX10,
+            11 => Generation::GFX11,
+            _ => return None
+        };
+        /* Code object v2 encoded xnack-enabled gfx900/gfx902 as gfx901/gfx903 */
+        let (stepping, xnack) = match (major, minor, stepping) {
+            (9, 0, 1) | (9, 0, 3) => (stepping - 1, Some(true)),
+            _ => (stepping, None)
+        };
+        let processor = format!("gfx{}{:x}{:x}", major, minor, stepping);
+
+        Some(Target { processor, generation, major, minor, stepping, xnack, sramecc: None })
+    }
+
+    /* Compares the target against the machine version recorded in amd_kernel_code_t */
+    pub fn matches_version(&self, major: u16, minor: u16, stepping: u16) -> bool {
+        match Target::from_version(major, minor, stepping) {
+            Some(other) => other.processor == self.processor,
+            None => false
+        }
+    }
+}
+
+/* Determines the target from the EF_AMDGPU_MACH field of the ELF header,
+ * falling back to the ISA notes of code object v2 */
+pub fn detect(code_object: &[u8], bin: &elf::File) -> io::Result<Target> {
+    if let Some(flags) = e_flags(code_object) {
+        if let Some(target) = target_from_mach(flags) {
+            let abi_version = code_object.get(8).cloned().unwrap_or(0);
+            let xnack = xnack_from_flags(abi_version, flags);
+            let sramecc = sramecc_from_flags(abi_version, flags);
+            return Ok(Target { xnack: xnack.or(target.xnack), sramecc, ..target });
+        }
+    }
+
+    let notes = match bin.get_section(".note") {
+        Some(section) => parse_notes(&section.data)?,
+        None => Vec::new()
+    };
+    notes.iter()
+        .filter(|n| n.name == "AMD")
+        .filter_map(target_from_note)
+        .next()
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData,
+            "unable to determine the target GPU from the code object, specify it with --mcpu"))
+}
+
+fn e_flags(code_object: &[u8]) -> Option<u32> {
+    /* elf::File does not expose e_flags, so it's read from the raw header */
+    if code_object.get(4) != Some(&ELFCLASS64) {
+        return None;
+    }
+    code_object.get(48..52).map(LE::read_u32)
+}
+
+/* Code object v3 has a single XNACK bit, v4+ distinguish on, off and any (unspecified) */
+fn xnack_from_flags(abi_version: u8, e_flags: u32) -> Option<bool> {
+    match abi_version {
+        0 => None,
+        ELFABIVERSION_AMDGPU_HSA_V3 => Some(e_flags & EF_AMDGPU_XNACK_V3 != 0),
+        _ => match e_flags & EF_AMDGPU_FEATURE_XNACK_V4 {
+            EF_AMDGPU_FEATURE_XNACK_ON_V4 => Some(true),
+            EF_AMDGPU_FEATURE_XNACK_OFF_V4 => Some(false),
+            _ => None
+        }
+    }
+}
+
+fn sramecc_from_flags(abi_version: u8, e_flags: u32) -> Option<bool> {
+    match abi_version {
+        0 => None,
+        ELFABIVERSION_AMDGPU_HSA_V3 => Some(e_flags & EF_AMDGPU_SRAM_ECC_V3 != 0),
+        _ => match e_flags & EF_AMDGPU_FEATURE_SRAMECC_V4 {
+            EF_AMDGPU_FEATURE_SRAMECC_ON_V4 => Some(true),
+            EF_AMDGPU_FEATURE_SRAMECC_OFF_V4 => Some(false),
+            _ => None
+        }
+    }
+}
+
+fn target_from_mach(e_flags: u32) -> Option<Target> {
+    let processor = match e_flags & EF_AMDGPU_MACH {
+        0x020 => "gfx600",
+        0x021 => "gfx601",
+        0x022 => "gfx700",
+        0x023 => "gfx701",
+        0x024 => "gfx702",
+        0x025 => "gfx703",
+        0x026 => "gfx704",
+        0x028 => "gfx801",
+        0x029 => "gfx802",
+        0x02a => "gfx803",
+        0x02b => "gfx810",
+        0x02c => "gfx900",
+        0x02d => "gfx902",
+        0x02e => "gfx904",
+        0x02f => "gfx906",
+        0x030 => "gfx908",
+        0x031 => "gfx909",
+        0x032 => "gfx90c",
+        0x033 => "gfx1010",
+        0x034 => "gfx1011",
+        0x035 => "gfx1012",
+        0x036 => "gfx1030",
+        0x037 => "gfx1031",
+        0x038 => "gfx1032",
+        0x039 => "gfx1033",
+        0x03a => "gfx602",
+        0x03b => "gfx705",
+        0x03c => "gfx805",
+        0x03d => "gfx1035",
+        0x03e => "gfx1034",
+        0x03f => "gfx90a",
+        0x041 => "gfx1100",
+        0x042 => "gfx1013",
+        0x044 => "gfx1103",
+        0x045 => "gfx1036",
+        0x046 => "gfx1101",
+        0x047 => "gfx1102",
+        _ => return None
+    };
+    Target::from_processor(processor)
+}
+
+fn target_from_note(note: &Note) -> Option<Target> {
+    match note.note_type {
+        NT_AMD_HSA_ISA_VERSION => {
+            /* u16 vendor name size, u16 architecture name size, u32 major, u32 minor, u32 stepping */
+            let mut crs = Cursor::new(&note.desc);
+            crs.set_position(4);
+            let major = crs.read_u32::<LE>().ok()?;
+            let minor = crs.read_u32::<LE>().ok()?;
+            let stepping = crs.read_u32::<LE>().ok()?;
+            Target::from_version(major as u16, minor as u16, stepping as u16)
+        },
+        NT_AMD_HSA_ISA_NAME => {
+            /* amdgcn-amd-amdhsa--gfx900 */
+            let isa_name = String::from_utf8_lossy(&note.desc);
+            isa_name.trim_end_matches('\0').rsplit('-').next().and_then(Target::from_processor)
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(target: Option<Target>) -> Option<String> {
+        target.map(|target| target.processor)
+    }
+
+    #[test]
+    fn processors_of_each_generation() {
+        for &(name, generation, version) in [
+            ("gfx601", Generation::GFX6, (6, 0, 1)),
+            ("gfx704", Generation::GFX7, (7, 0, 4)),
+            ("gfx803", Generation::GFX8, (8, 0, 3)),
+            ("gfx900", Generation::GFX9, (9, 0, 0)),
+            ("gfx90a", Generation::GFX9, (9, 0, 10)),
+            ("gfx90c", Generation::GFX9, (9, 0, 12)),
+            ("gfx1030", Generation::GFX10, (10, 3, 0)),
+            ("gfx1100", Generation::GFX11, (11, 0, 0))
+        ].iter() {
+            let target = Target::from_processor(name).unwrap();
+            assert_eq!((target.processor.as_str(), target.generation), (name, generation));
+            assert_eq!((target.major, target.minor, target.stepping), version);
+            assert!(target.matches_version(version.0, version.1, version.2));
+            assert_eq!((target.xnack, target.sramecc), (None, None));
+        }
+        assert_eq!(Target::from_processor("GFX906").unwrap().processor, "gfx906");
+        for name in ["gfx", "gfx90", "gfx1200", "gfx9g0", "sm_80"].iter() {
+            assert_eq!(Target::from_processor(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn processor_features() {
+        let features = |name| Target::from_processor(name).map(|target| (target.processor, target.xnack, target.sramecc)).unwrap();
+        assert_eq!(features("gfx906:xnack-"), ("gfx906".to_string(), Some(false), None));
+        assert_eq!(features("gfx90a:sramecc+:xnack+"), ("gfx90a".to_string(), Some(true), Some(true)));
+        assert_eq!(features("gfx906:sramecc-"), ("gfx906".to_string(), None, Some(false)));
+        /* Code object v3 syntax */
+        assert_eq!(features("gfx906+xnack+sram-ecc"), ("gfx906".to_string(), Some(true), None));
+        /* Code object v2 names of xnack-enabled processors */
+        assert_eq!(features("gfx901"), ("gfx900".to_string(), Some(true), None));
+        assert_eq!(features("gfx903:xnack-"), ("gfx902".to_string(), Some(false), None));
+    }
+
+    #[test]
+    fn mach_table() {
+        assert_eq!(processor(target_from_mach(0x020)), Some("gfx600".to_string()));
+        assert_eq!(processor(target_from_mach(0x022)), Some("gfx700".to_string()));
+        assert_eq!(processor(target_from_mach(0x02a)), Some("gfx803".to_string()));
+        assert_eq!(processor(target_from_mach(0x02c)), Some("gfx900".to_string()));
+        assert_eq!(processor(target_from_mach(0x03f)), Some("gfx90a".to_string()));
+        assert_eq!(processor(target_from_mach(0x036)), Some("gfx1030".to_string()));
+        assert_eq!(processor(target_from_mach(0x047)), Some("gfx1102".to_string()));
+        /* Feature bits are outside of the mach field */
+        assert_eq!(processor(target_from_mach(0xf00 | 0x02f)), Some("gfx906".to_string()));
+        assert_eq!(target_from_mach(0), None);
+        assert_eq!(target_from_mach(0x040), None);
+        /* Every entry maps to a processor that from_processor accepts */
+        assert_eq!((0..=EF_AMDGPU_MACH).filter_map(target_from_mach).count(), 37);
+    }
+
+    #[test]
+    fn feature_flags() {
+        assert_eq!(xnack_from_flags(0, 0x300), None);
+        assert_eq!(xnack_from_flags(ELFABIVERSION_AMDGPU_HSA_V3, EF_AMDGPU_XNACK_V3), Some(true));
+        assert_eq!(xnack_from_flags(ELFABIVERSION_AMDGPU_HSA_V3, 0), Some(false));
+        assert_eq!(xnack_from_flags(2, 0x300), Some(true));
+        assert_eq!(xnack_from_flags(2, 0x200), Some(false));
+        assert_eq!(xnack_from_flags(2, 0x100), None);
+
+        assert_eq!(sramecc_from_flags(ELFABIVERSION_AMDGPU_HSA_V3, EF_AMDGPU_SRAM_ECC_V3), Some(true));
+        assert_eq!(sramecc_from_flags(ELFABIVERSION_AMDGPU_HSA_V3, EF_AMDGPU_XNACK_V3), Some(false));
+        assert_eq!(sramecc_from_flags(2, 0xc00 | 0x300), Some(true));
+        assert_eq!(sramecc_from_flags(2, 0x800), Some(false));
+        assert_eq!(sramecc_from_flags(2, 0x400), None);
+    }
+
+    #[test]
+    fn isa_notes() {
+        let mut version = Vec::new();
+        for &field in [4u16, 7].iter() { version.extend_from_slice(&field.to_le_bytes()); }
+        for &field in [8u32, 0, 3].iter() { version.extend_from_slice(&field.to_le_bytes()); }
+        version.extend_from_slice(b"AMD\0AMDGPU\0");
+        let note = Note { name: "AMD".to_string(), note_type: NT_AMD_HSA_ISA_VERSION, desc: version };
+        assert_eq!(processor(target_from_note(&note)), Some("gfx803".to_string()));
+
+        let note = Note { name: "AMD".to_string(), note_type: NT_AMD_HSA_ISA_NAME, desc: b"amdgcn-amd-amdhsa--gfx901\0".to_vec() };
+        let target = target_from_note(&note).unwrap();
+        assert_eq!((target.processor.as_str(), target.xnack), ("gfx900", Some(true)));
+
+        let note = Note { name: "AMD".to_string(), note_type: NT_AMD_HSA_ISA_VERSION, desc: vec![0; 8] };
+        assert_eq!(target_from_note(&note), None);
+        let note = Note { name: "AMD".to_string(), note_type: 1, desc: Vec::new() };
+        assert_eq!(target_from_note(&note), None);
+    }
+}
@@ -0,0 +1,302 @@
+/* A parser for the subset of YAML emitted by LLVM's code object v2 metadata
+ * writer: block mappings, block sequences, flow sequences of scalars, and
+ * plain or quoted scalars. Anchors, tags and multi-line scalars are not supported. */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Yaml {
+    Scalar(String),
+    Seq(Vec<Yaml>),
+    Map(Vec<(String, Yaml)>)
+}
+
+impl Yaml {
+    pub fn get(&self, key: &str) -> Option<&Yaml> {
+        match self {
+            Yaml::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Yaml::Scalar(s) => Some(s.as_str()),
+            _ => None
+        }
+    }
+
+    pub fn as_seq(&self) -> Option<&[Yaml]> {
+        match self {
+            Yaml::Seq(items) => Some(items.as_slice()),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct YamlError {
+    pub line: usize,
+    pub message: String
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    content: String
+}
+
+pub fn parse(document: &str) -> Result<Yaml, YamlError> {
+    let mut lines: Vec<Line> = document.lines()
+        .enumerate()
+        .filter(|(_, l)| {
+            let trimmed = l.trim();
+            !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---" && trimmed != "..."
+        })
+        .map(|(i, l)| Line {
+            number: i + 1,
+            indent: l.len() - l.trim_start().len(),
+            content: l.trim().to_string()
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return Ok(Yaml::Map(Vec::new()));
+    }
+
+    let mut pos = 0;
+    let indent = lines[0].indent;
+    let root = parse_block(&mut lines, &mut pos, indent)?;
+
+    match lines.get(pos) {
+        Some(line) => Err(YamlError { line: line.number, message: "unexpected indentation".to_string() }),
+        None => Ok(root)
+    }
+}
+
+fn parse_block(lines: &mut Vec<Line>, pos: &mut usize, indent: usize) -> Result<Yaml, YamlError> {
+    if lines[*pos].content == "-" || lines[*pos].content.starts_with("- ") {
+        parse_seq(lines, pos, indent)
+    }
+    else {
+        parse_map(lines, pos, indent)
+    }
+}
+
+fn parse_seq(lines: &mut Vec<Line>, pos: &mut usize, indent: usize) -> Result<Yaml, YamlError> {
+    let mut items: Vec<Yaml> = Vec::new();
+
+    while *pos < lines.len() && lines[*pos].indent == indent
+        && (lines[*pos].content == "-" || lines[*pos].content.starts_with("- ")) {
+        if lines[*pos].content == "-" {
+            /* The item starts on the next line */
+            *pos += 1;
+            match lines.get(*pos) {
+                Some(next) if next.indent > indent => {
+                    let item_indent = next.indent;
+                    items.push(parse_block(lines, pos, item_indent)?);
+                },
+                _ => items.push(Yaml::Scalar(String::new()))
+            }
+            continue;
+        }
+
+        /* "- key: value" opens a mapping whose keys are aligned with "key" */
+        let rest = lines[*pos].content[2..].trim_start().to_string();
+        let item_indent = indent + lines[*pos].content.len() - rest.len();
+
+        if is_map_entry(&rest) || rest.starts_with("- ") {
+            lines[*pos].indent = item_indent;
+            lines[*pos].content = rest;
+            items.push(parse_block(lines, pos, item_indent)?);
+        }
+        else {
+            items.push(parse_scalar_or_flow(&rest, lines[*pos].number)?);
+            *pos += 1;
+        }
+    }
+
+    Ok(Yaml::Seq(items))
+}
+
+fn parse_map(lines: &mut Vec<Line>, pos: &mut usize, indent: usize) -> Result<Yaml, YamlError> {
+    let mut entries: Vec<(String, Yaml)> = Vec::new();
+
+    while *pos < lines.len() && lines[*pos].indent == indent {
+        let number = lines[*pos].number;
+        let content = lines[*pos].content.clone();
+
+        if content.starts_with("- ") || content == "-" {
+            return Err(YamlError { line: number, message: "sequence item in a mapping".to_string() });
+        }
+        let (key, value) = split_map_entry(&content)
+            .ok_or(YamlError { line: number, message: format!("expected a \"key: value\" pair, got {:?}", content) })?;
+        *pos += 1;
+
+        let value = if !value.is_empty() {
+            parse_scalar_or_flow(value, number)?
+        }
+        else {
+            match lines.get(*pos) {
+                /* Nested block, or a sequence at the same indentation as the key */
+                Some(next) if next.indent > indent
+                    || (next.indent == indent && (next.content.starts_with("- ") || next.content == "-")) => {
+                    let nested_indent = next.indent;
+                    parse_block(lines, pos, nested_indent)?
+                },
+                _ => Yaml::Scalar(String::new())
+            }
+        };
+        entries.push((unquote(key, number)?, value));
+    }
+
+    Ok(Yaml::Map(entries))
+}
+
+fn is_map_entry(content: &str) -> bool {
+    split_map_entry(content).is_some()
+}
+
+fn split_map_entry(content: &str) -> Option<(&str, &str)> {
+    /* Keys are never quoted in LLVM's output, but values may contain ": " inside quotes */
+    if content.starts_with('\'') || content.starts_with('"') || content.starts_with('[') {
+        return None;
+    }
+    if let Some(key) = content.strip_suffix(':') {
+        return Some((key, ""));
+    }
+    content.find(": ").map(|idx| (content[..idx].trim_end(), content[idx + 2..].trim()))
+}
+
+fn parse_scalar_or_flow(value: &str, line: usize) -> Result<Yaml, YamlError> {
+    if value.starts_with('[') {
+        if !value.ends_with(']') {
+            return Err(YamlError { line, message: format!("unterminated flow sequence {:?}", value) });
+        }
+        let inner = value[1..value.len() - 1].trim();
+        if inner.is_empty() {
+            return Ok(Yaml::Seq(Vec::new()));
+        }
+        return inner.split(',')
+            .map(|item| unquote(item.trim(), line).map(Yaml::Scalar))
+            .collect::<Result<_, _>>()
+            .map(Yaml::Seq);
+    }
+    unquote(value, line).map(Yaml::Scalar)
+}
+
+fn unquote(value: &str, line: usize) -> Result<String, YamlError> {
+    let unterminated = || YamlError { line, message: format!("unterminated quoted scalar {:?}", value) };
+
+    if value.starts_with('\'') {
+        if value.len() < 2 || !value.ends_with('\'') {
+            return Err(unterminated());
+        }
+        Ok(value[1..value.len() - 1].replace("''", "'"))
+    }
+    else if value.starts_with('"') {
+        if value.len() < 2 || !value.ends_with('"') {
+            return Err(unterminated());
+        }
+        let mut unescaped = String::new();
+        let mut chars = value[1..value.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some('0') => unescaped.push('\0'),
+                Some(other) => unescaped.push(other),
+                None => return Err(unterminated())
+            }
+        }
+        Ok(unescaped)
+    }
+    else {
+        Ok(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Yaml};
+
+    fn scalar(s: &str) -> Yaml {
+        Yaml::Scalar(s.to_string())
+    }
+
+    fn error_line(document: &str) -> usize {
+        parse(document).unwrap_err().line
+    }
+
+    #[test]
+    fn code_object_v2_metadata() {
+        let document = "---
+Version:         [ 1, 0 ]
+Kernels:
+  - Name:            add
+    SymbolName:      'add@kd'
+    Language:        OpenCL C
+    Args:
+      - Name:            a
+        Size:            8
+        Align:           8
+        ValueKind:       GlobalBuffer
+      - Size:            8
+        ValueKind:       HiddenGlobalOffsetX
+    CodeProps:
+      KernargSegmentSize: 16
+...
+";
+        let root = parse(document).unwrap();
+        assert_eq!(root.get("Version"), Some(&Yaml::Seq(vec![scalar("1"), scalar("0")])));
+
+        let kernels = root.get("Kernels").and_then(Yaml::as_seq).unwrap();
+        assert_eq!(kernels.len(), 1);
+        assert_eq!(kernels[0].get("SymbolName").and_then(Yaml::as_str), Some("add@kd"));
+        assert_eq!(kernels[0].get("Language").and_then(Yaml::as_str), Some("OpenCL C"));
+
+        let args = kernels[0].get("Args").and_then(Yaml::as_seq).unwrap();
+        assert_eq!(args[0], Yaml::Map(vec![
+            ("Name".to_string(), scalar("a")), ("Size".to_string(), scalar("8")),
+            ("Align".to_string(), scalar("8")), ("ValueKind".to_string(), scalar("GlobalBuffer"))]));
+        assert_eq!(args[1].get("Name"), None);
+        assert_eq!(args[1].get("ValueKind").and_then(Yaml::as_str), Some("HiddenGlobalOffsetX"));
+        assert_eq!(kernels[0].get("CodeProps").and_then(|props| props.get("KernargSegmentSize")), Some(&scalar("16")));
+    }
+
+    #[test]
+    fn sequences() {
+        let root = parse("Printf:\n- '1:1:4:%d\\n'\n- \"2:0:\"\nEmpty: []\nNested:\n  -\n    - a\n    - b\n  - c\n").unwrap();
+        assert_eq!(root.get("Printf"), Some(&Yaml::Seq(vec![scalar("1:1:4:%d\\n"), scalar("2:0:")])));
+        assert_eq!(root.get("Empty"), Some(&Yaml::Seq(Vec::new())));
+        assert_eq!(root.get("Nested"), Some(&Yaml::Seq(vec![Yaml::Seq(vec![scalar("a"), scalar("b")]), scalar("c")])));
+    }
+
+    #[test]
+    fn scalars() {
+        let root = parse("A: 'it''s'\nB: \"tab\\tnul\\0\"\nC: [ 'x', \"y\", z ]\nD:\nE: plain: text\n").unwrap();
+        assert_eq!(root.get("A").and_then(Yaml::as_str), Some("it's"));
+        assert_eq!(root.get("B").and_then(Yaml::as_str), Some("tab\tnul\0"));
+        assert_eq!(root.get("D").and_then(Yaml::as_str), Some(""));
+        assert_eq!(root.get("E").and_then(Yaml::as_str), Some("plain: text"));
+        assert_eq!(root.get("C"), Some(&Yaml::Seq(vec![scalar("x"), scalar("y"), scalar("z")])));
+    }
+
+    #[test]
+    fn empty_document() {
+        assert_eq!(parse("").unwrap(), Yaml::Map(Vec::new()));
+        assert_eq!(parse("---\n# comment\n...\n").unwrap(), Yaml::Map(Vec::new()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error_line("A: 1\n  B: 2\n"), 2);
+        assert_eq!(error_line("A: 1\nB\n"), 2);
+        assert_eq!(error_line("A: 1\n- b\n"), 2);
+        assert_eq!(error_line("A: 'open\n"), 1);
+        assert_eq!(error_line("A: \"open\\\"\n"), 1);
+        assert_eq!(error_line("A:\n  B: [ 1, 2\n"), 2);
+    }
+}
@@ -1,12 +1,11 @@
 use crate::asm::kernel_args::KernelArgs;
-use crate::expr_tree::{ProgramStatement, BoundExpr};
-use crate::data_flow::types::{BuiltIn, DataKind};
+use crate::data_flow::types::BuiltIn;
 
-pub fn builtin_ptr(args: &KernelArgs, ptr: BuiltIn, offset: i32, kind: DataKind) -> String {
+pub fn builtin_ptr(args: &KernelArgs, ptr: BuiltIn, offset: i32) -> String {
     use BuiltIn::*;
 
     match ptr {
-        PtrKernarg { .. } =>
+        PtrKernarg =>
             match args.find_idx_and_dword(offset as u32) {
                 Some((arg_idx, dword)) => {
                     let arg = match args[arg_idx].name.as_ref() {
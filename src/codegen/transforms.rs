@@ -3,63 +3,244 @@ use itertools::Itertools;
 
 use crate::asm::kernel_args::{KernelArgs, KernelArg};
 use crate::expr_tree::{ProgramStatement, BoundExpr};
-use crate::data_flow::types::BuiltIn;
+use crate::data_flow::types::{BuiltIn, DataKind, AtomicOp};
 use crate::codegen::ptr_resolution;
 
 type CodegenResult = Result<String, std::fmt::Error>;
 
-const VAR_UNION: &'static str = r#"
+const VAR_UNION: &str = r#"
 typedef union {
     int dword[4];
     long qword[2];
+    int4 dqword;
+    float f32[4];
+    double f64[2];
 } univar_t;
 "#;
 
 pub fn tree(tree: Vec<ProgramStatement>, args: &KernelArgs) -> CodegenResult {
     let mut code = String::new();
+    statements(&mut code, &tree, args)?;
 
-    for stmt in tree {
-        use ProgramStatement::*;
-        use BoundExpr::*;
-
-        match stmt {
-            Assignment { var_idx, expr } =>
-                writeln!(&mut code, "v{}.? = {};", var_idx, bound_expr(&expr, args))?,
-            Declaration { var_idx } =>
-                writeln!(&mut code, "univar_t v{};", var_idx)?,
-            Label { label_idx } =>
-                writeln!(&mut code, "label{}:", label_idx)?,
-            _ =>
-                writeln!(&mut code, "/* Unhandled statement {:?} */", stmt)?
-        }
+    Ok(format!("{} __kernel void decompiled({}) {{ {} }}", VAR_UNION, kernel_args(args), code))
+}
+
+fn statements(code: &mut String, stmts: &[ProgramStatement], args: &KernelArgs) -> Result<(), std::fmt::Error> {
+    for stmt in stmts {
+        statement(code, stmt, args)?;
     }
+    Ok(())
+}
 
-    Ok(format!("{} __kernel void decompiled({}) {{ {} }}", VAR_UNION, kernel_args(args), code))
+fn statement(code: &mut String, stmt: &ProgramStatement, args: &KernelArgs) -> Result<(), std::fmt::Error> {
+    use ProgramStatement::*;
+
+    match stmt {
+        Assignment { var_idx, dword, expr, kind } =>
+            writeln!(code, "v{}{} = {};", var_idx, member(*kind, *dword), bound_expr(expr, args))?,
+        Declaration { var_idx } =>
+            writeln!(code, "univar_t v{};", var_idx)?,
+        LocalArray { bytes } =>
+            writeln!(code, "__local uint lds[{}];", (bytes + 3) / 4)?,
+        LocalStore { addr, offset, data, kind } =>
+            writeln!(code, "{} = {};", local_element(addr, *offset, *kind, args), bound_expr(data, args))?,
+        LocalAtomic(expr) =>
+            writeln!(code, "{};", bound_expr(expr, args))?,
+        Barrier =>
+            writeln!(code, "barrier(CLK_LOCAL_MEM_FENCE);")?,
+        If { cond, then_body, else_body } => {
+            writeln!(code, "if ({}) {{", bound_expr(cond, args))?;
+            statements(code, then_body, args)?;
+            if !else_body.is_empty() {
+                writeln!(code, "}} else {{")?;
+                statements(code, else_body, args)?;
+            }
+            writeln!(code, "}}")?
+        },
+        While { cond, body } => {
+            writeln!(code, "while ({}) {{", bound_expr(cond, args))?;
+            statements(code, body, args)?;
+            writeln!(code, "}}")?
+        },
+        DoWhile { body, cond } => {
+            writeln!(code, "do {{")?;
+            statements(code, body, args)?;
+            writeln!(code, "}} while ({});", bound_expr(cond, args))?
+        },
+        For { init: box Assignment { var_idx: init_var, dword: init_dword, expr: init, kind: init_kind }, cond, step: box Assignment { var_idx: step_var, dword: step_dword, expr: step, kind: step_kind }, body } => {
+            writeln!(code, "for (v{}{} = {}; {}; v{}{} = {}) {{", init_var, member(*init_kind, *init_dword), bound_expr(init, args),
+                bound_expr(cond, args), step_var, member(*step_kind, *step_dword), bound_expr(step, args))?;
+            statements(code, body, args)?;
+            writeln!(code, "}}")?
+        },
+        Loop { body } => {
+            writeln!(code, "for (;;) {{")?;
+            statements(code, body, args)?;
+            writeln!(code, "}}")?
+        },
+        Break =>
+            writeln!(code, "break;")?,
+        Continue =>
+            writeln!(code, "continue;")?,
+        Label { label_idx } =>
+            writeln!(code, "label{}:;", label_idx)?,
+        Goto { label_idx } =>
+            writeln!(code, "goto label{};", label_idx)?,
+        Return =>
+            writeln!(code, "return;")?,
+        _ =>
+            writeln!(code, "/* Unhandled statement {:?} */", stmt)?
+    }
+    Ok(())
 }
 
 fn bound_expr(expr: &BoundExpr, args: &KernelArgs) -> String {
     use BoundExpr::*;
 
     match expr {
-        Deref { ptr: box InitState(builtin), offset, kind } =>
-            ptr_resolution::builtin_ptr(args, *builtin, *offset, *kind),
+        Deref { ptr: box InitState(builtin), offset, .. } =>
+            ptr_resolution::builtin_ptr(args, *builtin, *offset),
         InitState(builtin) =>
             init_state(builtin),
+        LocalDeref { addr, offset, kind } =>
+            local_element(addr, *offset, *kind, args),
+        LocalAtomic { op, addr, offset, args: operands } => {
+            let func = match op {
+                AtomicOp::Add => "atomic_add",
+                AtomicOp::Sub => "atomic_sub",
+                AtomicOp::Min | AtomicOp::MinU => "atomic_min",
+                AtomicOp::Max | AtomicOp::MaxU => "atomic_max",
+                AtomicOp::And => "atomic_and",
+                AtomicOp::Or => "atomic_or",
+                AtomicOp::Xor => "atomic_xor",
+                AtomicOp::Xchg => "atomic_xchg",
+                AtomicOp::CmpXchg => "atomic_cmpxchg"
+            };
+            /* The array holds unsigned dwords */
+            let ptr = match op {
+                AtomicOp::Min | AtomicOp::Max => format!("(volatile __local int*) &{}", local_element(addr, *offset, DataKind::Dword, args)),
+                _ => format!("&{}", local_element(addr, *offset, DataKind::Dword, args))
+            };
+            format!("{}({}, {})", func, ptr, operands.iter().map(|arg| bound_expr(arg, args)).join(", "))
+        },
         Mul(lhs, rhs) =>
-            format!("{} * {}", bound_expr(lhs.as_ref(), args), bound_expr(rhs.as_ref(), args)),
+            format!("({} * {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Div(lhs, rhs) =>
+            format!("({} / {})", bound_expr(lhs, args), bound_expr(rhs, args)),
         Add(lhs, rhs) =>
             format!("({} + {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Sub(lhs, rhs) =>
+            format!("({} - {})", bound_expr(lhs, args), bound_expr(rhs, args)),
         Shl(lhs, rhs) =>
-            format!("{} << {}", bound_expr(lhs, args), bound_expr(rhs, args)),
+            format!("({} << {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Shr(lhs, rhs) =>
+            format!("({} >> {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        And(lhs, rhs) =>
+            format!("({} & {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Or(lhs, rhs) =>
+            format!("({} | {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Xor(lhs, rhs) =>
+            format!("({} ^ {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Not(expr) =>
+            format!("~{}", bound_expr(expr, args)),
+        Min(lhs, rhs) =>
+            format!("min({}, {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Max(lhs, rhs) =>
+            format!("max({}, {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Abs(expr) =>
+            format!("abs({})", bound_expr(expr, args)),
+        MulHi(lhs, rhs) =>
+            format!("mul_hi({}, {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Mul24(lhs, rhs) =>
+            format!("mul24({}, {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Upsample(hi, lo) =>
+            format!("upsample({}, {})", bound_expr(hi, args), bound_expr(lo, args)),
+        Select { cond, if_true, if_false } =>
+            format!("({} ? {} : {})", bound_expr(cond, args), bound_expr(if_true, args), bound_expr(if_false, args)),
+        CompareLt(lhs, rhs) =>
+            format!("({} < {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        CompareLe(lhs, rhs) =>
+            format!("({} <= {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        CompareEql(lhs, rhs) =>
+            format!("({} == {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        CompareNe(lhs, rhs) =>
+            format!("({} != {})", bound_expr(lhs, args), bound_expr(rhs, args)),
+        Negate(expr) =>
+            format!("!{}", bound_expr(expr, args)),
+        /* Parenthesized so that negative literals do not turn into a decrement */
+        Neg(expr) =>
+            format!("-({})", bound_expr(expr, args)),
+        Call(name, call_args) =>
+            format!("{}({})", name, call_args.iter().map(|arg| bound_expr(arg, args)).join(", ")),
+        Variable { idx, dword, kind } =>
+            format!("v{}{}", idx, member(*kind, *dword)),
         Cast(expr, kind) =>
             format!("({:?}) {}", kind, bound_expr(expr, args)),
+        Unsigned(expr, DataKind::Qword) | Unsigned(expr, DataKind::I64) =>
+            format!("(ulong) {}", bound_expr(expr, args)),
+        Unsigned(expr, _) =>
+            format!("(uint) {}", bound_expr(expr, args)),
         U32(lit) =>
             format!("{}", lit),
+        I32(lit) =>
+            format!("{}", lit),
+        F32(lit) =>
+            format!("{:?}f", lit),
+        F64(lit) =>
+            format!("{:?}", lit),
         _ =>
             format!("(/* expr {:?} */)", expr)
     }
 }
 
+/* Element of the __local array at the byte address `addr` + offset, reinterpreted for kinds other
+ * than dwords. Addresses that scale an index by the element size are indexed directly */
+fn local_element(addr: &BoundExpr, offset: i32, kind: DataKind, args: &KernelArgs) -> String {
+    let (size, element_type): (i32, &str) = match kind {
+        DataKind::U8 => (1, "uchar"),
+        DataKind::I8 => (1, "char"),
+        DataKind::U16 | DataKind::F16 => (2, "ushort"),
+        DataKind::I16 => (2, "short"),
+        DataKind::Dword => (4, "uint"),
+        DataKind::F32 => (4, "float"),
+        DataKind::Qword => (8, "ulong"),
+        DataKind::I64 => (8, "long"),
+        DataKind::F64 => (8, "double"),
+        DataKind::DQword => (16, "uint4")
+    };
+    let array = match kind {
+        DataKind::Dword => "lds".to_string(),
+        _ => format!("((__local {}*) lds)", element_type)
+    };
+    let scaled_index = match addr {
+        BoundExpr::Shl(index, box BoundExpr::U32(shift)) if *shift == size.trailing_zeros() && offset % size == 0 => Some(index),
+        _ => None
+    };
+    let index = match scaled_index {
+        Some(index) if offset == 0 => bound_expr(index, args),
+        Some(index) => format!("{} + {}", bound_expr(index, args), offset / size),
+        None => match (offset, size) {
+            (0, 1) => bound_expr(addr, args),
+            (_, 1) => format!("{} + {}", bound_expr(addr, args), offset),
+            (0, _) => format!("{} / {}", bound_expr(addr, args), size),
+            _ => format!("({} + {}) / {}", bound_expr(addr, args), offset, size)
+        }
+    };
+    format!("{}[{}]", array, index)
+}
+
+/* Union member holding a variable of the given kind, half floats are widened and narrow
+ * integers are held in dwords */
+fn member(kind: DataKind, dword: u8) -> String {
+    match kind {
+        DataKind::F16 | DataKind::F32 => format!(".f32[{}]", dword),
+        DataKind::F64 => format!(".f64[{}]", dword / 2),
+        DataKind::Qword | DataKind::I64 => format!(".qword[{}]", dword / 2),
+        DataKind::DQword => ".dqword".to_string(),
+        DataKind::Dword | DataKind::U8 | DataKind::I8 | DataKind::U16 | DataKind::I16 => format!(".dword[{}]", dword)
+    }
+}
+
 fn init_state(builtin: &BuiltIn) -> String {
     use BuiltIn::*;
 
@@ -90,3 +271,57 @@ fn kernel_args(args: &KernelArgs) -> String {
         })
         .join(",")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{bound_expr, statement};
+    use crate::asm::kernel_args::KernelArgs;
+    use crate::expr_tree::{ProgramStatement, BoundExpr::{self, *}};
+    use crate::data_flow::types::{BuiltIn, DataKind};
+
+    fn local(index_shift: u32, offset: i32, kind: DataKind) -> String {
+        let addr = Shl(box InitState(BuiltIn::WorkitemIdX), box U32(index_shift));
+        bound_expr(&LocalDeref { addr: box addr, offset, kind }, &KernelArgs::from(Vec::new()))
+    }
+
+    #[test]
+    fn local_element_index() {
+        assert_eq!(local(2, 256, DataKind::Dword), "lds[get_local_id(0) + 64]");
+        assert_eq!(local(3, 256, DataKind::Qword), "((__local ulong*) lds)[get_local_id(0) + 32]");
+        /* Shifts that do not scale by the element size, including ones wider than the address */
+        assert_eq!(local(2, 256, DataKind::Qword), "((__local ulong*) lds)[((get_local_id(0) << 2) + 256) / 8]");
+        assert_eq!(local(40, 0, DataKind::Dword), "lds[(get_local_id(0) << 40) / 4]");
+    }
+
+    #[test]
+    fn binary_operands_are_parenthesized() {
+        let expr: BoundExpr = Add(box Shl(box U32(1), box U32(2)), box Mul(box U32(3), box U32(4)));
+        assert_eq!(bound_expr(&expr, &KernelArgs::from(Vec::new())), "((1 << 2) + (3 * 4))");
+    }
+
+    #[test]
+    fn negated_literals() {
+        let args = KernelArgs::from(Vec::new());
+        assert_eq!(bound_expr(&Neg(box I32(-1)), &args), "-(-1)");
+        assert_eq!(bound_expr(&Neg(box F32(-0.5)), &args), "-(-0.5f)");
+        assert_eq!(bound_expr(&Neg(box Neg(box U32(2))), &args), "-(-(2))");
+    }
+
+    #[test]
+    fn variable_members() {
+        let args = KernelArgs::from(Vec::new());
+        let assignment = |dword, kind| {
+            let mut code = String::new();
+            statement(&mut code, &ProgramStatement::Assignment { var_idx: 0, dword, expr: U32(1), kind }, &args).unwrap();
+            code
+        };
+        assert_eq!(assignment(1, DataKind::Dword), "v0.dword[1] = 1;\n");
+        assert_eq!(assignment(0, DataKind::Qword), "v0.qword[0] = 1;\n");
+        assert_eq!(assignment(2, DataKind::I64), "v0.qword[1] = 1;\n");
+        assert_eq!(assignment(0, DataKind::DQword), "v0.dqword = 1;\n");
+        assert_eq!(assignment(0, DataKind::U16), "v0.dword[0] = 1;\n");
+        assert_eq!(assignment(1, DataKind::F32), "v0.f32[1] = 1;\n");
+        assert_eq!(bound_expr(&Variable { idx: 2, dword: 0, kind: DataKind::Qword }, &args), "v2.qword[0]");
+        assert_eq!(bound_expr(&Variable { idx: 2, dword: 2, kind: DataKind::F64 }, &args), "v2.f64[1]");
+    }
+}
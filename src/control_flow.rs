@@ -1,64 +1,367 @@
-use crate::asm::{Instruction, Operand, Operand::*};
+pub mod dominators;
+pub mod loops;
 
-#[derive(Debug, Copy, Clone)]
-pub enum BranchKind {
-    SCCSet, SCCUnset, Uncond
+use std::io;
+
+use crate::asm::{Instruction, Operand::*};
+use crate::asm::semantics::{self, Branch, BranchCondition};
+
+pub type BlockIdx = usize;
+pub type InstructionIdx = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Terminator {
+    /* The last instruction is not a branch, execution continues with the next block */
+    Fallthrough(BlockIdx),
+    Jump(BlockIdx),
+    /* The not-taken edge always leads to the next block in layout order */
+    Branch { cond: BranchCondition, taken: BlockIdx, not_taken: BlockIdx },
+    /* The destination is computed at run time (s_setpc_b64, fork and join) */
+    Indirect,
+    Exit
 }
 
-type LabelIdx = usize;
-type InstructionIdx = usize;
+#[derive(Debug)]
+pub struct BasicBlock {
+    /* Instruction range [start; end), the terminating branch is the last instruction */
+    pub start: InstructionIdx,
+    pub end: InstructionIdx,
+    pub terminator: Terminator,
+    pub successors: Vec<BlockIdx>,
+    pub predecessors: Vec<BlockIdx>
+}
 
+/* Blocks are stored in layout order, the first one is the kernel entry. The entry
+ * has no predecessors: a loop starting at the first instruction is preceded by an
+ * empty block */
 #[derive(Debug)]
-pub struct ControlFlowMap {
-    jumps: Vec<(InstructionIdx, BranchKind, LabelIdx)>,
-    labels: Vec<usize>
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>
 }
 
-impl ControlFlowMap {
-    pub fn label_at_instruction(&self, instruction_idx: usize) -> Option<usize> {
-        self.labels.iter().position(|&idx| idx == instruction_idx)
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockIdx> {
+        match *self {
+            Terminator::Fallthrough(next) | Terminator::Jump(next) => vec![next],
+            Terminator::Branch { taken, not_taken, .. } if taken == not_taken => vec![taken],
+            Terminator::Branch { taken, not_taken, .. } => vec![taken, not_taken],
+            Terminator::Indirect | Terminator::Exit => Vec::new()
+        }
     }
+}
 
-    pub fn branch_at_instruction(&self, instruction_idx: usize) -> Option<(BranchKind, LabelIdx, InstructionIdx)> {
-        self.jumps.iter()
-            .find(|&(idx, _, _)| *idx == instruction_idx)
-            .map(|&(_, kind, label_idx)| (kind, label_idx, self.labels[label_idx]))
+impl ControlFlowGraph {
+    pub fn block_at_instruction(&self, instruction_idx: InstructionIdx) -> Option<BlockIdx> {
+        match self.blocks.binary_search_by_key(&instruction_idx, |b| b.start) {
+            /* Skip the empty entry block */
+            Ok(idx) => (idx..self.blocks.len()).find(|&idx| self.blocks[idx].end > instruction_idx),
+            Err(0) => None,
+            Err(idx) if instruction_idx < self.blocks[idx - 1].end => Some(idx - 1),
+            Err(_) => None
+        }
     }
-}
 
+    /* A block is entered by a jump rather than by falling through from the previous one */
+    pub fn is_jump_target(&self, block: BlockIdx) -> bool {
+        self.blocks[block].predecessors.iter().any(|&pred| match self.blocks[pred].terminator {
+            Terminator::Jump(dst) => dst == block,
+            Terminator::Branch { taken, .. } => taken == block,
+            _ => false
+        })
+    }
 
-pub fn build_map(instrs: &Vec<Instruction>) -> ControlFlowMap {
-    let mut jumps: Vec<(InstructionIdx, BranchKind, LabelIdx)> = Vec::new();
-    let mut labels: Vec<usize> = Vec::new();
+    /* s_cbranch_execz skips over a divergent region when all of its lanes are off.
+     * Vector instructions have no effect with an empty EXEC mask, so the analysis
+     * treats the branch as never taken and the region as straight-line code */
+    pub fn without_exec_skips(self) -> ControlFlowGraph {
+        let mut blocks = self.blocks;
+        for block in blocks.iter_mut() {
+            if let Terminator::Branch { cond: BranchCondition::ExecZ, not_taken, .. } = block.terminator {
+                block.terminator = Terminator::Fallthrough(not_taken);
+            }
+        }
+        link_blocks(&mut blocks);
+        ControlFlowGraph { blocks }
+    }
 
-    for (idx, (instr, ops)) in instrs.iter().enumerate() {
-        match instr.as_str() {
-            "s_branch" => {
-                labels.push(branch_destination(idx, ops));
-                jumps.push((idx, BranchKind::Uncond, labels.len() - 1));
-            },
-            "s_cbranch_scc1" => {
-                labels.push(branch_destination(idx, ops));
-                jumps.push((idx, BranchKind::SCCSet, labels.len() - 1));
-            },
-            "s_cbranch_scc0" => {
-                labels.push(branch_destination(idx, ops));
-                jumps.push((idx, BranchKind::SCCUnset, labels.len() - 1));
+    /* Blocks reachable from the entry, each one preceded by all of its predecessors
+     * except for the ones that reach it through a back edge */
+    pub fn reverse_postorder(&self) -> Vec<BlockIdx> {
+        let mut postorder: Vec<BlockIdx> = Vec::with_capacity(self.blocks.len());
+        let mut visited = vec![false; self.blocks.len()];
+        /* (block, index of the next successor to visit) */
+        let mut stack: Vec<(BlockIdx, usize)> = Vec::new();
+
+        if !self.blocks.is_empty() {
+            visited[0] = true;
+            stack.push((0, 0));
+        }
+        while let Some((block, succ_idx)) = stack.pop() {
+            match self.blocks[block].successors.get(succ_idx) {
+                Some(&succ) => {
+                    stack.push((block, succ_idx + 1));
+                    if !visited[succ] {
+                        visited[succ] = true;
+                        stack.push((succ, 0));
+                    }
+                },
+                None => postorder.push(block)
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
+}
+
+pub fn build_graph(instrs: &[Instruction]) -> io::Result<ControlFlowGraph> {
+    let branches: Vec<Option<Branch>> = instrs.iter()
+        .map(|instr| semantics::lookup(&instr.opcode).and_then(|s| s.branch))
+        .collect();
+    let reachable = reachable_instructions(instrs, &branches)?;
+
+    /* Branch targets and instructions following branches start new blocks */
+    let mut leaders: Vec<InstructionIdx> = vec![0];
+    for (idx, branch) in branches.iter().enumerate().filter(|&(idx, _)| reachable[idx]) {
+        match branch {
+            Some(Branch::Direct(cond)) => {
+                leaders.push(branch_destination(instrs, &instrs[idx])?);
+                /* A conditional branch at the end of the code falls through to the end of the kernel */
+                if *cond != BranchCondition::Always || idx + 1 < instrs.len() { leaders.push(idx + 1); }
             },
+            Some(Branch::Indirect) | Some(Branch::End) if idx + 1 < instrs.len() => leaders.push(idx + 1),
             _ => ()
         }
     }
+    leaders.sort();
+    leaders.dedup();
+    if instrs.is_empty() {
+        leaders.clear();
+    }
+
+    let block_starting_at = |instr_idx: InstructionIdx| leaders.binary_search(&instr_idx)
+        .unwrap_or_else(|_| panic!("No basic block starts at instruction {}", instr_idx));
+
+    let mut blocks: Vec<BasicBlock> = leaders.iter().enumerate()
+        .map(|(block_idx, &start)| {
+            let end = leaders.get(block_idx + 1).cloned().unwrap_or(instrs.len());
+            let last = if end > start { branches[end - 1] } else { None };
+            let next = block_idx + 1;
+            let terminator = match last {
+                /* Unreachable blocks (data or padding) are left unlinked */
+                _ if end > start && !reachable[start] => Terminator::Exit,
+                Some(Branch::Direct(cond)) => {
+                    let taken = block_starting_at(branch_destination(instrs, &instrs[end - 1])?);
+                    match cond {
+                        BranchCondition::Always => Terminator::Jump(taken),
+                        _ => Terminator::Branch { cond, taken, not_taken: next }
+                    }
+                },
+                Some(Branch::Indirect) => Terminator::Indirect,
+                Some(Branch::End) => Terminator::Exit,
+                _ if next < leaders.len() => Terminator::Fallthrough(next),
+                /* Running past the last instruction ends the kernel */
+                _ => Terminator::Exit
+            };
+            Ok(BasicBlock { start, end, terminator, successors: Vec::new(), predecessors: Vec::new() })
+        })
+        .collect::<io::Result<_>>()?;
+    link_blocks(&mut blocks);
+
+    if blocks.first().map_or(false, |entry| !entry.predecessors.is_empty()) {
+        for block in blocks.iter_mut() {
+            block.terminator = match block.terminator {
+                Terminator::Fallthrough(next) => Terminator::Fallthrough(next + 1),
+                Terminator::Jump(dst) => Terminator::Jump(dst + 1),
+                Terminator::Branch { cond, taken, not_taken } => Terminator::Branch { cond, taken: taken + 1, not_taken: not_taken + 1 },
+                terminator => terminator
+            };
+        }
+        blocks.insert(0, BasicBlock { start: 0, end: 0, terminator: Terminator::Fallthrough(1), successors: Vec::new(), predecessors: Vec::new() });
+        link_blocks(&mut blocks);
+    }
 
-    ControlFlowMap { jumps, labels }
+    Ok(ControlFlowGraph { blocks })
 }
 
-fn branch_destination(instr_idx: usize, branch_ops: &[Operand]) -> usize {
-    match branch_ops {
-        [Lit(branch_fwd_idx)] if *branch_fwd_idx <= 32767 =>
-            instr_idx + 1 + (*branch_fwd_idx as usize),
-        [Lit(branch_bwd_idx)] =>
-            (instr_idx as i64 + 1 + (*branch_bwd_idx as i16) as i64) as usize,
-        _ =>
-            panic!("Unrecognized branch operands: {:?}", branch_ops)
+/* Code following the end of the program or an unconditional jump is only executed if it is
+ * branched to. The rest is padding or data, and branches decoded from it are not followed */
+fn reachable_instructions(instrs: &[Instruction], branches: &[Option<Branch>]) -> io::Result<Vec<bool>> {
+    let mut reachable = vec![false; instrs.len()];
+    let mut pending: Vec<InstructionIdx> = if instrs.is_empty() { Vec::new() } else { vec![0] };
+    while let Some(mut idx) = pending.pop() {
+        while idx < instrs.len() && !reachable[idx] {
+            reachable[idx] = true;
+            match branches[idx] {
+                Some(Branch::Direct(BranchCondition::Always)) => {
+                    pending.push(branch_destination(instrs, &instrs[idx])?);
+                    break;
+                },
+                Some(Branch::Direct(_)) => pending.push(branch_destination(instrs, &instrs[idx])?),
+                Some(Branch::End) => break,
+                _ => ()
+            }
+            idx += 1;
+        }
+    }
+    Ok(reachable)
+}
+
+fn link_blocks(blocks: &mut [BasicBlock]) {
+    for block in blocks.iter_mut() {
+        block.successors = block.terminator.successors();
+        block.predecessors.clear();
+    }
+    for block_idx in 0..blocks.len() {
+        for succ in blocks[block_idx].successors.clone() {
+            blocks[succ].predecessors.push(block_idx);
+        }
+    }
+}
+
+/* SOPP branch offsets are signed dword counts relative to the next instruction,
+ * so the target is resolved by byte address: PC + 4 + simm16 * 4 */
+fn branch_destination(instrs: &[Instruction], branch: &Instruction) -> io::Result<InstructionIdx> {
+    let simm16 = match branch.operands.as_slice() {
+        [Lit(simm16)] => *simm16 as i16 as i64,
+        ops => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unrecognized branch operands: {:?}", ops)))
+    };
+    let target = branch.offset as i64 + 4 + simm16 * 4;
+
+    let end = |i: &Instruction| (i.offset + i.size) as i64;
+
+    match instrs.binary_search_by_key(&target, |i| i.offset as i64) {
+        Ok(idx) => Ok(idx),
+        Err(idx) if idx > 0 && target < end(&instrs[idx - 1]) => {
+            let enclosing = &instrs[idx - 1];
+            Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+                "Branch {} at {:#x} targets {:#x}, which falls in the middle of {} at {:#x} ({} bytes)",
+                branch.opcode, branch.offset, target, enclosing.opcode, enclosing.offset, enclosing.size)))
+        },
+        /* A branch past the last instruction is a jump to the end of the kernel */
+        Err(idx) if idx == instrs.len() && instrs.last().map(end) == Some(target) => Ok(idx),
+        Err(_) => Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+            "Branch {} at {:#x} targets {:#x}, which is outside of the kernel", branch.opcode, branch.offset, target)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::Operand;
+
+    fn instr(opcode: &str, operands: Vec<Operand>, offset: usize) -> Instruction {
+        Instruction { opcode: opcode.to_string(), operands, offset, size: 4 }
+    }
+
+    /* A graph with the given successors of each block: two successors make a branch,
+     * one a jump and none an exit. Blocks hold one instruction each */
+    pub fn graph(successors: &[&[BlockIdx]]) -> ControlFlowGraph {
+        let mut blocks: Vec<BasicBlock> = successors.iter().enumerate()
+            .map(|(idx, succs)| {
+                let terminator = match *succs {
+                    [] => Terminator::Exit,
+                    [dst] => Terminator::Jump(*dst),
+                    [taken, not_taken] => Terminator::Branch { cond: BranchCondition::SCC1, taken: *taken, not_taken: *not_taken },
+                    _ => panic!("Block {} has more than two successors", idx)
+                };
+                BasicBlock { start: idx, end: idx + 1, terminator, successors: Vec::new(), predecessors: Vec::new() }
+            })
+            .collect();
+        link_blocks(&mut blocks);
+        ControlFlowGraph { blocks }
+    }
+
+    #[test]
+    fn loop_at_entry_gets_preheader() {
+        let instrs = vec![
+            instr("s_add_u32", vec![SReg(0), SReg(0), Lit(1)], 0),
+            instr("s_cmp_lg_u32", vec![SReg(0), Lit(10)], 4),
+            instr("s_cbranch_scc1", vec![Lit(-3)], 8),
+            instr("s_endpgm", vec![], 12)
+        ];
+        let cfg = build_graph(&instrs).unwrap();
+        assert_eq!(cfg.blocks.len(), 3);
+        assert_eq!((cfg.blocks[0].start, cfg.blocks[0].end), (0, 0));
+        assert_eq!(cfg.blocks[0].terminator, Terminator::Fallthrough(1));
+        assert!(cfg.blocks[0].predecessors.is_empty());
+        assert_eq!(cfg.blocks[1].terminator, Terminator::Branch { cond: BranchCondition::SCC1, taken: 1, not_taken: 2 });
+        assert_eq!(cfg.blocks[1].predecessors, vec![0, 1]);
+        assert_eq!(cfg.block_at_instruction(0), Some(1));
+        assert_eq!(cfg.block_at_instruction(3), Some(2));
+    }
+
+    #[test]
+    fn if_else_diamond() {
+        let instrs = vec![
+            instr("s_cmp_eq_u32", vec![SReg(0), Lit(0)], 0),
+            instr("s_cbranch_scc0", vec![Lit(2)], 4),
+            instr("s_mov_b32", vec![SReg(1), Lit(1)], 8),
+            instr("s_branch", vec![Lit(1)], 12),
+            instr("s_mov_b32", vec![SReg(1), Lit(2)], 16),
+            instr("s_endpgm", vec![], 20)
+        ];
+        let cfg = build_graph(&instrs).unwrap();
+        let ranges: Vec<_> = cfg.blocks.iter().map(|b| (b.start, b.end)).collect();
+        assert_eq!(ranges, vec![(0, 2), (2, 4), (4, 5), (5, 6)]);
+        assert_eq!(cfg.blocks[0].terminator, Terminator::Branch { cond: BranchCondition::SCC0, taken: 2, not_taken: 1 });
+        assert_eq!(cfg.blocks[1].terminator, Terminator::Jump(3));
+        assert_eq!(cfg.blocks[2].terminator, Terminator::Fallthrough(3));
+        assert_eq!(cfg.blocks[3].terminator, Terminator::Exit);
+        assert_eq!(cfg.blocks[3].predecessors, vec![1, 2]);
+        assert!(cfg.is_jump_target(2) && cfg.is_jump_target(3) && !cfg.is_jump_target(1));
+        assert_eq!(cfg.reverse_postorder()[0], 0);
+        assert_eq!(cfg.block_at_instruction(4), Some(2));
+
+        /* A conditional branch to the end of the code */
+        let cfg = build_graph(&[instrs[0].clone(), instr("s_cbranch_scc0", vec![Lit(0)], 4)]).unwrap();
+        assert_eq!(cfg.blocks[0].terminator, Terminator::Branch { cond: BranchCondition::SCC0, taken: 1, not_taken: 1 });
+        assert_eq!(cfg.blocks[0].successors, vec![1]);
+    }
+
+    #[test]
+    fn data_after_end_is_unreachable() {
+        let instrs = vec![
+            instr("s_cbranch_execz", vec![Lit(1)], 0),
+            instr("s_endpgm", vec![], 4),
+            instr("s_endpgm", vec![], 8),
+            /* Decoded from constant data */
+            instr("s_cbranch_scc1", vec![Lit(0x7fff)], 12),
+            instr("s_branch", vec![Lit(-100)], 16),
+            instr("s_nop", vec![Lit(0)], 20)
+        ];
+        let cfg = build_graph(&instrs).unwrap();
+        let ranges: Vec<_> = cfg.blocks.iter().map(|b| (b.start, b.end)).collect();
+        assert_eq!(ranges, vec![(0, 1), (1, 2), (2, 3), (3, 6)]);
+        assert_eq!(cfg.blocks[2].terminator, Terminator::Exit);
+        assert_eq!(cfg.blocks[3].terminator, Terminator::Exit);
+        assert!(cfg.blocks[3].predecessors.is_empty());
+        assert_eq!(cfg.reverse_postorder(), vec![0, 1, 2]);
+
+        let cfg = cfg.without_exec_skips();
+        assert_eq!(cfg.blocks[0].successors, vec![1]);
+        assert_eq!(cfg.reverse_postorder(), vec![0, 1]);
+    }
+
+    #[test]
+    fn invalid_branch_targets() {
+        let mut instrs = vec![
+            instr("s_cbranch_scc1", vec![Lit(0)], 0),
+            instr("s_mov_b32", vec![SReg(0), Lit(0x12345)], 4),
+            instr("s_endpgm", vec![], 12)
+        ];
+        instrs[1].size = 8;
+        assert!(build_graph(&instrs).is_ok());
+        instrs[0].operands = vec![Lit(1)];
+        let err = build_graph(&instrs).unwrap_err();
+        assert!(err.to_string().contains("middle of s_mov_b32"), "{}", err);
+        instrs[0].operands = vec![Lit(3)];
+        assert!(build_graph(&instrs).is_ok());
+        instrs[0].operands = vec![Lit(4)];
+        let err = build_graph(&instrs).unwrap_err();
+        assert!(err.to_string().contains("outside of the kernel"), "{}", err);
+        instrs[0].operands = vec![Lit(-2)];
+        assert!(build_graph(&instrs).is_err());
+        instrs[0].operands = vec![SReg(0)];
+        assert!(build_graph(&instrs).is_err());
     }
 }
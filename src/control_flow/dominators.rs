@@ -0,0 +1,227 @@
+/* Dominator and post-dominator trees, computed with the iterative algorithm from
+ * "A Simple, Fast Dominance Algorithm" (Cooper, Harvey, Kennedy). Post-dominators
+ * are the dominators of the reversed graph, rooted at a virtual exit node that
+ * succeeds every block ending in s_endpgm or an indirect branch. */
+
+use super::{ControlFlowGraph, BlockIdx};
+
+#[derive(Debug)]
+pub struct DominatorTree {
+    /* Immediate dominator of each block. None for the entry (for post-dominators,
+     * blocks immediately post-dominated by the kernel exit) and for unreachable blocks */
+    idom: Vec<Option<BlockIdx>>,
+    /* Blocks visited by the traversal: unreachable blocks, and, for post-dominators,
+     * blocks that never reach an exit, are not part of the tree */
+    reachable: Vec<bool>,
+    children: Vec<Vec<BlockIdx>>,
+    frontiers: Vec<Vec<BlockIdx>>
+}
+
+impl DominatorTree {
+    pub fn dominators(cfg: &ControlFlowGraph) -> DominatorTree {
+        let succs: Vec<Vec<usize>> = cfg.blocks.iter().map(|b| b.successors.clone()).collect();
+        let preds: Vec<Vec<usize>> = cfg.blocks.iter().map(|b| b.predecessors.clone()).collect();
+        if cfg.blocks.is_empty() {
+            return DominatorTree::from_idoms(&[], &[], &preds);
+        }
+        let (idom, reachable) = immediate_dominators(0, &succs, &preds);
+        DominatorTree::from_idoms(&idom, &reachable, &preds)
+    }
+
+    pub fn post_dominators(cfg: &ControlFlowGraph) -> DominatorTree {
+        let exit = cfg.blocks.len();
+        let exits: Vec<BlockIdx> = (0..exit).filter(|&b| cfg.blocks[b].successors.is_empty()).collect();
+
+        /* The reversed graph with the virtual exit as its entry */
+        let mut succs: Vec<Vec<usize>> = cfg.blocks.iter().map(|b| b.predecessors.clone()).collect();
+        let mut preds: Vec<Vec<usize>> = cfg.blocks.iter().map(|b| b.successors.clone()).collect();
+        succs.push(exits.clone());
+        preds.push(Vec::new());
+        for &b in &exits { preds[b].push(exit); }
+
+        let (idom, reachable) = immediate_dominators(exit, &succs, &preds);
+        DominatorTree::from_idoms(&idom[..exit], &reachable[..exit], &preds[..exit])
+    }
+
+    /* Nodes past the last block (the virtual exit) are not part of the tree */
+    fn from_idoms(idom: &[Option<usize>], reachable: &[bool], preds: &[Vec<usize>]) -> DominatorTree {
+        let block_count = idom.len();
+        /* The entry is its own immediate dominator in the traversal */
+        let idom: Vec<Option<BlockIdx>> = idom.iter()
+            .enumerate()
+            .map(|(b, &d)| d.filter(|&d| d != b && d < block_count))
+            .collect();
+
+        let mut children: Vec<Vec<BlockIdx>> = vec![Vec::new(); block_count];
+        for (b, d) in idom.iter().enumerate() {
+            if let Some(d) = *d { children[d].push(b); }
+        }
+
+        /* Each join point belongs to the frontier of the blocks on the paths from its
+         * predecessors up to (and not including) its immediate dominator */
+        let mut frontiers: Vec<Vec<BlockIdx>> = vec![Vec::new(); block_count];
+        for b in (0..block_count).filter(|&b| reachable[b]) {
+            /* A block with a single predecessor is immediately dominated by it */
+            for pred in preds[b].iter().cloned().filter(|&p| p < block_count && reachable[p]) {
+                let mut runner = Some(pred);
+                while let Some(r) = runner {
+                    if Some(r) == idom[b] { break; }
+                    if !frontiers[r].contains(&b) { frontiers[r].push(b); }
+                    runner = idom[r];
+                }
+            }
+        }
+        for frontier in frontiers.iter_mut() { frontier.sort(); }
+
+        DominatorTree { idom, reachable: reachable.to_vec(), children, frontiers }
+    }
+
+    pub fn idom(&self, block: BlockIdx) -> Option<BlockIdx> {
+        self.idom[block]
+    }
+
+    pub fn children(&self, block: BlockIdx) -> &[BlockIdx] {
+        &self.children[block]
+    }
+
+    pub fn frontier(&self, block: BlockIdx) -> &[BlockIdx] {
+        &self.frontiers[block]
+    }
+
+    pub fn is_reachable(&self, block: BlockIdx) -> bool {
+        self.reachable[block]
+    }
+
+    /* Every block dominates itself */
+    pub fn dominates(&self, dominator: BlockIdx, block: BlockIdx) -> bool {
+        if !self.reachable[dominator] || !self.reachable[block] {
+            return false;
+        }
+        let mut current = Some(block);
+        while let Some(b) = current {
+            if b == dominator { return true; }
+            current = self.idom[b];
+        }
+        false
+    }
+}
+
+/* Returns the immediate dominator of each node (the root is its own dominator)
+ * and whether the node is reachable from the root */
+fn immediate_dominators(root: usize, succs: &[Vec<usize>], preds: &[Vec<usize>]) -> (Vec<Option<usize>>, Vec<bool>) {
+    let node_count = succs.len();
+    let order = postorder(root, succs);
+    let mut postorder_idx: Vec<usize> = vec![std::usize::MAX; node_count];
+    for (i, &node) in order.iter().enumerate() { postorder_idx[node] = i; }
+
+    let mut idom: Vec<Option<usize>> = vec![None; node_count];
+    idom[root] = Some(root);
+
+    let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while postorder_idx[a] < postorder_idx[b] { a = idom[a].unwrap(); }
+            while postorder_idx[b] < postorder_idx[a] { b = idom[b].unwrap(); }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in order.iter().rev().filter(|&&n| n != root) {
+            let new_idom = preds[node].iter()
+                .filter(|&&p| idom[p].is_some())
+                .fold(None, |acc, &p| match acc {
+                    None => Some(p),
+                    Some(acc) => Some(intersect(&idom, acc, p))
+                });
+            if new_idom.is_some() && idom[node] != new_idom {
+                idom[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    let reachable = postorder_idx.iter().map(|&i| i != std::usize::MAX).collect();
+    (idom, reachable)
+}
+
+fn postorder(root: usize, succs: &[Vec<usize>]) -> Vec<usize> {
+    let mut order: Vec<usize> = Vec::with_capacity(succs.len());
+    let mut visited = vec![false; succs.len()];
+    let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+    visited[root] = true;
+
+    while let Some((node, succ_idx)) = stack.pop() {
+        match succs[node].get(succ_idx) {
+            Some(&succ) => {
+                stack.push((node, succ_idx + 1));
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            },
+            None => order.push(node)
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DominatorTree;
+    use crate::control_flow::tests::graph;
+
+    fn idoms(tree: &DominatorTree, block_count: usize) -> Vec<Option<usize>> {
+        (0..block_count).map(|b| tree.idom(b)).collect()
+    }
+
+    fn frontiers(tree: &DominatorTree, block_count: usize) -> Vec<Vec<usize>> {
+        (0..block_count).map(|b| tree.frontier(b).to_vec()).collect()
+    }
+
+    #[test]
+    fn diamond() {
+        let cfg = graph(&[&[1, 2], &[3], &[3], &[]]);
+        let doms = DominatorTree::dominators(&cfg);
+        assert_eq!(idoms(&doms, 4), vec![None, Some(0), Some(0), Some(0)]);
+        assert_eq!(doms.children(0), &[1, 2, 3]);
+        assert_eq!(frontiers(&doms, 4), vec![vec![], vec![3], vec![3], vec![]]);
+        assert!(doms.dominates(0, 3) && !doms.dominates(1, 3) && doms.dominates(3, 3));
+
+        let post_doms = DominatorTree::post_dominators(&cfg);
+        assert_eq!(idoms(&post_doms, 4), vec![Some(3), Some(3), Some(3), None]);
+        assert_eq!(frontiers(&post_doms, 4), vec![vec![], vec![0], vec![0], vec![]]);
+    }
+
+    #[test]
+    fn nested_loops() {
+        /* 1 heads the outer loop (latch 4), 2 the inner one (latch 3) */
+        let cfg = graph(&[&[1], &[2], &[3], &[2, 4], &[1, 5], &[]]);
+        let doms = DominatorTree::dominators(&cfg);
+        assert_eq!(idoms(&doms, 6), vec![None, Some(0), Some(1), Some(2), Some(3), Some(4)]);
+        assert_eq!(frontiers(&doms, 6), vec![vec![], vec![1], vec![1, 2], vec![1, 2], vec![1], vec![]]);
+
+        let post_doms = DominatorTree::post_dominators(&cfg);
+        assert_eq!(idoms(&post_doms, 6), vec![Some(1), Some(2), Some(3), Some(4), Some(5), None]);
+    }
+
+    #[test]
+    fn irreducible() {
+        /* The cycle between 1 and 2 is entered through both */
+        let cfg = graph(&[&[1, 2], &[2], &[1, 3], &[]]);
+        let doms = DominatorTree::dominators(&cfg);
+        assert_eq!(idoms(&doms, 4), vec![None, Some(0), Some(0), Some(2)]);
+        assert_eq!(frontiers(&doms, 4), vec![vec![], vec![2], vec![1], vec![]]);
+    }
+
+    #[test]
+    fn unreachable_blocks() {
+        /* 2 is unreachable, 3 never reaches the exit */
+        let cfg = graph(&[&[1, 3], &[], &[1], &[3]]);
+        let doms = DominatorTree::dominators(&cfg);
+        assert!(!doms.is_reachable(2) && !doms.dominates(0, 2));
+        let post_doms = DominatorTree::post_dominators(&cfg);
+        assert!(!post_doms.is_reachable(3) && post_doms.is_reachable(2));
+    }
+}
@@ -0,0 +1,185 @@
+/* Natural loops: an edge from a block to one of its dominators is a back edge,
+ * the loop it forms consists of the header (the edge target) and all blocks that
+ * reach the edge source without passing through the header. Loops sharing a header
+ * are merged. Retreating edges to blocks that do not dominate their source enter
+ * an irreducible region, which has no single header. */
+
+use super::{ControlFlowGraph, BlockIdx};
+use super::dominators::DominatorTree;
+
+pub type LoopIdx = usize;
+
+#[derive(Debug)]
+pub struct Loop {
+    pub header: BlockIdx,
+    /* Sources of the back edges to the header */
+    pub latches: Vec<BlockIdx>,
+    /* Blocks of the loop in layout order, including the header and nested loops */
+    pub blocks: Vec<BlockIdx>,
+    /* Edges leaving the loop as (block inside, block outside) */
+    pub exits: Vec<(BlockIdx, BlockIdx)>,
+    pub parent: Option<LoopIdx>,
+    /* 1 for outermost loops */
+    pub depth: usize
+}
+
+#[derive(Debug)]
+pub struct LoopForest {
+    /* Outer loops precede the loops nested in them */
+    pub loops: Vec<Loop>,
+    /* Retreating edges (from, to) that are not back edges of a natural loop */
+    pub irreducible_edges: Vec<(BlockIdx, BlockIdx)>,
+    innermost: Vec<Option<LoopIdx>>
+}
+
+impl LoopForest {
+    pub fn find(cfg: &ControlFlowGraph, doms: &DominatorTree) -> LoopForest {
+        let block_count = cfg.blocks.len();
+
+        let rpo = cfg.reverse_postorder();
+        let mut rpo_idx: Vec<usize> = vec![std::usize::MAX; block_count];
+        for (i, &b) in rpo.iter().enumerate() { rpo_idx[b] = i; }
+
+        let mut headers: Vec<(BlockIdx, Vec<BlockIdx>)> = Vec::new();
+        let mut irreducible_edges: Vec<(BlockIdx, BlockIdx)> = Vec::new();
+        for &from in &rpo {
+            for &to in &cfg.blocks[from].successors {
+                if rpo_idx[to] > rpo_idx[from] {
+                    continue;
+                }
+                if !doms.dominates(to, from) {
+                    irreducible_edges.push((from, to));
+                    continue;
+                }
+                match headers.iter_mut().find(|(header, _)| *header == to) {
+                    Some((_, latches)) => latches.push(from),
+                    None => headers.push((to, vec![from]))
+                }
+            }
+        }
+
+        let mut loops: Vec<Loop> = headers.into_iter()
+            .map(|(header, mut latches)| {
+                latches.sort();
+                let blocks = loop_body(cfg, doms, header, &latches);
+                let exits = blocks.iter()
+                    .flat_map(|&b| cfg.blocks[b].successors.iter().map(move |&succ| (b, succ)))
+                    .filter(|(_, succ)| blocks.binary_search(succ).is_err())
+                    .collect();
+                Loop { header, latches, blocks, exits, parent: None, depth: 1 }
+            })
+            .collect();
+
+        /* A loop nested in another one has a smaller body */
+        loops.sort_by_key(|l| std::cmp::Reverse(l.blocks.len()));
+        for inner in 0..loops.len() {
+            let parent = (0..inner).rev()
+                .find(|&outer| loops[outer].blocks.binary_search(&loops[inner].header).is_ok());
+            if let Some(parent) = parent {
+                loops[inner].parent = Some(parent);
+                loops[inner].depth = loops[parent].depth + 1;
+            }
+        }
+
+        let mut innermost: Vec<Option<LoopIdx>> = vec![None; block_count];
+        for (loop_idx, l) in loops.iter().enumerate() {
+            for &b in &l.blocks { innermost[b] = Some(loop_idx); }
+        }
+
+        LoopForest { loops, irreducible_edges, innermost }
+    }
+
+    /* The innermost loop containing the block */
+    pub fn loop_of(&self, block: BlockIdx) -> Option<LoopIdx> {
+        self.innermost[block]
+    }
+
+    pub fn loop_with_header(&self, block: BlockIdx) -> Option<LoopIdx> {
+        self.loops.iter().position(|l| l.header == block)
+    }
+
+    /* Number of loops containing the block */
+    pub fn depth(&self, block: BlockIdx) -> usize {
+        self.innermost[block].map_or(0, |l| self.loops[l].depth)
+    }
+
+    pub fn is_back_edge(&self, from: BlockIdx, to: BlockIdx) -> bool {
+        self.loop_with_header(to).map_or(false, |l| self.loops[l].latches.contains(&from))
+    }
+
+    pub fn is_reducible(&self) -> bool {
+        self.irreducible_edges.is_empty()
+    }
+}
+
+fn loop_body(cfg: &ControlFlowGraph, doms: &DominatorTree, header: BlockIdx, latches: &[BlockIdx]) -> Vec<BlockIdx> {
+    let mut blocks: Vec<BlockIdx> = vec![header];
+    let mut worklist: Vec<BlockIdx> = latches.iter().cloned().filter(|&l| l != header).collect();
+
+    while let Some(b) = worklist.pop() {
+        if blocks.contains(&b) {
+            continue;
+        }
+        blocks.push(b);
+        worklist.extend(cfg.blocks[b].predecessors.iter().cloned()
+            .filter(|&p| doms.is_reachable(p) && !blocks.contains(&p)));
+    }
+    blocks.sort();
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoopForest;
+    use crate::control_flow::tests::graph;
+    use crate::control_flow::dominators::DominatorTree;
+
+    fn loops(successors: &[&[usize]]) -> LoopForest {
+        let cfg = graph(successors);
+        LoopForest::find(&cfg, &DominatorTree::dominators(&cfg))
+    }
+
+    #[test]
+    fn diamond() {
+        let forest = loops(&[&[1, 2], &[3], &[3], &[]]);
+        assert!(forest.loops.is_empty() && forest.is_reducible());
+        assert_eq!((0..4).map(|b| forest.depth(b)).collect::<Vec<_>>(), vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn nested_loops() {
+        let forest = loops(&[&[1], &[2], &[3], &[2, 4], &[1, 5], &[]]);
+        assert!(forest.is_reducible());
+        assert_eq!(forest.loops.len(), 2);
+
+        let (outer, inner) = (forest.loop_with_header(1).unwrap(), forest.loop_with_header(2).unwrap());
+        assert_eq!(forest.loops[outer].blocks, vec![1, 2, 3, 4]);
+        assert_eq!(forest.loops[outer].exits, vec![(4, 5)]);
+        assert_eq!(forest.loops[inner].blocks, vec![2, 3]);
+        assert_eq!(forest.loops[inner].parent, Some(outer));
+        assert_eq!(forest.loops[inner].latches, vec![3]);
+
+        assert_eq!((0..6).map(|b| forest.depth(b)).collect::<Vec<_>>(), vec![0, 1, 2, 2, 1, 0]);
+        assert_eq!((0..6).map(|b| forest.loop_of(b)).collect::<Vec<_>>(),
+                   vec![None, Some(outer), Some(inner), Some(inner), Some(outer), None]);
+        assert!(forest.is_back_edge(3, 2) && forest.is_back_edge(4, 1));
+        assert!(!forest.is_back_edge(1, 2) && !forest.is_back_edge(3, 4));
+    }
+
+    #[test]
+    fn self_loop() {
+        let forest = loops(&[&[1], &[1, 2], &[]]);
+        assert_eq!(forest.loops.len(), 1);
+        assert_eq!((forest.loops[0].header, forest.loops[0].blocks.clone()), (1, vec![1]));
+        assert!(forest.is_back_edge(1, 1));
+    }
+
+    #[test]
+    fn irreducible() {
+        let forest = loops(&[&[1, 2], &[2], &[1, 3], &[]]);
+        assert!(!forest.is_reducible());
+        assert!(forest.loops.is_empty());
+        assert_eq!(forest.irreducible_edges.len(), 1);
+        assert_eq!((0..4).map(|b| forest.depth(b)).collect::<Vec<_>>(), vec![0, 0, 0, 0]);
+    }
+}
@@ -1,196 +1,572 @@
 pub mod exec_state;
+pub mod lane_mask;
 pub mod types;
+pub mod out_of_ssa;
+mod divergence;
+mod loop_carried;
 mod ops;
 
-use itertools::Itertools;
+use std::io;
+use std::mem;
 
 use crate::asm::Instruction;
-use crate::control_flow::ControlFlowMap;
-use self::exec_state::ExecState;
-use self::types::{Program, Reg, Statement, Binding, Variable, DataKind};
+use crate::asm::semantics::BranchCondition;
+use crate::control_flow::{ControlFlowGraph, Terminator, BlockIdx};
+use self::exec_state::{ExecState, RegState, DivergentRegion};
+use self::lane_mask::LaneMask;
+use self::types::{Program, Reg, Statement, Condition, Value, Variable, Phi, PhiInput, PhiIdx, DataKind};
+
+/* Builds the SSA form of the kernel. Blocks are evaluated in reverse postorder, so
+ * that the register state of every block is known by the time its forward successors
+ * are reached. Loop headers are joined with the states reaching them through back
+ * edges from the previous evaluation of the loop: when a loop changes registers its
+ * header has no phis for, the analysis is rolled back to the header and the loop is
+ * evaluated again, until the loop-carried registers stop changing. Lanes that leave
+ * a divergent loop are removed from EXEC, so a lane goes on with the next iteration
+ * while the conditions EXEC was narrowed by in the loop hold (see loop_carried).
+ * Returns the statements of each block, ending with the branch condition if there
+ * is one. Blocks entered with a narrowed EXEC mask start with the mask. */
+pub fn analyze(st: &mut ExecState, instrs: &[Instruction], cfg: &ControlFlowGraph) -> io::Result<Vec<Program>> {
+    let block_count = cfg.blocks.len();
+    let order = cfg.reverse_postorder();
+    let mut position: Vec<usize> = vec![std::usize::MAX; block_count];
+    for (i, &block_idx) in order.iter().enumerate() { position[block_idx] = i; }
+
+    let mut out_states: Vec<Option<RegState>> = vec![None; block_count];
+    let mut bodies: Vec<Program> = (0..block_count).map(|_| Program::new()).collect();
+    let mut terminators: Vec<Option<Statement>> = (0..block_count).map(|_| None).collect();
+
+    /* States at the end of the blocks that jump back to a loop header, from the latest evaluation of the loop */
+    let mut back_edge_states: Vec<Vec<(BlockIdx, RegState)>> = vec![Vec::new(); block_count];
+    /* State at the start of each loop header and the index of its first phi */
+    let mut header_states: Vec<Option<(RegState, PhiIdx)>> = vec![None; block_count];
+    /* Analysis state from right before each loop header was evaluated */
+    let mut snapshots: Vec<Option<(ExecState, Vec<Program>)>> = (0..block_count).map(|_| None).collect();
 
-pub fn analyze(st: &mut ExecState, instrs: &[Instruction], cf_map: &ControlFlowMap) -> Program {
-    eval_instructions_within_block(st, instrs.iter().enumerate(), instrs.len(), cf_map)
-}
-
-type InstructionIter<'a> = std::iter::Enumerate<std::slice::Iter<'a, Instruction>>;
+    let mut i = 0;
+    while i < order.len() {
+        let block_idx = order[i];
+        let block = &cfg.blocks[block_idx];
+
+        let is_loop_header = block.predecessors.iter()
+            .any(|&pred| position[pred] >= i && position[pred] != std::usize::MAX);
+        if is_loop_header {
+            snapshots[block_idx] = Some((st.clone(), bodies.clone()));
+        }
 
-fn eval_instructions_within_block(st: &mut ExecState, mut instr_iter: InstructionIter<'_>, instr_count: usize, cf_map: &ControlFlowMap) -> Program {
-    let mut pgm = Program::new();
+        let preds: Vec<BlockIdx> = block.predecessors.iter().cloned()
+            .filter(|&pred| position[pred] < i)
+            .collect();
+        let back_edges: Vec<&RegState> = back_edge_states[block_idx].iter().map(|(_, regs)| regs).collect();
+        let first_phi_idx = st.phis.len();
+        let first_value_idx = st.values.len();
+        let in_state = match preds.as_slice() {
+            _ if block_idx == 0 => st.reg_state(),
+            [pred] if back_edges.is_empty() => out_states[*pred].clone().unwrap(),
+            _ => join_states(st, cfg, block_idx, &preds, &back_edges, &out_states, &mut bodies)
+        };
+        if is_loop_header {
+            header_states[block_idx] = Some((in_state.clone(), first_phi_idx));
+        }
+        st.set_reg_state(in_state);
+        define_values(st, &mut bodies[block_idx], block.start, first_value_idx);
+        if st.exec != LaneMask::all() {
+            bodies[block_idx].push((block.start, Statement::ExecMask { mask: st.exec.clone() }));
+        }
 
-    while let Some((instr_idx, (instr, ops))) = instr_iter.next() {
-        if let Some(index) = cf_map.label_at_instruction(instr_idx) {
-            pgm.push((instr_idx + 1, Statement::Label { index }));
+        for (instr_idx, Instruction { opcode: instr, operands: ops, .. }) in (block.start..).zip(&instrs[block.start..block.end]) {
+            let exec = st.exec.clone();
+            let first_value_idx = st.values.len();
+            ops::eval_gcn_instruction(st, &mut bodies[block_idx], instr_idx, instr.as_str(), ops.as_slice())?;
+            define_values(st, &mut bodies[block_idx], instr_idx, first_value_idx);
+            if st.exec != exec {
+                divergence::exec_changed(st, cfg, &mut bodies, block_idx, instr_idx);
+            }
         }
 
-        use crate::control_flow::BranchKind;
-        match cf_map.branch_at_instruction(instr_idx) {
-            Some((BranchKind::Uncond, _, dst)) => {
-                if dst < instr_idx {
-                    /* I'm not sure what a real-world use case for this would be and whether we
-                     * need to recompute the state and diff it against the current one to bind
-                     * variables */
-                    panic!("Unconditional backward jump from {} to {}", instr_idx, dst);
-                }
-                /* Simply skip the instructions we're jumping over, no need to insert a goto */
-                let _ = instr_iter.nth(dst - instr_idx - 2);
-                continue;
+        terminators[block_idx] = match block.terminator {
+            Terminator::Branch { cond: BranchCondition::SCC1, taken, .. } =>
+                Some(lane_branch(scc_condition(st, block.end - 1)?, true, taken)),
+            Terminator::Branch { cond: BranchCondition::SCC0, taken, .. } =>
+                Some(lane_branch(scc_condition(st, block.end - 1)?, false, taken)),
+            /* s_cbranch_vccnz is taken by the lanes in VCC, s_cbranch_vccz by the other lanes of EXEC */
+            Terminator::Branch { cond: BranchCondition::VCCNZ, taken, .. } => {
+                let (cond, holds) = vcc_condition(st, block.end - 1)?;
+                Some(lane_branch(cond, holds, taken))
             },
-            Some((ref kind, label_idx, dst)) if dst > instr_idx => {
-                /* A forward conditional branch wraps a block of instructions:
-                 *
-                 * branch_if_cond label0
-                 * ...instructions
-                 * label0:
-                 * ...rest of the program
-                 *
-                 * We clone the execution state and pass it through the block, then diff it
-                 * against the state before the jump. Registers with different contents are
-                 * then changed to point to the same _variable_, which is reassigned within
-                 * the block. */
-
-                let mut st_block = st.clone();
-                let block_instr_iter = instr_iter.clone().dropping_back(instr_count - dst);
-                let mut block = eval_instructions_within_block(&mut st_block, block_instr_iter, instr_count, cf_map);
-
-                let (declarations, assignments_executed, assignments_skipped) =
-                    block_variables(&mut st_block, st);
-
-                st.sgprs = st_block.sgprs;
-                st.vgprs = st_block.vgprs;
-                st.bindings = st_block.bindings;
-                st.variables = st_block.variables;
-
-                pgm.append(&mut declarations.into_iter().map(|statement| (instr_idx + 1, statement)).collect());
-                pgm.append(&mut assignments_skipped.into_iter().map(|statement| (instr_idx + 1, statement)).collect());
-
-                pgm.push((instr_idx + 1, match kind {
-                    BranchKind::SCCSet => Statement::JumpIf { cond: st.scc.unwrap(), label_idx },
-                    BranchKind::SCCUnset => Statement::JumpUnless { cond: st.scc.unwrap(), label_idx },
-                    _ => panic!("Unhandled branch kind: {:?}", kind)
-                }));
-                pgm.append(&mut assignments_executed.into_iter().map(|statement| (instr_idx + 2, statement)).collect());
-                /* TODO: Update expressions to point at variables where bindings were used */
-                pgm.append(&mut block);
-
-                /* Skip the block, we've already evaluated it */
-                let _ = instr_iter.nth(dst - instr_idx - 2);
-                continue;
+            Terminator::Branch { cond: BranchCondition::VCCZ, taken, .. } => {
+                let (cond, holds) = vcc_condition(st, block.end - 1)?;
+                Some(lane_branch(cond, !holds, taken))
             },
-            /* backward conditional branch */
-            Some((ref kind, label_idx, _)) => {
-                pgm.push((instr_idx + 1, match kind {
-                    BranchKind::SCCSet => Statement::JumpIf { cond: st.scc.unwrap(), label_idx },
-                    BranchKind::SCCUnset => Statement::JumpUnless { cond: st.scc.unwrap(), label_idx },
-                    _ => panic!("Unhandled branch kind: {:?}", kind)
-                }));
-
-                continue;
+            Terminator::Branch { cond: BranchCondition::ExecNZ, taken, .. } if position[taken] <= i =>
+                Some(loop_carried::continue_condition(&header_states[taken].as_ref().unwrap().0, &st.exec, taken, block.end - 1)?),
+            /* Taken by the lanes left in EXEC by the last narrowing */
+            Terminator::Branch { cond: BranchCondition::ExecNZ, taken, .. } => match st.exec.terms.last() {
+                Some(&(cond, holds)) => Some(lane_branch(cond, holds, taken)),
+                None => return Err(unsupported(format!("s_cbranch_execnz at instruction {} is taken by every lane", block.end - 1)))
             },
-            None => ()
+            Terminator::Branch { cond, .. } =>
+                return Err(unsupported(format!("Unhandled branch condition {:?} at instruction {}", cond, block.end - 1))),
+            Terminator::Indirect =>
+                return Err(unsupported(format!("Indirect branches are not supported (instruction {})", block.end - 1))),
+            Terminator::Jump(_) | Terminator::Fallthrough(_) | Terminator::Exit => None
+        };
+        let out_state = st.reg_state();
+
+        /* The outermost loop that has to be evaluated again */
+        let mut restart: Option<BlockIdx> = None;
+        for &header in block.successors.iter().filter(|&&succ| position[succ] <= i) {
+            let (header_state, first_phi_idx) = header_states[header].clone().unwrap();
+            let latch_state = loop_carried::merge_at_header(&header_state, &out_state, &st.values, block.end - 1)?;
+            let latches = &mut back_edge_states[header];
+            match latches.iter().position(|&(latch, _)| latch == block_idx) {
+                Some(latch_idx) => latches[latch_idx].1 = latch_state.clone(),
+                None => latches.push((block_idx, latch_state.clone()))
+            }
+            match loop_carried::back_edge_inputs(st, &header_state, &latch_state, first_phi_idx, block_idx) {
+                Some(inputs) =>
+                    bodies[block_idx].push((block.end, Statement::ParallelCopy { inputs })),
+                None if restart.map_or(true, |outer| position[header] < position[outer]) =>
+                    restart = Some(header),
+                None => ()
+            }
         }
+        out_states[block_idx] = Some(out_state);
+
+        match restart {
+            Some(header) => {
+                let (saved_st, saved_bodies) = snapshots[header].take().unwrap();
+                *st = saved_st;
+                bodies = saved_bodies;
+                /* Inner loops are evaluated from scratch */
+                for &block_idx in &order[position[header] + 1..] {
+                    back_edge_states[block_idx].clear();
+                }
+                i = position[header];
+            },
+            None => i += 1
+        }
+    }
 
-        ops::eval_gcn_instruction(st, &mut pgm, instr_idx, instr.as_str(), ops.as_slice());
+    /* Phi inputs are appended to predecessors after they have been evaluated,
+     * the branch has to follow them */
+    for (block_idx, terminator) in terminators.into_iter().enumerate() {
+        if let Some(terminator) = terminator {
+            bodies[block_idx].push((cfg.blocks[block_idx].end, terminator));
+        }
     }
+    Ok(bodies)
+}
 
-    pgm
+fn unsupported(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message)
 }
 
-fn block_variables(st_executed: &mut ExecState, st_skipped: &ExecState) -> (Vec<Statement>, Vec<Statement>, Vec<Statement>) {
-    let mut declarations: Vec<Statement> = Vec::new();
+fn scc_condition(st: &ExecState, branch_idx: usize) -> io::Result<Condition> {
+    st.scc.ok_or_else(|| unsupported(format!("Branch at instruction {} reads an unknown SCC", branch_idx)))
+}
 
-    let last_var_idx = if st_executed.variables.len() == 0 { 0 } else { st_executed.variables.len() - 1 };
-    let (sgprs, mut sgpr_executed, mut sgpr_skipped) =
-        compare_regs_extract_vars(&st_executed.sgprs, &st_skipped.sgprs, &mut st_executed.variables, &mut st_executed.bindings);
-    let (vgprs, mut vgpr_executed, mut vgpr_skipped) =
-        compare_regs_extract_vars(&st_executed.vgprs, &st_skipped.vgprs, &mut st_executed.variables, &mut st_executed.bindings);
+/* VCC holds the lanes of EXEC for which a compare holds, e.g. after v_cmp_eq_u32 vcc, ... */
+fn vcc_condition(st: &ExecState, branch_idx: usize) -> io::Result<(Condition, bool)> {
+    st.vcc.as_ref()
+        .and_then(|vcc| vcc.narrowing_of(&st.exec))
+        .ok_or_else(|| unsupported(format!("Branch at instruction {} depends on VCC {:?} that is not a compare within EXEC {:?}", branch_idx, st.vcc, st.exec)))
+}
 
-    sgpr_executed.append(&mut vgpr_executed);
-    sgpr_skipped.append(&mut vgpr_skipped);
+/* A branch taken by the lanes for which `cond` is `holds` */
+fn lane_branch(cond: Condition, holds: bool, label_idx: BlockIdx) -> Statement {
+    if holds { Statement::JumpIf { cond, label_idx } } else { Statement::JumpUnless { cond, label_idx } }
+}
 
-    st_executed.sgprs = sgprs;
-    st_executed.vgprs = vgprs;
+/* Values created since `first_value_idx` are defined at this point of the block */
+fn define_values(st: &ExecState, body: &mut Program, instr_idx: usize, first_value_idx: usize) {
+    if st.values.len() > first_value_idx {
+        body.push((instr_idx, Statement::Define { values: first_value_idx..st.values.len() }));
+    }
+}
 
-    for i in last_var_idx..st_executed.variables.len() {
-       declarations.push(Statement::VarDecl { var_idx: i + last_var_idx }); 
+/* Registers that hold different values coming from different predecessors are
+ * merged into phis. Inputs from back edges are added once the loop has been
+ * evaluated, see loop_carried */
+fn join_states(st: &mut ExecState, cfg: &ControlFlowGraph, block: BlockIdx, preds: &[BlockIdx], back_edges: &[&RegState],
+               out_states: &[Option<RegState>], bodies: &mut [Program]) -> RegState {
+    let pred_states: Vec<&RegState> = preds.iter().map(|&pred| out_states[pred].as_ref().unwrap())
+        .chain(back_edges.iter().cloned())
+        .collect();
+
+    let sgprs: Vec<&Vec<Reg>> = pred_states.iter().map(|regs| &regs.sgprs).collect();
+    let (sgprs, sgpr_inputs) = join_regs(st, block, &sgprs, preds);
+    let vgprs: Vec<&Vec<Reg>> = pred_states.iter().map(|regs| &regs.vgprs).collect();
+    let (vgprs, vgpr_inputs) = join_regs(st, block, &vgprs, preds);
+    let (scc, scc_inputs) = join_scc(st, block, &pred_states, preds);
+
+    for (i, &pred) in preds.iter().enumerate() {
+        let inputs: Vec<(PhiIdx, usize)> = sgpr_inputs[i].iter().chain(vgpr_inputs[i].iter()).chain(scc_inputs[i].iter()).cloned().collect();
+        if !inputs.is_empty() {
+            bodies[pred].push((cfg.blocks[pred].end, Statement::ParallelCopy { inputs }));
+        }
     }
 
-    (declarations, sgpr_executed, sgpr_skipped)
+    /* VCC is consumed by the block that computes it */
+    let (exec, divergent_regions) = join_exec(&pred_states);
+    RegState { sgprs, vgprs, vcc: None, scc, exec, divergent_regions }
 }
 
-fn compare_regs_extract_vars(regs_executed: &Vec<Reg>, regs_skipped: &Vec<Reg>, variables: &mut Vec<Variable>, bindings: &mut Vec<Binding>) -> (Vec<Reg>, Vec<Statement>, Vec<Statement>) {
-    let mut reg_iter = regs_executed.iter().zip(regs_skipped.iter()).enumerate();
+/* SCC set by the same comparison on every path is joined by making phis of the
+ * operands that differ. Other conditions, and SCC carried around a loop, are unknown */
+fn join_scc(st: &mut ExecState, block: BlockIdx, pred_states: &[&RegState], from: &[BlockIdx]) -> (Option<Condition>, Vec<Vec<(PhiIdx, usize)>>) {
+    let no_inputs = || from.iter().map(|_| Vec::new()).collect();
+    let conds: Vec<Condition> = match pred_states.iter().map(|regs| regs.scc).collect() {
+        Some(conds) => conds,
+        None => return (None, no_inputs())
+    };
+    if conds.iter().all(|&cond| cond == conds[0]) {
+        return (Some(conds[0]), no_inputs());
+    }
 
-    let mut executed_branch: Vec<Statement> = Vec::new();
-    let mut skipped_branch: Vec<Statement> = Vec::new();
+    let widths = |cond: &Condition| {
+        let (lhs, rhs) = cond.operands();
+        (value_dwords(&st.values[lhs], &st.phis), value_dwords(&st.values[rhs], &st.phis))
+    };
+    let (lhs_dwords, rhs_dwords) = widths(&conds[0]);
+    let same_comparison = conds.iter().all(|cond| mem::discriminant(cond) == mem::discriminant(&conds[0]) && widths(cond) == (lhs_dwords, rhs_dwords));
+    if pred_states.len() != from.len() || !same_comparison {
+        return (None, no_inputs());
+    }
 
-    let mut new_regs = regs_executed.clone();
+    let operands: Vec<Vec<Reg>> = conds.iter()
+        .map(|cond| {
+            let (lhs, rhs) = cond.operands();
+            (0..lhs_dwords).map(|dw| Reg(lhs, dw as u8)).chain((0..rhs_dwords).map(|dw| Reg(rhs, dw as u8))).collect()
+        })
+        .collect();
+    let operands: Vec<&Vec<Reg>> = operands.iter().collect();
+    let (joined, inputs) = join_regs(st, block, &operands, from);
+    (Some(conds[0].with_operands(joined[0].0, joined[lhs_dwords].0)), inputs)
+}
 
-    while let Some((reg_idx, (exec_reg, skip_reg))) = reg_iter.next() {
-        if exec_reg == skip_reg { continue; }
+/* Uniform branches do not change EXEC and divergent ones are not expressed as jumps,
+ * so predecessors usually agree on it; back edges of divergent loops are merged with
+ * the header by loop_carried. Otherwise, the block is entered by the lanes of any of
+ * the predecessors, within the divergent regions they share. A union that is not a
+ * conjunction of conditions is widened to the innermost shared region */
+fn join_exec(pred_states: &[&RegState]) -> (LaneMask, Vec<DivergentRegion>) {
+    let first = pred_states[0];
+    if pred_states.iter().all(|regs| same_exec(regs, first)) {
+        return (first.exec.clone(), first.divergent_regions.clone());
+    }
+    let shared = first.divergent_regions.iter().enumerate()
+        .take_while(|&(i, region)| pred_states.iter().all(|regs| regs.divergent_regions.get(i)
+            .map_or(false, |other| other.entry == region.entry && other.mask == region.mask)))
+        .count();
+    let divergent_regions = first.divergent_regions[..shared].to_vec();
+    let exec = pred_states[1..].iter()
+        .try_fold(first.exec.clone(), |exec, regs| exec.or(&regs.exec))
+        .unwrap_or_else(|| divergent_regions.last().map_or_else(LaneMask::all, |region| region.mask.clone()));
+    (exec, divergent_regions)
+}
 
-        let Reg(exec_idx, exec_lo_dword) = exec_reg;
-        let Reg(_, exec_hi_dword) = regs_executed[reg_idx..].iter()
-            .take_while(|&Reg(idx, _)| idx == exec_idx).last().unwrap();
-        let Reg(skip_idx, skip_lo_dword) = skip_reg;
-        let Reg(_, skip_hi_dword) = regs_skipped[reg_idx..].iter()
-            .take_while(|&Reg(idx, _)| idx == skip_idx).last().unwrap();
+fn same_exec(a: &RegState, b: &RegState) -> bool {
+    a.exec == b.exec && a.divergent_regions.len() == b.divergent_regions.len()
+        && a.divergent_regions.iter().zip(b.divergent_regions.iter()).all(|(a, b)| a.entry == b.entry && a.mask == b.mask)
+}
+
+/* Creates a phi for each run of registers that differs between the register files.
+ * Only the first `from.len()` register files become phi inputs, coming from the
+ * given blocks; the inputs of each are returned as (phi, input index) */
+fn join_regs(st: &mut ExecState, block: BlockIdx, regfiles: &[&Vec<Reg>], from: &[BlockIdx]) -> (Vec<Reg>, Vec<Vec<(PhiIdx, usize)>>) {
+    let len = regfiles.iter().map(|regs| regs.len()).max().unwrap_or(0);
+    /* Registers written on some of the paths only are uninitialized on the others */
+    let regfiles: Vec<Vec<Reg>> = regfiles.iter()
+        .map(|&regs| {
+            let mut regs = regs.clone();
+            regs.resize(len, Reg(std::usize::MAX, 0));
+            regs
+        })
+        .collect();
+
+    let mut inputs: Vec<Vec<(PhiIdx, usize)>> = from.iter().map(|_| Vec::new()).collect();
+    let mut joined = regfiles[0].clone();
+
+    let mut reg_idx = 0;
+    while reg_idx < len {
+        if regfiles.iter().all(|regs| regs[reg_idx] == regfiles[0][reg_idx]) {
+            reg_idx += 1;
+            continue;
+        }
 
-        let exec_dwords = exec_hi_dword - exec_lo_dword + 1;
-        let skip_dwords = skip_hi_dword - skip_lo_dword + 1;
+        let spans: Vec<usize> = regfiles.iter().map(|regs| value_span(&regs[reg_idx..])).collect();
+        let phi_dwords = *spans.iter().max().unwrap();
+        let min_dwords = *spans.iter().min().unwrap();
 
-        variables.push(match (exec_dwords, skip_dwords) {
+        let kind = match (phi_dwords, min_dwords) {
             (1, 1) => Variable::Dword,
             (2, 2) => Variable::Qword,
             (4, 4) => Variable::DQword,
-            (a, b) if a == b => panic!("{}-word variables are not supported", a),
             (2, 1) => Variable::PartialQword,
-            (4, a) if a < 4 => Variable::PartialDQword,
-            (a, b) => panic!("Unsupported variable size: {} dwords on the left, {} dwords on the right", a, b)
+            (4, _) => Variable::PartialDQword,
+            /* Values that do not line up are joined dword by dword */
+            _ => Variable::Dword
+        };
+        let phi_dwords = if kind == Variable::Dword { 1 } else { phi_dwords };
+        let phi_regs = reg_idx..reg_idx + phi_dwords;
+        st.phis.push(Phi {
+            block, kind,
+            inputs: from.iter().zip(regfiles.iter())
+                .map(|(&from, regs)| PhiInput { from, regs: regs[phi_regs.clone()].to_vec() })
+                .collect()
         });
-        let var_dwords = std::cmp::max(exec_dwords as usize, skip_dwords as usize);
-        let var_idx = variables.len() - 1;
+        let phi_idx = st.phis.len() - 1;
+        for (input_idx, pred_inputs) in inputs.iter_mut().enumerate() {
+            pred_inputs.push((phi_idx, input_idx));
+        }
 
-        bindings.push(Binding::Variable { idx: var_idx });
-        let var_binding_idx = bindings.len() - 1;
+        st.values.push(Value::Phi { idx: phi_idx });
+        let phi_value_idx = st.values.len() - 1;
+        for dw in 0..phi_dwords { joined[reg_idx + dw] = Reg(phi_value_idx, dw as u8); }
 
-        create_assignments(&mut executed_branch, &regs_executed[reg_idx..reg_idx + var_dwords], var_idx);
-        create_assignments(&mut skipped_branch, &regs_skipped[reg_idx..reg_idx + var_dwords], var_idx);
+        reg_idx = phi_regs.end;
+    }
+    (joined, inputs)
+}
 
-        for dw in 0..var_dwords { new_regs[reg_idx + dw] = Reg(var_binding_idx, dw as u8); }
+/* Number of consecutive registers holding successive dwords of the same value */
+fn value_span(regs: &[Reg]) -> usize {
+    let Reg(value_idx, lo_dword) = regs[0];
+    regs.iter().enumerate()
+        .take_while(|&(i, &Reg(idx, dword))| idx == value_idx && dword as usize == lo_dword as usize + i)
+        .count()
+}
 
-        if var_dwords > 1 {
-            // skip subsequent registers with the same binding
-            let _ = reg_iter.nth(var_dwords - 1);
-        }
+/* Number of registers taken up by a value */
+fn value_dwords(value: &Value, phis: &[Phi]) -> usize {
+    match *value {
+        Value::Phi { idx } => variable_dwords(phis[idx].kind),
+        Value::Computed { kind, .. } | Value::Deref { kind, .. } | Value::LocalLoad { kind, .. } | Value::Cast { kind, .. } => match kind {
+            DataKind::Dword | DataKind::U8 | DataKind::I8 | DataKind::U16 | DataKind::I16
+            | DataKind::F16 | DataKind::F32 => 1,
+            DataKind::Qword | DataKind::I64 | DataKind::F64 => 2,
+            DataKind::DQword => 4
+        },
+        Value::QwordElement { .. } => 2,
+        _ => 1
     }
-    (new_regs, executed_branch, skipped_branch)
 }
 
-fn create_assignments(assignments: &mut Vec<Statement>, var_regs: &[Reg], var_idx: usize) {
-    let mut i = 0;
-    while i < var_regs.len() {
-        let Reg(binding_idx, binding_dword) = var_regs[i];
-        let Reg(_, binding_hi_dword) = var_regs[i..].iter()
-            .take_while(|&Reg(idx, _)| *idx == binding_idx).last().unwrap();
-
-        let assignment_dwords = 1 + binding_hi_dword - binding_dword;
-
-        let assignment_kind = match assignment_dwords {
-            1 => DataKind::Dword,
-            2 => DataKind::Qword,
-            4 => DataKind::DQword,
-            n => panic!("{}-word variables are not supported", n)
+fn variable_dwords(kind: Variable) -> usize {
+    match kind {
+        Variable::Dword => 1,
+        Variable::Qword | Variable::PartialQword => 2,
+        Variable::DQword | Variable::PartialDQword => 4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::{Operand, Operand::*};
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+    use crate::control_flow::build_graph;
+    use self::types::{Condition, DataKind};
+
+    fn instr(opcode: &str, operands: Vec<Operand>, offset: usize) -> Instruction {
+        let size = if opcode.starts_with("s_load") { 8 } else { 4 };
+        Instruction { opcode: opcode.to_string(), operands, offset, size }
+    }
+
+    /* s[0:1] holds the kernarg pointer */
+    fn state() -> ExecState {
+        let mut kcode = KernelCode::default();
+        kcode.code_props.enable_sgpr_kernarg_segment_ptr = true;
+        ExecState::new(kcode, Target::from_processor("gfx900").unwrap())
+    }
+
+    /* (block, kind, input blocks) of a phi */
+    type PhiInputs = Vec<(BlockIdx, Variable, Vec<BlockIdx>)>;
+
+    /* Runs the analysis, returning the phis and the blocks out of SSA form */
+    fn analyze_kernel(instrs: &[Instruction]) -> (ExecState, PhiInputs, Vec<Program>) {
+        let mut st = state();
+        let cfg = build_graph(instrs).unwrap();
+        let blocks = analyze(&mut st, instrs, &cfg).unwrap();
+        let phis = st.phis.iter()
+            .map(|phi| (phi.block, phi.kind, phi.inputs.iter().map(|input| input.from).collect()))
+            .collect();
+        let blocks = out_of_ssa::destruct(&mut st, &cfg, blocks).unwrap();
+        (st, phis, blocks)
+    }
+
+    /* (variable, assigned constant) for the assignments of a block */
+    fn assignments(st: &ExecState, body: &Program) -> Vec<(usize, Option<u32>)> {
+        body.iter()
+            .filter_map(|(_, stmt)| match *stmt {
+                Statement::VarAssignment { var_idx, value_idx, .. } => match st.values[value_idx] {
+                    Value::U32(constant) => Some((var_idx, Some(constant))),
+                    _ => Some((var_idx, None))
+                },
+                _ => None
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diamond() {
+        let (st, phis, blocks) = analyze_kernel(&[
+            instr("s_load_dword", vec![SReg(2), SRegs(0, 1), Lit(0)], 0),
+            instr("s_cmp_eq_u32", vec![SReg(2), Lit(0)], 8),
+            instr("s_cbranch_scc1", vec![Lit(2)], 12),
+            instr("s_mov_b32", vec![SReg(3), Lit(1)], 16),
+            instr("s_branch", vec![Lit(1)], 20),
+            instr("s_mov_b32", vec![SReg(3), Lit(2)], 24),
+            instr("s_add_u32", vec![SReg(4), SReg(3), SReg(2)], 28),
+            instr("s_endpgm", vec![], 32)
+        ]);
+        assert_eq!(phis, vec![(3, Variable::Dword, vec![1, 2])]);
+        assert_eq!(assignments(&st, &blocks[1]), vec![(0, Some(1))]);
+        assert_eq!(assignments(&st, &blocks[2]), vec![(0, Some(2))]);
+        assert_eq!(st.variables, vec![Variable::Dword]);
+    }
+
+    #[test]
+    fn simple_loop() {
+        let (st, phis, blocks) = analyze_kernel(&[
+            instr("s_mov_b32", vec![SReg(2), Lit(0)], 0),
+            instr("s_add_u32", vec![SReg(2), SReg(2), Lit(1)], 4),
+            instr("s_cmp_lg_u32", vec![SReg(2), Lit(10)], 8),
+            instr("s_cbranch_scc1", vec![Lit(-3)], 12),
+            instr("s_endpgm", vec![], 16)
+        ]);
+        assert_eq!(phis, vec![(1, Variable::Dword, vec![0, 1])]);
+        assert_eq!(assignments(&st, &blocks[0]), vec![(0, Some(0))]);
+        assert_eq!(assignments(&st, &blocks[1]), vec![(0, None)]);
+    }
+
+    #[test]
+    fn nested_loops() {
+        let (st, phis, blocks) = analyze_kernel(&[
+            instr("s_mov_b32", vec![SReg(2), Lit(0)], 0),
+            instr("s_mov_b32", vec![SReg(3), Lit(0)], 4),
+            instr("s_add_u32", vec![SReg(3), SReg(3), Lit(1)], 8),
+            instr("s_cmp_lg_u32", vec![SReg(3), Lit(4)], 12),
+            instr("s_cbranch_scc1", vec![Lit(-3)], 16),
+            instr("s_add_u32", vec![SReg(2), SReg(2), SReg(3)], 20),
+            instr("s_cmp_lg_u32", vec![SReg(2), Lit(100)], 24),
+            instr("s_cbranch_scc1", vec![Lit(-7)], 28),
+            instr("s_endpgm", vec![], 32)
+        ]);
+        let mut phis = phis;
+        phis.sort_by_key(|&(block, ..)| block);
+        /* The inner counter is reset by the outer header, its phi there is never used */
+        assert_eq!(phis, vec![(1, Variable::Dword, vec![0, 3]), (1, Variable::Dword, vec![0, 3]), (2, Variable::Dword, vec![1, 2])]);
+        /* The outer counter and the inner one are live at the same time */
+        let outer: Vec<usize> = assignments(&st, &blocks[0]).iter().map(|&(var, _)| var).collect();
+        let inner: Vec<usize> = assignments(&st, &blocks[1]).iter().map(|&(var, _)| var).collect();
+        assert_eq!((outer.len(), inner.len()), (1, 1));
+        assert_ne!(outer, inner);
+    }
+
+    #[test]
+    fn uneven_spans_are_joined_by_dword() {
+        let mut st = state();
+        let (qword, dword) = (st.values.len(), st.values.len() + 1);
+        st.values.push(Value::Deref { ptr: 0, offset: 0, kind: DataKind::Qword });
+        st.values.push(Value::U32(7));
+        let a = vec![Reg(qword, 1), Reg(dword, 0)];
+        let b = vec![Reg(dword, 0), Reg(qword, 0)];
+        let (joined, inputs) = join_regs(&mut st, 2, &[&a, &b], &[0, 1]);
+        let kinds: Vec<Variable> = st.phis.iter().map(|phi| phi.kind).collect();
+        assert_eq!(kinds, vec![Variable::Dword, Variable::Dword]);
+        assert_eq!(joined.iter().map(|reg| reg.1).collect::<Vec<_>>(), vec![0, 0]);
+        assert_eq!(inputs, vec![vec![(0, 0), (1, 0)], vec![(0, 1), (1, 1)]]);
+    }
+
+    #[test]
+    fn different_exec_masks_are_joined() {
+        let st = state();
+        let region = |mask: &LaneMask, entry| DivergentRegion { mask: mask.clone(), entry, start: entry, vgprs: Vec::new(), if_phis: Vec::new() };
+        let (outer, inner) = (Condition::Ne(0, 1), Condition::Lt(0, 1));
+        let outer_mask = LaneMask::with_condition(outer, true);
+        let with_exec = |exec: LaneMask, regions: Vec<DivergentRegion>| {
+            let mut regs = st.reg_state();
+            regs.exec = exec;
+            regs.divergent_regions = regions;
+            regs
         };
 
-        assignments.push(Statement::VarAssignment {
-            var_idx,
-            binding_idx,
-            binding_dword,
-            var_dword: i as u8,
-            kind: assignment_kind });
+        let then_mask = outer_mask.and(&LaneMask::with_condition(inner, true));
+        let else_mask = outer_mask.and(&LaneMask::with_condition(inner, false));
+        let then_part = with_exec(then_mask.clone(), vec![region(&outer_mask, 0), region(&then_mask, 1)]);
+        let else_part = with_exec(else_mask.clone(), vec![region(&outer_mask, 0), region(&else_mask, 1)]);
+        let skipped = with_exec(outer_mask.clone(), vec![region(&outer_mask, 0)]);
+
+        let (exec, regions) = join_exec(&[&then_part, &else_part]);
+        assert_eq!((exec, regions.len()), (outer_mask.clone(), 1));
+        let (exec, regions) = join_exec(&[&then_part, &skipped]);
+        assert_eq!((exec, regions.len()), (outer_mask, 1));
+        let (exec, regions) = join_exec(&[&then_part, &with_exec(LaneMask::all(), Vec::new())]);
+        assert_eq!((exec, regions.len()), (LaneMask::all(), 0));
+    }
 
-        i += assignment_dwords as usize;
+    #[test]
+    fn vcc_branches() {
+        let branch = |opcode: &str| {
+            let instrs = [
+                instr("v_cmp_eq_u32_e32", vec![VCC, Lit(0), VReg(0)], 0),
+                instr(opcode, vec![Lit(1)], 4),
+                instr("v_mov_b32_e32", vec![VReg(1), Lit(1)], 8),
+                instr("s_endpgm", vec![], 12)
+            ];
+            let mut st = state();
+            let blocks = analyze(&mut st, &instrs, &build_graph(&instrs).unwrap()).unwrap();
+            blocks[0].last().unwrap().1.clone()
+        };
+        match (branch("s_cbranch_vccnz"), branch("s_cbranch_vccz")) {
+            (Statement::JumpIf { cond: Condition::Eql(..), label_idx: 2 }, Statement::JumpUnless { cond: Condition::Eql(..), label_idx: 2 }) => (),
+            other => panic!("Unexpected branches {:?}", other)
+        }
     }
-}
 
+    #[test]
+    fn indirect_branch_is_an_error() {
+        let instrs = [instr("s_setpc_b64", vec![SRegs(0, 1)], 0)];
+        let mut st = state();
+        assert!(analyze(&mut st, &instrs, &build_graph(&instrs).unwrap()).is_err());
+    }
+
+    #[test]
+    fn scc_joined_at_merge() {
+        let instrs = [
+            instr("s_cmp_eq_u32", vec![SReg(0), Lit(0)], 0),
+            instr("s_cbranch_scc1", vec![Lit(2)], 4),
+            instr("s_cmp_eq_u32", vec![SReg(0), Lit(1)], 8),
+            instr("s_branch", vec![Lit(1)], 12),
+            instr("s_cmp_eq_u32", vec![SReg(0), Lit(2)], 16),
+            instr("s_cbranch_scc1", vec![Lit(1)], 20),
+            instr("v_mov_b32_e32", vec![VReg(1), Lit(1)], 24),
+            instr("s_endpgm", vec![], 28)
+        ];
+        let mut st = state();
+        let cfg = build_graph(&instrs).unwrap();
+        let blocks = analyze(&mut st, &instrs, &cfg).unwrap();
+        let join = cfg.block_at_instruction(5).unwrap();
+        match blocks[join].last().unwrap().1 {
+            Statement::JumpIf { cond: Condition::Eql(lhs, rhs), .. } => {
+                assert!(!matches!(st.values[lhs], Value::Phi { .. }));
+                assert!(matches!(st.values[rhs], Value::Phi { .. }));
+            },
+            ref other => panic!("Unexpected branch {:?}", other)
+        }
+    }
+
+    #[test]
+    fn unknown_scc_branch_is_an_error() {
+        let instrs = [
+            instr("s_add_i32", vec![SReg(2), SReg(0), SReg(1)], 0),
+            instr("s_cbranch_scc1", vec![Lit(0)], 4),
+            instr("s_endpgm", vec![], 8)
+        ];
+        let mut st = state();
+        assert!(analyze(&mut st, &instrs, &build_graph(&instrs).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+/* Per-lane control flow. Narrowing EXEC (s_and_saveexec) enters a divergent region,
+ * switching to the complementary mask (s_xor exec) moves on to the else part, and
+ * restoring the saved mask (s_or exec) leaves the region. VGPRs written inside a
+ * region hold the new values only in the region's lanes, so on leaving it they are
+ * merged into phis: the value from before the region is copied where EXEC was
+ * narrowed, and the new value is copied at the end of the region. */
+
+use crate::control_flow::{ControlFlowGraph, BlockIdx};
+use super::{join_regs, define_values, Program};
+use super::exec_state::{ExecState, DivergentRegion, LeftRegion};
+use super::lane_mask::LaneMask;
+use super::types::{Statement, Value, PhiIdx, AsmInstructionIdx};
+
+pub fn exec_changed(st: &mut ExecState, cfg: &ControlFlowGraph, bodies: &mut [Program], block: BlockIdx, instr_idx: AsmInstructionIdx) {
+    let exec = st.exec.clone();
+    loop {
+        let enclosing = st.divergent_regions.last().map_or_else(LaneMask::all, |region| region.mask.clone());
+        if enclosing.contains(&exec) {
+            if enclosing != exec {
+                let region = match st.left_region.take() {
+                    /* EXEC is switched to the complement of the region just left, with nothing executed in between */
+                    Some(LeftRegion { region, exit, phis }) if region.mask != exec
+                        && region.mask.or(&exec).as_ref() == Some(&enclosing)
+                        && exec_changed_last(&bodies[block], exit) =>
+                        DivergentRegion { mask: exec.clone(), entry: region.entry, start: instr_idx, vgprs: st.vgprs.clone(), if_phis: phis },
+                    _ =>
+                        DivergentRegion { mask: exec.clone(), entry: instr_idx, start: instr_idx, vgprs: st.vgprs.clone(), if_phis: Vec::new() }
+                };
+                st.divergent_regions.push(region);
+            }
+            break;
+        }
+        /* All lanes are in the outermost mask, so there is always a region to leave */
+        let region = st.divergent_regions.pop().unwrap();
+        let phis = leave_region(st, cfg, bodies, block, instr_idx, &region);
+        st.left_region = Some(LeftRegion { region, exit: instr_idx, phis });
+    }
+    bodies[block].push((instr_idx, Statement::ExecMask { mask: exec }));
+}
+
+/* Whether the last statement executed is the EXEC change at `at`. Scalar values
+ * defined in between do not depend on EXEC */
+fn exec_changed_last(body: &Program, at: AsmInstructionIdx) -> bool {
+    match body.iter().rev().find(|(_, stmt)| !matches!(stmt, Statement::Define { .. })) {
+        Some((idx, Statement::ExecMask { .. })) => *idx == at,
+        _ => false
+    }
+}
+
+/* Position of the EXEC mask change made by `instr_idx` */
+fn exec_change_position(body: &Program, instr_idx: AsmInstructionIdx) -> usize {
+    body.iter()
+        .rposition(|(idx, stmt)| match stmt { Statement::ExecMask { .. } => *idx == instr_idx, _ => false })
+        .unwrap_or_else(|| panic!("No EXEC mask change recorded at instruction {}", instr_idx))
+}
+
+/* Returns the phis created for the registers written in the region */
+fn leave_region(st: &mut ExecState, cfg: &ControlFlowGraph, bodies: &mut [Program], block: BlockIdx, instr_idx: AsmInstructionIdx, region: &DivergentRegion) -> Vec<PhiIdx> {
+    let entry_block = cfg.block_at_instruction(region.entry).unwrap();
+    let start_block = cfg.block_at_instruction(region.start).unwrap();
+    let first_phi_idx = st.phis.len();
+    let first_value_idx = st.values.len();
+
+    let region_vgprs = st.vgprs.clone();
+    let (vgprs, mut inputs) = join_regs(st, block, &[&region.vgprs, &region_vgprs], &[entry_block, block]);
+    st.vgprs = vgprs;
+
+    /* Values from before the region are copied right before EXEC is narrowed. In the
+     * else part of an if/else, the results of the if part are only known once it ends */
+    let (from_if, before): (Vec<_>, Vec<_>) = inputs[0].drain(..)
+        .partition(|&(phi_idx, input_idx)| st.phis[phi_idx].inputs[input_idx].regs.iter()
+            .any(|reg| matches!(st.values.get(reg.0), Some(Value::Phi { idx }) if region.if_phis.contains(idx))));
+    for &(phi_idx, input_idx) in &from_if {
+        st.phis[phi_idx].inputs[input_idx].from = start_block;
+    }
+    for &(copy_block, at, ref copies) in [(entry_block, region.entry, before), (start_block, region.start, from_if)].iter() {
+        if !copies.is_empty() {
+            let position = exec_change_position(&bodies[copy_block], at);
+            bodies[copy_block].insert(position, (at, Statement::ParallelCopy { inputs: copies.clone() }));
+        }
+    }
+
+    if !inputs[1].is_empty() {
+        bodies[block].push((instr_idx, Statement::ParallelCopy { inputs: inputs.pop().unwrap() }));
+    }
+    define_values(st, &mut bodies[block], instr_idx, first_value_idx);
+    (first_phi_idx..st.phis.len()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asm::{Instruction, Operand, Operand::*};
+    use crate::asm::operand::SpecialReg;
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+    use crate::control_flow::build_graph;
+    use crate::data_flow::{analyze, exec_state::ExecState};
+    use crate::data_flow::types::{Statement, Value, Condition};
+
+    const EXEC: Operand = Special(SpecialReg::Exec);
+
+    /* Runs a single-block kernel, returning the EXEC masks it sets as (condition, holds) lists */
+    fn exec_masks(code: &[(&str, Vec<Operand>)]) -> (ExecState, Vec<Vec<(Condition, bool)>>) {
+        let instrs: Vec<Instruction> = code.iter().enumerate()
+            .map(|(idx, (opcode, operands))| Instruction { opcode: opcode.to_string(), operands: operands.clone(), offset: idx * 4, size: 4 })
+            .collect();
+        let mut st = ExecState::new(KernelCode::default(), Target::from_processor("gfx900").unwrap());
+        let blocks = analyze(&mut st, &instrs, &build_graph(&instrs).unwrap()).unwrap();
+        let masks = blocks.iter().flatten()
+            .filter_map(|(_, stmt)| match stmt {
+                Statement::ExecMask { mask } => Some(mask.terms.clone()),
+                _ => None
+            })
+            .collect();
+        (st, masks)
+    }
+
+    #[test]
+    fn nested_if_else() {
+        let (st, masks) = exec_masks(&[
+            ("v_cmp_eq_u32_e32", vec![VCC, Lit(0), VReg(0)]),
+            ("s_and_saveexec_b64", vec![SRegs(2, 3), VCC]),
+            ("v_cmp_lt_u32_e32", vec![VCC, Lit(1), VReg(0)]),
+            ("s_and_saveexec_b64", vec![SRegs(4, 5), VCC]),
+            ("v_mov_b32_e32", vec![VReg(1), Lit(1)]),
+            ("s_andn2_b64", vec![EXEC, SRegs(4, 5), EXEC]),
+            ("v_mov_b32_e32", vec![VReg(1), Lit(2)]),
+            ("s_or_b64", vec![EXEC, EXEC, SRegs(4, 5)]),
+            ("s_or_b64", vec![EXEC, EXEC, SRegs(2, 3)]),
+            ("s_endpgm", vec![])
+        ]);
+        let (outer, inner) = match masks[1].as_slice() {
+            &[(outer @ Condition::Eql(..), true), (inner @ Condition::LtU(..), true)] => (outer, inner),
+            other => panic!("Unexpected inner mask {:?}", other)
+        };
+        assert_eq!(masks, vec![
+            vec![(outer, true)],
+            vec![(outer, true), (inner, true)],
+            vec![(outer, true), (inner, false)],
+            vec![(outer, true)],
+            vec![]
+        ]);
+        /* v1 is joined once for the inner if/else and once for the outer if */
+        assert!(matches!(st.values[st.vgprs[1].0], Value::Phi { .. }));
+        assert_eq!(st.phis.len(), 3);
+    }
+
+    #[test]
+    fn literal_masks() {
+        let (_, masks) = exec_masks(&[
+            ("s_mov_b64", vec![EXEC, Lit(0)]),
+            ("s_or_saveexec_b64", vec![SRegs(2, 3), Lit(-1)]),
+            ("s_endpgm", vec![])
+        ]);
+        match masks.as_slice() {
+            [none, all] => {
+                assert!(matches!(none.as_slice(), [(Condition::Eql(lhs, rhs), false)] if lhs == rhs));
+                assert!(all.is_empty());
+            },
+            other => panic!("Unexpected masks {:?}", other)
+        }
+    }
+}
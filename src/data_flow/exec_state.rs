@@ -1,115 +1,195 @@
 use crate::asm::kernel_code::{KernelCode, VGPRWorkItemId};
-use crate::data_flow::types::{Binding, BuiltIn, Variable, Reg, Condition};
+use crate::asm::target::Target;
+use crate::data_flow::types::{Value, BuiltIn, Variable, Phi, Reg, Condition, DataKind, AsmInstructionIdx};
+use crate::data_flow::lane_mask::LaneMask;
 
 #[derive(Clone)]
 pub struct ExecState {
     pub sgprs: Vec<Reg>,
     pub vgprs: Vec<Reg>,
-    pub bindings: Vec<Binding>,
+    pub values: Vec<Value>,
+    pub phis: Vec<Phi>,
+    /* Assigned by out_of_ssa */
     pub variables: Vec<Variable>,
-    pub vcc: Option<Condition>,
-    pub scc: Option<Condition>
+    pub vcc: Option<LaneMask>,
+    pub scc: Option<Condition>,
+    pub exec: LaneMask,
+    /* Innermost last */
+    pub divergent_regions: Vec<DivergentRegion>,
+    pub left_region: Option<LeftRegion>,
+    /* Lane masks held in SGPRs, referenced by Value::LaneMask */
+    pub lane_masks: Vec<LaneMask>,
+    /* EXEC, VCC and VOPC results hold one bit per lane */
+    pub lane_mask: DataKind,
+    /* Bytes of LDS allocated to a work-group */
+    pub lds_size: u32,
+    pub target: Target
+}
+
+/* Code executed with EXEC narrowed down from the enclosing mask. VGPRs of the lanes
+ * that are switched off keep the values they had when the region was entered */
+#[derive(Debug, Clone)]
+pub struct DivergentRegion {
+    pub mask: LaneMask,
+    /* Values from before the region are copied before this instruction: the one that
+     * narrowed EXEC, or, for the else part of an if/else, the one that started the if part */
+    pub entry: AsmInstructionIdx,
+    /* The instruction that set the region's mask */
+    pub start: AsmInstructionIdx,
+    pub vgprs: Vec<Reg>,
+    /* Phis merging the results of the if part, which the else part starts with */
+    pub if_phis: Vec<usize>
+}
+
+/* The region most recently left by the instruction `exit`, and the phis created for it */
+#[derive(Debug, Clone)]
+pub struct LeftRegion {
+    pub region: DivergentRegion,
+    pub exit: AsmInstructionIdx,
+    pub phis: Vec<usize>
+}
+
+/* Register contents at a program point. Values and phis are shared by the
+ * whole program, so this is the only part of the state tracked per basic block */
+#[derive(Debug, Clone)]
+pub struct RegState {
+    pub sgprs: Vec<Reg>,
+    pub vgprs: Vec<Reg>,
+    pub vcc: Option<LaneMask>,
+    pub scc: Option<Condition>,
+    pub exec: LaneMask,
+    pub divergent_regions: Vec<DivergentRegion>
 }
 
 use std::fmt;
 
 impl fmt::Debug for ExecState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "Bindings:")?;
-        self.bindings.iter().enumerate()
-            .try_for_each(|(i, binding)| writeln!(f, "{:4} {:?}", i, binding))?;
+        writeln!(f, "Values:")?;
+        self.values.iter().enumerate()
+            .try_for_each(|(i, value)| writeln!(f, "{:4} {:?}", i, value))?;
+
+        writeln!(f, "Phis:")?;
+        self.phis.iter().enumerate()
+            .try_for_each(|(i, phi)| writeln!(f, "{:4} {:?}", i, phi))?;
 
         writeln!(f, "Variables:")?;
         self.variables.iter().enumerate()
             .try_for_each(|(i, variable)| writeln!(f, "{:4} {:?}", i, variable))?;
 
+        writeln!(f, "Lane masks:")?;
+        self.lane_masks.iter().enumerate()
+            .try_for_each(|(i, mask)| writeln!(f, "{:4} {:?}", i, mask))?;
+
         writeln!(f, "SGPRS: {:?}", self.sgprs.iter().enumerate().collect::<Vec<(usize, &Reg)>>())?;
         writeln!(f, "VGPRS: {:?}", self.vgprs.iter().enumerate().collect::<Vec<(usize, &Reg)>>())?;
-        writeln!(f, "SCC: {:?}, VCC: {:?}", self.scc, self.vcc)
+        writeln!(f, "SCC: {:?}, VCC: {:?}, EXEC: {:?}", self.scc, self.vcc, self.exec)
     }
 }
 
 macro_rules! bind_init_state {
-    (qword $val:expr, $bindings:expr, $regfile:expr) => {
-        $bindings.push(Binding::InitState($val));
-        $regfile.push(Reg($bindings.len() - 1, 0));
-        $regfile.push(Reg($bindings.len() - 1, 1));
+    (qword $val:expr, $values:expr, $regfile:expr) => {
+        $values.push(Value::InitState($val));
+        $regfile.push(Reg($values.len() - 1, 0));
+        $regfile.push(Reg($values.len() - 1, 1));
     };
-    (dword $val:expr, $bindings:expr, $regfile:expr) => {
-        $bindings.push(Binding::InitState($val));
-        $regfile.push(Reg($bindings.len() - 1, 0));
+    (dword $val:expr, $values:expr, $regfile:expr) => {
+        $values.push(Value::InitState($val));
+        $regfile.push(Reg($values.len() - 1, 0));
     }
 }
 
-impl From<KernelCode> for ExecState {
-    fn from(kcode: KernelCode) -> Self {
+impl ExecState {
+    pub fn new(kcode: KernelCode, target: Target) -> Self {
         let mut sgprs: Vec<Reg> = Vec::with_capacity(16);
-        let mut bindings: Vec<Binding> = Vec::with_capacity(16);
+        let mut values: Vec<Value> = Vec::with_capacity(16);
 
         /* https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-sgpr-register-set-up-order-table */
         if kcode.code_props.enable_sgpr_private_segment_buffer {
-            bindings.push(Binding::InitState(BuiltIn::PrivateSegmentBuffer));
-            for i in 0..4 { sgprs.push(Reg(bindings.len() - 1, i)); }
+            values.push(Value::InitState(BuiltIn::PrivateSegmentBuffer));
+            for i in 0..4 { sgprs.push(Reg(values.len() - 1, i)); }
         }
         if kcode.code_props.enable_sgpr_dispatch_ptr {
-            bind_init_state!(qword BuiltIn::PtrDispatchPacket, bindings, sgprs);
+            bind_init_state!(qword BuiltIn::PtrDispatchPacket, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_queue_ptr {
-            bind_init_state!(qword BuiltIn::PtrQueue, bindings, sgprs);
+            bind_init_state!(qword BuiltIn::PtrQueue, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_kernarg_segment_ptr {
-            bind_init_state!(qword BuiltIn::PtrKernarg, bindings, sgprs);
+            bind_init_state!(qword BuiltIn::PtrKernarg, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_dispatch_id {
-            bind_init_state!(qword BuiltIn::DispatchId, bindings, sgprs);
+            bind_init_state!(qword BuiltIn::DispatchId, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_flat_scratch_init {
-            bind_init_state!(qword BuiltIn::FlatScratchInit, bindings, sgprs);
+            bind_init_state!(qword BuiltIn::FlatScratchInit, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_grid_workgroup_count_x {
-            bind_init_state!(dword BuiltIn::WorkgroupCountX, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupCountX, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_grid_workgroup_count_y && sgprs.len() < 16 {
-            bind_init_state!(dword BuiltIn::WorkgroupCountY, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupCountY, values, sgprs);
         }
         if kcode.code_props.enable_sgpr_grid_workgroup_count_z && sgprs.len() < 16 {
-            bind_init_state!(dword BuiltIn::WorkgroupCountZ, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupCountZ, values, sgprs);
         }
         if kcode.pgm_props.enable_sgpr_workgroup_id_x {
-            bind_init_state!(dword BuiltIn::WorkgroupIdX, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupIdX, values, sgprs);
         }
         if kcode.pgm_props.enable_sgpr_workgroup_id_y {
-            bind_init_state!(dword BuiltIn::WorkgroupIdY, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupIdY, values, sgprs);
         }
         if kcode.pgm_props.enable_sgpr_workgroup_id_z {
-            bind_init_state!(dword BuiltIn::WorkgroupIdZ, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupIdZ, values, sgprs);
         }
         if kcode.pgm_props.enable_sgpr_workgroup_info {
-            bind_init_state!(dword BuiltIn::WorkgroupInfo, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::WorkgroupInfo, values, sgprs);
         }
         if kcode.pgm_props.enable_sgpr_private_segment_wavefront_offset {
-            bind_init_state!(dword BuiltIn::PrivateSegmentWavefrontOffset, bindings, sgprs);
+            bind_init_state!(dword BuiltIn::PrivateSegmentWavefrontOffset, values, sgprs);
         }
 
         /* https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-vgpr-register-set-up-order-table */
         let vgprs: Vec<Reg> = match kcode.pgm_props.enable_vgpr_workitem_id {
             VGPRWorkItemId::X => {
-                bindings.push(Binding::InitState(BuiltIn::WorkitemIdX));
-                vec![Reg(bindings.len() - 1, 0)]
+                values.push(Value::InitState(BuiltIn::WorkitemIdX));
+                vec![Reg(values.len() - 1, 0)]
             },
             VGPRWorkItemId::XY => {
-                bindings.push(Binding::InitState(BuiltIn::WorkitemIdX));
-                bindings.push(Binding::InitState(BuiltIn::WorkitemIdY));
-                vec![Reg(bindings.len() - 2, 0), Reg(bindings.len() - 1, 0)]
+                values.push(Value::InitState(BuiltIn::WorkitemIdX));
+                values.push(Value::InitState(BuiltIn::WorkitemIdY));
+                vec![Reg(values.len() - 2, 0), Reg(values.len() - 1, 0)]
             },
             VGPRWorkItemId::XYZ => {
-                bindings.push(Binding::InitState(BuiltIn::WorkitemIdX));
-                bindings.push(Binding::InitState(BuiltIn::WorkitemIdY));
-                bindings.push(Binding::InitState(BuiltIn::WorkitemIdZ));
-                vec![Reg(bindings.len() - 3, 0), Reg(bindings.len() - 2, 0), Reg(bindings.len() - 1, 0)]
+                values.push(Value::InitState(BuiltIn::WorkitemIdX));
+                values.push(Value::InitState(BuiltIn::WorkitemIdY));
+                values.push(Value::InitState(BuiltIn::WorkitemIdZ));
+                vec![Reg(values.len() - 3, 0), Reg(values.len() - 2, 0), Reg(values.len() - 1, 0)]
             }
         };
         
-        ExecState { sgprs, vgprs, bindings, scc: None, vcc: None, variables: Vec::new() }
+        let lane_mask = if kcode.wave_lanes() == 32 { DataKind::Dword } else { DataKind::Qword };
+        let lds_size = kcode.lds_size();
+
+        ExecState {
+            sgprs, vgprs, values, scc: None, vcc: None, lane_mask, lds_size, phis: Vec::new(), variables: Vec::new(), target,
+            exec: LaneMask::all(), divergent_regions: Vec::new(), left_region: None, lane_masks: Vec::new()
+        }
+    }
+
+    pub fn reg_state(&self) -> RegState {
+        RegState {
+            sgprs: self.sgprs.clone(), vgprs: self.vgprs.clone(), vcc: self.vcc.clone(), scc: self.scc,
+            exec: self.exec.clone(), divergent_regions: self.divergent_regions.clone()
+        }
+    }
+
+    pub fn set_reg_state(&mut self, regs: RegState) {
+        self.sgprs = regs.sgprs;
+        self.vgprs = regs.vgprs;
+        self.vcc = regs.vcc;
+        self.scc = regs.scc;
+        self.exec = regs.exec;
+        self.divergent_regions = regs.divergent_regions;
     }
 }
@@ -0,0 +1,130 @@
+/* Symbolic lane masks. EXEC, VCC and saved copies of EXEC are described as the
+ * lanes active at kernel entry for which a conjunction of per-lane conditions holds.
+ * This covers the masks of nested if/else regions (see docs/CONTROL_FLOW.md);
+ * masks that cannot be expressed this way are reported as None. */
+
+use super::types::{Condition, ValueIdx};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaneMask {
+    /* (condition, whether it holds) in the order the mask was narrowed */
+    pub terms: Vec<(Condition, bool)>
+}
+
+impl LaneMask {
+    /* Lanes active at kernel entry */
+    pub fn all() -> LaneMask {
+        LaneMask { terms: Vec::new() }
+    }
+
+    /* No lanes: those for which `value` differs from itself */
+    pub fn none(value: ValueIdx) -> LaneMask {
+        LaneMask::with_condition(Condition::Eql(value, value), false)
+    }
+
+    pub fn with_condition(cond: Condition, holds: bool) -> LaneMask {
+        LaneMask { terms: vec![(cond, holds)] }
+    }
+
+    /* Every lane of `other` is also in this mask */
+    pub fn contains(&self, other: &LaneMask) -> bool {
+        self.terms.iter().all(|term| other.terms.contains(term))
+    }
+
+    /* The condition this mask narrows `outer` by, when it is the only one */
+    pub fn narrowing_of(&self, outer: &LaneMask) -> Option<(Condition, bool)> {
+        let extra: Vec<&(Condition, bool)> = self.terms.iter().filter(|term| !outer.terms.contains(term)).collect();
+        match extra.as_slice() {
+            [&term] if outer.contains(self) => Some(term),
+            _ => None
+        }
+    }
+
+    pub fn and(&self, other: &LaneMask) -> LaneMask {
+        let mut terms = self.terms.clone();
+        terms.extend(other.terms.iter().filter(|term| !self.terms.contains(term)).cloned());
+        LaneMask { terms }
+    }
+
+    /* Lanes of this mask that are not in `other` */
+    pub fn and_not(&self, other: &LaneMask) -> Option<LaneMask> {
+        let extra: Vec<&(Condition, bool)> = other.terms.iter().filter(|term| !self.terms.contains(term)).collect();
+        match extra.as_slice() {
+            [&(cond, holds)] => {
+                let mut terms = self.terms.clone();
+                terms.push((cond, !holds));
+                Some(LaneMask { terms })
+            },
+            _ => None
+        }
+    }
+
+    pub fn or(&self, other: &LaneMask) -> Option<LaneMask> {
+        if self.contains(other) {
+            return Some(self.clone());
+        }
+        if other.contains(self) {
+            return Some(other.clone());
+        }
+        /* (m & c) | (m & ~c) = m */
+        match (self.terms.split_last(), other.terms.split_last()) {
+            (Some((&(cond, holds), rest)), Some((&(other_cond, other_holds), other_rest)))
+                if cond == other_cond && holds != other_holds
+                    && rest.iter().all(|term| other_rest.contains(term))
+                    && other_rest.iter().all(|term| rest.contains(term)) =>
+                Some(LaneMask { terms: rest.to_vec() }),
+            _ => None
+        }
+    }
+
+    pub fn xor(&self, other: &LaneMask) -> Option<LaneMask> {
+        if self.contains(other) {
+            self.and_not(other)
+        }
+        else if other.contains(self) {
+            other.and_not(self)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_masks_are_conjunctions() {
+        let (outer, inner) = ((Condition::Ne(0, 1), true), (Condition::Lt(0, 2), true));
+        let if_mask = LaneMask::all().and(&LaneMask { terms: vec![outer] });
+        let then_mask = if_mask.and(&LaneMask { terms: vec![outer, inner] });
+        assert_eq!(then_mask.terms, vec![outer, inner]);
+        assert!(if_mask.contains(&then_mask) && !then_mask.contains(&if_mask));
+        assert_eq!(then_mask.narrowing_of(&if_mask), Some(inner));
+        assert_eq!(then_mask.narrowing_of(&LaneMask::all()), None);
+
+        /* Else part of the inner if, then leaving both regions */
+        let else_mask = if_mask.and_not(&then_mask).unwrap();
+        assert_eq!(else_mask.terms, vec![outer, (inner.0, false)]);
+        assert_eq!(if_mask.xor(&then_mask), Some(else_mask.clone()));
+        assert_eq!(else_mask.or(&then_mask), Some(if_mask.clone()));
+        assert_eq!(if_mask.or(&LaneMask::all()), Some(LaneMask::all()));
+    }
+
+    #[test]
+    fn unsupported_combinations() {
+        let (a, b) = (LaneMask::with_condition(Condition::Ne(0, 1), true), LaneMask::with_condition(Condition::Lt(0, 2), true));
+        assert_eq!(a.or(&b), None);
+        assert_eq!(a.xor(&b), None);
+        assert_eq!(LaneMask::all().and_not(&a.and(&b)), None);
+    }
+
+    #[test]
+    fn no_lanes() {
+        let none = LaneMask::none(3);
+        assert_eq!(none.terms, vec![(Condition::Eql(3, 3), false)]);
+        assert!(LaneMask::all().contains(&none));
+        assert_eq!(none.or(&LaneMask::all()), Some(LaneMask::all()));
+    }
+}
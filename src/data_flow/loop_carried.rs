@@ -0,0 +1,104 @@
+/* Registers carried around a loop. The state at the loop header is joined with the
+ * states reaching it through back edges, and registers that differ become phis
+ * of the header; the blocks the back edges leave from supply their inputs.
+ * A loop that changes a register the header has no phi for has to be
+ * evaluated again (see data_flow::analyze).
+ *
+ * A divergent loop removes the lanes that leave it from EXEC, accumulating them in
+ * a lane mask cleared before the loop, and goes on while any lanes are left
+ * (s_cbranch_execnz). For a single lane, the loop goes on while the conditions EXEC
+ * was narrowed by hold, and the accumulated mask holds none of the lanes that run
+ * the next iteration: the header keeps the EXEC mask and the lane masks it is
+ * entered with. */
+
+use std::io;
+
+use crate::control_flow::BlockIdx;
+use super::exec_state::{ExecState, RegState};
+use super::lane_mask::LaneMask;
+use super::types::{Value, Reg, PhiIdx, PhiInput, Statement};
+use super::{value_span, unsupported};
+
+/* Adds the inputs coming from `latch_block` to the phis of the header, returning them
+ * as (phi, input index), or None when the latch changes registers that have no phi.
+ * The latch state is merged with the header first (see merge_at_header). Phis created
+ * for the header have indices starting at `first_phi_idx` */
+pub fn back_edge_inputs(st: &mut ExecState, header: &RegState, latch: &RegState, first_phi_idx: PhiIdx, latch_block: BlockIdx) -> Option<Vec<(PhiIdx, usize)>> {
+    let mut carried: Vec<(PhiIdx, Vec<Reg>)> = Vec::new();
+    for &(header_regs, latch_regs) in [(&header.sgprs, &latch.sgprs), (&header.vgprs, &latch.vgprs)].iter() {
+        if !carried_regs(&mut carried, header_regs, latch_regs, first_phi_idx, &st.values) {
+            return None;
+        }
+    }
+
+    let inputs = carried.into_iter()
+        .map(|(phi_idx, regs)| {
+            let phi_inputs = &mut st.phis[phi_idx].inputs;
+            match phi_inputs.iter().position(|input| input.from == latch_block) {
+                Some(input_idx) => {
+                    phi_inputs[input_idx].regs = regs;
+                    (phi_idx, input_idx)
+                },
+                None => {
+                    phi_inputs.push(PhiInput { from: latch_block, regs });
+                    (phi_idx, phi_inputs.len() - 1)
+                }
+            }
+        })
+        .collect();
+    Some(inputs)
+}
+
+/* Collects the latch registers of each header phi */
+fn carried_regs(carried: &mut Vec<(PhiIdx, Vec<Reg>)>, header: &[Reg], latch: &[Reg], first_phi_idx: PhiIdx, values: &[Value]) -> bool {
+    let len = header.len().max(latch.len());
+    let (mut header, mut latch) = (header.to_vec(), latch.to_vec());
+    header.resize(len, Reg(std::usize::MAX, 0));
+    latch.resize(len, Reg(std::usize::MAX, 0));
+
+    let mut reg_idx = 0;
+    while reg_idx < len {
+        let Reg(value_idx, dword) = header[reg_idx];
+        match values.get(value_idx) {
+            Some(&Value::Phi { idx }) if idx >= first_phi_idx && dword == 0 => {
+                let phi_regs = reg_idx..reg_idx + value_span(&header[reg_idx..]);
+                carried.push((idx, latch[phi_regs.clone()].to_vec()));
+                reg_idx = phi_regs.end;
+            },
+            _ if latch[reg_idx] != header[reg_idx] => return false,
+            _ => reg_idx += 1
+        }
+    }
+    true
+}
+
+/* The state a back edge carries into the header: lanes that left the loop are dropped
+ * from EXEC, and lane masks keep their value from the header. The back edge leaves
+ * at `branch_idx` */
+pub fn merge_at_header(header: &RegState, latch: &RegState, values: &[Value], branch_idx: usize) -> io::Result<RegState> {
+    if !header.exec.contains(&latch.exec) {
+        return Err(unsupported(format!("Loop branch at instruction {} runs lanes that are not active at the loop header: EXEC {:?}, at the header {:?}",
+                                       branch_idx, latch.exec, header.exec)));
+    }
+    let sgprs = latch.sgprs.iter().enumerate()
+        .map(|(reg_idx, &reg)| match (values.get(reg.0), header.sgprs.get(reg_idx)) {
+            (Some(Value::LaneMask { .. }), Some(&header_reg)) => header_reg,
+            _ => reg
+        })
+        .collect();
+    Ok(RegState {
+        sgprs, vgprs: latch.vgprs.clone(), vcc: latch.vcc.clone(), scc: latch.scc,
+        exec: header.exec.clone(), divergent_regions: header.divergent_regions.clone()
+    })
+}
+
+/* The branch back to the header of a divergent loop, taken by the lanes that are still
+ * in EXEC at `branch_idx` */
+pub fn continue_condition(header: &RegState, latch_exec: &LaneMask, header_block: BlockIdx, branch_idx: usize) -> io::Result<Statement> {
+    match latch_exec.narrowing_of(&header.exec) {
+        Some((cond, true)) => Ok(Statement::JumpIf { cond, label_idx: header_block }),
+        Some((cond, false)) => Ok(Statement::JumpUnless { cond, label_idx: header_block }),
+        None => Err(unsupported(format!("Unsupported divergent loop branch at instruction {}: EXEC {:?} at the branch, {:?} at the header",
+                                        branch_idx, latch_exec, header.exec)))
+    }
+}
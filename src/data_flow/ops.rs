@@ -1,6 +1,16 @@
-use crate::data_flow::{Program, exec_state::ExecState};
-use crate::data_flow::types::{Reg, Expr, Statement, Condition, Binding, BindingIdx, DataKind};
+mod float;
+mod lds;
+
+use std::io;
+
+use crate::data_flow::{Program, exec_state::ExecState, lane_mask::LaneMask, unsupported};
+use crate::data_flow::types::{Reg, Expr, BitFn, Statement, Condition, Value, ValueIdx, DataKind};
 use crate::asm::{Operand, Operand::*};
+use crate::asm::operand::SpecialReg;
+use crate::asm::semantics::{self, Semantics, MemoryAccess, AddressSpace, DataType, Branch, ImplicitReg};
+
+/* Constructor of a two-operand expression, such as Expr::Add */
+type BinaryExpr = fn(ValueIdx, ValueIdx) -> Expr;
 
 macro_rules! insert_into {
     ($vec:expr, $index:expr, $contents:expr) => {
@@ -16,242 +26,1041 @@ macro_rules! insert_into {
     }
 }
 
-pub fn eval_gcn_instruction(st: &mut ExecState, pgm: &mut Program, instr_idx: usize, instr: &str, ops: &[Operand]) {
+pub fn eval_gcn_instruction(st: &mut ExecState, pgm: &mut Program, instr_idx: usize, instr: &str, ops: &[Operand]) -> io::Result<()> {
     match instr {
-        "s_waitcnt" | "s_endpgm" => (),
-        "global_store_dword" => match ops {
-            [VRegs(dst_lo, dst_hi), VReg(src), _] if st.vgprs[*dst_lo].0 == st.vgprs[*dst_hi].0 && st.vgprs[*dst_lo].1 == 0 && st.vgprs[*dst_hi].1 == 1 => {
-                let Reg(binding_dst, _) = st.vgprs[*dst_lo];
-                let Reg(binding_src, _) = st.vgprs[*src];
-                pgm.push((instr_idx + 1, Statement::Store { addr: binding_dst, data: binding_src, kind: DataKind::Dword }))
+        "s_waitcnt" | "s_endpgm" => return Ok(()),
+        /* Alignment padding and constant data following s_endpgm */
+        "s_nop" | "s_code_end" | ".long" => return Ok(()),
+        /* Denormal and rounding modes, switched around divisions */
+        "s_setreg_b32" | "s_setreg_imm32_b32" | "s_denorm_mode" | "s_round_mode" => return Ok(()),
+        _ => ()
+    }
+
+    let semantics = semantics::lookup(instr)
+        .ok_or_else(|| unsupported(format!("Operation not supported: {}", instr)))?;
+
+    /* Branches are resolved by the control flow graph */
+    if let Some(Branch::Direct(_)) | Some(Branch::Indirect) = semantics.branch {
+        return Ok(());
+    }
+    if instr == "s_barrier" {
+        pgm.push((instr_idx, Statement::Barrier));
+        return Ok(());
+    }
+
+    match semantics.memory {
+        /* GFX8 has no global segment instructions and addresses global memory through flat */
+        Some(MemoryAccess::Store(AddressSpace::Global)) | Some(MemoryAccess::Store(AddressSpace::Flat))
+            if semantics.src_type == Some(DataType::Bits(32)) => match ops {
+            [VRegs(dst_lo, dst_hi), VReg(src), ..] if st.vgprs[*dst_lo].0 == st.vgprs[*dst_hi].0 && st.vgprs[*dst_lo].1 == 0 && st.vgprs[*dst_hi].1 == 1 => {
+                let Reg(value_dst, _) = st.vgprs[*dst_lo];
+                let Reg(value_src, _) = st.vgprs[*src];
+                /* Results of float operations are stored as floats */
+                let kind = match st.values[value_src] {
+                    Value::Computed { kind: DataKind::F32, .. } | Value::Deref { kind: DataKind::F32, .. }
+                    | Value::LocalLoad { kind: DataKind::F32, .. } | Value::F32(_) => DataKind::F32,
+                    _ => DataKind::Dword
+                };
+                pgm.push((instr_idx + 1, Statement::Store { addr: value_dst, data: value_src, kind }))
             },
             _ => ()
         },
-        instr if instr.starts_with("s_load") => eval_s_load(st, instr, ops),
-        instr if instr.starts_with("global_load") => eval_global_load(st, instr, ops),
-        instr if instr.starts_with("s_") => eval_salu_op(st, instr, ops),
-        instr if instr.starts_with("v_") => eval_valu_op(st, instr, ops),
-        unsupported => panic!("Operation not supported: {:?}", unsupported)
+        Some(MemoryAccess::Load(AddressSpace::Constant)) => eval_s_load(st, &semantics, instr, ops)?,
+        Some(MemoryAccess::Load(AddressSpace::Global)) | Some(MemoryAccess::Load(AddressSpace::Flat)) =>
+            eval_global_load(st, &semantics, instr, ops)?,
+        Some(MemoryAccess::Load(AddressSpace::Lds)) | Some(MemoryAccess::Store(AddressSpace::Lds)) | Some(MemoryAccess::Atomic(AddressSpace::Lds)) =>
+            lds::eval_ds_op(st, pgm, instr_idx, &semantics, instr, ops)?,
+        None if semantics.format.is_salu() && is_lane_mask_op(st, instr, ops) => eval_lane_mask_op(st, instr, ops),
+        None if semantics.format.is_salu() => return eval_salu_op(st, &semantics, instr, ops),
+        None if semantics.format.is_valu() => return eval_valu_op(st, &semantics, instr, ops),
+        _ => return Err(unsupported(format!("Operation not supported: {} {:?}", instr, ops)))
     }
+    Ok(())
 }
 
-fn eval_global_load(st: &mut ExecState, instr: &str, ops: &[Operand]) {
-    let kind = match &instr[12..] {
-        "ushort" => DataKind::U16,
-        "dword" => DataKind::Dword,
-        _ => panic!("Unknown data type modifier {}", &instr[12..])
+fn eval_global_load(st: &mut ExecState, semantics: &Semantics, instr: &str, ops: &[Operand]) -> io::Result<()> {
+    let kind = match semantics.dst_type {
+        Some(DataType::Uint(16)) => DataKind::U16,
+        Some(DataType::Bits(32)) => DataKind::Dword,
+        _ => return Err(unsupported(format!("Operation not supported: {} {:?}", instr, ops)))
     };
-    let binding = match ops {
-        [_, src, _, Offset(ref offset)] =>
-            Binding::Deref { ptr: load_ptr_binding(st, src), offset: *offset, kind },
-        [_, src, _] =>
-            Binding::Deref { ptr: load_ptr_binding(st, src), offset: 0, kind },
-        _ =>
-            panic!("Cannot resolve load, unrecognized operands {:?}", ops)
+    /* The offset may be followed by cache control flags (glc, slc) */
+    let value = match ops {
+        [VReg(_), src, ..] => {
+            let offset = ops[2..].iter().filter_map(|op| match op { Offset(offset) => Some(*offset), _ => None }).next();
+            Value::Deref { ptr: load_ptr_value(st, src), offset: offset.unwrap_or(0), kind }
+        },
+        _ => return Err(unsupported(format!("Cannot resolve load, unrecognized operands {:?}", ops)))
     };
-    st.bindings.push(binding);
-    match ops[0] {
-        VReg(ref dst) => insert_into!(st.vgprs, *dst, Reg(st.bindings.len() - 1, 0)),
-        _ => panic!("Cannot resolve load, unrecognized destination {:?}", ops[0])
+    st.values.push(value);
+    if let VReg(dst) = ops[0] {
+        insert_into!(st.vgprs, dst, Reg(st.values.len() - 1, 0));
     }
+    Ok(())
 }
 
-fn eval_s_load(st: &mut ExecState, instr: &str, ops: &[Operand]) {
-    let (ptr, offset) = match ops {
-        [_, source, Lit(ref offset)] => (load_ptr_binding(st, source), offset),
-        _ => panic!("Received invalid operands in eval_s_load")
+/* Scalar loads of one, two or four dwords into consecutive SGPRs */
+fn eval_s_load(st: &mut ExecState, semantics: &Semantics, instr: &str, ops: &[Operand]) -> io::Result<()> {
+    let not_supported = || unsupported(format!("Operation not supported: {} {:?}", instr, ops));
+    let (dst, ptr, offset) = match ops {
+        [SReg(dst), source, Lit(offset), ..] | [SRegs(dst, _), source, Lit(offset), ..] => (*dst, load_ptr_value(st, source), *offset),
+        _ => return Err(not_supported())
+    };
+    let (kind, dwords) = match semantics.dst_type {
+        Some(DataType::Bits(32)) => (DataKind::Dword, 1),
+        Some(DataType::Bits(64)) => (DataKind::Qword, 2),
+        Some(DataType::Bits(128)) => (DataKind::DQword, 4),
+        _ => return Err(not_supported())
+    };
+    st.values.push(Value::Deref { ptr, offset, kind });
+    for i in 0..dwords {
+        insert_into!(st.sgprs, dst + i as usize, Reg(st.values.len() - 1, i));
+    }
+    Ok(())
+}
+
+/* SALU operations on 32 and 64-bit integers. Signedness and width come from the
+ * operand type in the mnemonic; SCC is set the way the ISA defines it for the
+ * operation, or cleared when it holds an overflow that is not tracked */
+fn eval_salu_op(st: &mut ExecState, semantics: &Semantics, instr: &str, ops: &[Operand]) -> io::Result<()> {
+    let (signed, bits) = match semantics.src_type {
+        Some(DataType::Int(bits)) => (true, bits),
+        Some(DataType::Uint(bits)) | Some(DataType::Bits(bits)) => (false, bits),
+        _ => (false, 32)
+    };
+    let typehint = if signed { "i32" } else { "u32" };
+    let kind = if bits == 64 { DataKind::Qword } else { DataKind::Dword };
+    let source = |st: &mut ExecState, op: &Operand| match bits {
+        64 => operand_value_qw(st, op, typehint),
+        _ => operand_value_dw(st, op, typehint)
+    };
+    let computed = |st: &mut ExecState, expr: Expr| {
+        st.values.push(Value::Computed { expr, kind });
+        st.values.len() - 1
+    };
+    /* Bit counts and positions, and the operands of 64-bit s_bfe and s_bfm that define fields */
+    let dword = |st: &mut ExecState, expr: Expr| {
+        st.values.push(Value::Computed { expr, kind: DataKind::Dword });
+        st.values.len() - 1
     };
+    let scc = |st: &ExecState| st.scc.ok_or_else(|| unsupported(format!("{} reads an unknown SCC", instr)));
+    /* SOPK immediates are 16 bits wide, sign-extended for signed operations */
+    let imm16 = |imm: i32| if signed { imm as i16 as i32 } else { imm & 0xffff };
 
-    match (instr, ops) {
-        ("s_load_dword", [SReg(ref dst), _, _]) => {
-            st.bindings.push(Binding::Deref { ptr, offset: *offset, kind: DataKind::Dword });
-            insert_into!(st.sgprs, *dst, Reg(st.bindings.len() - 1, 0));
+    if instr == "s_sext_i32_i8" || instr == "s_sext_i32_i16" {
+        if let [dst, src] = ops {
+            let shift = constant(st, 32 - bits as u32);
+            let src = operand_value_dw(st, src, "i32");
+            st.values.push(Value::Computed { expr: Expr::Shl(src, shift), kind: DataKind::Dword });
+            let shl = st.values.len() - 1;
+            st.values.push(Value::Computed { expr: Expr::Ashr(shl, shift), kind: DataKind::Dword });
+            set_sdst(st, dst, st.values.len() - 1);
+            return Ok(());
+        }
+    }
+
+    let op = &instr[..instr.rfind('_').unwrap_or(instr.len())];
+    match (op, ops) {
+        ("s_mov", [dst, src]) if bits == 64 => {
+            let regs = match *src {
+                SRegs(lo, _) => [st.sgprs[lo], st.sgprs[lo + 1]],
+                /* Inline constants are sign-extended to 64 bits */
+                Lit(imm) => {
+                    let lo = constant(st, imm as u32);
+                    let hi = constant(st, if imm < 0 { std::u32::MAX } else { 0 });
+                    [Reg(lo, 0), Reg(hi, 0)]
+                },
+                _ => return Err(unsupported(format!("Operation not supported: {} {:?}", instr, ops)))
+            };
+            set_sdst_regs(st, dst, &regs);
         },
-        ("s_load_dwordx2", [SRegs(ref dst_lo, _), _, _]) => {
-            st.bindings.push(Binding::Deref { ptr, offset: *offset, kind: DataKind::Qword });
-            for i in 0..2 { insert_into!(st.sgprs, *dst_lo + i as usize, Reg(st.bindings.len() - 1, i)); }
+        ("s_mov", [dst, src]) => {
+            let reg = operand_reg(st, src, typehint);
+            set_sdst_regs(st, dst, &[reg]);
         },
-        ("s_load_dwordx4", [SRegs(ref dst_lo, _), _, _]) => {
-            st.bindings.push(Binding::Deref { ptr, offset: *offset, kind: DataKind::DQword });
-            for i in 0..4 { insert_into!(st.sgprs, *dst_lo + i as usize, Reg(st.bindings.len() - 1, i)); }
+        ("s_movk", [dst, Lit(imm)]) => {
+            st.values.push(Value::I32(imm16(*imm)));
+            set_sdst(st, dst, st.values.len() - 1);
         },
-        unsupported => panic!("Operation not supported: {:?}", unsupported)
-    }
-}
-
-fn eval_salu_op(st: &mut ExecState, instr: &str, ops: &[Operand]) {
-    match (instr, ops) {
-        ("s_mul_i32", [SReg(ref dst), SReg(ref op1), SReg(ref op2)]) => {
-            let expr = match (st.sgprs[*op1], st.sgprs[*op2]) {
-                (Reg(op1_idx, 0), Reg(op2_idx, 0)) => Expr::Mul(op1_idx, op2_idx),
-                _ => panic!("Operation not supported: {:?} {:?}", instr, ops)
+        ("s_cmov", [dst, src]) | ("s_cmovk", [dst, src]) | ("s_cselect", [dst, src, _]) => {
+            let cond = scc(st)?;
+            let if_true = match (op, src) {
+                ("s_cmovk", Lit(imm)) => {
+                    st.values.push(Value::I32(imm16(*imm)));
+                    st.values.len() - 1
+                },
+                _ => source(st, src)
+            };
+            /* s_cmov keeps the destination when SCC is clear */
+            let if_false = match ops {
+                [_, _, if_false] => source(st, if_false),
+                _ => source(st, dst)
+            };
+            let result = computed(st, Expr::Select { cond, if_true, if_false });
+            set_sdst(st, dst, result);
+        },
+        ("s_add", [dst, lhs, rhs]) | ("s_sub", [dst, lhs, rhs]) | ("s_mul", [dst, lhs, rhs]) => {
+            let (lhs, rhs) = (source(st, lhs), source(st, rhs));
+            let result = computed(st, match op {
+                "s_add" => Expr::Add(lhs, rhs),
+                "s_sub" => Expr::Sub(lhs, rhs),
+                _ => Expr::Mul(lhs, rhs)
+            });
+            set_sdst(st, dst, result);
+            match (op, signed) {
+                /* Carry out and borrow */
+                ("s_add", false) => st.scc = Some(Condition::LtU(result, lhs)),
+                ("s_sub", false) => st.scc = Some(Condition::LtU(lhs, rhs)),
+                /* Signed overflow */
+                ("s_add", true) | ("s_sub", true) => st.scc = None,
+                _ => ()
+            }
+        },
+        ("s_addk", [dst, Lit(imm)]) | ("s_mulk", [dst, Lit(imm)]) => {
+            let lhs = source(st, dst);
+            st.values.push(Value::I32(imm16(*imm)));
+            let rhs = st.values.len() - 1;
+            let result = computed(st, if op == "s_addk" { Expr::Add(lhs, rhs) } else { Expr::Mul(lhs, rhs) });
+            set_sdst(st, dst, result);
+            if op == "s_addk" {
+                st.scc = None;
+            }
+        },
+        ("s_addc", [SReg(ref dst), op1, op2]) if low_dword_add(st, *dst).is_some() => {
+            /* The high half of a 64-bit addition, whose low half was added by s_add_u32 */
+            let (lo_op1, lo_op2) = low_dword_add(st, *dst).unwrap();
+            let (op1_reg, op2_reg) = (operand_reg(st, op1, "u32"), operand_reg(st, op2, "u32"));
+            let (expr, carry_from) = match addc_qword_matching_operands(&mut st.values, op1_reg, op2_reg, lo_op1, lo_op2) {
+                Some((op1_adc, op2_adc)) => (Expr::Add(op1_adc, op2_adc), op1_adc),
+                None => {
+                    let (hi_op1, hi_op2) = (operand_value_dw(st, op1, "u32"), operand_value_dw(st, op2, "u32"));
+                    /* The first qword operand, for the carry out of the high half */
+                    let shift = constant(st, 32);
+                    st.values.push(Value::Computed { expr: Expr::Shl(hi_op1, shift), kind: DataKind::Qword });
+                    st.values.push(Value::Computed { expr: Expr::Or(st.values.len() - 1, lo_op1), kind: DataKind::Qword });
+                    (Expr::AddHiLo { lo_op1, lo_op2, hi_op1, hi_op2 }, st.values.len() - 1)
+                }
+            };
+            st.values.push(Value::Computed { expr, kind: DataKind::Qword });
+            let result = st.values.len() - 1;
+            for i in 0..2 { insert_into!(st.sgprs, *dst - 1 + i as usize, Reg(result, i)); }
+            st.scc = Some(Condition::LtU(result, carry_from));
+        },
+        ("s_addc", [dst, lhs, rhs]) | ("s_subb", [dst, lhs, rhs]) => {
+            let cond = scc(st)?;
+            let (lhs, rhs) = (source(st, lhs), source(st, rhs));
+            let (one, zero) = (constant(st, 1), constant(st, 0));
+            let carry_in = computed(st, Expr::Select { cond, if_true: one, if_false: zero });
+            let result = match op {
+                "s_addc" => {
+                    let sum = computed(st, Expr::Add(lhs, rhs));
+                    computed(st, Expr::Add(sum, carry_in))
+                },
+                _ => {
+                    let difference = computed(st, Expr::Sub(lhs, rhs));
+                    computed(st, Expr::Sub(difference, carry_in))
+                }
+            };
+            set_sdst(st, dst, result);
+            /* With a carry in, the sum wraps around to lhs or below and the difference borrows
+             * when rhs is not below lhs; without it, the way s_add_u32 and s_sub_u32 set SCC */
+            let (with_carry, without_carry) = match op {
+                "s_addc" => (Condition::LeU(result, lhs), Condition::LtU(result, lhs)),
+                _ => (Condition::LeU(lhs, rhs), Condition::LtU(lhs, rhs))
             };
-            st.bindings.push(Binding::Computed { expr, kind: DataKind::Dword });
-            insert_into!(st.sgprs, *dst, Reg(st.bindings.len() - 1, 0));
-        },
-        ("s_add_i32", [SReg(ref dst), op1_raw, op2_raw]) => {
-            let op1 = operand_binding_dw(st, op1_raw, "i32");
-            let op2 = operand_binding_dw(st, op2_raw, "i32");
-            st.bindings.push(Binding::Computed { expr: Expr::Add(op1, op2), kind: DataKind::Dword });
-            insert_into!(st.sgprs, *dst, Reg(st.bindings.len() - 1, 0));
-        },
-        ("s_and_b32", [SReg(ref dst), op_raw, mask_raw]) => {
-            let op = operand_binding_dw(st, op_raw, "u32");
-            let mask = operand_binding_dw(st, mask_raw, "u32");
-            let kind = match st.bindings[mask] {
-                Binding::U32(65535) => DataKind::U16, /* 0xffff is most likely a 32 -> 16 downcast */
+            let with_carry = computed(st, Expr::Select { cond: with_carry, if_true: one, if_false: zero });
+            let without_carry = computed(st, Expr::Select { cond: without_carry, if_true: one, if_false: zero });
+            let carry_out = computed(st, Expr::Select { cond, if_true: with_carry, if_false: without_carry });
+            st.scc = Some(Condition::Ne(carry_out, zero));
+        },
+        ("s_and", [dst, lhs, mask_raw]) if bits == 32 => {
+            let lhs = source(st, lhs);
+            let mask = source(st, mask_raw);
+            let kind = match st.values[mask] {
+                Value::U32(65535) => DataKind::U16, /* 0xffff is most likely a 32 -> 16 downcast */
                 _ => DataKind::Dword
             };
-            st.bindings.push(Binding::Computed { expr: Expr::And(op, mask), kind });
-            insert_into!(st.sgprs, *dst, Reg(st.bindings.len() - 1, 0));
+            st.values.push(Value::Computed { expr: Expr::And(lhs, mask), kind });
+            let result = st.values.len() - 1;
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
+        },
+        ("s_and", [dst, lhs, rhs]) | ("s_or", [dst, lhs, rhs]) | ("s_xor", [dst, lhs, rhs])
+        | ("s_andn2", [dst, lhs, rhs]) | ("s_orn2", [dst, lhs, rhs])
+        | ("s_nand", [dst, lhs, rhs]) | ("s_nor", [dst, lhs, rhs]) | ("s_xnor", [dst, lhs, rhs]) => {
+            let lhs = source(st, lhs);
+            let mut rhs = source(st, rhs);
+            if op == "s_andn2" || op == "s_orn2" {
+                rhs = computed(st, Expr::Not(rhs));
+            }
+            let mut result = computed(st, match op {
+                "s_and" | "s_andn2" | "s_nand" => Expr::And(lhs, rhs),
+                "s_or" | "s_orn2" | "s_nor" => Expr::Or(lhs, rhs),
+                _ => Expr::Xor(lhs, rhs)
+            });
+            if op == "s_nand" || op == "s_nor" || op == "s_xnor" {
+                result = computed(st, Expr::Not(result));
+            }
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
+        },
+        ("s_not", [dst, src]) => {
+            let src = source(st, src);
+            let result = computed(st, Expr::Not(src));
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
+        },
+        ("s_lshl", [dst, src, shift]) | ("s_lshr", [dst, src, shift]) | ("s_ashr", [dst, src, shift]) => {
+            let src = source(st, src);
+            let shift = operand_value_dw(st, shift, "u32");
+            let result = computed(st, match op {
+                "s_lshl" => Expr::Shl(src, shift),
+                "s_lshr" => Expr::Shr(src, shift),
+                _ => Expr::Ashr(src, shift)
+            });
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
         },
-        ("s_cmp_lt_i32", [op1, op2]) =>
-            match (operand_reg(st, op1, "i32"), operand_reg(st, op2, "i32")) {
-                (Reg(op1_idx, 0), Reg(op2_idx, 0)) => {
-                    st.scc = Some(Condition::Lt(op1_idx, op2_idx))
+        ("s_bfe", [dst, src, field]) => {
+            /* The field is given by offset in bits [5:0] and width in bits [22:16] */
+            let src = source(st, src);
+            let (offset, width) = match *field {
+                Lit(field) => {
+                    let (offset, width) = ((field as u32) & (bits as u32 - 1), ((field as u32) >> 16) & 0x7f);
+                    (constant(st, offset), constant(st, width))
                 },
-                other => panic!("Unrecognized operands: {:?}", other)
-            },
-        ("s_cmp_eq_u32", [op1, op2]) =>
-            match (operand_reg(st, op1, "u32"), operand_reg(st, op2, "u32")) {
-                (Reg(op1_idx, 0), Reg(op2_idx, 0)) => {
-                    st.scc = Some(Condition::Eql(op1_idx, op2_idx))
+                ref field => {
+                    let field = operand_value_dw(st, field, "u32");
+                    let (offset_mask, width_shift, width_mask) = (constant(st, bits as u32 - 1), constant(st, 16), constant(st, 0x7f));
+                    let offset = dword(st, Expr::And(field, offset_mask));
+                    let width_bits = dword(st, Expr::Shr(field, width_shift));
+                    (offset, dword(st, Expr::And(width_bits, width_mask)))
+                }
+            };
+            let result = bitfield_extract(st, src, offset, width, signed, kind);
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
+        },
+        ("s_lshl1_add", [dst, src, addend]) | ("s_lshl2_add", [dst, src, addend])
+        | ("s_lshl3_add", [dst, src, addend]) | ("s_lshl4_add", [dst, src, addend]) => {
+            let shift = (op.as_bytes()[6] - b'0') as u32;
+            let (src, addend) = (source(st, src), source(st, addend));
+            let (shift, shifted_out_shift) = (constant(st, shift), constant(st, 32 - shift));
+            let shifted = computed(st, Expr::Shl(src, shift));
+            let result = computed(st, Expr::Add(shifted, addend));
+            set_sdst(st, dst, result);
+            /* SCC is set when bits are shifted out or the addition carries */
+            let shifted_out = computed(st, Expr::Shr(src, shifted_out_shift));
+            let (one, zero) = (constant(st, 1), constant(st, 0));
+            let overflow = computed(st, Expr::Select { cond: Condition::LtU(result, shifted), if_true: one, if_false: shifted_out });
+            st.scc = Some(Condition::Ne(overflow, zero));
+        },
+        ("s_mul_hi", [dst, lhs, rhs]) => {
+            let (lhs, rhs) = (source(st, lhs), source(st, rhs));
+            let result = computed(st, if signed { Expr::MulHi(lhs, rhs) } else { Expr::MulHiU(lhs, rhs) });
+            set_sdst(st, dst, result);
+        },
+        /* A mask of `width` bits starting at `offset` */
+        ("s_bfm", [dst, width, offset]) => {
+            let field_mask = constant(st, bits as u32 - 1);
+            let (width, offset) = (operand_value_dw(st, width, "u32"), operand_value_dw(st, offset, "u32"));
+            let (width, offset) = (dword(st, Expr::And(width, field_mask)), dword(st, Expr::And(offset, field_mask)));
+            let one = source(st, &Lit(1));
+            let bit = computed(st, Expr::Shl(one, width));
+            let field = computed(st, Expr::Sub(bit, one));
+            let result = computed(st, Expr::Shl(field, offset));
+            set_sdst(st, dst, result);
+        },
+        ("s_brev", [dst, src]) => {
+            let src = source(st, src);
+            let result = computed(st, Expr::Bits(BitFn::Reverse, src));
+            set_sdst(st, dst, result);
+        },
+        /* Counts of zero and one bits */
+        ("s_bcnt0_i32", [dst, src]) | ("s_bcnt1_i32", [dst, src]) => {
+            let mut src = source(st, src);
+            if op == "s_bcnt0_i32" {
+                src = computed(st, Expr::Not(src));
+            }
+            let result = dword(st, Expr::Bits(BitFn::Count, src));
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
+        },
+        /* Position of the lowest one bit, -1 when there is none */
+        ("s_ff1_i32", [dst, src]) => {
+            let src = source(st, src);
+            let (zero, none) = (constant(st, 0), constant(st, std::u32::MAX));
+            let position = dword(st, Expr::Bits(BitFn::TrailingZeros, src));
+            let result = dword(st, Expr::Select { cond: Condition::Eql(src, zero), if_true: none, if_false: position });
+            set_sdst(st, dst, result);
+        },
+        ("s_min", [dst, lhs, rhs]) | ("s_max", [dst, lhs, rhs]) => {
+            let (lhs, rhs) = (source(st, lhs), source(st, rhs));
+            let result = computed(st, match (op, signed) {
+                ("s_min", true) => Expr::Min(lhs, rhs),
+                ("s_min", false) => Expr::MinU(lhs, rhs),
+                (_, true) => Expr::Max(lhs, rhs),
+                (_, false) => Expr::MaxU(lhs, rhs)
+            });
+            set_sdst(st, dst, result);
+            /* Whether the first operand was selected */
+            st.scc = compare(if op == "s_min" { "lt" } else { "gt" }, lhs, rhs, signed);
+        },
+        ("s_abs", [dst, src]) => {
+            let src = source(st, src);
+            let result = computed(st, Expr::Abs(src));
+            set_sdst(st, dst, result);
+            set_scc_nonzero(st, result);
+        },
+        (_, [lhs, rhs]) if semantics.compare => {
+            let lhs = source(st, lhs);
+            let rhs = match *rhs {
+                Lit(imm) if op.starts_with("s_cmpk_") => {
+                    st.values.push(if signed { Value::I32(imm16(imm)) } else { Value::U32(imm16(imm) as u32) });
+                    st.values.len() - 1
                 },
-                other => panic!("Unrecognized operands: {:?}", other)
-            },
-        unsupported => panic!("Operation not supported: {:?}", unsupported)
+                ref rhs => source(st, rhs)
+            };
+            let relation = &op[op.rfind('_').unwrap() + 1..];
+            /* s_bitcmp and s_setvskip are not compared by relation */
+            let cond = compare(relation, lhs, rhs, signed)
+                .ok_or_else(|| unsupported(format!("Operation not supported: {} {:?}", instr, ops)))?;
+            st.scc = Some(cond);
+        },
+        _ => return Err(unsupported(format!("Operation not supported: {} {:?}", instr, ops)))
+    }
+    Ok(())
+}
+
+/* Integer comparison by its mnemonic infix (s_cmp_lg_u32, v_cmp_ge_i32) */
+fn compare(relation: &str, lhs: ValueIdx, rhs: ValueIdx, signed: bool) -> Option<Condition> {
+    let cond = match (relation, signed) {
+        ("eq", _) => Condition::Eql(lhs, rhs),
+        ("lg", _) | ("ne", _) => Condition::Ne(lhs, rhs),
+        ("lt", true) => Condition::Lt(lhs, rhs),
+        ("lt", false) => Condition::LtU(lhs, rhs),
+        ("le", true) => Condition::Le(lhs, rhs),
+        ("le", false) => Condition::LeU(lhs, rhs),
+        ("gt", true) => Condition::Lt(rhs, lhs),
+        ("gt", false) => Condition::LtU(rhs, lhs),
+        ("ge", true) => Condition::Le(rhs, lhs),
+        ("ge", false) => Condition::LeU(rhs, lhs),
+        _ => return None
+    };
+    Some(cond)
+}
+
+fn constant(st: &mut ExecState, value: u32) -> ValueIdx {
+    st.values.push(Value::U32(value));
+    st.values.len() - 1
+}
+
+fn constant_value(st: &ExecState, idx: ValueIdx) -> Option<u32> {
+    match st.values[idx] {
+        Value::U32(value) => Some(value),
+        Value::I32(value) => Some(value as u32),
+        _ => None
     }
 }
 
-fn eval_valu_op(st: &mut ExecState, instr: &str, ops: &[Operand]) {
-    match (instr, ops) {
-        ("v_mov_b32_e32", [VReg(ref dst), src]) => {
+fn is_zero(st: &ExecState, Reg(idx, _): Reg) -> bool {
+    constant_value(st, idx) == Some(0)
+}
+
+/* SCC is set when the result is non-zero */
+fn set_scc_nonzero(st: &mut ExecState, value: ValueIdx) {
+    let zero = constant(st, 0);
+    st.scc = Some(Condition::Ne(value, zero));
+}
+
+fn set_sdst(st: &mut ExecState, dst: &Operand, value: ValueIdx) {
+    let regs: Vec<Reg> = match *dst {
+        SRegs(lo, hi) => (0..=(hi - lo)).map(|i| Reg(value, i as u8)).collect(),
+        _ => vec![Reg(value, 0)]
+    };
+    set_sdst_regs(st, dst, &regs);
+}
+
+fn set_vdst(st: &mut ExecState, dst: &Operand, value: ValueIdx) {
+    match *dst {
+        VReg(idx) => insert_into!(st.vgprs, idx, Reg(value, 0)),
+        VRegs(lo, hi) => for i in 0..=(hi - lo) { insert_into!(st.vgprs, lo + i, Reg(value, i as u8)); },
+        _ => panic!("Unsupported VALU destination {:?}", dst)
+    }
+}
+
+fn set_sdst_regs(st: &mut ExecState, dst: &Operand, regs: &[Reg]) {
+    match *dst {
+        SReg(idx) | SRegs(idx, _) => for (i, &reg) in regs.iter().enumerate() { insert_into!(st.sgprs, idx + i, reg); },
+        /* M0 only holds LDS limits and message data, which are not tracked */
+        Special(SpecialReg::M0) => (),
+        _ => panic!("Unsupported SALU destination {:?}", dst)
+    }
+}
+
+/* An SALU operation on 32 (wave32) or 64-bit lane masks involving EXEC, VCC or a saved mask.
+ * Literals are lane masks only when combined with one of these */
+fn is_lane_mask_op(st: &ExecState, instr: &str, ops: &[Operand]) -> bool {
+    let width_suffix = match st.lane_mask {
+        DataKind::Dword => "_b32",
+        _ => "_b64"
+    };
+    instr.ends_with(width_suffix) && (instr.contains("_saveexec_")
+        || ops.iter().any(|op| !matches!(op, Lit(_)) && lane_mask_operand(st, op).is_some() || *op == Special(SpecialReg::Exec)))
+}
+
+fn lane_mask_operand(st: &ExecState, op: &Operand) -> Option<LaneMask> {
+    let Reg(value, _) = match *op {
+        Special(SpecialReg::Exec) => return Some(st.exec.clone()),
+        Lit(-1) => return Some(LaneMask::all()),
+        /* vcc_lo is the whole of VCC in wave32 */
+        VCC | Special(SpecialReg::VCCLo) => return st.vcc.clone(),
+        SReg(idx) | SRegs(idx, _) => *st.sgprs.get(idx)?,
+        _ => return None
+    };
+    match st.values.get(value) {
+        Some(Value::LaneMask { idx }) => Some(st.lane_masks[*idx].clone()),
+        _ => None
+    }
+}
+
+fn set_lane_mask_operand(st: &mut ExecState, op: &Operand, mask: LaneMask) {
+    match *op {
+        Special(SpecialReg::Exec) => st.exec = mask,
+        VCC | Special(SpecialReg::VCCLo) => st.vcc = Some(mask),
+        SReg(_) | SRegs(_, _) => {
+            st.lane_masks.push(mask);
+            st.values.push(Value::LaneMask { idx: st.lane_masks.len() - 1 });
+            let (lo, hi) = match *op { SRegs(lo, hi) => (lo, hi), SReg(idx) => (idx, idx), _ => unreachable!() };
+            for i in 0..=(hi - lo) { insert_into!(st.sgprs, lo + i, Reg(st.values.len() - 1, i as u8)); }
+        },
+        _ => panic!("Unsupported lane mask destination {:?}", op)
+    }
+}
+
+fn eval_lane_mask_op(st: &mut ExecState, instr: &str, ops: &[Operand]) {
+    let operand = |st: &mut ExecState, op: &Operand| match *op {
+        Lit(0) => {
+            let zero = constant(st, 0);
+            LaneMask::none(zero)
+        },
+        _ => lane_mask_operand(st, op).unwrap_or_else(|| panic!("{} operand {:?} is not a known lane mask", instr, op))
+    };
+    let unsupported = || panic!("Unsupported lane mask operation: {} {:?}", instr, ops);
+
+    /* A register cleared before a divergent loop collects the lanes that leave it */
+    let is_empty = |st: &ExecState, op: &Operand| match *op {
+        Lit(0) => true,
+        SReg(first) | SRegs(first, _) => {
+            let last = if let SRegs(_, last) = *op { last } else { first };
+            (first..=last).all(|idx| matches!(st.sgprs.get(idx).and_then(|reg| st.values.get(reg.0)), Some(Value::U32(0)) | Some(Value::I32(0))))
+        },
+        _ => false
+    };
+
+    /* The width suffix has been checked by is_lane_mask_op */
+    let op = &instr[..instr.len() - 4];
+    match (op, ops) {
+        ("s_or", [dst, lhs, rhs]) | ("s_andn2", [dst, lhs, rhs]) if is_empty(st, rhs) => {
+            let mask = operand(st, lhs);
+            set_lane_mask_operand(st, dst, mask);
+        },
+        ("s_or", [dst, lhs, rhs]) if is_empty(st, lhs) => {
+            let mask = operand(st, rhs);
+            set_lane_mask_operand(st, dst, mask);
+        },
+        ("s_mov", [dst, src]) => {
+            let mask = operand(st, src);
+            set_lane_mask_operand(st, dst, mask);
+        },
+        ("s_and_saveexec", [dst, src]) | ("s_or_saveexec", [dst, src]) | ("s_andn2_saveexec", [dst, src]) => {
+            let (exec, src) = (st.exec.clone(), operand(st, src));
+            st.exec = match op {
+                "s_and_saveexec" => Some(exec.and(&src)),
+                "s_or_saveexec" => src.or(&exec),
+                _ => src.and_not(&exec)
+            }.unwrap_or_else(unsupported);
+            set_lane_mask_operand(st, dst, exec);
+        },
+        ("s_and", [dst, lhs, rhs]) | ("s_or", [dst, lhs, rhs]) | ("s_xor", [dst, lhs, rhs]) | ("s_andn2", [dst, lhs, rhs]) => {
+            let (lhs, rhs) = (operand(st, lhs), operand(st, rhs));
+            let mask = match op {
+                "s_and" => Some(lhs.and(&rhs)),
+                "s_or" => lhs.or(&rhs),
+                "s_xor" => lhs.xor(&rhs),
+                _ => lhs.and_not(&rhs)
+            }.unwrap_or_else(unsupported);
+            set_lane_mask_operand(st, dst, mask);
+        },
+        _ => panic!("Unsupported lane mask operation: {} {:?}", instr, ops)
+    }
+    /* SCC is set when the result is non-zero, which is not tracked */
+    st.scc = None;
+}
+
+/* VOPC and their VOP3 encodings, writing VCC or an SGPR pair. Inactive lanes are cleared,
+ * so the result is limited to EXEC. v_cmpx also writes EXEC, and only EXEC on GFX10 */
+fn eval_vector_compare(st: &mut ExecState, semantics: &Semantics, instr: &str, op: &str, ops: &[Operand]) {
+    let operands: Vec<&Operand> = ops.iter().filter(|op| **op != Clamp).collect();
+    let (dst, lhs, rhs) = match operands.as_slice() {
+        [dst, lhs, rhs] => (Some(*dst), *lhs, *rhs),
+        [lhs, rhs] => (None, *lhs, *rhs),
+        _ => panic!("Operation not supported: {:?}", (instr, ops))
+    };
+    /* v_cmp_lt, v_cmpx_nge, v_cmp_class */
+    let relation = op.splitn(3, '_').nth(2).unwrap_or_else(|| panic!("Operation not supported: {:?}", (instr, ops)));
+
+    let (cond, holds) = match (relation, semantics.src_type) {
+        ("f", _) | ("t", _) | ("tru", _) => {
+            let zero = constant(st, 0);
+            (Condition::Eql(zero, zero), relation != "f")
+        },
+        ("class", Some(DataType::Float(bits))) => {
+            let (val, mask) = (float::float_source(st, lhs, bits), operand_value_dw(st, rhs, "u32"));
+            (Condition::Class(val, mask), true)
+        },
+        (_, Some(DataType::Float(bits))) => {
+            let (lhs, rhs) = (float::float_source(st, lhs, bits), float::float_source(st, rhs, bits));
+            /* Negated relations (nlt, nge) and u hold for unordered operands */
+            let (relation, holds) = match relation.len() {
+                3 => (&relation[1..], false),
+                _ => (relation, true)
+            };
+            let cond = match relation {
+                "lt" => Condition::FLt(lhs, rhs),
+                "le" => Condition::FLe(lhs, rhs),
+                "eq" => Condition::FEq(lhs, rhs),
+                "gt" => Condition::FLt(rhs, lhs),
+                "ge" => Condition::FLe(rhs, lhs),
+                "lg" => Condition::FLg(lhs, rhs),
+                "o" | "u" => Condition::FOrd(lhs, rhs),
+                _ => panic!("Unknown comparison {:?}", relation)
+            };
+            (cond, holds == (relation != "u"))
+        },
+        (_, src_type) => {
+            let (signed, bits) = match src_type {
+                Some(DataType::Int(bits)) => (true, bits),
+                Some(DataType::Uint(bits)) | Some(DataType::Bits(bits)) => (false, bits),
+                _ => panic!("Operation not supported: {:?}", (instr, ops))
+            };
+            let typehint = if signed { "i32" } else { "u32" };
+            let (lhs, rhs) = match bits {
+                64 => (operand_value_qw(st, lhs, typehint), operand_value_qw(st, rhs, typehint)),
+                _ => (operand_value_dw(st, lhs, typehint), operand_value_dw(st, rhs, typehint))
+            };
+            let cond = compare(relation, lhs, rhs, signed).unwrap_or_else(|| panic!("Unknown comparison {:?}", relation));
+            (cond, true)
+        }
+    };
+
+    let mask = st.exec.and(&LaneMask::with_condition(cond, holds));
+    if semantics.implicit_defs.contains(&ImplicitReg::Exec) {
+        st.exec = mask.clone();
+    }
+    if let Some(dst) = dst {
+        set_lane_mask_operand(st, dst, mask);
+    }
+}
+
+/* VALU operations, floating-point ones are evaluated by float::eval_float_op and compares by
+ * eval_vector_compare. VOP2 and VOP3 (_e64) encodings are evaluated alike: carry-outs are not
+ * tracked, except for the v_add_co/v_addc_co and v_sub_co/v_subb_co pairs that make up 64-bit
+ * additions and subtractions */
+fn eval_valu_op(st: &mut ExecState, semantics: &Semantics, instr: &str, ops: &[Operand]) -> io::Result<()> {
+    let name = ["_e32", "_e64"].iter()
+        .find(|suffix| instr.ends_with(*suffix))
+        .map(|suffix| &instr[..instr.len() - suffix.len()])
+        .unwrap_or(instr);
+    let (signed, bits) = match semantics.src_type {
+        Some(DataType::Int(bits)) => (true, bits),
+        Some(DataType::Uint(bits)) | Some(DataType::Bits(bits)) => (false, bits),
+        _ => (false, 32)
+    };
+    let integer = !matches!(semantics.src_type, Some(DataType::Float(_)));
+    let typehint = if signed { "i32" } else { "u32" };
+    let kind = match semantics.dst_type {
+        Some(DataType::Int(64)) | Some(DataType::Uint(64)) | Some(DataType::Bits(64)) => DataKind::Qword,
+        _ => DataKind::Dword
+    };
+    let source = |st: &mut ExecState, op: &Operand| match bits {
+        64 => operand_value_qw(st, op, typehint),
+        _ => operand_value_dw(st, op, typehint)
+    };
+    let computed = |st: &mut ExecState, expr: Expr| {
+        st.values.push(Value::Computed { expr, kind });
+        st.values.len() - 1
+    };
+
+    /* The mnemonic up to the operand types, without the carry-out (_co) and no-carry (_nc) infixes */
+    let is_type = |c: &str| c.len() > 1 && "iubf".contains(&c[..1]) && c[1..].bytes().all(|b| b.is_ascii_digit());
+    let op_end = name.split('_').take_while(|c| !is_type(c)).map(|c| c.len() + 1).sum::<usize>() - 1;
+    let op = name[..op_end].trim_end_matches("_co").trim_end_matches("_nc");
+
+    if semantics.compare {
+        eval_vector_compare(st, semantics, instr, op, ops);
+        return Ok(());
+    }
+    if op.starts_with("v_cvt") {
+        return float::eval_conversion(st, semantics, name, ops);
+    }
+    if !integer {
+        float::eval_float_op(st, semantics, instr, op, ops);
+        return Ok(());
+    }
+
+    let dst = &ops[0];
+    /* GFX8 spells out the carry-out of v_add_u32 and v_sub_u32, which lack the _co infix */
+    let skip = match ops.get(semantics.defs) {
+        Some(VCC) if semantics.defs == 1 && ops.len() > 3 => 2,
+        _ => semantics.defs
+    };
+    let srcs: Vec<Operand> = ops.iter().skip(skip)
+        .filter(|op| !matches!(op, Clamp | Omod(_)))
+        .cloned()
+        .collect();
+
+    match (op, dst, srcs.as_slice()) {
+        ("v_mov", VReg(dst), [src]) if bits == 32 => {
             let contents = operand_reg(st, src, "u32");
             insert_into!(st.vgprs, *dst, contents);
         },
-        ("v_add_u32_e32", [VReg(ref dst), op1, op2]) => {
-            let op1_idx = operand_binding_dw(st, op1, "u32");
-            let op2_idx = operand_binding_dw(st, op2, "u32");
-            st.bindings.push(Binding::Computed { expr: Expr::Add(op1_idx, op2_idx), kind: DataKind::Dword });
-            insert_into!(st.vgprs, *dst, Reg(st.bindings.len() - 1, 0));
+        /* Lanes outside EXEC are not written, so only the conditions the mask adds to EXEC select */
+        ("v_cndmask", _, [if_false, if_true, mask]) => {
+            let mask = lane_mask_operand(st, mask)
+                .ok_or_else(|| unsupported(format!("{} operand {:?} is not a known lane mask", instr, mask)))?;
+            let (if_false, if_true) = (source(st, if_false), source(st, if_true));
+            let exec = st.exec.clone();
+            let result = mask.terms.iter().rev()
+                .filter(|term| !exec.terms.contains(term))
+                .fold(if_true, |selected, &(cond, holds)| {
+                    let (if_true, if_false) = if holds { (selected, if_false) } else { (if_false, selected) };
+                    computed(st, Expr::Select { cond, if_true, if_false })
+                });
+            set_vdst(st, dst, result);
+        },
+        ("v_add", _, [op1, op2]) | ("v_sub", _, [op1, op2]) | ("v_subrev", _, [op1, op2]) | ("v_mul_lo", _, [op1, op2]) => {
+            let (op1, op2) = (source(st, op1), source(st, op2));
+            let result = computed(st, match op {
+                "v_add" => Expr::Add(op1, op2),
+                "v_sub" => Expr::Sub(op1, op2),
+                "v_subrev" => Expr::Sub(op2, op1),
+                _ => Expr::Mul(op1, op2)
+            });
+            set_vdst(st, dst, result);
+        },
+        ("v_addc", VReg(dst), [op1, op2, _]) | ("v_subb", VReg(dst), [op1, op2, _]) | ("v_subbrev", VReg(dst), [op1, op2, _]) => {
+            /* We assume that this instruction computes the high half of a 64-bit addition or subtraction —
+             * the previous operation in this case must have been "v_add_co_u32" or "v_sub(rev)_co_u32"
+             * writing the register below, which left the carry or borrow in VCC. */
+            let lo_value = dst.checked_sub(1).and_then(|lo| st.vgprs.get(lo)).and_then(|&Reg(idx, _)| st.values.get(idx).cloned());
+            let (lo_op1, lo_op2) = match (op, lo_value) {
+                ("v_addc", Some(Value::Computed { expr: Expr::Add(lo_op1, lo_op2), .. })) => (lo_op1, lo_op2),
+                ("v_subb", Some(Value::Computed { expr: Expr::Sub(lo_op1, lo_op2), .. }))
+                | ("v_subbrev", Some(Value::Computed { expr: Expr::Sub(lo_op1, lo_op2), .. })) => (lo_op1, lo_op2),
+                _ => return Err(unsupported(format!("64-bit arithmetic heuristic failed: {} does not follow the low half of a 64-bit operation", instr)))
+            };
+            /* The low half of v_subrev has its operands swapped already */
+            let (op1, op2) = if op == "v_subbrev" { (op2, op1) } else { (op1, op2) };
+
+            /* GFX10 can read the high dword of a scalar pointer directly from an SGPR,
+             * a constant high dword is zero when a dword is added to a pointer */
+            let (op1_reg, op2_reg) = (operand_reg(st, op1, "u32"), operand_reg(st, op2, "u32"));
+
+            let expr = match (op, addc_qword_matching_operands(&mut st.values, op1_reg, op2_reg, lo_op1, lo_op2)) {
+                ("v_addc", Some((op1_adc, op2_adc))) => Expr::Add(op1_adc, op2_adc),
+                /* If we can't figure out qword operands from the previous v_add instruction,
+                 * we'll have to have four operands */
+                ("v_addc", None) => Expr::AddHiLo {
+                    lo_op1, lo_op2,
+                    hi_op1: operand_value_dw(st, op1, "u32"),
+                    hi_op2: operand_value_dw(st, op2, "u32")
+                },
+                (_, Some((op1_sbb, op2_sbb))) => Expr::Sub(op1_sbb, op2_sbb),
+                /* The qword operands are put together from their halves */
+                (_, None) => {
+                    let shift = constant(st, 32);
+                    let mut qword = |lo: ValueIdx, hi: &Operand| {
+                        let hi = operand_value_dw(st, hi, "u32");
+                        st.values.push(Value::Computed { expr: Expr::Shl(hi, shift), kind: DataKind::Qword });
+                        st.values.push(Value::Computed { expr: Expr::Or(st.values.len() - 1, lo), kind: DataKind::Qword });
+                        st.values.len() - 1
+                    };
+                    Expr::Sub(qword(lo_op1, op1), qword(lo_op2, op2))
+                }
+            };
+            st.values.push(Value::Computed { expr, kind: DataKind::Qword });
+            let qword_idx = st.values.len() - 1;
+            for i in 0..2 { insert_into!(st.vgprs, *dst - 1 + i as usize, Reg(qword_idx, i)); }
         },
-        ("v_mul_lo_u32", [VReg(ref dst), op1, op2]) => {
-            let op1_idx = operand_binding_dw(st, op1, "u32");
-            let op2_idx = operand_binding_dw(st, op2, "u32");
-            st.bindings.push(Binding::Computed { expr: Expr::Mul(op1_idx, op2_idx), kind: DataKind::Dword });
-            insert_into!(st.vgprs, *dst, Reg(st.bindings.len() - 1, 0));
+        ("v_mul_hi", _, [op1, op2]) | ("v_mul", _, [op1, op2]) if bits == 32 || bits == 24 => {
+            let (mut op1, mut op2) = (source(st, op1), source(st, op2));
+            if op == "v_mul_hi" && bits == 24 {
+                /* The high dword of the 48-bit product of the low 24 bits */
+                op1 = low_bits(st, op1, 24, signed);
+                op2 = low_bits(st, op2, 24, signed);
+            }
+            let result = computed(st, match (op, bits, signed) {
+                ("v_mul_hi", _, true) => Expr::MulHi(op1, op2),
+                ("v_mul_hi", _, false) => Expr::MulHiU(op1, op2),
+                ("v_mul", 24, true) => Expr::Mul24(op1, op2),
+                ("v_mul", 24, false) => Expr::Mul24U(op1, op2),
+                _ => panic!("Operation not supported: {:?}", (instr, ops))
+            });
+            set_vdst(st, dst, result);
+        },
+        ("v_mad", _, [op1, op2, addend]) => {
+            let (op1, op2) = (operand_value_dw(st, op1, typehint), operand_value_dw(st, op2, typehint));
+            let product = match (bits, kind, signed) {
+                (24, DataKind::Dword, true) => Expr::Mul24(op1, op2),
+                (24, DataKind::Dword, false) => Expr::Mul24U(op1, op2),
+                (32, DataKind::Qword, true) => Expr::MulWide(op1, op2),
+                (32, DataKind::Qword, false) => Expr::MulWideU(op1, op2),
+                _ => panic!("Operation not supported: {:?}", (instr, ops))
+            };
+            let product = computed(st, product);
+            let addend = match kind {
+                DataKind::Qword => operand_value_qw(st, addend, typehint),
+                _ => operand_value_dw(st, addend, typehint)
+            };
+            let result = computed(st, Expr::Add(product, addend));
+            set_vdst(st, dst, result);
         },
-        ("v_ashrrev_i32_e32", [VReg(ref dst), Lit(31), VReg(ref src)]) if *dst == *src + 1 => {
+        ("v_ashrrev", VReg(dst), [Lit(31), VReg(src)]) if bits == 32 && *dst == *src + 1 => {
             /* This is most likely sign-extension of i32 to i64 */
             let Reg(src_idx, _) = st.vgprs[*src];
-            st.bindings.push(Binding::Cast { source: src_idx, kind: DataKind::I64 });
-            for i in 0..2 { insert_into!(st.vgprs, *src + i as usize, Reg(st.bindings.len() - 1, i)); }
-        },
-        ("v_lshlrev_b64", [VRegs(ref dst_lo, _), shift_by, VRegs(ref src_lo, _)]) => {
-            let shift = operand_binding_dw(st, shift_by, "u32");
-            let Reg(src_idx, _) = st.vgprs[*src_lo];
-            st.bindings.push(Binding::Computed { expr: Expr::Shl(src_idx, shift), kind: DataKind::Qword });
-            for i in 0..2 { insert_into!(st.vgprs, *dst_lo + i as usize, Reg(st.bindings.len() - 1, i)); }
-        },
-        ("v_add_co_u32_e32", [VReg(ref dst), VCC, op1, op2]) => {
-            let op1_idx = operand_binding_dw(st, op1, "u32");
-            let op2_idx = operand_binding_dw(st, op2, "u32");
-            st.bindings.push(Binding::Computed { expr: Expr::Add(op1_idx, op2_idx), kind: DataKind::Dword });
-            insert_into!(st.vgprs, *dst, Reg(st.bindings.len() - 1, 0));
-        },
-        ("v_addc_co_u32_e32", [_, VCC, Lit(0), _, VCC]) | ("v_addc_co_u32_e32", [_, VCC, _, Lit(0), VCC]) => (/* ¯\_(ツ)_/¯ */),
-        ("v_addc_co_u32_e32", [VReg(ref dst), VCC, op1, op2, VCC]) => {
-            /* We assume that this instruction is used for 64-bit addition — the previous operation in
-             * this case must have been "v_add_co_u32_e32", which used VCC as a carry flag. */
-            let Reg(lo_idx, _) = st.vgprs[*dst - 1];
-            let lo_binding = st.bindings[lo_idx];
-            
-            let (op1_reg, op2_reg) = match (op1, op2) {
-                (VReg(op1_idx), VReg(op2_idx)) => (st.vgprs[*op1_idx], st.vgprs[*op2_idx]),
-                _ => panic!("Unexpected v_addc_co_u32_e32 operands: {:?}", ops)
+            st.values.push(Value::Cast { source: src_idx, kind: DataKind::I64 });
+            for i in 0..2 { insert_into!(st.vgprs, *src + i as usize, Reg(st.values.len() - 1, i)); }
+        },
+        ("v_ashrrev", _, [Lit(shift), VRegs(src_lo, src_hi)]) if bits == 64 && is_zero(st, st.vgprs[*src_lo]) => {
+            /* Most likely an i32 -> i64 conversion with optional multiplication/division
+             * by a power of two expressed as a shift relative to 32 */
+            let src = operand_value_dw(st, &VReg(*src_hi), "i32");
+            st.values.push(Value::Cast { source: src, kind: DataKind::I64 });
+            let extended = st.values.len() - 1;
+            let result = match *shift {
+                32 => extended,
+                shift if shift < 32 => {
+                    let by = constant(st, 32 - shift as u32);
+                    computed(st, Expr::Shl(extended, by))
+                },
+                shift => {
+                    let by = constant(st, shift as u32 - 32);
+                    computed(st, Expr::Ashr(extended, by))
+                }
             };
-
-            if let Binding::Computed { expr: Expr::Add(lo_op1, lo_op2), kind: _ } = lo_binding {
-                let expr = match addc_qword_matching_operands(&mut st.bindings, op1_reg, op2_reg, lo_op1, lo_op2) {
-                    Some((op1_adc, op2_adc)) => Expr::Add(op1_adc, op2_adc),
-                    _ => {
-                        /* If we can't figure out qword operands from the previous v_add instruction,
-                         * we'll have to have four operands */
-                        Expr::AddHiLo {
-                            lo_op1, lo_op2,
-                            hi_op1: operand_binding_dw(st, op1, "u32"),
-                            hi_op2: operand_binding_dw(st, op2, "u32")
-                        }
-                    }
-                };
-                st.bindings[lo_idx] = Binding::Computed { expr, kind: DataKind::Qword };
-                for i in 0..2 { insert_into!(st.vgprs, *dst - 1 + i as usize, Reg(lo_idx, i)); }
+            set_vdst(st, dst, result);
+        },
+        ("v_lshlrev", _, [shift, src]) | ("v_lshrrev", _, [shift, src]) | ("v_ashrrev", _, [shift, src])
+        | ("v_lshl", _, [src, shift]) | ("v_lshr", _, [src, shift]) | ("v_ashr", _, [src, shift]) => {
+            let shift = operand_value_dw(st, shift, "u32");
+            let src = source(st, src);
+            let result = computed(st, match op {
+                "v_lshlrev" | "v_lshl" => Expr::Shl(src, shift),
+                "v_lshrrev" | "v_lshr" => Expr::Shr(src, shift),
+                _ => Expr::Ashr(src, shift)
+            });
+            set_vdst(st, dst, result);
+        },
+        ("v_lshl_add", _, [src, shift, addend]) | ("v_lshl_or", _, [src, shift, addend]) => {
+            let (src, shift, addend) = (source(st, src), source(st, shift), source(st, addend));
+            let shifted = computed(st, Expr::Shl(src, shift));
+            let result = computed(st, if op == "v_lshl_add" { Expr::Add(shifted, addend) } else { Expr::Or(shifted, addend) });
+            set_vdst(st, dst, result);
+        },
+        ("v_add3", _, [op1, op2, op3]) | ("v_or3", _, [op1, op2, op3]) | ("v_and_or", _, [op1, op2, op3]) => {
+            let (op1, op2, op3) = (source(st, op1), source(st, op2), source(st, op3));
+            let (first, second): (BinaryExpr, BinaryExpr) = match op {
+                "v_add3" => (Expr::Add, Expr::Add),
+                "v_or3" => (Expr::Or, Expr::Or),
+                _ => (Expr::And, Expr::Or)
+            };
+            let partial = computed(st, first(op1, op2));
+            let result = computed(st, second(partial, op3));
+            set_vdst(st, dst, result);
+        },
+        ("v_and", _, [op1, op2]) | ("v_or", _, [op1, op2]) | ("v_xor", _, [op1, op2]) => {
+            let (op1, op2) = (source(st, op1), source(st, op2));
+            let result = computed(st, match op {
+                "v_and" => Expr::And(op1, op2),
+                "v_or" => Expr::Or(op1, op2),
+                _ => Expr::Xor(op1, op2)
+            });
+            set_vdst(st, dst, result);
+        },
+        ("v_not", _, [src]) => {
+            let src = source(st, src);
+            let result = computed(st, Expr::Not(src));
+            set_vdst(st, dst, result);
+        },
+        ("v_bfe", _, [src, offset, width]) => {
+            let (src, offset, width) = (source(st, src), source(st, offset), source(st, width));
+            /* Only bits [4:0] of the offset and width are read, as OpenCL shifts do for unknown fields */
+            let field = |st: &mut ExecState, value: ValueIdx| match constant_value(st, value) {
+                Some(bits) if bits > 31 => constant(st, bits & 31),
+                _ => value
+            };
+            let (offset, width) = (field(st, offset), field(st, width));
+            let result = bitfield_extract(st, src, offset, width, signed, kind);
+            set_vdst(st, dst, result);
+        },
+        ("v_bfi", _, [mask, insert, base]) => {
+            /* Bits of `insert` where the mask is set, bits of `base` elsewhere */
+            let (mask, insert, base) = (source(st, mask), source(st, insert), source(st, base));
+            let inserted = computed(st, Expr::And(mask, insert));
+            let inverted = computed(st, Expr::Not(mask));
+            let kept = computed(st, Expr::And(inverted, base));
+            let result = computed(st, Expr::Or(inserted, kept));
+            set_vdst(st, dst, result);
+        },
+        ("v_alignbit", _, [hi, lo, shift]) => {
+            let (hi, lo, shift) = (source(st, hi), source(st, lo), source(st, shift));
+            let result = computed(st, Expr::AlignBit { hi, lo, shift });
+            set_vdst(st, dst, result);
+        },
+        ("v_min", _, [op1, op2]) | ("v_max", _, [op1, op2]) if bits == 32 || bits == 16 => {
+            let (op1, op2) = (source(st, op1), source(st, op2));
+            let (op1, op2) = (low_bits(st, op1, bits, signed), low_bits(st, op2, bits, signed));
+            let result = computed(st, match (op, signed) {
+                ("v_min", true) => Expr::Min(op1, op2),
+                ("v_min", false) => Expr::MinU(op1, op2),
+                (_, true) => Expr::Max(op1, op2),
+                (_, false) => Expr::MaxU(op1, op2)
+            });
+            set_vdst(st, dst, result);
+        },
+        ("v_med3", _, [op1, op2, op3]) if bits == 32 || bits == 16 => {
+            let (op1, op2, op3) = (source(st, op1), source(st, op2), source(st, op3));
+            let (op1, op2, op3) = (low_bits(st, op1, bits, signed), low_bits(st, op2, bits, signed), low_bits(st, op3, bits, signed));
+            let (min, max): (BinaryExpr, BinaryExpr) =
+                if signed { (Expr::Min, Expr::Max) } else { (Expr::MinU, Expr::MaxU) };
+            let in_order = match (constant_value(st, op2), constant_value(st, op3)) {
+                (Some(lo), Some(hi)) if signed => lo as i32 <= hi as i32,
+                (Some(lo), Some(hi)) => lo <= hi,
+                _ => false
+            };
+            let result = if in_order {
+                /* Clamping to a constant range */
+                let lower = computed(st, max(op1, op2));
+                computed(st, min(lower, op3))
             }
             else {
-                panic!("64-bit addition heuristic failed; v_addc_co_u32_e32 is _not_ used to add up the high part of a 64-bit int");
-            }
+                let lower = computed(st, min(op1, op2));
+                let upper = computed(st, max(op1, op2));
+                let upper = computed(st, min(upper, op3));
+                computed(st, max(lower, upper))
+            };
+            set_vdst(st, dst, result);
         },
-        ("v_mac_f32_e32", [VReg(ref dst), op1, op2]) => {
-            let Reg(dst_idx, _) = st.vgprs[*dst];
-            let op1_idx = operand_binding_dw(st, op1, "f32");
-            let op2_idx = operand_binding_dw(st, op2, "f32");
-            st.bindings.push(Binding::Computed { expr: Expr::Mul(op1_idx, op2_idx), kind: DataKind::Dword });
-            let mul_idx = st.bindings.len() - 1;
-            st.bindings.push(Binding::Computed { expr: Expr::Add(dst_idx, mul_idx), kind: DataKind::Dword });
-            insert_into!(st.vgprs, *dst, Reg(st.bindings.len() - 1, 0));
-        },
-        unsupported => panic!("Operation not supported: {:?}", unsupported)
-//       ("v_ashrrev_i64", [VRegs(ref dst_lo, ref dst_hi), Lit(ref shift), VRegs(ref src_lo, ref src_hi)]) => {
-//           /* Most likely an i32 -> i64 conversion with optional multiplication/division
-//            * by a power of two expressed as a shift relative to 32 */
-//           if st.vgprs[*src_lo].as_str() == "0" {
-//               let result = if *shift == 32 {
-//                   st.vgprs[*src_hi].to_owned()
-//               }
-//               else if *shift < 32 {
-//                   format!("{} * {}", st.vgprs[*src_hi], 2u32.pow(32 - shift))
-//               }
-//               else {
-//                   format!("{} / {}", st.vgprs[*src_hi], 2u32.pow(shift - 32))
-//               };
-//               insert_into!(st.vgprs, *dst_lo, result.to_owned());
-//               insert_into!(st.vgprs, *dst_hi, result);
-//           }
-//       },
+        _ => return Err(unsupported(format!("Operation not supported: {} {:?}", instr, ops)))
     }
+    Ok(())
 }
 
-fn addc_qword_matching_operands(bindings: &mut Vec<Binding>, op1: Reg, op2: Reg, lo_op1: BindingIdx, lo_op2: BindingIdx) -> Option<(BindingIdx, BindingIdx)> {
-    let op1_adc = match (bindings[lo_op1], op1, op2) {
-        (Binding::DwordElement { of, dword }, Reg(of_hi, dword_hi), _) if of == of_hi && dword + 1 == dword_hi => {
-            bindings.push(Binding::QwordElement { of, dword });
-            bindings.len() - 1
+/* The low `bits` of a dword operand, sign-extended for signed operations. Constants are folded,
+ * full dwords are returned as is */
+fn low_bits(st: &mut ExecState, src: ValueIdx, bits: u16, signed: bool) -> ValueIdx {
+    if bits >= 32 {
+        return src;
+    }
+    let shift = 32 - u32::from(bits);
+    if let Some(value) = constant_value(st, src) {
+        st.values.push(match signed {
+            true => Value::I32(((value << shift) as i32) >> shift),
+            false => Value::U32(value & ((1 << bits) - 1))
+        });
+        return st.values.len() - 1;
+    }
+    let expr = if signed {
+        let shift = constant(st, shift);
+        st.values.push(Value::Computed { expr: Expr::Shl(src, shift), kind: DataKind::Dword });
+        Expr::Ashr(st.values.len() - 1, shift)
+    }
+    else {
+        Expr::And(src, constant(st, (1 << bits) - 1))
+    };
+    st.values.push(Value::Computed { expr, kind: DataKind::Dword });
+    st.values.len() - 1
+}
+
+/* Extracts `width` bits of `src` starting at `offset`, sign-extending them for signed extractions.
+ * Shifts are constant-folded when the field is known */
+fn bitfield_extract(st: &mut ExecState, src: ValueIdx, offset: ValueIdx, width: ValueIdx, signed: bool, kind: DataKind) -> ValueIdx {
+    let bits = match kind { DataKind::Qword => 64, _ => 32 };
+    let computed = |st: &mut ExecState, expr: Expr| {
+        st.values.push(Value::Computed { expr, kind });
+        st.values.len() - 1
+    };
+    match (constant_value(st, offset), constant_value(st, width)) {
+        (_, Some(0)) => constant(st, 0),
+        /* Fields that reach the top bit are only shifted down */
+        (Some(offset_bits), Some(width)) if offset_bits + width >= bits =>
+            computed(st, if signed { Expr::Ashr(src, offset) } else { Expr::Shr(src, offset) }),
+        /* Masks wider than a dword constant are applied by shifting the field to the top and back */
+        (Some(offset), Some(width)) if signed || width > 32 => {
+            let (shl, shr) = (constant(st, bits - offset - width), constant(st, bits - width));
+            let shifted = computed(st, Expr::Shl(src, shl));
+            computed(st, if signed { Expr::Ashr(shifted, shr) } else { Expr::Shr(shifted, shr) })
         },
-        (Binding::DwordElement { of, dword }, _, Reg(of_hi, dword_hi)) if of == of_hi && dword + 1 == dword_hi => {
-            bindings.push(Binding::QwordElement { of, dword });
-            bindings.len() - 1
+        (Some(_), Some(width)) => {
+            let shifted = computed(st, Expr::Shr(src, offset));
+            let mask = constant(st, ((1u64 << width) - 1) as u32);
+            computed(st, Expr::And(shifted, mask))
         },
-        (_, Reg(of_hi, dword_hi), _) if lo_op1 == of_hi && dword_hi == 1 => lo_op1,
-        (_, _, Reg(of_hi, dword_hi)) if lo_op1 == of_hi && dword_hi == 1 => lo_op1,
-        _ => return None
-    };
-    let op2_adc = match (bindings[lo_op2], op1, op2) {
-        (Binding::DwordElement { of, dword }, Reg(of_hi, dword_hi), _) if of == of_hi && dword + 1 == dword_hi => {
-            bindings.push(Binding::QwordElement { of, dword });
-            bindings.len() - 1
+        _ if signed => {
+            let total = constant(st, bits);
+            let shr = computed(st, Expr::Sub(total, width));
+            let shl = computed(st, Expr::Sub(shr, offset));
+            let shifted = computed(st, Expr::Shl(src, shl));
+            computed(st, Expr::Ashr(shifted, shr))
         },
-        (Binding::DwordElement { of, dword }, _, Reg(of_hi, dword_hi)) if of == of_hi && dword + 1 == dword_hi => {
-            bindings.push(Binding::QwordElement { of, dword });
-            bindings.len() - 1
+        _ => {
+            let one = constant(st, 1);
+            let shifted = computed(st, Expr::Shr(src, offset));
+            let bit = computed(st, Expr::Shl(one, width));
+            let mask = computed(st, Expr::Sub(bit, one));
+            computed(st, Expr::And(shifted, mask))
+        }
+    }
+}
+
+/* Operands of the s_add_u32 that wrote the register below `hi` if SCC still holds its carry */
+fn low_dword_add(st: &ExecState, hi: usize) -> Option<(ValueIdx, ValueIdx)> {
+    let Reg(lo, dword) = *st.sgprs.get(hi.checked_sub(1)?)?;
+    match (st.scc, st.values.get(lo)?) {
+        (Some(Condition::LtU(sum, carry_lhs)), &Value::Computed { expr: Expr::Add(lhs, rhs), .. })
+            if dword == 0 && sum == lo && carry_lhs == lhs => Some((lhs, rhs)),
+        _ => None
+    }
+}
+
+fn addc_qword_matching_operands(values: &mut Vec<Value>, op1: Reg, op2: Reg, lo_op1: ValueIdx, lo_op2: ValueIdx) -> Option<(ValueIdx, ValueIdx)> {
+    let qword = |values: &mut Vec<Value>, lo_op: ValueIdx| match (values[lo_op], op1, op2) {
+        (Value::DwordElement { of, dword }, Reg(of_hi, dword_hi), _) if of == of_hi && dword + 1 == dword_hi => {
+            values.push(Value::QwordElement { of, dword });
+            Some(values.len() - 1)
         },
-        (_, Reg(of_hi, dword_hi), _) if lo_op2 == of_hi && dword_hi == 1 => lo_op2,
-        (_, _, Reg(of_hi, dword_hi)) if lo_op2 == of_hi && dword_hi == 1 => lo_op2,
-        _ => return None
+        (Value::DwordElement { of, dword }, _, Reg(of_hi, dword_hi)) if of == of_hi && dword + 1 == dword_hi => {
+            values.push(Value::QwordElement { of, dword });
+            Some(values.len() - 1)
+        },
+        (_, Reg(of_hi, dword_hi), _) if lo_op == of_hi && dword_hi == 1 => Some(lo_op),
+        (_, _, Reg(of_hi, dword_hi)) if lo_op == of_hi && dword_hi == 1 => Some(lo_op),
+        _ => None
     };
-    Some((op1_adc, op2_adc))
+    let (op1_adc, op2_adc) = (qword(values, lo_op1), qword(values, lo_op2));
+
+    /* A dword added to a qword has a zero high dword */
+    let is_zero = |Reg(idx, _): Reg| matches!(values[idx], Value::U32(0) | Value::I32(0));
+    match (op1_adc, op2_adc) {
+        (Some(op1_adc), Some(op2_adc)) => Some((op1_adc, op2_adc)),
+        (Some(op1_adc), None) if is_zero(op1) || is_zero(op2) => Some((op1_adc, lo_op2)),
+        (None, Some(op2_adc)) if is_zero(op1) || is_zero(op2) => Some((lo_op1, op2_adc)),
+        _ => None
+    }
 }
 
-fn load_ptr_binding(st: &ExecState, source: &Operand) -> BindingIdx {
+fn load_ptr_value(st: &ExecState, source: &Operand) -> ValueIdx {
     let (src_lo, src_hi) = match source {
         SRegs(ref src_lo, ref src_hi) => (st.sgprs[*src_lo], st.sgprs[*src_hi]),
         VRegs(ref src_lo, ref src_hi) => (st.vgprs[*src_lo], st.vgprs[*src_hi]),
@@ -269,27 +1078,546 @@ fn operand_reg(st: &mut ExecState, op: &Operand, typehint: &str) -> Reg {
         VReg(ref i) => st.vgprs[*i],
         Lit(ref contents) => {
             match typehint {
-                "i32" => st.bindings.push(Binding::I32(*contents)),
-                "u32" | _ => st.bindings.push(Binding::U32(*contents as u32))
+                "i32" => st.values.push(Value::I32(*contents)),
+                _ => st.values.push(Value::U32(*contents as u32))
             }
-            Reg(st.bindings.len() - 1, 0)
+            Reg(st.values.len() - 1, 0)
+        },
+        Float(ref value) => {
+            st.values.push(Value::F32(*value));
+            Reg(st.values.len() - 1, 0)
         },
         _ => panic!("Unrecognized operand {:?}", op)
     }
 }
 
-fn operand_binding_dw(st: &mut ExecState, op: &Operand, typehint: &str) -> BindingIdx {
+fn operand_value_dw(st: &mut ExecState, op: &Operand, typehint: &str) -> ValueIdx {
     let Reg(of, dword) = operand_reg(st, op, typehint);
 
-    if let Binding::Deref { kind: DataKind::DQword, .. } = st.bindings[of] {
-        st.bindings.push(Binding::DwordElement { of, dword });
-        st.bindings.len() - 1
+    if let Value::Deref { kind: DataKind::DQword, .. } = st.values[of] {
+        st.values.push(Value::DwordElement { of, dword });
+        st.values.len() - 1
     }
     else if dword == 0 {
         of
     }
     else {
-        st.bindings.push(Binding::DwordElement { of, dword });
-        st.bindings.len() - 1
+        st.values.push(Value::DwordElement { of, dword });
+        st.values.len() - 1
+    }
+}
+
+/* A 64-bit operand: consecutive dwords of one value, a zero-extended dword or an inline constant */
+fn operand_value_qw(st: &mut ExecState, op: &Operand, typehint: &str) -> ValueIdx {
+    let (lo, hi) = match *op {
+        SRegs(lo, _) => (st.sgprs[lo], st.sgprs[lo + 1]),
+        VRegs(lo, _) => (st.vgprs[lo], st.vgprs[lo + 1]),
+        _ => return operand_value_dw(st, op, typehint)
+    };
+    match (lo, hi) {
+        (Reg(of, 0), Reg(of_hi, 1)) if of == of_hi => match st.values[of] {
+            Value::Deref { kind: DataKind::DQword, .. } => {
+                st.values.push(Value::QwordElement { of, dword: 0 });
+                st.values.len() - 1
+            },
+            _ => of
+        },
+        (Reg(of, dword), Reg(of_hi, dword_hi)) if of == of_hi && dword + 1 == dword_hi => {
+            st.values.push(Value::QwordElement { of, dword });
+            st.values.len() - 1
+        },
+        /* A dword zero-extended to 64 bits */
+        (Reg(of, dword), hi) if is_zero(st, hi) => match dword {
+            0 => of,
+            _ => {
+                st.values.push(Value::DwordElement { of, dword });
+                st.values.len() - 1
+            }
+        },
+        _ => panic!("Unsupported 64-bit operand {:?}: dwords of different values (lo: {:?}, hi: {:?})", op, lo, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{eval_gcn_instruction, eval_salu_op, eval_valu_op};
+
+    use crate::data_flow::exec_state::ExecState;
+    use crate::data_flow::types::{Reg, Expr, BitFn, Statement, Condition, Value, ValueIdx, DataKind};
+    use crate::asm::{Operand, Operand::*};
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+    use crate::asm::semantics;
+
+    /* s0-s3 hold the dwords, s[4:5] and s[6:7] the qwords, both built from constants */
+    fn state(dwords: [u32; 4], qwords: [u64; 2]) -> ExecState {
+        let mut st = ExecState::new(KernelCode::default(), Target::from_processor("gfx900").unwrap());
+        st.sgprs.clear();
+        for &dword in dwords.iter() {
+            st.values.push(Value::U32(dword));
+            st.sgprs.push(Reg(st.values.len() - 1, 0));
+        }
+        for &qword in qwords.iter() {
+            st.values.push(Value::U32(qword as u32));
+            st.values.push(Value::U32((qword >> 32) as u32));
+            st.values.push(Value::U32(32));
+            let (lo, hi, shift) = (st.values.len() - 3, st.values.len() - 2, st.values.len() - 1);
+            st.values.push(Value::Computed { expr: Expr::Shl(hi, shift), kind: DataKind::Qword });
+            st.values.push(Value::Computed { expr: Expr::Or(st.values.len() - 1, lo), kind: DataKind::Qword });
+            st.sgprs.push(Reg(st.values.len() - 1, 0));
+            st.sgprs.push(Reg(st.values.len() - 1, 1));
+        }
+        st.sgprs.resize(16, Reg(std::usize::MAX, 0));
+        st.vgprs.resize(16, Reg(std::usize::MAX, 0));
+        st
+    }
+
+    /* Runs the instruction and returns SCC, evaluated on the constants */
+    fn eval(st: &mut ExecState, instr: &str, ops: Vec<Operand>) -> Option<bool> {
+        eval_salu_op(st, &semantics::lookup(instr).unwrap(), instr, &ops).unwrap();
+        st.scc.map(|cond| condition(st, cond))
+    }
+
+    /* Runs the vector instruction and returns the dword written to v0 */
+    fn eval_vector(st: &mut ExecState, instr: &str, srcs: Vec<Operand>) -> u32 {
+        let ops: Vec<Operand> = vec![VReg(0)].into_iter().chain(srcs).collect();
+        eval_valu_op(st, &semantics::lookup(instr).unwrap(), instr, &ops).unwrap();
+        let Reg(value, dword) = st.vgprs[0];
+        (eval_value(st, value) >> (32 * dword as u32)) as u32
+    }
+
+    fn sgpr(st: &ExecState, idx: usize) -> u32 {
+        let Reg(value, dword) = st.sgprs[idx];
+        (eval_value(st, value) >> (32 * dword as u32)) as u32
+    }
+
+    fn sgprs(st: &ExecState, lo: usize) -> u64 {
+        sgpr(st, lo) as u64 | (sgpr(st, lo + 1) as u64) << 32
+    }
+
+    fn width(st: &ExecState, idx: ValueIdx) -> u32 {
+        match st.values[idx] {
+            Value::Computed { kind: DataKind::Qword, .. } | Value::Computed { kind: DataKind::I64, .. } => 64,
+            Value::QwordElement { .. } => 64,
+            _ => 32
+        }
+    }
+
+    fn truncate(value: u64, bits: u32) -> u64 {
+        if bits == 64 { value } else { value & ((1 << bits) - 1) }
+    }
+
+    fn signed(st: &ExecState, idx: ValueIdx) -> i64 {
+        let shift = 64 - width(st, idx);
+        ((eval_value(st, idx) << shift) as i64) >> shift
+    }
+
+    fn eval_value(st: &ExecState, idx: ValueIdx) -> u64 {
+        let bits = width(st, idx);
+        let value = match st.values[idx] {
+            Value::U32(value) => value as u64,
+            Value::I32(value) => value as u32 as u64,
+            Value::DwordElement { of, dword } => eval_value(st, of) >> (32 * dword as u32),
+            Value::QwordElement { of, dword } => eval_value(st, of) >> (32 * dword as u32),
+            Value::Computed { ref expr, .. } => {
+                let val = |idx: ValueIdx| eval_value(st, idx);
+                let sext = |idx: ValueIdx| ((val(idx) << (64 - bits)) as i64) >> (64 - bits);
+                match *expr {
+                    Expr::Add(a, b) => val(a).wrapping_add(val(b)),
+                    Expr::Sub(a, b) => val(a).wrapping_sub(val(b)),
+                    Expr::And(a, b) => val(a) & val(b),
+                    Expr::Or(a, b) => val(a) | val(b),
+                    Expr::Xor(a, b) => val(a) ^ val(b),
+                    Expr::Not(a) => !val(a),
+                    Expr::Shl(a, b) => val(a).checked_shl(val(b) as u32).unwrap_or(0),
+                    Expr::Shr(a, b) => truncate(val(a), bits).checked_shr(val(b) as u32).unwrap_or(0),
+                    Expr::Ashr(a, b) => (sext(a) >> val(b).min(63)) as u64,
+                    Expr::Min(a, b) => sext(a).min(sext(b)) as u64,
+                    Expr::Max(a, b) => sext(a).max(sext(b)) as u64,
+                    Expr::MinU(a, b) => truncate(val(a), bits).min(truncate(val(b), bits)),
+                    Expr::MaxU(a, b) => truncate(val(a), bits).max(truncate(val(b), bits)),
+                    Expr::Abs(a) => sext(a).unsigned_abs(),
+                    Expr::MulHi(a, b) => ((sext(a) * sext(b)) >> 32) as u64,
+                    Expr::MulHiU(a, b) => (truncate(val(a), 32) * truncate(val(b), 32)) >> 32,
+                    Expr::Bits(BitFn::Count, a) => truncate(val(a), width(st, a)).count_ones() as u64,
+                    Expr::Bits(BitFn::Reverse, a) => val(a).reverse_bits() >> (64 - width(st, a)),
+                    Expr::Bits(BitFn::TrailingZeros, a) => truncate(val(a), width(st, a)).trailing_zeros().min(width(st, a)) as u64,
+                    Expr::Select { cond, if_true, if_false } =>
+                        if condition(st, cond) { val(if_true) } else { val(if_false) },
+                    Expr::AddHiLo { hi_op1, hi_op2, lo_op1, lo_op2 } =>
+                        (val(hi_op1) << 32 | val(lo_op1)).wrapping_add(val(hi_op2) << 32 | val(lo_op2)),
+                    ref other => panic!("Unexpected expression {:?}", other)
+                }
+            },
+            ref other => panic!("Unexpected value {:?}", other)
+        };
+        truncate(value, bits)
+    }
+
+    fn condition(st: &ExecState, cond: Condition) -> bool {
+        match cond {
+            Condition::Lt(a, b) => signed(st, a) < signed(st, b),
+            Condition::Le(a, b) => signed(st, a) <= signed(st, b),
+            Condition::LtU(a, b) => eval_value(st, a) < eval_value(st, b),
+            Condition::LeU(a, b) => eval_value(st, a) <= eval_value(st, b),
+            Condition::Eql(a, b) => eval_value(st, a) == eval_value(st, b),
+            Condition::Ne(a, b) => eval_value(st, a) != eval_value(st, b),
+            other => panic!("Unexpected condition {:?}", other)
+        }
+    }
+
+    #[test]
+    fn add_sub_carry() {
+        let mut st = state([0xffff_ffff, 1, 2, 0], [0, 0]);
+        assert_eq!(eval(&mut st, "s_add_u32", vec![SReg(8), SReg(0), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0);
+        assert_eq!(eval(&mut st, "s_add_u32", vec![SReg(8), SReg(1), SReg(2)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 3);
+
+        assert_eq!(eval(&mut st, "s_sub_u32", vec![SReg(8), SReg(1), SReg(2)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0xffff_ffff);
+        assert_eq!(eval(&mut st, "s_sub_u32", vec![SReg(8), SReg(2), SReg(1)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 1);
+
+        /* Signed overflow is not tracked */
+        assert_eq!(eval(&mut st, "s_add_i32", vec![SReg(8), SReg(0), SReg(1)]), None);
+        assert_eq!(sgpr(&st, 8), 0);
+    }
+
+    #[test]
+    fn add_sub_with_carry_in() {
+        let mut st = state([0xffff_ffff, 0, 5, 3], [0, 0]);
+        for &(carry_in, lhs, rhs, sum, carry_out) in &[(true, 0, 1, 0, true), (false, 0, 1, 0xffff_ffff, false),
+                                                       (true, 1, 1, 1, false), (true, 0, 0, 0xffff_ffff, true)] {
+            /* s1 == s1 sets SCC, s1 == s0 clears it */
+            eval(&mut st, "s_cmp_eq_u32", vec![SReg(1), SReg(if carry_in { 1 } else { 0 })]);
+            assert_eq!(eval(&mut st, "s_addc_u32", vec![SReg(8), SReg(lhs), SReg(rhs)]), Some(carry_out));
+            assert_eq!(sgpr(&st, 8), sum);
+        }
+        for &(borrow_in, lhs, rhs, diff, borrow_out) in &[(true, 2, 3, 1, false), (true, 3, 2, 0xffff_fffd, true),
+                                                          (true, 1, 1, 0xffff_ffff, true), (false, 1, 1, 0, false)] {
+            eval(&mut st, "s_cmp_eq_u32", vec![SReg(1), SReg(if borrow_in { 1 } else { 0 })]);
+            assert_eq!(eval(&mut st, "s_subb_u32", vec![SReg(8), SReg(lhs), SReg(rhs)]), Some(borrow_out));
+            assert_eq!(sgpr(&st, 8), diff);
+        }
+    }
+
+    #[test]
+    fn add_qword_halves() {
+        let mut st = state([0xffff_ffff, 1, 1, 2], [0, 0]);
+        eval(&mut st, "s_add_u32", vec![SReg(8), SReg(0), SReg(2)]);
+        assert_eq!(eval(&mut st, "s_addc_u32", vec![SReg(9), SReg(1), SReg(3)]), Some(false));
+        assert_eq!(sgprs(&st, 8), 0x4_0000_0000);
+        let Reg(sum, _) = st.sgprs[8];
+        assert_eq!(st.sgprs[9], Reg(sum, 1));
+
+        let mut st = state([0, 0xffff_ffff, 1, 1], [0, 0]);
+        eval(&mut st, "s_add_u32", vec![SReg(8), SReg(0), SReg(2)]);
+        assert_eq!(eval(&mut st, "s_addc_u32", vec![SReg(9), SReg(1), SReg(3)]), Some(true));
+        assert_eq!(sgprs(&st, 8), 1);
+    }
+
+    #[test]
+    fn bitwise_nonzero() {
+        let mut st = state([0xf0, 0x0f, 0, 0xffff_ffff], [0xffff_0000_0000, 0xff_0000_0000]);
+        for &(instr, lhs, rhs, result) in &[("s_and_b32", 0, 1, 0), ("s_or_b32", 0, 1, 0xff), ("s_xor_b32", 0, 0, 0),
+                                            ("s_andn2_b32", 0, 1, 0xf0), ("s_orn2_b32", 2, 3, 0), ("s_nand_b32", 3, 3, 0),
+                                            ("s_nor_b32", 0, 1, 0xffff_ff00), ("s_xnor_b32", 0, 1, 0xffff_ff00)] {
+            assert_eq!(eval(&mut st, instr, vec![SReg(8), SReg(lhs), SReg(rhs)]), Some(result != 0), "{}", instr);
+            assert_eq!(sgpr(&st, 8), result, "{}", instr);
+        }
+        assert_eq!(eval(&mut st, "s_not_b32", vec![SReg(8), SReg(3)]), Some(false));
+        assert_eq!(eval(&mut st, "s_and_b64", vec![SRegs(8, 9), SRegs(4, 5), SRegs(6, 7)]), Some(true));
+        assert_eq!(sgprs(&st, 8), 0xff_0000_0000);
+        assert_eq!(eval(&mut st, "s_xor_b64", vec![SRegs(8, 9), SRegs(4, 5), SRegs(4, 5)]), Some(false));
+    }
+
+    #[test]
+    fn shifts_nonzero() {
+        let mut st = state([0x8000_0000, 1, 31, 32], [0x8000_0000_0000_0000, 0]);
+        assert_eq!(eval(&mut st, "s_lshl_b32", vec![SReg(8), SReg(0), SReg(1)]), Some(false));
+        assert_eq!(eval(&mut st, "s_lshr_b32", vec![SReg(8), SReg(0), SReg(2)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 1);
+        assert_eq!(eval(&mut st, "s_ashr_i32", vec![SReg(8), SReg(0), SReg(2)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0xffff_ffff);
+        assert_eq!(eval(&mut st, "s_lshr_b64", vec![SRegs(8, 9), SRegs(4, 5), SReg(3)]), Some(true));
+        assert_eq!(sgprs(&st, 8), 0x8000_0000);
+        assert_eq!(eval(&mut st, "s_lshl_b64", vec![SRegs(8, 9), SRegs(4, 5), SReg(1)]), Some(false));
+    }
+
+    #[test]
+    fn bitfield_extract_nonzero() {
+        let mut st = state([0x8765_4321, 0, 0, 0], [0x8000_00ff_0000_0000, 0]);
+        assert_eq!(eval(&mut st, "s_bfe_u32", vec![SReg(8), SReg(0), Lit(8 << 16 | 4)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0x32);
+        assert_eq!(eval(&mut st, "s_bfe_u32", vec![SReg(8), SReg(1), Lit(8 << 16 | 4)]), Some(false));
+        assert_eq!(eval(&mut st, "s_bfe_i32", vec![SReg(8), SReg(0), Lit(8 << 16 | 24)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0xffff_ff87);
+        /* Fields past the top bit stop there */
+        assert_eq!(eval(&mut st, "s_bfe_u32", vec![SReg(8), SReg(0), Lit(16 << 16 | 28)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 8);
+        assert_eq!(eval(&mut st, "s_bfe_u32", vec![SReg(8), SReg(0), Lit(4)]), Some(false));
+
+        /* 64-bit fields wider than a dword */
+        assert_eq!(eval(&mut st, "s_bfe_u64", vec![SRegs(8, 9), SRegs(4, 5), Lit(40 << 16 | 8)]), Some(true));
+        assert_eq!(sgprs(&st, 8), 0xff_0000_0000 >> 8);
+        assert_eq!(eval(&mut st, "s_bfe_i64", vec![SRegs(8, 9), SRegs(4, 5), Lit(40 << 16 | 24)]), Some(true));
+        assert_eq!(sgprs(&st, 8), 0xffff_ff80_0000_ff00);
+        assert_eq!(eval(&mut st, "s_bfe_u64", vec![SRegs(8, 9), SRegs(4, 5), Lit(32 << 16)]), Some(false));
+    }
+
+    #[test]
+    fn min_max_selects_first() {
+        let mut st = state([0xffff_ffff, 1, 0, 0], [0, 0]);
+        assert_eq!(eval(&mut st, "s_min_i32", vec![SReg(8), SReg(0), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0xffff_ffff);
+        assert_eq!(eval(&mut st, "s_min_u32", vec![SReg(8), SReg(0), SReg(1)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 1);
+        assert_eq!(eval(&mut st, "s_max_i32", vec![SReg(8), SReg(0), SReg(1)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 1);
+        assert_eq!(eval(&mut st, "s_max_u32", vec![SReg(8), SReg(0), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0xffff_ffff);
+    }
+
+    #[test]
+    fn abs_nonzero() {
+        let mut st = state([0xffff_fffb, 0, 0, 0], [0, 0]);
+        assert_eq!(eval(&mut st, "s_abs_i32", vec![SReg(8), SReg(0)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 5);
+        assert_eq!(eval(&mut st, "s_abs_i32", vec![SReg(8), SReg(1)]), Some(false));
+    }
+
+    #[test]
+    fn compare() {
+        let mut st = state([0xffff_ffff, 1, 0, 0], [5, 5]);
+        assert_eq!(eval(&mut st, "s_cmp_lt_i32", vec![SReg(0), SReg(1)]), Some(true));
+        assert_eq!(eval(&mut st, "s_cmp_lt_u32", vec![SReg(0), SReg(1)]), Some(false));
+        assert_eq!(eval(&mut st, "s_cmp_lg_u32", vec![SReg(2), SReg(3)]), Some(false));
+        assert_eq!(eval(&mut st, "s_cmp_ge_i32", vec![SReg(1), SReg(0)]), Some(true));
+        assert_eq!(eval(&mut st, "s_cmp_eq_u64", vec![SRegs(4, 5), SRegs(6, 7)]), Some(true));
+        assert_eq!(eval(&mut st, "s_cmp_lg_u64", vec![SRegs(4, 5), SRegs(6, 7)]), Some(false));
+        /* SOPK immediates are sign-extended for signed comparisons only */
+        assert_eq!(eval(&mut st, "s_cmpk_lt_i32", vec![SReg(0), Lit(0xffff)]), Some(false));
+        assert_eq!(eval(&mut st, "s_cmpk_lt_u32", vec![SReg(1), Lit(0xffff)]), Some(true));
+        assert_eq!(eval(&mut st, "s_cmpk_eq_u32", vec![SReg(0), Lit(0xffff)]), Some(false));
+    }
+
+    #[test]
+    fn select_reads_scc() {
+        let mut st = state([7, 9, 0, 0], [0, 0]);
+        eval(&mut st, "s_cmp_lt_u32", vec![SReg(0), SReg(1)]);
+        assert_eq!(eval(&mut st, "s_cselect_b32", vec![SReg(8), SReg(0), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 7);
+        eval(&mut st, "s_cmp_lt_u32", vec![SReg(1), SReg(0)]);
+        assert_eq!(eval(&mut st, "s_cselect_b32", vec![SReg(8), SReg(0), SReg(1)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 9);
+    }
+
+    #[test]
+    fn vector_bitfield_extract_masks_field() {
+        let mut st = state([0x8765_4321, 0, 0, 0], [0, 0]);
+        assert_eq!(eval_vector(&mut st, "v_bfe_u32", vec![SReg(0), Lit(36), Lit(8)]), 0x32);
+        assert_eq!(eval_vector(&mut st, "v_bfe_u32", vec![SReg(0), Lit(4), Lit(40)]), 0x32);
+        assert_eq!(eval_vector(&mut st, "v_bfe_u32", vec![SReg(0), Lit(4), Lit(32)]), 0);
+        assert_eq!(eval_vector(&mut st, "v_bfe_i32", vec![SReg(0), Lit(56), Lit(60)]), 0xffff_ff87);
+        assert_eq!(eval_vector(&mut st, "v_bfe_u32", vec![SReg(0), Lit(63), Lit(31)]), 1);
+    }
+
+    #[test]
+    fn vector_half_min_max_med3() {
+        let mut st = state([0x1_fffe, 0x2_0003, 0xffff, 5], [0, 0]);
+        assert_eq!(eval_vector(&mut st, "v_min_u16", vec![SReg(0), SReg(1)]), 3);
+        assert_eq!(eval_vector(&mut st, "v_max_u16", vec![SReg(0), SReg(1)]), 0xfffe);
+        assert_eq!(eval_vector(&mut st, "v_min_i16", vec![SReg(0), SReg(1)]) & 0xffff, 0xfffe);
+        assert_eq!(eval_vector(&mut st, "v_max_i16", vec![SReg(0), SReg(1)]), 3);
+        assert_eq!(eval_vector(&mut st, "v_med3_u16", vec![SReg(0), SReg(1), SReg(3)]), 5);
+        assert_eq!(eval_vector(&mut st, "v_med3_i16", vec![SReg(0), SReg(1), SReg(2)]) & 0xffff, 0xffff);
+        /* Clamping to a constant range */
+        assert_eq!(eval_vector(&mut st, "v_med3_i16", vec![SReg(0), Lit(0), Lit(0x7f)]), 0);
+        assert_eq!(eval_vector(&mut st, "v_med3_u16", vec![SReg(1), Lit(0), Lit(2)]), 2);
+    }
+
+    #[test]
+    fn vector_mul_hi_24() {
+        let mut st = state([0xffff_ffff, 0x1000_0100, 0x80_0000, 0x10_0000], [0, 0]);
+        assert_eq!(eval_vector(&mut st, "v_mul_hi_u32_u24", vec![SReg(0), SReg(0)]), 0xffff);
+        assert_eq!(eval_vector(&mut st, "v_mul_hi_u32_u24", vec![SReg(1), SReg(1)]), 0);
+        assert_eq!(eval_vector(&mut st, "v_mul_hi_u32_u24", vec![SReg(2), SReg(3)]), 0x800);
+        assert_eq!(eval_vector(&mut st, "v_mul_hi_i32_i24", vec![SReg(2), SReg(3)]), 0xffff_f800);
+        assert_eq!(eval_vector(&mut st, "v_mul_hi_i32_i24", vec![SReg(0), SReg(0)]), 0);
+    }
+
+    #[test]
+    fn shift_add_overflow() {
+        let mut st = state([0x4000_0001, 4, 1, 0x3fff_ffff], [0, 0]);
+        assert_eq!(eval(&mut st, "s_lshl2_add_u32", vec![SReg(8), SReg(0), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 8);
+        assert_eq!(eval(&mut st, "s_lshl1_add_u32", vec![SReg(8), SReg(2), SReg(1)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 6);
+        assert_eq!(eval(&mut st, "s_lshl2_add_u32", vec![SReg(8), SReg(3), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0);
+    }
+
+    #[test]
+    fn mul_hi_and_bitfield_masks() {
+        let mut st = state([0x8000_0000, 4, 8, 40], [0, 0]);
+        eval(&mut st, "s_mul_hi_u32", vec![SReg(8), SReg(0), SReg(1)]);
+        assert_eq!(sgpr(&st, 8), 2);
+        eval(&mut st, "s_mul_hi_i32", vec![SReg(8), SReg(0), SReg(1)]);
+        assert_eq!(sgpr(&st, 8), 0xffff_fffe);
+        eval(&mut st, "s_bfm_b32", vec![SReg(8), SReg(1), SReg(2)]);
+        assert_eq!(sgpr(&st, 8), 0xf00);
+        eval(&mut st, "s_bfm_b64", vec![SRegs(8, 9), SReg(3), SReg(2)]);
+        assert_eq!(sgprs(&st, 8), ((1 << 40) - 1) << 8);
+    }
+
+    #[test]
+    fn bit_counts_and_positions() {
+        let mut st = state([0xf0f0, 0xffff_ffff, 0, 1], [0x1_0000_0001, 0]);
+        assert_eq!(eval(&mut st, "s_bcnt1_i32_b32", vec![SReg(8), SReg(0)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 8);
+        assert_eq!(eval(&mut st, "s_bcnt0_i32_b32", vec![SReg(8), SReg(1)]), Some(false));
+        assert_eq!(sgpr(&st, 8), 0);
+        eval(&mut st, "s_bcnt1_i32_b64", vec![SReg(8), SRegs(4, 5)]);
+        assert_eq!(sgpr(&st, 8), 2);
+        eval(&mut st, "s_brev_b32", vec![SReg(8), SReg(3)]);
+        assert_eq!(sgpr(&st, 8), 0x8000_0000);
+        eval(&mut st, "s_ff1_i32_b32", vec![SReg(8), SReg(0)]);
+        assert_eq!(sgpr(&st, 8), 4);
+        eval(&mut st, "s_ff1_i32_b32", vec![SReg(8), SReg(2)]);
+        assert_eq!(sgpr(&st, 8), 0xffff_ffff);
+    }
+
+    #[test]
+    fn bitfield_extract_register_field() {
+        let mut st = state([0x8765_4321, 8 << 16 | 4, 0, 0], [0, 0]);
+        assert_eq!(eval(&mut st, "s_bfe_u32", vec![SReg(8), SReg(0), SReg(1)]), Some(true));
+        assert_eq!(sgpr(&st, 8), 0x32);
+    }
+
+    #[test]
+    fn unknown_scc_and_unsupported_ops_are_errors() {
+        let mut st = state([0, 0, 0, 0], [0, 0]);
+        st.scc = None;
+        for &(instr, ref ops) in [("s_cselect_b32", vec![SReg(8), SReg(0), SReg(1)]), ("s_addc_u32", vec![SReg(8), SReg(0), SReg(1)]),
+                                  ("s_cmov_b32", vec![SReg(8), SReg(0)]), ("s_wqm_b32", vec![SReg(8), SReg(0)])].iter() {
+            let result = eval_salu_op(&mut st, &semantics::lookup(instr).unwrap(), instr, ops);
+            assert!(result.unwrap_err().to_string().contains(instr));
+        }
+    }
+
+    /* Runs the vector instruction, writing v[0:1], and returns the qword */
+    fn eval_vector_qword(st: &mut ExecState, lo: (&str, Vec<Operand>), hi: (&str, Vec<Operand>)) -> io::Result<u64> {
+        for (dst, &(instr, ref srcs)) in [lo, hi].iter().enumerate() {
+            let ops: Vec<Operand> = vec![VReg(dst), VCC].into_iter().chain(srcs.iter().cloned()).collect();
+            eval_valu_op(st, &semantics::lookup(instr).unwrap(), instr, &ops)?;
+        }
+        let dword = |idx: usize| {
+            let Reg(value, dword) = st.vgprs[idx];
+            (eval_value(st, value) >> (32 * dword as u32)) as u32 as u64
+        };
+        Ok(dword(0) | dword(1) << 32)
+    }
+
+    #[test]
+    fn sub_qword_halves() {
+        let mut st = state([0, 1, 1, 0], [0x1_0000_0000, 1]);
+        /* Halves of unrelated registers */
+        let difference = eval_vector_qword(&mut st, ("v_sub_co_u32_e32", vec![SReg(0), SReg(2)]), ("v_subb_co_u32_e32", vec![SReg(1), SReg(3), VCC]));
+        assert_eq!(difference.unwrap(), 0xffff_ffff);
+        let difference = eval_vector_qword(&mut st, ("v_subrev_co_u32_e32", vec![SReg(2), SReg(0)]), ("v_subbrev_co_u32_e32", vec![SReg(3), SReg(1), VCC]));
+        assert_eq!(difference.unwrap(), 0xffff_ffff);
+        /* Qword registers */
+        let difference = eval_vector_qword(&mut st, ("v_sub_co_u32_e32", vec![SReg(4), SReg(6)]), ("v_subb_co_u32_e32", vec![SReg(5), SReg(7), VCC]));
+        assert_eq!(difference.unwrap(), 0xffff_ffff);
+        let difference = eval_vector_qword(&mut st, ("v_subrev_co_u32_e32", vec![SReg(6), SReg(4)]), ("v_subbrev_co_u32_e32", vec![SReg(7), SReg(5), VCC]));
+        assert_eq!(difference.unwrap(), 0xffff_ffff);
+    }
+
+    #[test]
+    fn carry_without_low_half_is_an_error() {
+        let mut st = state([0, 1, 1, 0], [0, 0]);
+        let sum = eval_vector_qword(&mut st, ("v_sub_co_u32_e32", vec![SReg(0), SReg(2)]), ("v_addc_co_u32_e32", vec![SReg(1), SReg(3), VCC]));
+        assert!(sum.unwrap_err().to_string().contains("v_addc_co_u32_e32"));
+    }
+
+    #[test]
+    fn dispatch_by_semantics() {
+        let mut st = state([1, 2, 3, 4], [0x1000, 0]);
+        let mut pgm = Vec::new();
+        let mut eval_instr = |st: &mut ExecState, instr: &str, ops: Vec<Operand>| eval_gcn_instruction(st, &mut pgm, 0, instr, &ops);
+
+        eval_instr(&mut st, "s_load_dwordx2", vec![SRegs(8, 9), SRegs(4, 5), Lit(16)]).unwrap();
+        assert!(matches!(st.values[st.sgprs[8].0], Value::Deref { ptr, offset: 16, kind: DataKind::Qword } if ptr == st.sgprs[4].0));
+        assert_eq!(st.sgprs[9], Reg(st.sgprs[8].0, 1));
+        assert!(eval_instr(&mut st, "s_load_dwordx8", vec![SRegs(8, 15), SRegs(4, 5), Lit(0)]).is_err());
+
+        /* Compares are recognized in their VOP3 encodings as well */
+        eval_instr(&mut st, "v_cmp_lt_u32_e64", vec![SRegs(10, 11), SReg(0), SReg(1)]).unwrap();
+        assert!(matches!(st.values[st.sgprs[10].0], Value::LaneMask { .. }));
+        eval_instr(&mut st, "s_cmpk_lt_u32", vec![SReg(1), Lit(3)]).unwrap();
+        assert_eq!(st.scc.map(|cond| condition(&st, cond)), Some(true));
+        assert!(eval_instr(&mut st, "s_bitcmp1_b32", vec![SReg(0), Lit(0)]).is_err());
+
+        st.vgprs[0] = st.sgprs[4];
+        st.vgprs[1] = st.sgprs[5];
+        st.vgprs[2] = st.sgprs[0];
+        eval_instr(&mut st, "global_store_dword", vec![VRegs(0, 1), VReg(2), Off]).unwrap();
+        assert!(eval_instr(&mut st, "global_store_dwordx2", vec![VRegs(0, 1), VRegs(2, 3), Off]).is_err());
+        assert!(matches!(pgm.as_slice(), [(1, Statement::Store { kind: DataKind::Dword, .. })]));
+    }
+
+    #[test]
+    fn cndmask_selects_on_each_mask_term() {
+        for &(s0, s2) in [(1, 0), (1, 1), (0, 0), (0, 1)].iter() {
+            let mut st = state([s0, 1, s2, 1], [0, 0]);
+            let mut pgm = Vec::new();
+            for (instr, ops) in vec![
+                ("v_cmp_eq_u32_e64", vec![SRegs(8, 9), SReg(0), SReg(1)]),
+                ("v_cmp_eq_u32_e64", vec![SRegs(10, 11), SReg(2), SReg(3)]),
+                ("s_andn2_b64", vec![VCC, SRegs(8, 9), SRegs(10, 11)]),
+                ("v_cndmask_b32_e32", vec![VReg(0), Lit(7), Lit(9), VCC])
+            ] {
+                eval_gcn_instruction(&mut st, &mut pgm, 0, instr, &ops).unwrap();
+            }
+            let Reg(result, _) = st.vgprs[0];
+            /* s0 == s1 && s2 != s3 */
+            assert_eq!(eval_value(&st, result), if s0 == 1 && s2 == 0 { 9 } else { 7 });
+            match st.values[result] {
+                Value::Computed { expr: Expr::Select { if_true, if_false, .. }, .. } => assert!([if_true, if_false].iter()
+                    .any(|&idx| matches!(st.values[idx], Value::Computed { expr: Expr::Select { .. }, .. }))),
+                ref other => panic!("Unexpected value {:?}", other)
+            }
+        }
+        let mut st = state([0, 0, 0, 0], [0, 0]);
+        let result = eval_gcn_instruction(&mut st, &mut Vec::new(), 0, "v_cndmask_b32_e64", &[VReg(0), Lit(7), Lit(9), SRegs(12, 13)]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn cmpx_narrows_exec() {
+        let mut st = state([0, 1, 2, 3], [0, 0]);
+        let mut pgm = Vec::new();
+        let (s0, s1, s2, s3) = (st.sgprs[0].0, st.sgprs[1].0, st.sgprs[2].0, st.sgprs[3].0);
+        eval_gcn_instruction(&mut st, &mut pgm, 0, "v_cmpx_lt_u32_e32", &[VCC, SReg(0), SReg(1)]).unwrap();
+        assert_eq!(st.exec.terms, vec![(Condition::LtU(s0, s1), true)]);
+        assert_eq!(st.vcc.as_ref().map(|vcc| vcc.terms.clone()), Some(st.exec.terms.clone()));
+
+        /* Masks computed under the narrowed EXEC only select on their own condition */
+        eval_gcn_instruction(&mut st, &mut pgm, 0, "v_cmp_eq_u32_e64", &[SRegs(8, 9), SReg(2), SReg(3)]).unwrap();
+        eval_gcn_instruction(&mut st, &mut pgm, 0, "v_cndmask_b32_e64", &[VReg(0), Lit(7), Lit(9), SRegs(8, 9)]).unwrap();
+        match st.values[st.vgprs[0].0] {
+            Value::Computed { expr: Expr::Select { cond: Condition::Eql(lhs, rhs), if_true, if_false }, .. } => {
+                assert_eq!((lhs, rhs), (s2, s3));
+                assert_eq!((eval_value(&st, if_true), eval_value(&st, if_false)), (9, 7));
+            },
+            ref other => panic!("Unexpected value {:?}", other)
+        }
+
+        /* The GFX10 form only writes EXEC */
+        eval_gcn_instruction(&mut st, &mut pgm, 0, "v_cmpx_ne_u32_e64", &[SReg(2), SReg(3)]).unwrap();
+        assert_eq!(st.exec.terms, vec![(Condition::LtU(s0, s1), true), (Condition::Ne(s2, s3), true)]);
     }
 }
@@ -0,0 +1,398 @@
+/* Floating-point VALU operations on f16, f32 and f64. Source modifiers (neg, abs) and
+ * output modifiers (omod, clamp) of VOP3 encodings become operations of their own.
+ * Division is expanded by the compiler into v_div_scale, a Newton-Raphson refinement of v_rcp,
+ * v_div_fmas and v_div_fixup, which handles special values and takes the numerator and the
+ * denominator again: the fixup becomes the division, and the steps before it are left unused. */
+
+use std::io;
+
+use crate::data_flow::{exec_state::ExecState, unsupported};
+use crate::data_flow::types::{Expr, MathFn, Value, ValueIdx, DataKind};
+use crate::asm::{Operand, Operand::*};
+use crate::asm::operand::OutputModifier;
+use crate::asm::semantics::{Semantics, DataType};
+use super::{operand_value_dw, operand_value_qw, set_vdst, constant, low_bits};
+
+pub fn eval_float_op(st: &mut ExecState, semantics: &Semantics, instr: &str, op: &str, ops: &[Operand]) {
+    let bits = match semantics.src_type {
+        Some(DataType::Float(bits)) => bits,
+        _ => panic!("Operation not supported: {:?}", (instr, ops))
+    };
+    let kind = float_kind(bits);
+    let computed = |st: &mut ExecState, expr: Expr| {
+        st.values.push(Value::Computed { expr, kind });
+        st.values.len() - 1
+    };
+
+    let dst = &ops[0];
+    let srcs: Vec<&Operand> = ops.iter().skip(semantics.defs)
+        .filter(|op| match op {
+            Clamp | Omod(_) => false,
+            OpSel(sel) if sel.iter().all(|&half| half == 0) => false,
+            OpSel(_) => panic!("High halves selected by op_sel are not supported: {:?}", (instr, ops)),
+            _ => true
+        })
+        .collect();
+
+    let result = match (op, srcs.as_slice()) {
+        ("v_add", [lhs, rhs]) | ("v_sub", [lhs, rhs]) | ("v_subrev", [lhs, rhs]) | ("v_mul", [lhs, rhs])
+        | ("v_min", [lhs, rhs]) | ("v_max", [lhs, rhs]) => {
+            let (lhs, rhs) = (float_source(st, lhs, bits), float_source(st, rhs, bits));
+            computed(st, match op {
+                "v_add" => Expr::Add(lhs, rhs),
+                "v_sub" => Expr::Sub(lhs, rhs),
+                "v_subrev" => Expr::Sub(rhs, lhs),
+                "v_mul" => Expr::Mul(lhs, rhs),
+                "v_min" => Expr::FMin(lhs, rhs),
+                _ => Expr::FMax(lhs, rhs)
+            })
+        },
+        /* The operand is scaled only when the quotient would lose precision, VCC tells v_div_fmas to undo it */
+        ("v_div_scale", [op1, _, _]) => {
+            if ops[1] == VCC {
+                st.vcc = None;
+            }
+            float_source(st, op1, bits)
+        },
+        ("v_div_fixup", [_, denominator, numerator]) => {
+            let (numerator, denominator) = (float_source(st, numerator, bits), float_source(st, denominator, bits));
+            computed(st, Expr::Div(numerator, denominator))
+        },
+        ("v_fma", [op1, op2, op3]) | ("v_div_fmas", [op1, op2, op3]) => {
+            let (op1, op2, op3) = (float_source(st, op1, bits), float_source(st, op2, bits), float_source(st, op3, bits));
+            computed(st, Expr::Fma(op1, op2, op3))
+        },
+        ("v_mad", [op1, op2, op3]) => {
+            let (op1, op2, op3) = (float_source(st, op1, bits), float_source(st, op2, bits), float_source(st, op3, bits));
+            let product = computed(st, Expr::Mul(op1, op2));
+            computed(st, Expr::Add(product, op3))
+        },
+        /* The destination is the accumulator */
+        ("v_mac", [op1, op2]) | ("v_fmac", [op1, op2]) => {
+            let (op1, op2, acc) = (float_source(st, op1, bits), float_source(st, op2, bits), float_source(st, dst, bits));
+            if op == "v_fmac" {
+                computed(st, Expr::Fma(op1, op2, acc))
+            }
+            else {
+                let product = computed(st, Expr::Mul(op1, op2));
+                computed(st, Expr::Add(acc, product))
+            }
+        },
+        ("v_ldexp", [val, exp]) => {
+            let val = float_source(st, val, bits);
+            let exp = operand_value_dw(st, exp, "i32");
+            computed(st, Expr::Ldexp(val, exp))
+        },
+        /* The exponent is an integer */
+        ("v_frexp_exp", [val]) => {
+            let val = float_source(st, val, bits);
+            st.values.push(Value::Computed { expr: Expr::Math(MathFn::FrexpExp, val), kind: DataKind::Dword });
+            st.values.len() - 1
+        },
+        (_, [val]) => {
+            let func = match op {
+                "v_rcp" => MathFn::Rcp,
+                "v_rsq" => MathFn::Rsq,
+                "v_sqrt" => MathFn::Sqrt,
+                "v_exp" => MathFn::Exp2,
+                "v_log" => MathFn::Log2,
+                "v_sin" => MathFn::Sin,
+                "v_cos" => MathFn::Cos,
+                "v_floor" => MathFn::Floor,
+                "v_ceil" => MathFn::Ceil,
+                "v_trunc" => MathFn::Trunc,
+                "v_rndne" => MathFn::Rint,
+                "v_fract" => MathFn::Fract,
+                "v_frexp_mant" => MathFn::FrexpMant,
+                _ => panic!("Operation not supported: {:?}", (instr, ops))
+            };
+            let val = float_source(st, val, bits);
+            computed(st, Expr::Math(func, val))
+        },
+        _ => panic!("Operation not supported: {:?}", (instr, ops))
+    };
+
+    let result = output_modifiers(st, ops, result, kind);
+    set_vdst(st, dst, result);
+}
+
+/* v_cvt_<dst type>_<src type>, v_cvt_flr and v_cvt_rpi round to integers toward -inf and to the
+ * nearest one, v_cvt_f32_ubyte<n> converts byte n of a dword */
+pub fn eval_conversion(st: &mut ExecState, semantics: &Semantics, name: &str, ops: &[Operand]) -> io::Result<()> {
+    let not_supported = || unsupported(format!("Operation not supported: {} {:?}", name, ops));
+    /* Fixed-point, normalized and packed conversions */
+    if ["v_cvt_off_", "v_cvt_norm_", "v_cvt_pk"].iter().any(|prefix| name.starts_with(prefix)) {
+        return Err(not_supported());
+    }
+    let dst = &ops[0];
+    let src = match ops[semantics.defs..].iter().filter(|op| !matches!(op, Clamp | Omod(_))).collect::<Vec<_>>().as_slice() {
+        [src] => *src,
+        _ => return Err(not_supported())
+    };
+    let converted = |st: &mut ExecState, src: ValueIdx, unsigned: bool, kind: DataKind| {
+        let expr = if unsigned { Expr::ConvertU(src) } else { Expr::Convert(src) };
+        st.values.push(Value::Computed { expr, kind });
+        st.values.len() - 1
+    };
+    let byte = name.strip_prefix("v_cvt_f32_ubyte").and_then(|byte| byte.parse::<u32>().ok());
+
+    let result = match (byte, semantics.dst_type, semantics.src_type) {
+        (Some(byte), _, _) => {
+            let src = operand_value_dw(st, src, "u32");
+            let (shift, mask) = (constant(st, byte * 8), constant(st, 0xff));
+            st.values.push(Value::Computed { expr: Expr::Shr(src, shift), kind: DataKind::Dword });
+            st.values.push(Value::Computed { expr: Expr::And(st.values.len() - 1, mask), kind: DataKind::Dword });
+            let byte = st.values.len() - 1;
+            converted(st, byte, true, DataKind::F32)
+        },
+        (None, Some(DataType::Float(bits)), Some(DataType::Float(src_bits))) => {
+            let src = float_source(st, src, src_bits);
+            converted(st, src, false, float_kind(bits))
+        },
+        (None, Some(DataType::Float(bits)), Some(DataType::Int(src_bits))) | (None, Some(DataType::Float(bits)), Some(DataType::Uint(src_bits))) => {
+            let unsigned = matches!(semantics.src_type, Some(DataType::Uint(_)));
+            let src = operand_value_dw(st, src, if unsigned { "u32" } else { "i32" });
+            let src = low_bits(st, src, src_bits, !unsigned);
+            converted(st, src, unsigned, float_kind(bits))
+        },
+        (None, Some(DataType::Int(bits)), Some(DataType::Float(src_bits))) | (None, Some(DataType::Uint(bits)), Some(DataType::Float(src_bits))) => {
+            let unsigned = matches!(semantics.dst_type, Some(DataType::Uint(_)));
+            let mut src = float_source(st, src, src_bits);
+            let kind = float_kind(src_bits);
+            if name.starts_with("v_cvt_rpi_") {
+                st.values.push(if src_bits == 64 { Value::F64(0.5) } else { Value::F32(0.5) });
+                st.values.push(Value::Computed { expr: Expr::Add(src, st.values.len() - 1), kind });
+                src = st.values.len() - 1;
+            }
+            if name.starts_with("v_cvt_rpi_") || name.starts_with("v_cvt_flr_") {
+                st.values.push(Value::Computed { expr: Expr::Math(MathFn::Floor, src), kind });
+                src = st.values.len() - 1;
+            }
+            converted(st, src, unsigned, if bits == 64 { DataKind::I64 } else { DataKind::Dword })
+        },
+        _ => return Err(not_supported())
+    };
+
+    let result = match semantics.dst_type {
+        Some(DataType::Float(bits)) => output_modifiers(st, ops, result, float_kind(bits)),
+        _ => result
+    };
+    set_vdst(st, dst, result);
+    Ok(())
+}
+
+fn float_kind(bits: u16) -> DataKind {
+    match bits {
+        16 => DataKind::F16,
+        64 => DataKind::F64,
+        _ => DataKind::F32
+    }
+}
+
+/* Literals are bit patterns of the operand type. Integer inline constants are too, sign-extended
+ * to 64 bits for f64 operations, while other f64 literals hold the high dword */
+pub fn float_source(st: &mut ExecState, op: &Operand, bits: u16) -> ValueIdx {
+    let value = match (op, bits) {
+        (Neg(inner), _) | (Abs(inner), _) => {
+            let func = match op { Neg(_) => MathFn::Neg, _ => MathFn::Abs };
+            let val = float_source(st, inner, bits);
+            Value::Computed { expr: Expr::Math(func, val), kind: float_kind(bits) }
+        },
+        (&Lit(imm), 16) => Value::F32(f16_to_f32(imm as u16)),
+        (&Lit(imm), 64) if (-16..=64).contains(&imm) => Value::F64(f64::from_bits(imm as i64 as u64)),
+        (&Lit(imm), 64) => Value::F64(f64::from_bits((imm as u32 as u64) << 32)),
+        (&Lit(imm), _) => Value::F32(f32::from_bits(imm as u32)),
+        (_, 64) => {
+            let value = operand_value_qw(st, op, "u32");
+            return float_load(st, value, bits);
+        },
+        _ => {
+            let value = operand_value_dw(st, op, "u32");
+            return float_load(st, value, bits);
+        }
+    };
+    st.values.push(value);
+    st.values.len() - 1
+}
+
+/* Loads of the operand width read by a float operation are typed as floats, so that kernel
+ * arguments and buffer elements are declared with the type they are used as */
+fn float_load(st: &mut ExecState, value: ValueIdx, bits: u16) -> ValueIdx {
+    let untyped = |kind: DataKind| matches!((kind, bits), (DataKind::U16, 16) | (DataKind::Dword, 32) | (DataKind::Qword, 64));
+    match st.values[value] {
+        Value::Deref { ref mut kind, .. } | Value::LocalLoad { ref mut kind, .. } if untyped(*kind) => *kind = float_kind(bits),
+        _ => ()
+    }
+    value
+}
+
+/* The result is multiplied by omod and then clamped to [0, 1] */
+fn output_modifiers(st: &mut ExecState, ops: &[Operand], result: ValueIdx, kind: DataKind) -> ValueIdx {
+    let mut result = result;
+    let omod = ops.iter().filter_map(|op| match op { Omod(omod) => Some(*omod), _ => None }).next();
+    if let Some(omod) = omod {
+        let factor = match omod {
+            OutputModifier::Mul2 => 2.0,
+            OutputModifier::Mul4 => 4.0,
+            OutputModifier::Div2 => 0.5
+        };
+        st.values.push(match kind {
+            DataKind::F64 => Value::F64(factor),
+            _ => Value::F32(factor as f32)
+        });
+        st.values.push(Value::Computed { expr: Expr::Mul(result, st.values.len() - 1), kind });
+        result = st.values.len() - 1;
+    }
+    if ops.contains(&Clamp) {
+        st.values.push(Value::Computed { expr: Expr::Math(MathFn::Saturate, result), kind });
+        result = st.values.len() - 1;
+    }
+    result
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let (exp, mant) = (((half >> 10) & 0x1f) as i32, (half & 0x3ff) as f32);
+    sign * match exp {
+        0 => mant * 2f32.powi(-24),
+        0x1f if mant == 0.0 => std::f32::INFINITY,
+        0x1f => std::f32::NAN,
+        _ => (1.0 + mant / 1024.0) * 2f32.powi(exp - 15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data_flow::{Program, exec_state::ExecState, ops::eval_gcn_instruction};
+    use crate::data_flow::types::{Reg, Expr, MathFn, Statement, Value, ValueIdx, DataKind};
+    use crate::asm::{Operand, Operand::*};
+    use crate::asm::operand::OutputModifier;
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+
+    /* s0 holds a kernel argument, v[1:2] a buffer address, v3 and v4 buffer elements and v[5:6] a qword element */
+    fn state() -> ExecState {
+        let mut st = ExecState::new(KernelCode::default(), Target::from_processor("gfx900").unwrap());
+        let first = st.values.len();
+        st.values.push(Value::Deref { ptr: 0, offset: 16, kind: DataKind::Dword });
+        st.values.push(Value::Deref { ptr: 0, offset: 0, kind: DataKind::Qword });
+        st.values.push(Value::Deref { ptr: first + 1, offset: 0, kind: DataKind::Dword });
+        st.values.push(Value::Deref { ptr: first + 1, offset: 4, kind: DataKind::Dword });
+        st.values.push(Value::Deref { ptr: first + 1, offset: 8, kind: DataKind::Qword });
+        st.sgprs = vec![Reg(first, 0)];
+        st.vgprs = vec![st.vgprs[0], Reg(first + 1, 0), Reg(first + 1, 1), Reg(first + 2, 0), Reg(first + 3, 0), Reg(first + 4, 0), Reg(first + 4, 1)];
+        st
+    }
+
+    fn eval(st: &mut ExecState, pgm: &mut Program, instr: &str, ops: Vec<Operand>) {
+        eval_gcn_instruction(st, pgm, 0, instr, &ops).unwrap();
+    }
+
+    fn kind(st: &ExecState, value: ValueIdx) -> DataKind {
+        match st.values[value] {
+            Value::Deref { kind, .. } | Value::Computed { kind, .. } => kind,
+            ref other => panic!("Unexpected value {:?}", other)
+        }
+    }
+
+    #[test]
+    fn float_operands_and_stores() {
+        let (mut st, mut pgm) = (state(), Program::new());
+        let (arg, element, other) = (st.sgprs[0].0, st.vgprs[3].0, st.vgprs[4].0);
+        eval(&mut st, &mut pgm, "v_mul_f32_e32", vec![VReg(7), SReg(0), VReg(3)]);
+        eval(&mut st, &mut pgm, "global_store_dword", vec![VRegs(1, 2), VReg(7), Off]);
+        eval(&mut st, &mut pgm, "v_add_u32_e32", vec![VReg(8), VReg(4), VReg(4)]);
+        eval(&mut st, &mut pgm, "global_store_dword", vec![VRegs(1, 2), VReg(8), Off]);
+
+        assert!(matches!(kind(&st, arg), DataKind::F32));
+        assert!(matches!(kind(&st, element), DataKind::F32));
+        assert!(matches!(kind(&st, other), DataKind::Dword));
+        let kinds: Vec<DataKind> = pgm.iter().map(|stmt| match stmt {
+            (_, Statement::Store { kind, .. }) => *kind,
+            other => panic!("Unexpected statement {:?}", other)
+        }).collect();
+        assert!(matches!(kinds.as_slice(), [DataKind::F32, DataKind::Dword]));
+    }
+
+    #[test]
+    fn output_modifier_width() {
+        let (mut st, mut pgm) = (state(), Program::new());
+        let qword = st.vgprs[5].0;
+        eval(&mut st, &mut pgm, "v_add_f64", vec![VRegs(7, 8), VRegs(5, 6), VRegs(5, 6), Omod(OutputModifier::Mul2)]);
+        assert!(matches!(kind(&st, qword), DataKind::F64));
+        match st.values[st.vgprs[7].0] {
+            Value::Computed { expr: Expr::Mul(_, factor), kind: DataKind::F64 } =>
+                assert!(matches!(st.values[factor], Value::F64(factor) if factor == 2.0)),
+            ref other => panic!("Unexpected value {:?}", other)
+        }
+
+        eval(&mut st, &mut pgm, "v_add_f32_e64", vec![VReg(9), VReg(3), VReg(3), Omod(OutputModifier::Div2)]);
+        match st.values[st.vgprs[9].0] {
+            Value::Computed { expr: Expr::Mul(_, factor), kind: DataKind::F32 } =>
+                assert!(matches!(st.values[factor], Value::F32(factor) if factor == 0.5)),
+            ref other => panic!("Unexpected value {:?}", other)
+        }
+    }
+
+    #[test]
+    fn division_sequence() {
+        let (mut st, mut pgm) = (state(), Program::new());
+        let (numerator, denominator) = (st.vgprs[3].0, st.vgprs[4].0);
+        let neg = |reg| Neg(Box::new(VReg(reg)));
+        for (instr, ops) in vec![
+            ("v_div_scale_f32", vec![VReg(5), SRegs(8, 9), VReg(4), VReg(4), VReg(3)]),
+            ("v_div_scale_f32", vec![VReg(6), VCC, VReg(3), VReg(4), VReg(3)]),
+            ("v_rcp_f32_e32", vec![VReg(7), VReg(5)]),
+            ("s_setreg_imm32_b32", vec![Lit(0x904), Lit(3)]),
+            ("v_fma_f32", vec![VReg(8), neg(5), VReg(7), Float(1.0)]),
+            ("v_fma_f32", vec![VReg(7), VReg(8), VReg(7), VReg(7)]),
+            ("v_mul_f32_e32", vec![VReg(8), VReg(6), VReg(7)]),
+            ("v_fma_f32", vec![VReg(9), neg(5), VReg(8), VReg(6)]),
+            ("v_fma_f32", vec![VReg(8), VReg(9), VReg(7), VReg(8)]),
+            ("v_fma_f32", vec![VReg(5), neg(5), VReg(8), VReg(6)]),
+            ("s_setreg_imm32_b32", vec![Lit(0x904), Lit(0)]),
+            ("v_div_fmas_f32", vec![VReg(5), VReg(5), VReg(7), VReg(8)]),
+            ("v_div_fixup_f32", vec![VReg(10), VReg(5), VReg(4), VReg(3)])
+        ] {
+            eval(&mut st, &mut pgm, instr, ops);
+        }
+
+        match st.values[st.vgprs[10].0] {
+            Value::Computed { expr: Expr::Div(lhs, rhs), kind: DataKind::F32 } => assert_eq!((lhs, rhs), (numerator, denominator)),
+            ref other => panic!("Unexpected value {:?}", other)
+        }
+        assert!(matches!(kind(&st, numerator), DataKind::F32));
+        assert!(matches!(kind(&st, denominator), DataKind::F32));
+        assert!(st.vcc.is_none());
+    }
+
+    #[test]
+    fn conversions() {
+        let (mut st, mut pgm) = (state(), Program::new());
+        let (float_element, int_element) = (st.vgprs[3].0, st.vgprs[4].0);
+        let converted = |st: &ExecState, reg: usize| match st.values[st.vgprs[reg].0] {
+            Value::Computed { expr: Expr::Convert(src), kind } => (false, src, kind),
+            Value::Computed { expr: Expr::ConvertU(src), kind } => (true, src, kind),
+            ref other => panic!("Unexpected value {:?}", other)
+        };
+
+        eval(&mut st, &mut pgm, "v_cvt_i32_f32_e32", vec![VReg(7), VReg(3)]);
+        assert!(matches!(converted(&st, 7), (false, src, DataKind::Dword) if src == float_element));
+        assert!(matches!(kind(&st, float_element), DataKind::F32));
+        eval(&mut st, &mut pgm, "v_cvt_f32_u32_e32", vec![VReg(7), VReg(4)]);
+        assert!(matches!(converted(&st, 7), (true, src, DataKind::F32) if src == int_element));
+        assert!(matches!(kind(&st, int_element), DataKind::Dword));
+        eval(&mut st, &mut pgm, "v_cvt_f64_f32_e32", vec![VRegs(7, 8), VReg(3)]);
+        assert!(matches!(converted(&st, 7), (false, src, DataKind::F64) if src == float_element));
+        assert_eq!(st.vgprs[8], Reg(st.vgprs[7].0, 1));
+
+        eval(&mut st, &mut pgm, "v_cvt_flr_i32_f32_e32", vec![VReg(7), VReg(3)]);
+        let (_, floor, _) = converted(&st, 7);
+        assert!(matches!(st.values[floor], Value::Computed { expr: Expr::Math(MathFn::Floor, src), .. } if src == float_element));
+        eval(&mut st, &mut pgm, "v_cvt_f32_ubyte1_e32", vec![VReg(7), VReg(4)]);
+        let (_, byte, _) = converted(&st, 7);
+        assert!(matches!(st.values[byte], Value::Computed { expr: Expr::And(..), .. }));
+
+        assert!(eval_gcn_instruction(&mut st, &mut pgm, 0, "v_cvt_off_f32_i4_e32", &[VReg(7), VReg(4)]).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+/* LDS (ds_*) reads, writes and atomics on dwords, as accesses to the __local array at byte
+ * addresses. Writes and atomics are statements; out_of_ssa assigns a read to a variable when
+ * it is used past one of them or past a barrier */
+
+use std::io;
+
+use crate::data_flow::{Program, exec_state::ExecState, unsupported};
+use crate::data_flow::types::{Reg, Statement, Value, ValueIdx, DataKind, AtomicOp};
+use crate::asm::{Operand, Operand::*};
+use crate::asm::semantics::{Semantics, MemoryAccess};
+use super::{operand_value_dw, set_vdst};
+
+pub fn eval_ds_op(st: &mut ExecState, pgm: &mut Program, instr_idx: usize, semantics: &Semantics, instr: &str, ops: &[Operand]) -> io::Result<()> {
+    let not_supported = || unsupported(format!("Operation not supported: {} {:?}", instr, ops));
+    if ops.contains(&Gds) {
+        return Err(not_supported());
+    }
+    let regs: Vec<&Operand> = ops.iter().take_while(|op| matches!(op, VReg(_) | VRegs(_, _))).collect();
+    let offset = ops.iter().filter_map(|op| match op { Offset(offset) => Some(*offset), _ => None }).next().unwrap_or(0);
+    let offset0 = ops.iter().filter_map(|op| match op { Offset0(offset) => Some(*offset as i32), _ => None }).next().unwrap_or(0);
+    let offset1 = ops.iter().filter_map(|op| match op { Offset1(offset) => Some(*offset as i32), _ => None }).next().unwrap_or(0);
+
+    /* ds_read2st64_b64 -> (read2st64, b64) */
+    let (op, ty) = match (instr.find('_'), instr.rfind('_')) {
+        (Some(start), Some(end)) if start < end => (&instr[start + 1..end], &instr[end + 1..]),
+        _ => return Err(not_supported())
+    };
+    /* The offsets of read2 and write2 count elements, or 64 elements for st64 */
+    let stride = match ty {
+        "b64" => 8,
+        _ => 4
+    } * if op.ends_with("st64") { 64 } else { 1 };
+
+    match (semantics.memory, op, regs.as_slice()) {
+        (Some(MemoryAccess::Load(_)), "read", [dst, addr]) => {
+            let addr = operand_value_dw(st, addr, "u32");
+            let kind = match ty {
+                "u8" => DataKind::U8,
+                "i8" => DataKind::I8,
+                "u16" => DataKind::U16,
+                "i16" => DataKind::I16,
+                "b32" => DataKind::Dword,
+                "b64" => DataKind::Qword,
+                "b96" | "b128" => {
+                    /* Wider reads are split into dwords */
+                    let first = first_vreg(dst);
+                    for dword in 0..dwords(dst) {
+                        let value = load(st, addr, offset + dword as i32 * 4, DataKind::Dword);
+                        set_vdst(st, &VReg(first + dword), value);
+                    }
+                    return Ok(());
+                },
+                _ => return Err(not_supported())
+            };
+            let value = load(st, addr, offset, kind);
+            set_vdst(st, dst, value);
+        },
+        (Some(MemoryAccess::Load(_)), "read2", [dst, addr]) | (Some(MemoryAccess::Load(_)), "read2st64", [dst, addr]) => {
+            let addr = operand_value_dw(st, addr, "u32");
+            let (kind, element_dwords) = match ty {
+                "b64" => (DataKind::Qword, 2),
+                _ => (DataKind::Dword, 1)
+            };
+            let first = first_vreg(dst);
+            for (i, &element_offset) in [offset0, offset1].iter().enumerate() {
+                let value = load(st, addr, element_offset * stride, kind);
+                let element_dst = match element_dwords {
+                    1 => VReg(first + i),
+                    _ => VRegs(first + 2 * i, first + 2 * i + 1)
+                };
+                set_vdst(st, &element_dst, value);
+            }
+        },
+        (Some(MemoryAccess::Store(_)), "write", [addr, data]) => {
+            let addr = operand_value_dw(st, addr, "u32");
+            let kind = match ty {
+                "b8" => DataKind::U8,
+                "b16" => DataKind::U16,
+                "b32" => DataKind::Dword,
+                "b64" | "b96" | "b128" => {
+                    store_dwords(st, pgm, instr_idx, addr, offset, data);
+                    return Ok(());
+                },
+                _ => return Err(not_supported())
+            };
+            let data = operand_value_dw(st, data, "u32");
+            pgm.push((instr_idx, Statement::LocalStore { addr, offset, data, kind }));
+        },
+        (Some(MemoryAccess::Store(_)), "write2", [addr, data0, data1]) | (Some(MemoryAccess::Store(_)), "write2st64", [addr, data0, data1]) => {
+            let addr = operand_value_dw(st, addr, "u32");
+            store_dwords(st, pgm, instr_idx, addr, offset0 * stride, data0);
+            store_dwords(st, pgm, instr_idx, addr, offset1 * stride, data1);
+        },
+        (Some(MemoryAccess::Atomic(_)), _, _) => {
+            let (dst, operands) = regs.split_at(semantics.defs);
+            let atomic_op = match (op.trim_end_matches("_rtn"), ty) {
+                ("add", "u32") => AtomicOp::Add,
+                ("sub", "u32") => AtomicOp::Sub,
+                ("min", "i32") => AtomicOp::Min,
+                ("max", "i32") => AtomicOp::Max,
+                ("min", "u32") => AtomicOp::MinU,
+                ("max", "u32") => AtomicOp::MaxU,
+                ("and", "b32") => AtomicOp::And,
+                ("or", "b32") => AtomicOp::Or,
+                ("xor", "b32") => AtomicOp::Xor,
+                ("wrxchg", "b32") => AtomicOp::Xchg,
+                ("cmpst", "b32") => AtomicOp::CmpXchg,
+                _ => return Err(not_supported())
+            };
+            let value = match operands {
+                /* ds_cmpst compares with the first data operand and stores the second */
+                [addr, cmp, data] if atomic_op == AtomicOp::CmpXchg => {
+                    let (addr, cmp, data) = (operand_value_dw(st, addr, "u32"), operand_value_dw(st, cmp, "u32"), operand_value_dw(st, data, "u32"));
+                    Value::LocalAtomic { op: atomic_op, addr, offset, data, cmp: Some(cmp) }
+                },
+                [addr, data] if atomic_op != AtomicOp::CmpXchg => {
+                    let (addr, data) = (operand_value_dw(st, addr, "u32"), operand_value_dw(st, data, "u32"));
+                    Value::LocalAtomic { op: atomic_op, addr, offset, data, cmp: None }
+                },
+                _ => return Err(not_supported())
+            };
+            st.values.push(value);
+            let value = st.values.len() - 1;
+            pgm.push((instr_idx, Statement::LocalAtomic { value }));
+            if let [dst] = dst {
+                set_vdst(st, dst, value);
+            }
+        },
+        _ => return Err(not_supported())
+    }
+    Ok(())
+}
+
+fn load(st: &mut ExecState, addr: ValueIdx, offset: i32, kind: DataKind) -> ValueIdx {
+    st.values.push(Value::LocalLoad { addr, offset, kind });
+    st.values.len() - 1
+}
+
+/* A 64-bit value held in the registers is stored at once, other registers dword by dword */
+fn store_dwords(st: &mut ExecState, pgm: &mut Program, instr_idx: usize, addr: ValueIdx, offset: i32, data: &Operand) {
+    let first = first_vreg(data);
+    let count = dwords(data);
+    if count == 2 {
+        if let (Reg(value, 0), Reg(value_hi, 1)) = (st.vgprs[first], st.vgprs[first + 1]) {
+            let is_qword = match st.values[value] {
+                Value::Computed { kind, .. } | Value::Deref { kind, .. } | Value::LocalLoad { kind, .. } | Value::Cast { kind, .. } =>
+                    matches!(kind, DataKind::Qword | DataKind::I64 | DataKind::F64),
+                _ => false
+            };
+            if value == value_hi && is_qword {
+                pgm.push((instr_idx, Statement::LocalStore { addr, offset, data: value, kind: DataKind::Qword }));
+                return;
+            }
+        }
+    }
+    for dword in 0..count {
+        let data = operand_value_dw(st, &VReg(first + dword), "u32");
+        pgm.push((instr_idx, Statement::LocalStore { addr, offset: offset + dword as i32 * 4, data, kind: DataKind::Dword }));
+    }
+}
+
+fn first_vreg(op: &Operand) -> usize {
+    match *op {
+        VReg(idx) | VRegs(idx, _) => idx,
+        _ => panic!("Unsupported LDS data operand {:?}", op)
+    }
+}
+
+fn dwords(op: &Operand) -> usize {
+    match *op {
+        VRegs(lo, hi) => hi - lo + 1,
+        _ => 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::eval_ds_op;
+
+    use crate::data_flow::{Program, exec_state::ExecState};
+    use crate::data_flow::types::{Reg, Statement, Value, DataKind};
+    use crate::asm::{Operand, Operand::*};
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+    use crate::asm::semantics;
+
+    /* v0 holds the work-item id, used as the address; v[2:3] a qword and v4, v5 dwords */
+    fn state() -> ExecState {
+        let mut st = ExecState::new(KernelCode::default(), Target::from_processor("gfx900").unwrap());
+        st.values.push(Value::Deref { ptr: 0, offset: 0, kind: DataKind::Qword });
+        let qword = st.values.len() - 1;
+        st.values.push(Value::U32(4));
+        st.values.push(Value::U32(5));
+        st.vgprs = vec![st.vgprs[0], Reg(std::usize::MAX, 0), Reg(qword, 0), Reg(qword, 1), Reg(qword + 1, 0), Reg(qword + 2, 0)];
+        st
+    }
+
+    fn eval(st: &mut ExecState, instr: &str, ops: Vec<Operand>) -> Program {
+        let mut pgm = Program::new();
+        eval_ds_op(st, &mut pgm, 0, &semantics::lookup(instr).unwrap(), instr, &ops).unwrap();
+        pgm
+    }
+
+    /* Offsets and kinds of the loads held in consecutive registers from `first`, one per element */
+    fn loads(st: &ExecState, first: usize, count: usize, dwords: usize) -> Vec<(i32, usize)> {
+        (0..count).map(|i| {
+            let Reg(value, dword) = st.vgprs[first + i * dwords];
+            assert_eq!(dword, 0);
+            for hi in 1..dwords {
+                assert_eq!(st.vgprs[first + i * dwords + hi], Reg(value, hi as u8));
+            }
+            match st.values[value] {
+                Value::LocalLoad { offset, kind: DataKind::Dword, .. } => (offset, 1),
+                Value::LocalLoad { offset, kind: DataKind::Qword, .. } => (offset, 2),
+                ref other => panic!("Unexpected value {:?}", other)
+            }
+        }).collect()
+    }
+
+    fn stores(pgm: &Program) -> Vec<(i32, usize)> {
+        pgm.iter().map(|stmt| match stmt {
+            (_, Statement::LocalStore { offset, kind: DataKind::Dword, .. }) => (*offset, 1),
+            (_, Statement::LocalStore { offset, kind: DataKind::Qword, .. }) => (*offset, 2),
+            other => panic!("Unexpected statement {:?}", other)
+        }).collect()
+    }
+
+    #[test]
+    fn read2() {
+        let mut st = state();
+        eval(&mut st, "ds_read2_b32", vec![VRegs(6, 7), VReg(0), Offset0(1), Offset1(3)]);
+        assert_eq!(loads(&st, 6, 2, 1), vec![(4, 1), (12, 1)]);
+
+        eval(&mut st, "ds_read2st64_b32", vec![VRegs(6, 7), VReg(0), Offset0(1), Offset1(3)]);
+        assert_eq!(loads(&st, 6, 2, 1), vec![(256, 1), (768, 1)]);
+
+        eval(&mut st, "ds_read2_b64", vec![VRegs(6, 9), VReg(0), Offset0(1), Offset1(3)]);
+        assert_eq!(loads(&st, 6, 2, 2), vec![(8, 2), (24, 2)]);
+
+        eval(&mut st, "ds_read2st64_b64", vec![VRegs(6, 9), VReg(0), Offset0(1), Offset1(3)]);
+        assert_eq!(loads(&st, 6, 2, 2), vec![(512, 2), (1536, 2)]);
+    }
+
+    #[test]
+    fn write2() {
+        let mut st = state();
+        let pgm = eval(&mut st, "ds_write2_b32", vec![VReg(0), VReg(4), VReg(5), Offset0(1), Offset1(3)]);
+        assert_eq!(stores(&pgm), vec![(4, 1), (12, 1)]);
+
+        let pgm = eval(&mut st, "ds_write2st64_b32", vec![VReg(0), VReg(4), VReg(5), Offset0(1), Offset1(3)]);
+        assert_eq!(stores(&pgm), vec![(256, 1), (768, 1)]);
+
+        /* A qword value is stored at once, a pair of dwords one by one */
+        let pgm = eval(&mut st, "ds_write2_b64", vec![VReg(0), VRegs(2, 3), VRegs(4, 5), Offset0(1), Offset1(3)]);
+        assert_eq!(stores(&pgm), vec![(8, 2), (24, 1), (28, 1)]);
+
+        let pgm = eval(&mut st, "ds_write2st64_b64", vec![VReg(0), VRegs(2, 3), VRegs(4, 5), Offset0(1), Offset1(3)]);
+        assert_eq!(stores(&pgm), vec![(512, 2), (1536, 1), (1540, 1)]);
+    }
+
+    #[test]
+    fn unsupported_ops_are_errors() {
+        let mut st = state();
+        for (instr, ops) in vec![
+            ("ds_add_u32", vec![VReg(0), VReg(4), Gds]),
+            ("ds_add_f32", vec![VReg(0), VReg(4)]),
+            ("ds_add_u64", vec![VReg(0), VRegs(2, 3)]),
+            ("ds_wrxchg2_rtn_b32", vec![VRegs(6, 7), VReg(0), VReg(4), VReg(5), Offset0(0), Offset1(1)])
+        ] {
+            let result = eval_ds_op(&mut st, &mut Program::new(), 0, &semantics::lookup(instr).unwrap(), instr, &ops);
+            assert!(result.unwrap_err().to_string().contains(instr));
+        }
+    }
+}
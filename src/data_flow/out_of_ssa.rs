@@ -0,0 +1,603 @@
+/* Translation out of SSA form. Phis become variables, assigned by the parallel copies
+ * on their incoming edges. Other values stay inlined into the expressions that use
+ * them, unless a variable or LDS location they read may be written between their
+ * definition and a use: such values are saved to a variable where they are defined,
+ * as are the results of LDS atomics. Phis connected
+ * by a copy share a variable when neither is live where the other is assigned, so
+ * that most copies disappear. */
+
+use std::io;
+
+use crate::control_flow::{ControlFlowGraph, BlockIdx};
+use super::{value_span, value_dwords, variable_dwords};
+use super::exec_state::ExecState;
+use super::types::{Program, Statement, Value, ValueIdx, Phi, PhiIdx, Reg, Expr, Condition, Variable, DataKind};
+
+/* Statement position within a block */
+type Point = (BlockIdx, usize);
+
+/* Variables read when a value is evaluated */
+#[derive(Copy, Clone, PartialEq)]
+enum Storage {
+    /* The variable shared by a class of phis, identified by its representative */
+    Phis(PhiIdx),
+    /* The variable a value is saved to */
+    Saved(ValueIdx),
+    /* Written by LDS stores and atomics, and by other work-items before a barrier */
+    Lds
+}
+
+pub fn destruct(st: &mut ExecState, cfg: &ControlFlowGraph, blocks: Vec<Program>) -> io::Result<Vec<Program>> {
+    let mut ssa = Ssa::new(cfg, &st.values, &st.phis, &blocks);
+
+    let conservative_uses = ssa.uses(&vec![false; st.values.len()], true);
+    ssa.coalesce_phis(&conservative_uses);
+
+    /* An atomic is performed once, where it is defined */
+    let mut saved: Vec<bool> = (0..st.values.len())
+        .map(|value_idx| match st.values[value_idx] {
+            Value::LocalAtomic { .. } => !conservative_uses[value_idx].is_empty(),
+            _ => false
+        })
+        .collect();
+
+    /* Saving a value adds a write to the variable it is saved to, which can make
+     * other values stale */
+    let uses = loop {
+        let (uses, changed) = ssa.save_stale(&mut saved);
+        if !changed { break uses; }
+    };
+    ssa.coalesce_saved(&saved, &uses);
+
+    let Ssa { classes, saved_class, .. } = ssa;
+    emit(st, blocks, &classes, &saved_class, &saved)
+}
+
+struct Ssa<'a> {
+    cfg: &'a ControlFlowGraph,
+    values: &'a [Value],
+    phis: &'a [Phi],
+    defs: Vec<Option<Point>>,
+    /* (point, phi, input index) for every phi input */
+    copies: Vec<(Point, PhiIdx, usize)>,
+    direct_uses: Vec<Vec<Point>>,
+    /* LDS stores, atomics and barriers */
+    lds_writes: Vec<Point>,
+    users: Vec<Vec<ValueIdx>>,
+    phi_values: Vec<ValueIdx>,
+    /* Union-find parents of phis */
+    classes: Vec<PhiIdx>,
+    /* Saved values that share the variable of a phi class */
+    saved_class: Vec<Option<PhiIdx>>
+}
+
+impl<'a> Ssa<'a> {
+    fn new(cfg: &'a ControlFlowGraph, values: &'a [Value], phis: &'a [Phi], blocks: &[Program]) -> Self {
+        let mut defs: Vec<Option<Point>> = vec![None; values.len()];
+        let mut copies: Vec<(Point, PhiIdx, usize)> = Vec::new();
+        let mut direct_uses: Vec<Vec<Point>> = vec![Vec::new(); values.len()];
+        let mut lds_writes: Vec<Point> = Vec::new();
+
+        for (block_idx, body) in blocks.iter().enumerate() {
+            for (stmt_idx, (_, stmt)) in body.iter().enumerate() {
+                let point = (block_idx, stmt_idx);
+                let mut used: Vec<ValueIdx> = Vec::new();
+                match stmt {
+                    Statement::Define { values } => for value_idx in values.clone() { defs[value_idx] = Some(point); },
+                    Statement::ParallelCopy { inputs } => for &(phi_idx, input_idx) in inputs {
+                        copies.push((point, phi_idx, input_idx));
+                        used.extend(phis[phi_idx].inputs[input_idx].regs.iter().map(|reg| reg.0));
+                    },
+                    Statement::JumpIf { cond, .. } | Statement::JumpUnless { cond, .. } => used.extend(condition_operands(cond)),
+                    Statement::ExecMask { mask } => for (cond, _) in &mask.terms { used.extend(condition_operands(cond)); },
+                    Statement::Store { addr, data, .. } => used.extend(&[*addr, *data]),
+                    Statement::LocalStore { addr, data, .. } => {
+                        used.extend(&[*addr, *data]);
+                        lds_writes.push(point);
+                    },
+                    /* The atomic reads its operands, its result is used elsewhere */
+                    Statement::LocalAtomic { value } => {
+                        used.extend(operands(&values[*value]));
+                        lds_writes.push(point);
+                    },
+                    Statement::Barrier => lds_writes.push(point),
+                    Statement::VarDecl { .. } | Statement::VarAssignment { .. } => ()
+                }
+                for value_idx in used.into_iter().filter(|&idx| idx < values.len()) {
+                    direct_uses[value_idx].push(point);
+                }
+            }
+        }
+
+        let mut users: Vec<Vec<ValueIdx>> = vec![Vec::new(); values.len()];
+        let mut phi_values: Vec<ValueIdx> = vec![std::usize::MAX; phis.len()];
+        for (value_idx, value) in values.iter().enumerate() {
+            for operand in operands(value) { users[operand].push(value_idx); }
+            if let Value::Phi { idx } = *value { phi_values[idx] = value_idx; }
+        }
+
+        Ssa {
+            cfg, values, phis, defs, copies, direct_uses, lds_writes, users, phi_values,
+            classes: (0..phis.len()).collect(),
+            saved_class: vec![None; values.len()]
+        }
+    }
+
+    fn class(&self, mut phi_idx: PhiIdx) -> PhiIdx {
+        while self.classes[phi_idx] != phi_idx { phi_idx = self.classes[phi_idx]; }
+        phi_idx
+    }
+
+    /* Points where each value is evaluated: its own uses and those of the values that
+     * inline it. A saved value is evaluated where it is defined; `conservative` adds
+     * the uses of saved values too */
+    fn uses(&self, saved: &[bool], conservative: bool) -> Vec<Vec<Point>> {
+        let mut uses: Vec<Vec<Point>> = vec![Vec::new(); self.values.len()];
+        for value_idx in (0..self.values.len()).rev() {
+            uses[value_idx] = self.value_uses(value_idx, saved, conservative, &uses);
+        }
+        uses
+    }
+
+    /* Uses of the users of `value_idx` have to be known */
+    fn value_uses(&self, value_idx: ValueIdx, saved: &[bool], conservative: bool, uses: &[Vec<Point>]) -> Vec<Point> {
+        let mut points = self.direct_uses[value_idx].clone();
+        for &user in &self.users[value_idx] {
+            if saved[user] || conservative {
+                points.extend(self.defs[user]);
+            }
+            if !saved[user] || conservative {
+                points.extend_from_slice(&uses[user]);
+            }
+        }
+        points.sort();
+        points.dedup();
+        points
+    }
+
+    /* Whether `to` can be reached right after `from` without passing `def` */
+    fn reaches(&self, from: Point, to: Point, def: Option<Point>) -> bool {
+        if Some(to) == def {
+            return false;
+        }
+        let def_between = |block: BlockIdx, after: usize, before: usize| match def {
+            Some((def_block, def_idx)) => def_block == block && def_idx > after && def_idx < before,
+            None => false
+        };
+        if from.0 == to.0 && from.1 < to.1 {
+            return !def_between(from.0, from.1, to.1);
+        }
+        if def_between(from.0, from.1, std::usize::MAX) {
+            return false;
+        }
+        let mut visited = vec![false; self.cfg.blocks.len()];
+        let mut pending: Vec<BlockIdx> = self.cfg.blocks[from.0].successors.clone();
+        while let Some(block_idx) = pending.pop() {
+            if visited[block_idx] { continue; }
+            visited[block_idx] = true;
+            let def_in_block = def.map_or(false, |(def_block, _)| def_block == block_idx);
+            if block_idx == to.0 && (!def_in_block || def.unwrap().1 > to.1) {
+                return true;
+            }
+            if def_in_block { continue; }
+            pending.extend_from_slice(&self.cfg.blocks[block_idx].successors);
+        }
+        false
+    }
+
+    fn live_at(&self, value_idx: ValueIdx, uses: &[Point], at: Point) -> bool {
+        uses.iter().any(|&point| self.reaches(at, point, self.defs[value_idx]))
+    }
+
+    /* The value whose dwords the registers hold, in order and in full */
+    fn whole_value(&self, regs: &[Reg]) -> Option<ValueIdx> {
+        let value_idx = regs.first()?.0;
+        let whole = value_idx < self.values.len()
+            && regs.len() == value_dwords(&self.values[value_idx], self.phis)
+            && regs.iter().enumerate().all(|(dword, &reg)| reg == Reg(value_idx, dword as u8));
+        if whole { Some(value_idx) } else { None }
+    }
+
+    fn input_source(&self, phi_idx: PhiIdx, input_idx: usize) -> Option<ValueIdx> {
+        self.whole_value(&self.phis[phi_idx].inputs[input_idx].regs)
+    }
+
+    /* Whether a copy leaves the variable of `class` unchanged, given that it is also
+     * shared by `merged_with` */
+    fn is_identity(&self, phi_idx: PhiIdx, input_idx: usize, class: PhiIdx, merged_with: Option<PhiIdx>) -> bool {
+        let shares = |other: PhiIdx| other == class || Some(other) == merged_with;
+        match self.input_source(phi_idx, input_idx) {
+            Some(source) => match self.values[source] {
+                Value::Phi { idx } => shares(self.class(idx)),
+                _ => self.saved_class[source].map_or(false, shares)
+            },
+            None => false
+        }
+    }
+
+    /* Points where the variable of `class` is assigned */
+    fn class_writes(&self, class: PhiIdx, merged_with: Option<PhiIdx>) -> Vec<Point> {
+        let copies = self.copies.iter()
+            .filter(|&&(_, phi_idx, input_idx)| self.class(phi_idx) == class
+                && !self.is_identity(phi_idx, input_idx, class, merged_with))
+            .map(|&(point, _, _)| point);
+        let saved = (0..self.values.len())
+            .filter(|&value_idx| self.saved_class[value_idx] == Some(class))
+            .filter_map(|value_idx| self.defs[value_idx]);
+        copies.chain(saved).collect()
+    }
+
+    fn class_members(&self, class: PhiIdx) -> Vec<PhiIdx> {
+        (0..self.phis.len()).filter(|&phi_idx| self.class(phi_idx) == class).collect()
+    }
+
+    /* Phis of one class are live where the variable of the other is assigned */
+    fn interfere(&self, a: PhiIdx, b: PhiIdx, uses: &[Vec<Point>]) -> bool {
+        [(a, b), (b, a)].iter().any(|&(live, written)| {
+            let writes = self.class_writes(written, Some(live));
+            self.class_members(live).into_iter().any(|phi_idx| {
+                let value_idx = self.phi_values[phi_idx];
+                writes.iter().any(|&point| self.live_at(value_idx, &uses[value_idx], point))
+            })
+        })
+    }
+
+    /* Phis copied to one another in full share a variable when they do not interfere */
+    fn coalesce_phis(&mut self, uses: &[Vec<Point>]) {
+        for copy_idx in 0..self.copies.len() {
+            let (_, phi_idx, input_idx) = self.copies[copy_idx];
+            let source = match self.input_source(phi_idx, input_idx).map(|source| &self.values[source]) {
+                Some(&Value::Phi { idx }) if self.phis[idx].kind == self.phis[phi_idx].kind => idx,
+                _ => continue
+            };
+            let (class, source_class) = (self.class(phi_idx), self.class(source));
+            if class != source_class && !self.interfere(class, source_class, uses) {
+                self.classes[source_class] = class;
+            }
+        }
+    }
+
+    fn reads(&self, value_idx: ValueIdx, saved: &[bool], storage: &mut Vec<Storage>) {
+        if let Value::Phi { idx } = self.values[value_idx] {
+            storage.push(Storage::Phis(self.class(idx)));
+            return;
+        }
+        if let Value::LocalLoad { .. } = self.values[value_idx] {
+            storage.push(Storage::Lds);
+        }
+        for operand in operands(&self.values[value_idx]) {
+            if saved[operand] {
+                storage.push(Storage::Saved(operand));
+            }
+            else {
+                self.reads(operand, saved, storage);
+            }
+        }
+    }
+
+    /* Marks values that may read a reassigned variable where they are used. Returns
+     * the uses of all values and whether any value was marked */
+    fn save_stale(&self, saved: &mut [bool]) -> (Vec<Vec<Point>>, bool) {
+        let mut changed = false;
+        let mut uses: Vec<Vec<Point>> = vec![Vec::new(); self.values.len()];
+        for value_idx in (0..self.values.len()).rev() {
+            uses[value_idx] = self.value_uses(value_idx, saved, false, &uses);
+            if saved[value_idx] || uses[value_idx].is_empty() {
+                continue;
+            }
+            let mut storage: Vec<Storage> = Vec::new();
+            self.reads(value_idx, saved, &mut storage);
+            let stale = storage.iter().any(|&storage| {
+                let writes = match storage {
+                    Storage::Phis(class) => self.class_writes(class, None),
+                    Storage::Saved(saved_idx) => self.defs[saved_idx].into_iter().collect(),
+                    Storage::Lds => self.lds_writes.clone()
+                };
+                writes.into_iter().any(|point| self.live_at(value_idx, &uses[value_idx], point))
+            });
+            if stale {
+                saved[value_idx] = true;
+                changed = true;
+            }
+        }
+        (uses, changed)
+    }
+
+    /* A saved value copied to a phi in full is saved to the phi's variable when that
+     * does not clobber it, so that e.g. a loop counter is incremented in place */
+    fn coalesce_saved(&mut self, saved: &[bool], uses: &[Vec<Point>]) {
+        for copy_idx in 0..self.copies.len() {
+            let (_, phi_idx, input_idx) = self.copies[copy_idx];
+            let source = match self.input_source(phi_idx, input_idx) {
+                Some(source) if saved[source] && self.saved_class[source].is_none() => source,
+                _ => continue
+            };
+            let def = match self.defs[source] {
+                Some(def) => def,
+                None => continue
+            };
+            let same_size = match self.values[source] {
+                Value::Phi { .. } => false,
+                ref value => value_dwords(value, self.phis) == variable_dwords(self.phis[phi_idx].kind)
+            };
+            let class = self.class(phi_idx);
+            if !same_size || self.class_members(class).into_iter().any(|member| {
+                let value_idx = self.phi_values[member];
+                self.live_at(value_idx, &uses[value_idx], def)
+            }) {
+                continue;
+            }
+            self.saved_class[source] = Some(class);
+            let clobbered = self.class_writes(class, None).into_iter()
+                .filter(|&point| point != def)
+                .any(|point| self.live_at(source, &uses[source], point));
+            if clobbered {
+                self.saved_class[source] = None;
+            }
+        }
+    }
+}
+
+fn emit(st: &mut ExecState, blocks: Vec<Program>, classes: &[PhiIdx], saved_class: &[Option<PhiIdx>], saved: &[bool]) -> io::Result<Vec<Program>> {
+    let class = |mut phi_idx: PhiIdx| {
+        while classes[phi_idx] != phi_idx { phi_idx = classes[phi_idx]; }
+        phi_idx
+    };
+
+    let mut class_vars: Vec<usize> = vec![std::usize::MAX; classes.len()];
+    for phi_idx in 0..classes.len() {
+        let root = class(phi_idx);
+        if class_vars[root] == std::usize::MAX {
+            st.variables.push(st.phis[root].kind);
+            class_vars[root] = st.variables.len() - 1;
+        }
+        class_vars[phi_idx] = class_vars[root];
+    }
+
+    /* Saved values are replaced by their variable, the expression moves to a new value */
+    let mut saved_to: Vec<Option<(usize, ValueIdx)>> = vec![None; saved.len()];
+    for value_idx in 0..saved.len() {
+        let value = match st.values[value_idx] {
+            Value::Phi { idx } => Value::Variable { idx: class_vars[idx] },
+            value => value
+        };
+        if saved[value_idx] {
+            let var_idx = match saved_class[value_idx] {
+                Some(class) => class_vars[class],
+                None => {
+                    let kind = match st.values[value_idx] {
+                        Value::Phi { idx } => st.phis[idx].kind,
+                        ref value => match value_dwords(value, &st.phis) {
+                            1 => Variable::Dword,
+                            2 => Variable::Qword,
+                            _ => Variable::DQword
+                        }
+                    };
+                    st.variables.push(kind);
+                    st.variables.len() - 1
+                }
+            };
+            st.values.push(value);
+            saved_to[value_idx] = Some((var_idx, st.values.len() - 1));
+            st.values[value_idx] = Value::Variable { idx: var_idx };
+        }
+        else {
+            st.values[value_idx] = value;
+        }
+    }
+
+    let mut programs: Vec<Program> = Vec::with_capacity(blocks.len());
+    for body in blocks {
+        let mut program = Program::new();
+        for (instr_idx, stmt) in body {
+            match stmt {
+                Statement::Define { values } => for value_idx in values {
+                    if let Some((var_idx, expr_idx)) = saved_to[value_idx] {
+                        let dwords = variable_dwords(st.variables[var_idx]);
+                        let regs: Vec<Reg> = (0..dwords).map(|dword| Reg(expr_idx, dword as u8)).collect();
+                        let mut assignments: Vec<Statement> = Vec::new();
+                        create_assignments(&mut assignments, &regs, var_idx);
+                        program.extend(assignments.into_iter().map(|stmt| (instr_idx, stmt)));
+                    }
+                },
+                /* Performed by the assignment of its result */
+                Statement::LocalAtomic { value } if saved[value] => (),
+                Statement::ParallelCopy { inputs } => {
+                    let mut assignments: Vec<Statement> = Vec::new();
+                    for (phi_idx, input_idx) in inputs {
+                        create_assignments(&mut assignments, &st.phis[phi_idx].inputs[input_idx].regs, class_vars[phi_idx]);
+                    }
+                    /* Uninitialized registers and copies of a variable to itself */
+                    assignments.retain(|stmt| match *stmt {
+                        Statement::VarAssignment { value_idx, .. } if value_idx >= st.values.len() => false,
+                        Statement::VarAssignment { var_idx, value_idx, value_dword, var_dword, .. } => match st.values[value_idx] {
+                            Value::Variable { idx } => idx != var_idx || value_dword != var_dword,
+                            _ => true
+                        },
+                        _ => true
+                    });
+                    program.extend(sequentialize(assignments, st)?.into_iter().map(|stmt| (instr_idx, stmt)));
+                },
+                stmt => program.push((instr_idx, stmt))
+            }
+        }
+        programs.push(program);
+    }
+
+    if let Some(entry) = programs.first_mut() {
+        let mut declarations: Program = (0..st.variables.len())
+            .map(|var_idx| (0, Statement::VarDecl { var_idx }))
+            .collect();
+        declarations.append(entry);
+        *entry = declarations;
+    }
+    Ok(programs)
+}
+
+fn create_assignments(assignments: &mut Vec<Statement>, var_regs: &[Reg], var_idx: usize) {
+    let mut i = 0;
+    while i < var_regs.len() {
+        let span = value_span(&var_regs[i..]);
+
+        /* Spans of other lengths are assigned in qwords, the odd dword last */
+        let pieces: Vec<(usize, DataKind)> = match span {
+            1 => vec![(1, DataKind::Dword)],
+            2 => vec![(2, DataKind::Qword)],
+            4 => vec![(4, DataKind::DQword)],
+            n => (0..n / 2).map(|_| (2, DataKind::Qword)).chain((0..n % 2).map(|_| (1, DataKind::Dword))).collect()
+        };
+
+        for (assignment_dwords, assignment_kind) in pieces {
+            let Reg(value_idx, value_dword) = var_regs[i];
+            assignments.push(Statement::VarAssignment {
+                var_idx,
+                value_idx,
+                value_dword,
+                var_dword: i as u8,
+                kind: assignment_kind });
+
+            i += assignment_dwords;
+        }
+    }
+}
+
+/* The copies take effect at once: a variable is assigned only after the values that
+ * read it have been assigned. A cycle of reads is broken by moving one of the values
+ * into a temporary variable first */
+fn sequentialize(mut pending: Vec<Statement>, st: &mut ExecState) -> io::Result<Vec<Statement>> {
+    let mut ordered: Vec<Statement> = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let targets: Vec<usize> = pending.iter().map(assigned_variable).collect::<io::Result<_>>()?;
+        let reads: Vec<Vec<usize>> = pending.iter().map(|stmt| variables_read(stmt, &st.values)).collect();
+        let is_read_by_others = |i: usize| (0..pending.len())
+            .any(|j| targets[j] != targets[i] && reads[j].contains(&targets[i]));
+
+        match (0..pending.len()).find(|&i| !is_read_by_others(i)) {
+            Some(i) => ordered.push(pending.remove(i)),
+            None => {
+                let reader = (0..pending.len())
+                    .find(|&j| targets[j] != targets[0] && reads[j].contains(&targets[0]))
+                    .unwrap();
+                if let Statement::VarAssignment { ref mut value_idx, ref mut value_dword, kind, .. } = pending[reader] {
+                    st.variables.push(match kind {
+                        DataKind::Dword | DataKind::U8 | DataKind::I8 | DataKind::U16 | DataKind::I16
+                        | DataKind::F16 | DataKind::F32 => Variable::Dword,
+                        DataKind::Qword | DataKind::I64 | DataKind::F64 => Variable::Qword,
+                        DataKind::DQword => Variable::DQword
+                    });
+                    let temp_idx = st.variables.len() - 1;
+                    ordered.push(Statement::VarAssignment {
+                        var_idx: temp_idx, value_idx: *value_idx, value_dword: *value_dword, var_dword: 0, kind });
+                    st.values.push(Value::Variable { idx: temp_idx });
+                    *value_idx = st.values.len() - 1;
+                    *value_dword = 0;
+                }
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+fn assigned_variable(stmt: &Statement) -> io::Result<usize> {
+    match *stmt {
+        Statement::VarAssignment { var_idx, .. } => Ok(var_idx),
+        ref stmt => Err(io::Error::new(io::ErrorKind::Other, format!("Not a variable assignment: {:?}", stmt)))
+    }
+}
+
+fn variables_read(stmt: &Statement, values: &[Value]) -> Vec<usize> {
+    let mut variables: Vec<usize> = Vec::new();
+    if let Statement::VarAssignment { value_idx, .. } = *stmt {
+        value_variables(value_idx, values, &mut variables);
+    }
+    variables
+}
+
+fn value_variables(value_idx: ValueIdx, values: &[Value], variables: &mut Vec<usize>) {
+    match values.get(value_idx) {
+        Some(&Value::Variable { idx }) => variables.push(idx),
+        Some(value) => for operand in operands(value) { value_variables(operand, values, variables); },
+        None => ()
+    }
+}
+
+fn operands(value: &Value) -> Vec<ValueIdx> {
+    match *value {
+        Value::Computed { expr: Expr::Mul(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Add(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Sub(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::And(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Or(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Xor(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Shl(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Shr(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Ashr(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Min(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Max(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::MinU(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::MaxU(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::MulHi(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::MulHiU(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Mul24(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Mul24U(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::MulWide(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::MulWideU(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::FMin(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::FMax(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Ldexp(lhs, rhs), .. } |
+        Value::Computed { expr: Expr::Div(lhs, rhs), .. } => vec![lhs, rhs],
+        Value::Computed { expr: Expr::Not(op), .. } | Value::Computed { expr: Expr::Abs(op), .. }
+        | Value::Computed { expr: Expr::Math(_, op), .. } | Value::Computed { expr: Expr::Bits(_, op), .. }
+        | Value::Computed { expr: Expr::Convert(op), .. } | Value::Computed { expr: Expr::ConvertU(op), .. } => vec![op],
+        Value::Computed { expr: Expr::Fma(op1, op2, op3), .. } => vec![op1, op2, op3],
+        Value::Computed { expr: Expr::Select { cond, if_true, if_false }, .. } => {
+            let (lhs, rhs) = cond.operands();
+            vec![lhs, rhs, if_true, if_false]
+        },
+        Value::Computed { expr: Expr::AlignBit { hi, lo, shift }, .. } => vec![hi, lo, shift],
+        Value::Computed { expr: Expr::AddHiLo { hi_op1, hi_op2, lo_op1, lo_op2 }, .. } =>
+            vec![hi_op1, hi_op2, lo_op1, lo_op2],
+        Value::Deref { ptr, .. } => vec![ptr],
+        Value::LocalLoad { addr, .. } => vec![addr],
+        Value::LocalAtomic { addr, data, cmp, .. } => vec![addr, data].into_iter().chain(cmp).collect(),
+        Value::DwordElement { of, .. } | Value::QwordElement { of, .. } => vec![of],
+        Value::Cast { source, .. } => vec![source],
+        _ => Vec::new()
+    }
+}
+
+fn condition_operands(cond: &Condition) -> Vec<ValueIdx> {
+    let (lhs, rhs) = cond.operands();
+    vec![lhs, rhs]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* (var_dword, value_dword, dwords) of each assignment */
+    fn pieces(var_regs: &[Reg]) -> Vec<(u8, u8, DataKind)> {
+        let mut assignments: Vec<Statement> = Vec::new();
+        create_assignments(&mut assignments, var_regs, 0);
+        assignments.into_iter()
+            .map(|stmt| match stmt {
+                Statement::VarAssignment { var_dword, value_dword, kind, .. } => (var_dword, value_dword, kind),
+                stmt => panic!("Unexpected statement {:?}", stmt)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn uneven_spans_are_split() {
+        let three: Vec<Reg> = (0..3).map(|dw| Reg(5, dw)).chain(Some(Reg(6, 0))).collect();
+        assert_eq!(format!("{:?}", pieces(&three)), "[(0, 0, Qword), (2, 2, Dword), (3, 0, Dword)]");
+
+        let eight: Vec<Reg> = (0..8).map(|dw| Reg(5, dw)).collect();
+        assert_eq!(format!("{:?}", pieces(&eight)), "[(0, 0, Qword), (2, 2, Qword), (4, 4, Qword), (6, 6, Qword)]");
+    }
+
+    #[test]
+    fn copies_other_than_assignments_are_an_error() {
+        assert!(assigned_variable(&Statement::Barrier).is_err());
+    }
+}
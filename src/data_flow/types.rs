@@ -1,35 +1,73 @@
-pub type BindingIdx = usize;
+use std::ops::Range;
+
+use crate::control_flow::BlockIdx;
+use super::lane_mask::LaneMask;
+
+/* The program is in SSA form: every value is defined once, by the instruction
+ * that computes it or by a phi node where register contents from different
+ * paths meet. Registers refer to dwords of values. */
+pub type ValueIdx = usize;
+pub type PhiIdx = usize;
 pub type DwordIdx = u8;
 pub type AsmInstructionIdx = usize;
 
 pub type Program = Vec<(AsmInstructionIdx, Statement)>;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub struct Reg(pub BindingIdx, pub DwordIdx);
+pub struct Reg(pub ValueIdx, pub DwordIdx);
 
 #[derive(Debug, Copy, Clone)]
 pub enum DataKind {
-    Dword, Qword, DQword, U16, I64
+    Dword, Qword, DQword, U8, I8, U16, I16, I64, F16, F32, F64
 }
 
-#[derive(Debug, Copy, Clone)]
+/* Type of a phi node and of the variable it is turned into by out_of_ssa.
+ * Partial variables are not written in full on some of the paths */
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Variable {
     Dword, Qword, DQword, PartialQword, PartialDQword
 }
 
 #[derive(Debug, Copy, Clone)]
-pub enum Binding {
+pub enum Value {
     U32(u32),
     I32(i32),
-    Deref { ptr: BindingIdx, offset: i32, kind: DataKind },
+    F32(f32),
+    F64(f64),
+    Deref { ptr: ValueIdx, offset: i32, kind: DataKind },
+    /* LDS contents at the byte address `addr` + offset */
+    LocalLoad { addr: ValueIdx, offset: i32, kind: DataKind },
+    /* Dword previously held by the LDS location, see Statement::LocalAtomic */
+    LocalAtomic { op: AtomicOp, addr: ValueIdx, offset: i32, data: ValueIdx, cmp: Option<ValueIdx> },
     Computed { expr: Expr, kind: DataKind },
-    DwordElement { of: BindingIdx, dword: u8 },
-    QwordElement { of: BindingIdx, dword: u8 },
-    Cast { source: BindingIdx, kind: DataKind },
+    DwordElement { of: ValueIdx, dword: u8 },
+    QwordElement { of: ValueIdx, dword: u8 },
+    Cast { source: ValueIdx, kind: DataKind },
+    /* Index into ExecState::phis */
+    Phi { idx: PhiIdx },
+    /* Replaces phis and values that cannot be inlined where they are used, see out_of_ssa */
     Variable { idx: usize },
+    /* Index into ExecState::lane_masks */
+    LaneMask { idx: usize },
     InitState(BuiltIn)
 }
 
+/* Registers that hold different values on different paths (control flow joins,
+ * lanes switched off by EXEC) are merged into a phi. Each input is the register
+ * contents in `from`, copied at the matching Statement::ParallelCopy */
+#[derive(Debug, Clone)]
+pub struct Phi {
+    pub block: BlockIdx,
+    pub kind: Variable,
+    pub inputs: Vec<PhiInput>
+}
+
+#[derive(Debug, Clone)]
+pub struct PhiInput {
+    pub from: BlockIdx,
+    pub regs: Vec<Reg>
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum BuiltIn {
     PrivateSegmentBuffer,
@@ -53,25 +91,140 @@ pub enum BuiltIn {
 
 #[derive(Debug, Copy, Clone)]
 pub enum Expr {
-    Mul(BindingIdx, BindingIdx),
-    Add(BindingIdx, BindingIdx),
-    And(BindingIdx, BindingIdx),
-    Shl(BindingIdx, BindingIdx),
-    AddHiLo { hi_op1: BindingIdx, hi_op2: BindingIdx, lo_op1: BindingIdx, lo_op2: BindingIdx }
+    Mul(ValueIdx, ValueIdx),
+    Add(ValueIdx, ValueIdx),
+    Sub(ValueIdx, ValueIdx),
+    And(ValueIdx, ValueIdx),
+    Or(ValueIdx, ValueIdx),
+    Xor(ValueIdx, ValueIdx),
+    Not(ValueIdx),
+    Shl(ValueIdx, ValueIdx),
+    /* Logical shift */
+    Shr(ValueIdx, ValueIdx),
+    /* Arithmetic shift */
+    Ashr(ValueIdx, ValueIdx),
+    Min(ValueIdx, ValueIdx),
+    Max(ValueIdx, ValueIdx),
+    MinU(ValueIdx, ValueIdx),
+    MaxU(ValueIdx, ValueIdx),
+    Abs(ValueIdx),
+    /* High dword of the 64-bit product */
+    MulHi(ValueIdx, ValueIdx),
+    MulHiU(ValueIdx, ValueIdx),
+    /* Product of the low 24 bits of the operands */
+    Mul24(ValueIdx, ValueIdx),
+    Mul24U(ValueIdx, ValueIdx),
+    /* 64-bit product of dword operands */
+    MulWide(ValueIdx, ValueIdx),
+    MulWideU(ValueIdx, ValueIdx),
+    /* Dword extracted from {hi, lo} shifted right */
+    AlignBit { hi: ValueIdx, lo: ValueIdx, shift: ValueIdx },
+    /* Floating-point operations, Add, Sub and Mul are reused with a float kind */
+    Math(MathFn, ValueIdx),
+    Bits(BitFn, ValueIdx),
+    Div(ValueIdx, ValueIdx),
+    Fma(ValueIdx, ValueIdx, ValueIdx),
+    FMin(ValueIdx, ValueIdx),
+    FMax(ValueIdx, ValueIdx),
+    Ldexp(ValueIdx, ValueIdx),
+    Select { cond: Condition, if_true: ValueIdx, if_false: ValueIdx },
+    /* Conversion between integers and floats to the kind of the value, rounding toward zero and
+     * saturating to integers. ConvertU converts from or to an unsigned integer */
+    Convert(ValueIdx),
+    ConvertU(ValueIdx),
+    AddHiLo { hi_op1: ValueIdx, hi_op2: ValueIdx, lo_op1: ValueIdx, lo_op2: ValueIdx }
+}
+
+/* Floating-point functions of a single operand. Sin and Cos take the angle in revolutions,
+ * Saturate clamps to [0, 1] */
+#[derive(Debug, Copy, Clone)]
+pub enum MathFn {
+    Neg, Abs, Saturate,
+    Rcp, Rsq, Sqrt, Exp2, Log2, Sin, Cos,
+    Floor, Ceil, Trunc, Rint, Fract,
+    FrexpMant, FrexpExp
 }
 
-#[derive(Debug)]
+/* Integer functions of the bits of a single operand. TrailingZeros of zero is the operand width */
+#[derive(Debug, Copy, Clone)]
+pub enum BitFn {
+    Count, Reverse, TrailingZeros
+}
+
+/* Read-modify-write operations on dwords, signed unless suffixed with U */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AtomicOp {
+    Add, Sub, Min, Max, MinU, MaxU, And, Or, Xor, Xchg,
+    /* Stores the data if the location holds `cmp` */
+    CmpXchg
+}
+
+#[derive(Debug, Clone)]
 pub enum Statement {
     JumpIf { cond: Condition, label_idx: usize },
     JumpUnless { cond: Condition, label_idx: usize },
-    Store { addr: BindingIdx, data: BindingIdx, kind: DataKind },
-    Label { index: usize },
+    Store { addr: ValueIdx, data: ValueIdx, kind: DataKind },
+    LocalStore { addr: ValueIdx, offset: i32, data: ValueIdx, kind: DataKind },
+    /* Performs the Value::LocalAtomic, unless out_of_ssa assigns its result to a variable */
+    LocalAtomic { value: ValueIdx },
+    /* Waits for the work-group, LDS writes of other work-items are visible after it */
+    Barrier,
+    /* Values computed by an instruction, in order of execution */
+    Define { values: Range<ValueIdx> },
+    /* Phi inputs (phi, input index) taking effect at once */
+    ParallelCopy { inputs: Vec<(PhiIdx, usize)> },
+    /* Statements that follow are executed by the lanes in the mask only */
+    ExecMask { mask: LaneMask },
+    /* Replace Define and ParallelCopy after out_of_ssa */
     VarDecl { var_idx: usize },
-    VarAssignment { var_idx: usize, binding_idx: BindingIdx, binding_dword: u8, var_dword: u8, kind: DataKind },
+    VarAssignment { var_idx: usize, value_idx: ValueIdx, value_dword: u8, var_dword: u8, kind: DataKind }
 }
 
-#[derive(Debug, Copy, Clone)]
+/* Signed comparisons unless suffixed with U, F comparisons are ordered (false for NaN operands) */
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Condition {
-    Lt(BindingIdx, BindingIdx),
-    Eql(BindingIdx, BindingIdx)
+    Lt(ValueIdx, ValueIdx),
+    Le(ValueIdx, ValueIdx),
+    LtU(ValueIdx, ValueIdx),
+    LeU(ValueIdx, ValueIdx),
+    Eql(ValueIdx, ValueIdx),
+    Ne(ValueIdx, ValueIdx),
+    FLt(ValueIdx, ValueIdx),
+    FLe(ValueIdx, ValueIdx),
+    FEq(ValueIdx, ValueIdx),
+    /* Less or greater */
+    FLg(ValueIdx, ValueIdx),
+    /* Neither operand is NaN */
+    FOrd(ValueIdx, ValueIdx),
+    /* The float belongs to one of the classes in the v_cmp_class mask */
+    Class(ValueIdx, ValueIdx)
+}
+
+impl Condition {
+    pub fn operands(&self) -> (ValueIdx, ValueIdx) {
+        match *self {
+            Condition::Lt(lhs, rhs) | Condition::Le(lhs, rhs) | Condition::LtU(lhs, rhs)
+            | Condition::LeU(lhs, rhs) | Condition::Eql(lhs, rhs) | Condition::Ne(lhs, rhs)
+            | Condition::FLt(lhs, rhs) | Condition::FLe(lhs, rhs) | Condition::FEq(lhs, rhs)
+            | Condition::FLg(lhs, rhs) | Condition::FOrd(lhs, rhs) | Condition::Class(lhs, rhs) => (lhs, rhs)
+        }
+    }
+
+    /* The same comparison of other operands */
+    pub fn with_operands(&self, lhs: ValueIdx, rhs: ValueIdx) -> Condition {
+        match *self {
+            Condition::Lt(..) => Condition::Lt(lhs, rhs),
+            Condition::Le(..) => Condition::Le(lhs, rhs),
+            Condition::LtU(..) => Condition::LtU(lhs, rhs),
+            Condition::LeU(..) => Condition::LeU(lhs, rhs),
+            Condition::Eql(..) => Condition::Eql(lhs, rhs),
+            Condition::Ne(..) => Condition::Ne(lhs, rhs),
+            Condition::FLt(..) => Condition::FLt(lhs, rhs),
+            Condition::FLe(..) => Condition::FLe(lhs, rhs),
+            Condition::FEq(..) => Condition::FEq(lhs, rhs),
+            Condition::FLg(..) => Condition::FLg(lhs, rhs),
+            Condition::FOrd(..) => Condition::FOrd(lhs, rhs),
+            Condition::Class(..) => Condition::Class(lhs, rhs)
+        }
+    }
 }
@@ -0,0 +1,304 @@
+/* Annotated disassembly listing. Each instruction is printed with its byte offset
+ * and encoding, followed by what is known about its register operands before
+ * any analysis takes place: initial register roles, kernel arguments and
+ * dispatch packet fields loaded through the respective pointers. */
+
+use byteorder::{LE, ByteOrder};
+
+use crate::asm::{Instruction, Operand};
+use crate::asm::dialect::Dialect;
+use crate::asm::kernel_args::KernelArgs;
+use crate::asm::semantics::{self, MemoryAccess, AddressSpace};
+use crate::data_flow::exec_state::ExecState;
+use crate::data_flow::types::{Value, BuiltIn, Reg};
+
+/* https://llvm.org/docs/AMDGPUUsage.html#amdgpu-amdhsa-sgpr-register-set-up-order-table */
+const DISPATCH_PTR: &str = "dispatch_ptr";
+const KERNARG_SEGMENT_PTR: &str = "kernarg_segment_ptr";
+
+/* hsa_kernel_dispatch_packet_t fields as (byte offset, size, name)
+ * http://www.hsafoundation.com/html/Content/Runtime/Topics/02_Core/hsa_kernel_dispatch_packet_t.htm */
+const DISPATCH_PACKET_FIELDS: &[(u32, u32, &str)] = &[
+    (0, 2, "header"),
+    (2, 2, "setup"),
+    (4, 2, "workgroup_size_x"),
+    (6, 2, "workgroup_size_y"),
+    (8, 2, "workgroup_size_z"),
+    (10, 2, "reserved0"),
+    (12, 4, "grid_size_x"),
+    (16, 4, "grid_size_y"),
+    (20, 4, "grid_size_z"),
+    (24, 4, "private_segment_size"),
+    (28, 4, "group_segment_size"),
+    (32, 8, "kernel_object"),
+    (40, 8, "kernarg_address"),
+    (48, 8, "reserved2"),
+    (56, 8, "completion_signal")
+];
+
+/* A value held in a register, dword is the register's part of a multi-dword value */
+#[derive(Debug, Clone, PartialEq)]
+struct Role {
+    name: String,
+    dword: u8,
+    dwords: u8
+}
+
+impl Role {
+    fn describe(&self) -> String {
+        if self.dwords > 1 { format!("{}.dword[{}]", self.name, self.dword) } else { self.name.clone() }
+    }
+}
+
+/* Roles of registers that have not been overwritten since they were assigned */
+struct Roles {
+    sgprs: Vec<Option<Role>>,
+    vgprs: Vec<Option<Role>>
+}
+
+pub fn print_listing(code: &[u8], instructions: &[Instruction], dialect: &Dialect, state: &ExecState, args: &KernelArgs) {
+    let mut roles = Roles::new(state);
+    /* Annotations are derived from the GFX9 form, the listing shows instructions as decoded */
+    let normalized = dialect.normalize(instructions.to_vec());
+
+    for (instr, gfx9_instr) in instructions.iter().zip(normalized.iter()) {
+        let encoding = code.get(instr.offset..instr.offset + instr.size).map_or(String::new(), encoding);
+        let comment = roles.annotate(gfx9_instr, args);
+
+        let line = format!("{:06x}: {:<17}  {}", instr.offset, encoding, instr);
+        if comment.is_empty() {
+            println!("{}", line);
+        }
+        else {
+            println!("{:<72} ; {}", line, comment.join(", "));
+        }
+    }
+}
+
+fn encoding(raw: &[u8]) -> String {
+    raw.chunks(4)
+        .map(|word| match word.len() {
+            4 => format!("{:08x}", LE::read_u32(word)),
+            _ => word.iter().rev().map(|b| format!("{:02x}", b)).collect()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn builtin_name(builtin: BuiltIn) -> &'static str {
+    match builtin {
+        BuiltIn::PrivateSegmentBuffer => "private_segment_buffer",
+        BuiltIn::PtrDispatchPacket => DISPATCH_PTR,
+        BuiltIn::PtrQueue => "queue_ptr",
+        BuiltIn::PtrKernarg => KERNARG_SEGMENT_PTR,
+        BuiltIn::DispatchId => "dispatch_id",
+        BuiltIn::FlatScratchInit => "flat_scratch_init",
+        BuiltIn::WorkgroupCountX => "grid_workgroup_count_x",
+        BuiltIn::WorkgroupCountY => "grid_workgroup_count_y",
+        BuiltIn::WorkgroupCountZ => "grid_workgroup_count_z",
+        BuiltIn::WorkgroupIdX => "workgroup_id_x",
+        BuiltIn::WorkgroupIdY => "workgroup_id_y",
+        BuiltIn::WorkgroupIdZ => "workgroup_id_z",
+        BuiltIn::WorkgroupInfo => "workgroup_info",
+        BuiltIn::PrivateSegmentWavefrontOffset => "private_segment_wavefront_offset",
+        BuiltIn::WorkitemIdX => "workitem_id_x",
+        BuiltIn::WorkitemIdY => "workitem_id_y",
+        BuiltIn::WorkitemIdZ => "workitem_id_z"
+    }
+}
+
+/* (is_vgpr, first, last) register index range of a plain register operand */
+fn register_range(op: &Operand) -> Option<(bool, usize, usize)> {
+    match *op {
+        Operand::SReg(idx) => Some((false, idx, idx)),
+        Operand::SRegs(from, to) => Some((false, from, to)),
+        Operand::VReg(idx) => Some((true, idx, idx)),
+        Operand::VRegs(from, to) => Some((true, from, to)),
+        _ => None
+    }
+}
+
+fn kernarg_role(args: &KernelArgs, offset: u32) -> Option<Role> {
+    let (idx, dword) = args.find_idx_and_dword(offset)?;
+    let arg = &args[idx];
+    let dwords = std::cmp::max(1, (arg.size + 3) / 4) as u8;
+    if dword < dwords { Some(Role { name: arg.name.clone(), dword, dwords }) } else { None }
+}
+
+fn dispatch_packet_role(offset: u32) -> Option<Role> {
+    let fields: Vec<&(u32, u32, &str)> = DISPATCH_PACKET_FIELDS.iter()
+        .filter(|&&(field_offset, size, _)| field_offset < offset + 4 && offset < field_offset + size)
+        .collect();
+    match fields.as_slice() {
+        [] => None,
+        [&(field_offset, size, name)] if size >= 4 =>
+            Some(Role { name: format!("dispatch_packet.{}", name), dword: ((offset - field_offset) / 4) as u8, dwords: (size / 4) as u8 }),
+        /* Fields narrower than a dword are packed together */
+        _ => Some(Role {
+            name: format!("dispatch_packet.{}", fields.iter().map(|&&(_, _, name)| name).collect::<Vec<_>>().join("/")),
+            dword: 0, dwords: 1
+        })
+    }
+}
+
+impl Roles {
+    fn new(state: &ExecState) -> Self {
+        let role = |&Reg(value, dword): &Reg, regfile: &[Reg]| match state.values[value] {
+            Value::InitState(builtin) => Some(Role {
+                name: builtin_name(builtin).to_owned(),
+                dword,
+                dwords: regfile.iter().filter(|reg| reg.0 == value).count() as u8
+            }),
+            _ => None
+        };
+        Roles {
+            sgprs: state.sgprs.iter().map(|reg| role(reg, &state.sgprs)).collect(),
+            vgprs: state.vgprs.iter().map(|reg| role(reg, &state.vgprs)).collect()
+        }
+    }
+
+    fn slot(&mut self, vgpr: bool, idx: usize) -> &mut Option<Role> {
+        let regfile = if vgpr { &mut self.vgprs } else { &mut self.sgprs };
+        if regfile.len() <= idx {
+            regfile.resize(idx + 1, None);
+        }
+        &mut regfile[idx]
+    }
+
+    fn get(&self, vgpr: bool, idx: usize) -> Option<&Role> {
+        let regfile = if vgpr { &self.vgprs } else { &self.sgprs };
+        regfile.get(idx).and_then(Option::as_ref)
+    }
+
+    /* Name of the 64-bit pointer held in s[lo:lo+1] */
+    fn pointer(&self, lo: usize, hi: usize) -> Option<&str> {
+        match (self.get(false, lo), self.get(false, hi)) {
+            (Some(lo_role), Some(hi_role)) if hi == lo + 1 && lo_role.name == hi_role.name
+                && lo_role.dwords == 2 && lo_role.dword == 0 && hi_role.dword == 1 => Some(lo_role.name.as_str()),
+            _ => None
+        }
+    }
+
+    fn describe(&self, op: &Operand) -> Option<String> {
+        let (vgpr, from, to) = register_range(op)?;
+        let roles: Vec<Option<&Role>> = (from..=to).map(|idx| self.get(vgpr, idx)).collect();
+
+        let first = roles[0]?;
+        let whole_value = roles.len() == first.dwords as usize && roles.iter().enumerate()
+            .all(|(i, role)| role.map_or(false, |role| role.name == first.name && role.dword as usize == i));
+        if whole_value {
+            return Some(format!("{} = {}", op, first.name));
+        }
+
+        let prefix = if vgpr { "v" } else { "s" };
+        let parts: Vec<String> = roles.iter().zip(from..=to)
+            .filter_map(|(role, idx)| role.map(|role| format!("{}{} = {}", prefix, idx, role.describe())))
+            .collect();
+        if parts.is_empty() { None } else { Some(parts.join(", ")) }
+    }
+
+    fn assign(&mut self, op: &Operand, roles: Vec<Option<Role>>) {
+        if let Some((vgpr, from, to)) = register_range(op) {
+            let mut roles = roles.into_iter();
+            for idx in from..=to {
+                *self.slot(vgpr, idx) = roles.next().and_then(|role| role);
+            }
+        }
+    }
+
+    fn annotate(&mut self, instr: &Instruction, args: &KernelArgs) -> Vec<String> {
+        let ops = instr.operands.as_slice();
+        let semantics = match semantics::lookup(&instr.opcode) {
+            Some(semantics) => semantics,
+            None => {
+                /* Without semantics, assume the first operand is overwritten */
+                if let Some(dst) = ops.first() { self.assign(dst, Vec::new()); }
+                return Vec::new();
+            }
+        };
+
+        if let Some(MemoryAccess::Load(AddressSpace::Constant)) = semantics.memory {
+            if let [dst, Operand::SRegs(lo, hi), Operand::Lit(offset), ..] = ops {
+                let base: Option<fn(&KernelArgs, u32) -> Option<Role>> = match self.pointer(*lo, *hi) {
+                    Some(DISPATCH_PTR) => Some(|_, offset| dispatch_packet_role(offset)),
+                    Some(KERNARG_SEGMENT_PTR) => Some(kernarg_role),
+                    _ => None
+                };
+                if let (Some(role_at), Some((_, from, to))) = (base, register_range(dst)) {
+                    let loaded = (0..=(to - from) as u32).map(|i| role_at(args, *offset as u32 + i * 4)).collect();
+                    self.assign(dst, loaded);
+                    return self.describe(dst).into_iter().collect();
+                }
+            }
+        }
+
+        let mut comment: Vec<String> = Vec::new();
+        for op in semantics.uses(ops) {
+            if let Some(description) = self.describe(op) {
+                if !comment.contains(&description) { comment.push(description); }
+            }
+        }
+        for op in semantics.defs(ops) {
+            self.assign(op, Vec::new());
+        }
+        comment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::Operand::*;
+    use crate::asm::kernel_args::KernelArg;
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+
+    fn role(offset: u32) -> Option<(String, u8, u8)> {
+        dispatch_packet_role(offset).map(|Role { name, dword, dwords }| (name, dword, dwords))
+    }
+
+    #[test]
+    fn dispatch_packet_fields() {
+        assert_eq!(role(12), Some(("dispatch_packet.grid_size_x".to_string(), 0, 1)));
+        assert_eq!(role(40), Some(("dispatch_packet.kernarg_address".to_string(), 0, 2)));
+        assert_eq!(role(44), Some(("dispatch_packet.kernarg_address".to_string(), 1, 2)));
+        /* Sub-dword fields are loaded together */
+        assert_eq!(role(0), Some(("dispatch_packet.header/setup".to_string(), 0, 1)));
+        assert_eq!(role(4), Some(("dispatch_packet.workgroup_size_x/workgroup_size_y".to_string(), 0, 1)));
+        assert_eq!(role(8), Some(("dispatch_packet.workgroup_size_z/reserved0".to_string(), 0, 1)));
+        /* A dword that straddles two fields */
+        assert_eq!(role(30), Some(("dispatch_packet.group_segment_size/kernel_object".to_string(), 0, 1)));
+        assert_eq!(role(10), Some(("dispatch_packet.reserved0/grid_size_x".to_string(), 0, 1)));
+        assert_eq!(role(64), None);
+    }
+
+    #[test]
+    fn kernarg_loads() {
+        let mut kcode = KernelCode::default();
+        kcode.code_props.enable_sgpr_private_segment_buffer = true;
+        kcode.code_props.enable_sgpr_dispatch_ptr = true;
+        kcode.code_props.enable_sgpr_kernarg_segment_ptr = true;
+        let state = ExecState::new(kcode, Target::from_processor("gfx900").unwrap());
+        let args = KernelArgs::from(vec![
+            KernelArg { name: "out".to_string(), size: 8, offset: 0, ..Default::default() },
+            KernelArg { name: "n".to_string(), size: 4, offset: 8, ..Default::default() }
+        ]);
+        let mut roles = Roles::new(&state);
+        let mut annotate = |opcode: &str, operands| roles.annotate(&Instruction { opcode: opcode.to_string(), operands, offset: 0, size: 8 }, &args);
+
+        assert_eq!(annotate("s_load_dwordx2", vec![SRegs(10, 11), SRegs(6, 7), Lit(0)]), vec!["s[10:11] = out"]);
+        assert_eq!(annotate("s_load_dword", vec![SReg(12), SRegs(6, 7), Lit(8)]), vec!["s12 = n"]);
+        assert_eq!(annotate("s_load_dwordx4", vec![SRegs(16, 19), SRegs(6, 7), Lit(0)]),
+            vec!["s16 = out.dword[0], s17 = out.dword[1], s18 = n"]);
+        assert_eq!(annotate("s_load_dword", vec![SReg(13), SRegs(4, 5), Lit(4)]),
+            vec!["s13 = dispatch_packet.workgroup_size_x/workgroup_size_y"]);
+        /* The roles are reported where the registers are used, until they are overwritten */
+        assert_eq!(annotate("v_mov_b32_e32", vec![VReg(0), SReg(12)]), vec!["s12 = n"]);
+        assert_eq!(annotate("v_mov_b32_e32", vec![VReg(1), SReg(11)]), vec!["s11 = out.dword[1]"]);
+        assert_eq!(annotate("s_mov_b32", vec![SReg(12), Lit(0)]), Vec::<String>::new());
+        assert_eq!(annotate("v_mov_b32_e32", vec![VReg(0), SReg(12)]), Vec::<String>::new());
+        /* Loads through other pointers are not annotated */
+        assert_eq!(annotate("s_load_dword", vec![SReg(12), SRegs(10, 11), Lit(0)]), vec!["s[10:11] = out"]);
+        assert_eq!(annotate("v_mov_b32_e32", vec![VReg(0), SReg(12)]), Vec::<String>::new());
+    }
+}
@@ -1,130 +1,515 @@
-use std::collections::HashMap;
+mod structure;
 
 use crate::asm::kernel_args::KernelArgs;
-use crate::data_flow::types::{Program, Statement, Binding, BuiltIn, DataKind, Expr, Condition};
+use crate::control_flow::ControlFlowGraph;
+use crate::control_flow::dominators::DominatorTree;
+use crate::control_flow::loops::LoopForest;
+use crate::data_flow::types::{Program, Statement, Value, Variable, BuiltIn, DataKind, Expr, MathFn, BitFn, Condition, AtomicOp};
 use crate::data_flow::exec_state::ExecState;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum BoundExpr {
     Mul(Box<BoundExpr>, Box<BoundExpr>),
+    Div(Box<BoundExpr>, Box<BoundExpr>),
     Add(Box<BoundExpr>, Box<BoundExpr>),
+    Sub(Box<BoundExpr>, Box<BoundExpr>),
     And(Box<BoundExpr>, Box<BoundExpr>),
+    Or(Box<BoundExpr>, Box<BoundExpr>),
+    Xor(Box<BoundExpr>, Box<BoundExpr>),
+    Not(Box<BoundExpr>),
     Shl(Box<BoundExpr>, Box<BoundExpr>),
+    /* Arithmetic for signed operands, logical for Unsigned ones */
+    Shr(Box<BoundExpr>, Box<BoundExpr>),
+    Min(Box<BoundExpr>, Box<BoundExpr>),
+    Max(Box<BoundExpr>, Box<BoundExpr>),
+    Abs(Box<BoundExpr>),
+    MulHi(Box<BoundExpr>, Box<BoundExpr>),
+    Mul24(Box<BoundExpr>, Box<BoundExpr>),
+    /* 64-bit integer with the first operand in the upper half */
+    Upsample(Box<BoundExpr>, Box<BoundExpr>),
+    /* Arithmetic negation, Negate is the logical one */
+    Neg(Box<BoundExpr>),
+    /* Built-in function */
+    Call(&'static str, Vec<BoundExpr>),
+    Select { cond: Box<BoundExpr>, if_true: Box<BoundExpr>, if_false: Box<BoundExpr> },
     CompareLt(Box<BoundExpr>, Box<BoundExpr>),
+    CompareLe(Box<BoundExpr>, Box<BoundExpr>),
     CompareEql(Box<BoundExpr>, Box<BoundExpr>),
+    CompareNe(Box<BoundExpr>, Box<BoundExpr>),
     Negate(Box<BoundExpr>),
     Cast(Box<BoundExpr>, DataKind),
+    /* Reinterpreted as an unsigned integer of the given width */
+    Unsigned(Box<BoundExpr>, DataKind),
     I32(i32),
     U32(u32),
+    F32(f32),
+    F64(f64),
     InitState(BuiltIn),
     Deref { ptr: Box<BoundExpr>, offset: i32, kind: DataKind },
-    Variable { idx: usize, dword: u8 },
-    Placeholder
+    /* Element of the __local array at a byte address */
+    LocalDeref { addr: Box<BoundExpr>, offset: i32, kind: DataKind },
+    /* Returns the previous dword, the arguments are the data and, for CmpXchg, the compared value first */
+    LocalAtomic { op: AtomicOp, addr: Box<BoundExpr>, offset: i32, args: Vec<BoundExpr> },
+    /* Floating-point variables are accessed through the union members of their kind */
+    Variable { idx: usize, dword: u8, kind: DataKind }
 }
 
+/* Conditions a lane mask was narrowed by, see data_flow::lane_mask */
+pub type LaneTerms = Vec<(Condition, bool)>;
+
 #[derive(Debug)]
 pub enum ProgramStatement {
     Declaration { var_idx: usize },
-    Assignment { var_idx: usize, expr: BoundExpr },
-    JumpIf { label_idx: usize, cond: BoundExpr },
+    /* Assigns the union member of the given kind at `dword` */
+    Assignment { var_idx: usize, dword: u8, expr: BoundExpr, kind: DataKind },
+    Store { addr: usize, data: BoundExpr, kind: DataKind },
+    /* The __local array of the given size, declared first in the kernel */
+    LocalArray { bytes: u32 },
+    LocalStore { addr: BoundExpr, offset: i32, data: BoundExpr, kind: DataKind },
+    /* LDS atomic whose result is unused */
+    LocalAtomic(BoundExpr),
+    Barrier,
+    If { cond: BoundExpr, then_body: Vec<ProgramStatement>, else_body: Vec<ProgramStatement> },
+    While { cond: BoundExpr, body: Vec<ProgramStatement> },
+    DoWhile { body: Vec<ProgramStatement>, cond: BoundExpr },
+    For { init: Box<ProgramStatement>, cond: BoundExpr, step: Box<ProgramStatement>, body: Vec<ProgramStatement> },
+    /* Infinite loop, left through break or return */
+    Loop { body: Vec<ProgramStatement> },
+    Break,
+    Continue,
+    Return,
+    /* Irreducible control flow is expressed with gotos, labels are block indices */
     Label { label_idx: usize },
-    Store { addr: usize, data: BoundExpr, kind: DataKind }
+    Goto { label_idx: usize },
+    /* Executed by the lanes where all of the conditions hold. Consecutive divergent
+     * statements are nested into if statements once the control flow is structured */
+    Divergent { lanes: LaneTerms, stmt: Box<ProgramStatement> }
 }
 
-pub fn build(args: &KernelArgs, st: ExecState, program: Program) -> Vec<ProgramStatement> {
-    /* Binding index -> variable index */
-    let mut var_bindings: HashMap<usize, usize> = HashMap::new();
-
-    let mut stmts: Vec<ProgramStatement> = Vec::with_capacity(var_bindings.len());
-
-    for (_, stmt) in program {
-        match stmt {
-            Statement::VarAssignment { binding_idx, var_idx, .. } if binding_idx < std::usize::MAX => {
-                // TODO FIXME: handle binding_dword and data kind
-                println!("binding {:?} -> var {:?}", binding_idx, var_idx);
-                var_bindings.insert(binding_idx, var_idx);
-                let expr = reduce_binding_to_expr(binding_idx, &st.bindings, &var_bindings, args);
-                stmts.push(ProgramStatement::Assignment { var_idx, expr });
-            },
-            Statement::VarAssignment { .. } => (), // std::usize::max => uninitialized register state
-            Statement::VarDecl { var_idx } => {
-                stmts.push(ProgramStatement::Declaration { var_idx })
-            },
-            Statement::JumpIf { cond, label_idx } => {
-                let cond_expr = match cond {
-                    Condition::Lt(lhs, rhs) =>
-                        BoundExpr::CompareLt(box reduce_binding_to_expr(lhs, &st.bindings, &var_bindings, args),
-                                             box reduce_binding_to_expr(rhs, &st.bindings, &var_bindings, args)),
-                    Condition::Eql(lhs, rhs) =>
-                        BoundExpr::CompareEql(box reduce_binding_to_expr(lhs, &st.bindings, &var_bindings, args),
-                                              box reduce_binding_to_expr(rhs, &st.bindings, &var_bindings, args))
-                };
-                stmts.push(ProgramStatement::JumpIf { label_idx, cond: cond_expr });
-            },
-            Statement::JumpUnless { cond, label_idx } => {
-                let cond_expr = match cond {
-                    Condition::Lt(lhs, rhs) =>
-                        BoundExpr::CompareLt(box reduce_binding_to_expr(lhs, &st.bindings, &var_bindings, args),
-                                             box reduce_binding_to_expr(rhs, &st.bindings, &var_bindings, args)),
-                    Condition::Eql(lhs, rhs) =>
-                        BoundExpr::CompareEql(box reduce_binding_to_expr(lhs, &st.bindings, &var_bindings, args),
-                                              box reduce_binding_to_expr(rhs, &st.bindings, &var_bindings, args))
+pub fn build(args: &KernelArgs, st: ExecState, program: Vec<Program>, cfg: &ControlFlowGraph, loops: &LoopForest, post_dominators: &DominatorTree) -> Vec<ProgramStatement> {
+    let mut blocks: Vec<Vec<ProgramStatement>> = Vec::with_capacity(program.len());
+    /* Condition under which the taken edge of a conditional branch is followed */
+    let mut conditions: Vec<Option<BoundExpr>> = Vec::with_capacity(program.len());
+    let vars = variable_kinds(&program, &st.values, &st.variables);
+
+    for block in program {
+        let mut stmts: Vec<ProgramStatement> = Vec::new();
+        let mut exec: LaneTerms = Vec::new();
+        let mut branch_cond: Option<BoundExpr> = None;
+
+        for (_, stmt) in block {
+            let stmt = match stmt {
+                Statement::VarAssignment { var_idx, value_idx, value_dword, var_dword, kind } => {
+                    let expr = reduce_element_to_expr(value_idx, value_dword, kind, &st.values, &vars, args);
+                    /* Floating-point variables assigned in part are written through their integer members */
+                    let kind = if var_dword == 0 && dwords(kind) == dwords(vars[var_idx]) { vars[var_idx] } else { kind };
+                    ProgramStatement::Assignment { var_idx, dword: var_dword, expr, kind }
+                },
+                Statement::VarDecl { var_idx } => {
+                    ProgramStatement::Declaration { var_idx }
+                },
+                Statement::JumpIf { cond, .. } => {
+                    branch_cond = Some(reduce_condition(cond, &st.values, &vars, args));
+                    continue;
+                },
+                Statement::JumpUnless { cond, .. } => {
+                    branch_cond = Some(BoundExpr::Negate(box reduce_condition(cond, &st.values, &vars, args)));
+                    continue;
+                },
+                Statement::Store { addr, data, kind } => {
+                    ProgramStatement::Store { addr, kind, data: reduce_value_to_expr(data, &st.values, &vars, args) }
+                },
+                Statement::LocalStore { addr, offset, data, kind } => {
+                    let (addr, data) = (reduce_value_to_expr(addr, &st.values, &vars, args), reduce_value_to_expr(data, &st.values, &vars, args));
+                    ProgramStatement::LocalStore { addr, offset, data, kind }
+                },
+                Statement::LocalAtomic { value } => {
+                    ProgramStatement::LocalAtomic(reduce_value_to_expr(value, &st.values, &vars, args))
+                },
+                Statement::Barrier => ProgramStatement::Barrier,
+                Statement::ExecMask { mask } => {
+                    exec = mask.terms;
+                    continue;
+                },
+                Statement::Define { .. } | Statement::ParallelCopy { .. } =>
+                    panic!("The program is still in SSA form: {:?}", stmt)
+            };
+            stmts.push(if exec.is_empty() { stmt } else { ProgramStatement::Divergent { lanes: exec.clone(), stmt: box stmt } });
+        }
+
+        blocks.push(stmts);
+        conditions.push(branch_cond);
+    }
+
+    let tree = structure::structure(cfg, loops, post_dominators, blocks, conditions);
+    let lane_cond = |cond| reduce_condition(cond, &st.values, &vars, args);
+    let mut tree = nest_divergent(tree, &lane_cond);
+    if st.lds_size > 0 {
+        tree.insert(0, ProgramStatement::LocalArray { bytes: st.lds_size });
+    }
+    tree
+}
+
+/* Variables assigned floating-point values of their width in full hold floats, the rest
+ * integers. Copies of a float variable are floats as well */
+fn variable_kinds(program: &[Program], values: &[Value], variables: &[Variable]) -> Vec<DataKind> {
+    let is_float = |kind| matches!(kind, DataKind::F16 | DataKind::F32 | DataKind::F64);
+    let mut kinds: Vec<DataKind> = variables.iter()
+        .map(|variable| match variable {
+            Variable::Dword => DataKind::Dword,
+            Variable::Qword | Variable::PartialQword => DataKind::Qword,
+            Variable::DQword | Variable::PartialDQword => DataKind::DQword
+        })
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (_, stmt) in program.iter().flatten() {
+            if let Statement::VarAssignment { var_idx, value_idx, value_dword: 0, var_dword: 0, .. } = *stmt {
+                let kind = match values[value_idx] {
+                    Value::Computed { kind, .. } | Value::Cast { kind, .. } => kind,
+                    Value::F32(_) => DataKind::F32,
+                    Value::F64(_) => DataKind::F64,
+                    Value::Variable { idx } => kinds[idx],
+                    _ => DataKind::Dword
                 };
-                stmts.push(ProgramStatement::JumpIf { label_idx, cond: BoundExpr::Negate(box cond_expr) });
-            },
-            Statement::Label { index } => {
-                stmts.push(ProgramStatement::Label { label_idx: index })
-            },
-            Statement::Store { addr, data, kind } => {
-                stmts.push(ProgramStatement::Store { addr, kind, data: reduce_binding_to_expr(data, &st.bindings, &var_bindings, args) })
+                if is_float(kind) && !is_float(kinds[var_idx]) && dwords(kind) == dwords(kinds[var_idx]) {
+                    kinds[var_idx] = kind;
+                    changed = true;
+                }
             }
         }
     }
+    kinds
+}
+
+fn nest_divergent(stmts: Vec<ProgramStatement>, lane_cond: &dyn Fn(Condition) -> BoundExpr) -> Vec<ProgramStatement> {
+    use self::ProgramStatement::*;
 
-    stmts
+    let stmts = stmts.into_iter()
+        .map(|stmt| match stmt {
+            Divergent { lanes, stmt: box stmt } => (lanes, stmt),
+            If { cond, then_body, else_body } =>
+                (Vec::new(), If { cond, then_body: nest_divergent(then_body, lane_cond), else_body: nest_divergent(else_body, lane_cond) }),
+            While { cond, body } => (Vec::new(), While { cond, body: nest_divergent(body, lane_cond) }),
+            DoWhile { body, cond } => (Vec::new(), DoWhile { body: nest_divergent(body, lane_cond), cond }),
+            For { init, cond, step, body } => (Vec::new(), For { init, cond, step, body: nest_divergent(body, lane_cond) }),
+            Loop { body } => (Vec::new(), Loop { body: nest_divergent(body, lane_cond) }),
+            stmt => (Vec::new(), stmt)
+        })
+        .collect();
+    nest_lanes(stmts, 0, lane_cond)
 }
 
-fn reduce_binding_to_expr(idx: usize, bindings: &Vec<Binding>, vars: &HashMap<usize, usize>, args: &KernelArgs) -> BoundExpr {
-    match bindings[idx] {
-        Binding::Computed { expr, kind: _ } => {
+/* Statements executed under a narrowed EXEC mask are placed in if statements, one
+ * level per condition the mask was narrowed by. Statements executed by the lanes
+ * where the condition does not hold, if they follow, form the else branch. */
+fn nest_lanes(stmts: Vec<(LaneTerms, ProgramStatement)>, depth: usize, lane_cond: &dyn Fn(Condition) -> BoundExpr) -> Vec<ProgramStatement> {
+    let mut nested: Vec<ProgramStatement> = Vec::new();
+    let mut stmts = stmts.into_iter().peekable();
+
+    while let Some((exec, stmt)) = stmts.next() {
+        if exec.len() <= depth {
+            nested.push(stmt);
+            continue;
+        }
+        let (cond, holds) = exec[depth];
+        let mut then_stmts = vec![(exec, stmt)];
+        let mut else_stmts = Vec::new();
+        while let Some((exec, _)) = stmts.peek() {
+            match exec.get(depth) {
+                Some(&term) if term == (cond, holds) && else_stmts.is_empty() => then_stmts.push(stmts.next().unwrap()),
+                Some(&term) if term == (cond, !holds) => else_stmts.push(stmts.next().unwrap()),
+                _ => break
+            }
+        }
+        nested.push(ProgramStatement::If {
+            cond: if holds { lane_cond(cond) } else { BoundExpr::Negate(box lane_cond(cond)) },
+            then_body: nest_lanes(then_stmts, depth + 1, lane_cond),
+            else_body: nest_lanes(else_stmts, depth + 1, lane_cond)
+        });
+    }
+    nested
+}
+
+fn reduce_condition(cond: Condition, values: &Vec<Value>, vars: &[DataKind], args: &KernelArgs) -> BoundExpr {
+    let op = |idx| box reduce_value_to_expr(idx, values, vars, args);
+    let unsigned = |idx| box BoundExpr::Unsigned(op(idx), value_kind(idx, values));
+    match cond {
+        Condition::Lt(lhs, rhs) => BoundExpr::CompareLt(op(lhs), op(rhs)),
+        Condition::Le(lhs, rhs) => BoundExpr::CompareLe(op(lhs), op(rhs)),
+        Condition::LtU(lhs, rhs) => BoundExpr::CompareLt(unsigned(lhs), unsigned(rhs)),
+        Condition::LeU(lhs, rhs) => BoundExpr::CompareLe(unsigned(lhs), unsigned(rhs)),
+        Condition::Eql(lhs, rhs) => BoundExpr::CompareEql(op(lhs), op(rhs)),
+        Condition::Ne(lhs, rhs) => BoundExpr::CompareNe(op(lhs), op(rhs)),
+        /* C comparisons of floats are ordered */
+        Condition::FLt(lhs, rhs) => BoundExpr::CompareLt(op(lhs), op(rhs)),
+        Condition::FLe(lhs, rhs) => BoundExpr::CompareLe(op(lhs), op(rhs)),
+        Condition::FEq(lhs, rhs) => BoundExpr::CompareEql(op(lhs), op(rhs)),
+        Condition::FLg(lhs, rhs) => BoundExpr::Call("islessgreater", vec![*op(lhs), *op(rhs)]),
+        Condition::FOrd(lhs, rhs) => BoundExpr::Call("isordered", vec![*op(lhs), *op(rhs)]),
+        Condition::Class(val, mask) => {
+            /* Bits of the mask: signaling and quiet NaN, then -inf, -normal, -denormal, -0, +0, +denormal, +normal, +inf */
+            let func = match values[mask] {
+                Value::U32(0x3) | Value::I32(0x3) => "isnan",
+                Value::U32(0x204) | Value::I32(0x204) => "isinf",
+                Value::U32(0x1f8) | Value::I32(0x1f8) => "isfinite",
+                Value::U32(0x108) | Value::I32(0x108) => "isnormal",
+                _ => return BoundExpr::Call("__builtin_amdgcn_class", vec![*op(val), *op(mask)])
+            };
+            BoundExpr::Call(func, vec![*op(val)])
+        }
+    }
+}
+
+/* Width of a value for casts, untyped values are dwords */
+fn value_kind(idx: usize, values: &[Value]) -> DataKind {
+    match values[idx] {
+        Value::Computed { kind, .. } | Value::Deref { kind, .. } | Value::LocalLoad { kind, .. } | Value::Cast { kind, .. } => kind,
+        Value::QwordElement { .. } => DataKind::Qword,
+        Value::F64(_) => DataKind::F64,
+        _ => DataKind::Dword
+    }
+}
+
+fn dwords(kind: DataKind) -> usize {
+    match kind {
+        DataKind::Dword | DataKind::U8 | DataKind::I8 | DataKind::U16 | DataKind::I16
+        | DataKind::F16 | DataKind::F32 => 1,
+        DataKind::Qword | DataKind::I64 | DataKind::F64 => 2,
+        DataKind::DQword => 4
+    }
+}
+
+/* OpenCL conversion to a value of the kind, halves are kept in floats */
+fn convert_function(kind: DataKind, unsigned: bool) -> &'static str {
+    match (kind, unsigned) {
+        (DataKind::F16, _) | (DataKind::F32, _) => "convert_float",
+        (DataKind::F64, _) => "convert_double",
+        (DataKind::Qword, _) | (DataKind::I64, _) if unsigned => "convert_ulong_sat",
+        (DataKind::Qword, _) | (DataKind::I64, _) => "convert_long_sat",
+        (_, true) => "convert_uint_sat",
+        (_, false) => "convert_int_sat"
+    }
+}
+
+fn reduce_math(func: MathFn, val: BoundExpr) -> BoundExpr {
+    let call = |name, val| BoundExpr::Call(name, vec![val]);
+    /* Exponent and mantissa of frexp, for finite non-zero operands */
+    let frexp_exp = |val| BoundExpr::Add(box call("ilogb", val), box BoundExpr::I32(1));
+    match func {
+        MathFn::Neg => BoundExpr::Neg(box val),
+        MathFn::Abs => call("fabs", val),
+        MathFn::Saturate => BoundExpr::Call("clamp", vec![val, BoundExpr::F32(0.0), BoundExpr::F32(1.0)]),
+        MathFn::Rcp => call("native_recip", val),
+        MathFn::Rsq => call("rsqrt", val),
+        MathFn::Sqrt => call("sqrt", val),
+        MathFn::Exp2 => call("exp2", val),
+        MathFn::Log2 => call("log2", val),
+        MathFn::Sin => call("sinpi", BoundExpr::Mul(box BoundExpr::F32(2.0), box val)),
+        MathFn::Cos => call("cospi", BoundExpr::Mul(box BoundExpr::F32(2.0), box val)),
+        MathFn::Floor => call("floor", val),
+        MathFn::Ceil => call("ceil", val),
+        MathFn::Trunc => call("trunc", val),
+        MathFn::Rint => call("rint", val),
+        MathFn::Fract => {
+            let floor = call("floor", val.clone());
+            BoundExpr::Sub(box val, box floor)
+        },
+        MathFn::FrexpExp => frexp_exp(val),
+        MathFn::FrexpMant => {
+            let exp = frexp_exp(val.clone());
+            BoundExpr::Call("ldexp", vec![val, BoundExpr::Neg(box exp)])
+        }
+    }
+}
+
+#[allow(clippy::only_used_in_recursion)]
+fn reduce_value_to_expr(idx: usize, values: &Vec<Value>, vars: &[DataKind], args: &KernelArgs) -> BoundExpr {
+    let op = |idx| box reduce_value_to_expr(idx, values, vars, args);
+    let unsigned = |idx| box BoundExpr::Unsigned(op(idx), value_kind(idx, values));
+    match values[idx] {
+        Value::Computed { expr, kind } => {
             match expr {
-                Expr::Mul(lhs, rhs) => {
-                    BoundExpr::Mul(box reduce_binding_to_expr(lhs, bindings, vars, args),
-                                   box reduce_binding_to_expr(rhs, bindings, vars, args))
+                Expr::Mul(lhs, rhs) => BoundExpr::Mul(op(lhs), op(rhs)),
+                Expr::Add(lhs, rhs) => BoundExpr::Add(op(lhs), op(rhs)),
+                Expr::Sub(lhs, rhs) => BoundExpr::Sub(op(lhs), op(rhs)),
+                Expr::And(lhs, rhs) => BoundExpr::And(op(lhs), op(rhs)),
+                Expr::Or(lhs, rhs) => BoundExpr::Or(op(lhs), op(rhs)),
+                Expr::Xor(lhs, rhs) => BoundExpr::Xor(op(lhs), op(rhs)),
+                Expr::Not(val) => BoundExpr::Not(op(val)),
+                Expr::Shl(lhs, rhs) => BoundExpr::Shl(op(lhs), op(rhs)),
+                Expr::Shr(lhs, rhs) => BoundExpr::Shr(unsigned(lhs), op(rhs)),
+                Expr::Ashr(lhs, rhs) => BoundExpr::Shr(op(lhs), op(rhs)),
+                Expr::Min(lhs, rhs) => BoundExpr::Min(op(lhs), op(rhs)),
+                Expr::Max(lhs, rhs) => BoundExpr::Max(op(lhs), op(rhs)),
+                Expr::MinU(lhs, rhs) => BoundExpr::Min(unsigned(lhs), unsigned(rhs)),
+                Expr::MaxU(lhs, rhs) => BoundExpr::Max(unsigned(lhs), unsigned(rhs)),
+                Expr::Abs(val) => BoundExpr::Abs(op(val)),
+                Expr::MulHi(lhs, rhs) => BoundExpr::MulHi(op(lhs), op(rhs)),
+                Expr::MulHiU(lhs, rhs) => BoundExpr::MulHi(unsigned(lhs), unsigned(rhs)),
+                Expr::Mul24(lhs, rhs) => BoundExpr::Mul24(op(lhs), op(rhs)),
+                Expr::Mul24U(lhs, rhs) => BoundExpr::Mul24(unsigned(lhs), unsigned(rhs)),
+                Expr::MulWide(lhs, rhs) => BoundExpr::Mul(box BoundExpr::Cast(op(lhs), DataKind::I64), op(rhs)),
+                Expr::MulWideU(lhs, rhs) => BoundExpr::Mul(box BoundExpr::Unsigned(op(lhs), DataKind::Qword), unsigned(rhs)),
+                Expr::AlignBit { hi, lo, shift } => {
+                    let concat = box BoundExpr::Upsample(unsigned(hi), unsigned(lo));
+                    BoundExpr::Unsigned(box BoundExpr::Shr(concat, op(shift)), DataKind::Dword)
                 },
-                Expr::Add(lhs, rhs) => {
-                    BoundExpr::Add(box reduce_binding_to_expr(lhs, bindings, vars, args),
-                                   box reduce_binding_to_expr(rhs, bindings, vars, args))
+                Expr::Math(func, val) => reduce_math(func, *op(val)),
+                Expr::Bits(BitFn::Count, val) => BoundExpr::Call("popcount", vec![*unsigned(val)]),
+                Expr::Bits(BitFn::TrailingZeros, val) => BoundExpr::Call("ctz", vec![*unsigned(val)]),
+                /* Clang builtin, OpenCL has no bit reversal */
+                Expr::Bits(BitFn::Reverse, val) => {
+                    let func = if dwords(value_kind(val, values)) == 2 { "__builtin_bitreverse64" } else { "__builtin_bitreverse32" };
+                    BoundExpr::Call(func, vec![*unsigned(val)])
                 },
-                Expr::And(lhs, rhs) => {
-                    BoundExpr::And(box reduce_binding_to_expr(lhs, bindings, vars, args),
-                                   box reduce_binding_to_expr(rhs, bindings, vars, args))
-                },
-                Expr::Shl(lhs, rhs) => {
-                    BoundExpr::Shl(box reduce_binding_to_expr(lhs, bindings, vars, args),
-                                   box reduce_binding_to_expr(rhs, bindings, vars, args))
+                Expr::Div(lhs, rhs) => BoundExpr::Div(op(lhs), op(rhs)),
+                Expr::Fma(op1, op2, op3) => BoundExpr::Call("fma", vec![*op(op1), *op(op2), *op(op3)]),
+                Expr::FMin(lhs, rhs) => BoundExpr::Call("fmin", vec![*op(lhs), *op(rhs)]),
+                Expr::FMax(lhs, rhs) => BoundExpr::Call("fmax", vec![*op(lhs), *op(rhs)]),
+                Expr::Ldexp(val, exp) => BoundExpr::Call("ldexp", vec![*op(val), *op(exp)]),
+                Expr::Select { cond, if_true, if_false } =>
+                    BoundExpr::Select { cond: box reduce_condition(cond, values, vars, args), if_true: op(if_true), if_false: op(if_false) },
+                Expr::Convert(val) => BoundExpr::Call(convert_function(kind, false), vec![*op(val)]),
+                /* Unsigned integer sources are reinterpreted, unsigned results are converted to */
+                Expr::ConvertU(val) => match kind {
+                    DataKind::F16 | DataKind::F32 | DataKind::F64 => BoundExpr::Call(convert_function(kind, true), vec![*unsigned(val)]),
+                    _ => BoundExpr::Call(convert_function(kind, true), vec![*op(val)])
                 },
                 _ => panic!("Unhandled expr: {:?}", expr)
             }
         },
-        Binding::U32(val) => BoundExpr::U32(val),
-        Binding::I32(val) => BoundExpr::I32(val),
-        Binding::Deref { ptr, offset, kind } =>
-            BoundExpr::Deref { ptr: box reduce_binding_to_expr(ptr, bindings, vars, args), offset, kind },
-        Binding::InitState(builtin) =>
-            BoundExpr::InitState(builtin),
-        Binding::DwordElement { of, dword } if vars.contains_key(&of) => {
-            BoundExpr::Variable { idx: of, dword }
+        Value::U32(val) => BoundExpr::U32(val),
+        Value::I32(val) => BoundExpr::I32(val),
+        Value::F32(val) => BoundExpr::F32(val),
+        Value::F64(val) => BoundExpr::F64(val),
+        Value::Deref { ptr, offset, kind } =>
+            BoundExpr::Deref { ptr: box reduce_value_to_expr(ptr, values, vars, args), offset, kind },
+        Value::LocalLoad { addr, offset, kind } =>
+            BoundExpr::LocalDeref { addr: box reduce_value_to_expr(addr, values, vars, args), offset, kind },
+        Value::LocalAtomic { op, addr, offset, data, cmp } => {
+            let operands = cmp.into_iter().chain(Some(data)).map(|idx| reduce_value_to_expr(idx, values, vars, args)).collect();
+            BoundExpr::LocalAtomic { op, addr: box reduce_value_to_expr(addr, values, vars, args), offset, args: operands }
         },
-        Binding::DwordElement { of, dword } | Binding::QwordElement { of, dword } => {
-            match bindings[of] {
-                Binding::Deref { ptr, offset, kind: _ } =>
-                    BoundExpr::Deref { ptr: box reduce_binding_to_expr(ptr, bindings, vars, args), offset: offset + dword as i32, kind: DataKind::Dword },
-                _ => panic!("Unable to resolve dword element #{:?} of {:?}", dword, bindings[of])
-            }
+        Value::InitState(builtin) =>
+            BoundExpr::InitState(builtin),
+        Value::Variable { idx } =>
+            BoundExpr::Variable { idx, dword: 0, kind: vars[idx] },
+        Value::DwordElement { of, dword } =>
+            reduce_element_to_expr(of, dword, DataKind::Dword, values, vars, args),
+        Value::QwordElement { of, dword } =>
+            reduce_element_to_expr(of, dword, DataKind::Qword, values, vars, args),
+        Value::Cast { source, kind } =>
+            BoundExpr::Cast(box reduce_value_to_expr(source, values, vars, args), kind),
+        Value::LaneMask { .. } =>
+            panic!("Lane mask #{} is used as a value", idx),
+        Value::Phi { .. } =>
+            panic!("Phi #{} is used as a value, the program is still in SSA form", idx)
+    }
+}
+
+/* The part of a value of the given kind that starts at `dword`. Memory and variables are
+ * accessed at the element, other values are shifted and truncated to it */
+fn reduce_element_to_expr(idx: usize, dword: u8, kind: DataKind, values: &Vec<Value>, vars: &[DataKind], args: &KernelArgs) -> BoundExpr {
+    let value_dwords = match values[idx] {
+        Value::Variable { idx } => dwords(vars[idx]),
+        _ => dwords(value_kind(idx, values))
+    };
+    if dword == 0 && dwords(kind) == value_dwords {
+        return reduce_value_to_expr(idx, values, vars, args);
+    }
+    let op = |idx| box reduce_value_to_expr(idx, values, vars, args);
+    match (values[idx], dwords(kind), dword) {
+        (Value::Deref { ptr, offset, .. }, _, _) =>
+            BoundExpr::Deref { ptr: op(ptr), offset: offset + dword as i32 * 4, kind },
+        (Value::LocalLoad { addr, offset, .. }, _, _) =>
+            BoundExpr::LocalDeref { addr: op(addr), offset: offset + dword as i32 * 4, kind },
+        (Value::Variable { idx }, _, _) =>
+            BoundExpr::Variable { idx, dword, kind },
+        /* Halves of a 64-bit value */
+        (_, 1, 0) =>
+            BoundExpr::Unsigned(op(idx), DataKind::Dword),
+        (_, 1, 1) => {
+            let shifted = BoundExpr::Shr(box BoundExpr::Unsigned(op(idx), DataKind::Qword), box BoundExpr::U32(32));
+            BoundExpr::Unsigned(box shifted, DataKind::Dword)
         },
-        Binding::Cast { source, kind } =>
-            BoundExpr::Cast(box reduce_binding_to_expr(source, bindings, vars, args), kind),
-        other => panic!("Unhandled binding: {:?}", other)
+        (_, 2, 0) =>
+            *op(idx),
+        /* State with no OpenCL C equivalent, such as the private segment buffer descriptor, stays opaque */
+        (Value::InitState(_), _, _) =>
+            *op(idx),
+        _ => panic!("Unable to resolve {:?} element #{} of {:?}", kind, dword, values[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, reduce_value_to_expr, ProgramStatement};
+    use crate::asm::Instruction;
+    use crate::asm::kernel_args::KernelArgs;
+    use crate::asm::kernel_code::KernelCode;
+    use crate::asm::target::Target;
+    use crate::control_flow::{self, dominators::DominatorTree, loops::LoopForest};
+    use crate::data_flow::exec_state::ExecState;
+    use crate::data_flow::types::{Statement, Value, Variable, BuiltIn, DataKind, Expr};
+
+    /* s_lshl_b64 s[2:3], s[0:1], 2 with s0 and s1 loaded from the kernarg segment */
+    fn shifted_qword() -> Vec<Value> {
+        vec![
+            Value::InitState(BuiltIn::PtrKernarg),
+            Value::Deref { ptr: 0, offset: 8, kind: DataKind::Qword },
+            Value::U32(2),
+            Value::Computed { expr: Expr::Shl(1, 2), kind: DataKind::Qword }
+        ]
+    }
+
+    #[test]
+    fn dword_elements() {
+        let mut values = shifted_qword();
+        values.push(Value::DwordElement { of: 3, dword: 0 });
+        values.push(Value::DwordElement { of: 3, dword: 1 });
+        values.push(Value::DwordElement { of: 1, dword: 1 });
+        let args = KernelArgs::from(Vec::new());
+        let element = |idx| format!("{:?}", reduce_value_to_expr(idx, &values, &[], &args));
+
+        let qword = "Shl(Deref { ptr: InitState(PtrKernarg), offset: 8, kind: Qword }, U32(2))";
+        assert_eq!(element(4), format!("Unsigned({}, Dword)", qword));
+        assert_eq!(element(5), format!("Unsigned(Shr(Unsigned({}, Qword), U32(32)), Dword)", qword));
+        assert_eq!(element(6), "Deref { ptr: InitState(PtrKernarg), offset: 12, kind: Dword }");
+    }
+
+    #[test]
+    fn partial_variable_assignment() {
+        let instrs = vec![Instruction { opcode: "s_endpgm".to_string(), operands: Vec::new(), offset: 0, size: 4 }];
+        let cfg = control_flow::build_graph(&instrs).unwrap();
+        let dominators = DominatorTree::dominators(&cfg);
+        let post_dominators = DominatorTree::post_dominators(&cfg);
+        let loops = LoopForest::find(&cfg, &dominators);
+
+        let mut st = ExecState::new(KernelCode::default(), Target::from_processor("gfx900").unwrap());
+        st.values = shifted_qword();
+        st.variables = vec![Variable::PartialQword, Variable::Qword];
+        st.values.push(Value::Variable { idx: 1 });
+        let program = vec![vec![
+            (0, Statement::VarAssignment { var_idx: 0, value_idx: 3, value_dword: 1, var_dword: 1, kind: DataKind::Dword }),
+            (0, Statement::VarAssignment { var_idx: 1, value_idx: 3, value_dword: 0, var_dword: 0, kind: DataKind::Qword }),
+            (0, Statement::VarAssignment { var_idx: 0, value_idx: 4, value_dword: 0, var_dword: 0, kind: DataKind::Dword })
+        ]];
+
+        let tree = build(&KernelArgs::from(Vec::new()), st, program, &cfg, &loops, &post_dominators);
+        let assignments: Vec<(usize, u8, String, String)> = tree.into_iter()
+            .map(|stmt| match stmt {
+                ProgramStatement::Assignment { var_idx, dword, expr, kind } => (var_idx, dword, format!("{:?}", expr), format!("{:?}", kind)),
+                other => panic!("Unexpected statement {:?}", other)
+            })
+            .collect();
+
+        let qword = "Shl(Deref { ptr: InitState(PtrKernarg), offset: 8, kind: Qword }, U32(2))";
+        assert_eq!(assignments, vec![
+            (0, 1, format!("Unsigned(Shr(Unsigned({}, Qword), U32(32)), Dword)", qword), "Dword".to_string()),
+            (1, 0, qword.to_string(), "Qword".to_string()),
+            (0, 0, "Variable { idx: 1, dword: 0, kind: Dword }".to_string(), "Dword".to_string())
+        ]);
     }
 }
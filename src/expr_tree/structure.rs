@@ -0,0 +1,361 @@
+/* Structured control flow recovery. Blocks are visited starting from the entry:
+ * a loop header opens a loop whose body is every path back to the header, a
+ * conditional branch opens an if statement whose arms end at the immediate
+ * post-dominator of the branch. Edges to the header and to the block following
+ * the innermost loop become continue and break; edges that cannot be expressed
+ * this way (irreducible regions, exits from several loops at once, blocks shared
+ * by both arms without post-dominating the branch) fall back to goto. */
+
+use std::collections::HashSet;
+
+use crate::control_flow::{ControlFlowGraph, Terminator, BlockIdx};
+use crate::control_flow::dominators::DominatorTree;
+use crate::control_flow::loops::{LoopForest, LoopIdx};
+use super::{ProgramStatement, BoundExpr};
+
+#[derive(Clone)]
+struct LoopContext {
+    loop_idx: LoopIdx,
+    header: BlockIdx,
+    /* The block where execution continues after the loop */
+    follow: Option<BlockIdx>
+}
+
+struct Structurer<'a> {
+    cfg: &'a ControlFlowGraph,
+    loops: &'a LoopForest,
+    post_dominators: &'a DominatorTree,
+    /* Statements of blocks that have not been placed yet */
+    blocks: Vec<Option<Vec<ProgramStatement>>>,
+    conditions: Vec<Option<BoundExpr>>,
+    emitted: Vec<bool>,
+    goto_targets: HashSet<BlockIdx>
+}
+
+pub fn structure(cfg: &ControlFlowGraph, loops: &LoopForest, post_dominators: &DominatorTree, blocks: Vec<Vec<ProgramStatement>>, conditions: Vec<Option<BoundExpr>>) -> Vec<ProgramStatement> {
+    let mut s = Structurer {
+        cfg, loops, post_dominators,
+        emitted: vec![false; blocks.len()],
+        blocks: blocks.into_iter().map(Some).collect(),
+        conditions,
+        goto_targets: HashSet::new()
+    };
+
+    let mut body = if cfg.blocks.is_empty() { Vec::new() } else { s.region(0, None, &[]) };
+
+    /* Every path through the structured body ends with a return or a goto,
+     * blocks only entered through gotos are placed after it */
+    while let Some(pending) = (0..cfg.blocks.len()).find(|b| s.goto_targets.contains(b) && !s.emitted[*b]) {
+        let mut stmts = s.region(pending, None, &[]);
+        body.append(&mut stmts);
+    }
+
+    let mut body = refine(remove_unused_labels(body, &s.goto_targets));
+    if let Some(ProgramStatement::Return) = body.last() {
+        body.pop();
+    }
+    body
+}
+
+impl<'a> Structurer<'a> {
+    /* Places blocks starting with `start` until `follow` is reached */
+    fn region(&mut self, start: BlockIdx, follow: Option<BlockIdx>, ctx: &[LoopContext]) -> Vec<ProgramStatement> {
+        let mut out: Vec<ProgramStatement> = Vec::new();
+        let mut current = Some(start);
+
+        while let Some(b) = current {
+            if Some(b) == follow {
+                break;
+            }
+            if self.emitted[b] {
+                out.push(self.goto(b));
+                break;
+            }
+            if let Some(loop_idx) = self.loops.loop_with_header(b) {
+                if !ctx.iter().any(|c| c.loop_idx == loop_idx) {
+                    let (stmt, loop_follow) = self.structure_loop(loop_idx, ctx);
+                    out.push(stmt);
+                    current = match loop_follow {
+                        Some(f) => self.edge(f, follow, ctx, &mut out),
+                        None => None
+                    };
+                    continue;
+                }
+            }
+
+            self.emitted[b] = true;
+            out.push(ProgramStatement::Label { label_idx: b });
+            out.append(&mut self.blocks[b].take().unwrap_or_default());
+
+            current = match self.cfg.blocks[b].terminator {
+                Terminator::Exit | Terminator::Indirect => {
+                    out.push(ProgramStatement::Return);
+                    None
+                },
+                Terminator::Branch { taken, not_taken, .. } if taken == not_taken =>
+                    self.edge(taken, follow, ctx, &mut out),
+                Terminator::Fallthrough(next) | Terminator::Jump(next) =>
+                    self.edge(next, follow, ctx, &mut out),
+                Terminator::Branch { taken, not_taken, .. } => {
+                    let cond = self.conditions[b].take()
+                        .unwrap_or_else(|| panic!("No condition for the branch at the end of block {}", b));
+                    self.structure_if(b, cond, taken, not_taken, follow, ctx, &mut out)
+                }
+            };
+        }
+        out
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn structure_if(&mut self, b: BlockIdx, cond: BoundExpr, taken: BlockIdx, not_taken: BlockIdx, follow: Option<BlockIdx>, ctx: &[LoopContext], out: &mut Vec<ProgramStatement>) -> Option<BlockIdx> {
+        let merge = self.merge_point(b, ctx);
+
+        /* Without a merge point, an arm that only leaves the region (break, continue, goto)
+         * is placed in the if statement and the other one follows it */
+        if merge.is_none() {
+            let mut then_body = Vec::new();
+            if self.edge(taken, None, ctx, &mut then_body).is_none() {
+                out.push(ProgramStatement::If { cond, then_body, else_body: Vec::new() });
+                return self.edge(not_taken, follow, ctx, out);
+            }
+            let mut else_body = Vec::new();
+            if self.edge(not_taken, None, ctx, &mut else_body).is_none() {
+                out.push(ProgramStatement::If { cond: negate(cond), then_body: else_body, else_body: Vec::new() });
+                return self.edge(taken, follow, ctx, out);
+            }
+        }
+
+        let then_body = self.arm(taken, merge, ctx);
+        let else_body = self.arm(not_taken, merge, ctx);
+        match (then_body.is_empty(), else_body.is_empty()) {
+            (true, true) => (),
+            (true, false) => out.push(ProgramStatement::If { cond: negate(cond), then_body: else_body, else_body: then_body }),
+            _ => out.push(ProgramStatement::If { cond, then_body, else_body })
+        }
+        merge
+    }
+
+    fn arm(&mut self, start: BlockIdx, merge: Option<BlockIdx>, ctx: &[LoopContext]) -> Vec<ProgramStatement> {
+        let mut body = Vec::new();
+        if let Some(next) = self.edge(start, merge, ctx, &mut body) {
+            if Some(next) != merge {
+                let mut stmts = self.region(next, merge, ctx);
+                body.append(&mut stmts);
+            }
+        }
+        body
+    }
+
+    /* The loop body is structured with the header emitted as an ordinary block,
+     * returns the loop statement and the block following the loop */
+    fn structure_loop(&mut self, loop_idx: LoopIdx, ctx: &[LoopContext]) -> (ProgramStatement, Option<BlockIdx>) {
+        let header = self.loops.loops[loop_idx].header;
+        let follow = self.loop_follow(loop_idx);
+
+        let mut inner_ctx = ctx.to_vec();
+        inner_ctx.push(LoopContext { loop_idx, header, follow });
+
+        let body = self.region(header, None, &inner_ctx);
+        (ProgramStatement::Loop { body }, follow)
+    }
+
+    /* The most common exit target, the first one in layout order on a tie */
+    fn loop_follow(&self, loop_idx: LoopIdx) -> Option<BlockIdx> {
+        let exits = &self.loops.loops[loop_idx].exits;
+        let mut targets: Vec<BlockIdx> = exits.iter().map(|&(_, to)| to).collect();
+        targets.sort();
+        targets.dedup();
+        targets.into_iter()
+            .max_by_key(|&t| (exits.iter().filter(|&&(_, to)| to == t).count(), std::cmp::Reverse(t)))
+    }
+
+    /* The immediate post-dominator of a branch, as long as it is a block inside
+     * the innermost loop that has not been placed yet */
+    fn merge_point(&self, b: BlockIdx, ctx: &[LoopContext]) -> Option<BlockIdx> {
+        let merge = self.post_dominators.idom(b)?;
+        if self.emitted[merge] {
+            return None;
+        }
+        match ctx.last() {
+            Some(c) if merge == c.header || Some(merge) == c.follow => None,
+            Some(c) if self.loops.loops[c.loop_idx].blocks.binary_search(&merge).is_err() => None,
+            _ => Some(merge)
+        }
+    }
+
+    /* Emits break, continue or goto for an edge that leaves the current region,
+     * otherwise returns the destination to continue with */
+    fn edge(&mut self, to: BlockIdx, follow: Option<BlockIdx>, ctx: &[LoopContext], out: &mut Vec<ProgramStatement>) -> Option<BlockIdx> {
+        for (depth, c) in ctx.iter().enumerate().rev() {
+            let innermost = depth + 1 == ctx.len();
+            if to == c.header {
+                out.push(if innermost { ProgramStatement::Continue } else { self.goto(to) });
+                return None;
+            }
+            if Some(to) == c.follow {
+                out.push(if innermost { ProgramStatement::Break } else { self.goto(to) });
+                return None;
+            }
+            if innermost && self.loops.loops[c.loop_idx].blocks.binary_search(&to).is_err() {
+                out.push(self.goto(to));
+                return None;
+            }
+        }
+        if Some(to) != follow && self.emitted[to] {
+            out.push(self.goto(to));
+            return None;
+        }
+        Some(to)
+    }
+
+    fn goto(&mut self, to: BlockIdx) -> ProgramStatement {
+        self.goto_targets.insert(to);
+        ProgramStatement::Goto { label_idx: to }
+    }
+}
+
+fn negate(cond: BoundExpr) -> BoundExpr {
+    match cond {
+        BoundExpr::Negate(box inner) => inner,
+        cond => BoundExpr::Negate(box cond)
+    }
+}
+
+fn remove_unused_labels(stmts: Vec<ProgramStatement>, goto_targets: &HashSet<BlockIdx>) -> Vec<ProgramStatement> {
+    use self::ProgramStatement::*;
+
+    let strip = |body| remove_unused_labels(body, goto_targets);
+    stmts.into_iter()
+        .filter(|stmt| match stmt {
+            Label { label_idx } => goto_targets.contains(label_idx),
+            _ => true
+        })
+        .map(|stmt| match stmt {
+            If { cond, then_body, else_body } => If { cond, then_body: strip(then_body), else_body: strip(else_body) },
+            Loop { body } => Loop { body: strip(body) },
+            stmt => stmt
+        })
+        .collect()
+}
+
+/* Turns infinite loops into while, do-while and for loops where the condition
+ * is checked at the start or at the end of the body */
+fn refine(stmts: Vec<ProgramStatement>) -> Vec<ProgramStatement> {
+    use self::ProgramStatement::*;
+
+    let mut out: Vec<ProgramStatement> = Vec::with_capacity(stmts.len());
+    for stmt in stmts {
+        let stmt = match stmt {
+            If { cond, then_body, else_body } => If { cond, then_body: refine(then_body), else_body: refine(else_body) },
+            Loop { body } => refine_loop(refine(body)),
+            stmt => stmt
+        };
+        match stmt {
+            While { cond, body } => {
+                let stmt = match out.pop() {
+                    Some(init) => for_loop(init, cond, body, &mut out),
+                    None => While { cond, body }
+                };
+                out.push(stmt);
+            },
+            stmt => out.push(stmt)
+        }
+    }
+    out
+}
+
+fn refine_loop(mut body: Vec<ProgramStatement>) -> ProgramStatement {
+    use self::ProgramStatement::*;
+
+    if let Some(Continue) = body.last() {
+        body.pop();
+    }
+
+    let exit_cond = matches!(body.first(), Some(If { then_body, else_body, .. }) if is_break(then_body) && else_body.is_empty());
+    if exit_cond {
+        if let If { cond, .. } = body.remove(0) {
+            return While { cond: negate(cond), body };
+        }
+    }
+
+    /* The condition at the end of the body is either `if (!cond) break;` or `if (cond) continue; break;` */
+    let tail = match (body.iter().rev().nth(1), body.last()) {
+        (_, Some(If { then_body, else_body, .. })) if is_break(then_body) && else_body.is_empty() => 1,
+        (Some(If { then_body, else_body, .. }), Some(Break)) if is_continue(then_body) && else_body.is_empty() => 2,
+        _ => 0
+    };
+    /* continue in a do-while loop skips to the condition rather than the start of the body */
+    if tail == 0 || body[..body.len() - tail].iter().any(continues_loop) {
+        return Loop { body };
+    }
+    if tail == 2 {
+        body.pop();
+    }
+    match body.pop() {
+        Some(If { cond, .. }) if tail == 1 => DoWhile { body, cond: negate(cond) },
+        Some(If { cond, .. }) => DoWhile { body, cond },
+        _ => unreachable!()
+    }
+}
+
+/* while (cond) { ...; v = step; } preceded by v = init, where cond depends on v */
+fn for_loop(init: ProgramStatement, cond: BoundExpr, mut body: Vec<ProgramStatement>, out: &mut Vec<ProgramStatement>) -> ProgramStatement {
+    use self::ProgramStatement::*;
+
+    let var_idx = match init {
+        Assignment { var_idx, .. } if uses_variable(&cond, var_idx) => var_idx,
+        init => {
+            out.push(init);
+            return While { cond, body };
+        }
+    };
+    let has_step = match body.last() {
+        Some(Assignment { var_idx: step_var, .. }) => *step_var == var_idx && !body.iter().any(continues_loop),
+        _ => false
+    };
+    if !has_step {
+        out.push(init);
+        return While { cond, body };
+    }
+    let step = body.pop().unwrap();
+    For { init: box init, cond, step: box step, body }
+}
+
+fn is_break(body: &[ProgramStatement]) -> bool {
+    matches!(body, [ProgramStatement::Break])
+}
+
+fn is_continue(body: &[ProgramStatement]) -> bool {
+    matches!(body, [ProgramStatement::Continue])
+}
+
+/* Whether the statement continues the enclosing loop (nested loops are not searched) */
+fn continues_loop(stmt: &ProgramStatement) -> bool {
+    match stmt {
+        ProgramStatement::Continue => true,
+        ProgramStatement::If { then_body, else_body, .. } =>
+            then_body.iter().any(continues_loop) || else_body.iter().any(continues_loop),
+        _ => false
+    }
+}
+
+fn uses_variable(expr: &BoundExpr, var_idx: usize) -> bool {
+    use super::BoundExpr::*;
+
+    match expr {
+        Variable { idx, .. } => *idx == var_idx,
+        Mul(lhs, rhs) | Div(lhs, rhs) | Add(lhs, rhs) | Sub(lhs, rhs) | And(lhs, rhs) | Or(lhs, rhs) | Xor(lhs, rhs)
+        | Shl(lhs, rhs) | Shr(lhs, rhs) | Min(lhs, rhs) | Max(lhs, rhs)
+        | MulHi(lhs, rhs) | Mul24(lhs, rhs) | Upsample(lhs, rhs)
+        | CompareLt(lhs, rhs) | CompareLe(lhs, rhs) | CompareEql(lhs, rhs) | CompareNe(lhs, rhs) =>
+            uses_variable(lhs, var_idx) || uses_variable(rhs, var_idx),
+        Select { cond, if_true, if_false } =>
+            uses_variable(cond, var_idx) || uses_variable(if_true, var_idx) || uses_variable(if_false, var_idx),
+        Not(expr) | Abs(expr) | Negate(expr) | Neg(expr) | Cast(expr, _) | Unsigned(expr, _) => uses_variable(expr, var_idx),
+        Call(_, args) => args.iter().any(|arg| uses_variable(arg, var_idx)),
+        Deref { ptr, .. } => uses_variable(ptr, var_idx),
+        LocalDeref { addr, .. } => uses_variable(addr, var_idx),
+        LocalAtomic { addr, args, .. } => uses_variable(addr, var_idx) || args.iter().any(|arg| uses_variable(arg, var_idx)),
+        I32(_) | U32(_) | F32(_) | F64(_) | InitState(_) => false
+    }
+}
@@ -0,0 +1,11 @@
+#![feature(box_syntax)]
+#![feature(box_patterns)]
+/* Registers and fields are named as in the ISA documentation (VCC, XYZ) */
+#![allow(clippy::upper_case_acronyms)]
+
+pub mod asm;
+pub mod control_flow;
+pub mod data_flow;
+pub mod expr_tree;
+pub mod codegen;
+pub mod disasm;
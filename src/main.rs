@@ -1,38 +1,103 @@
-#![feature(try_from)]
-#![feature(slice_patterns)]
-#![feature(box_syntax)]
-#![feature(box_patterns)]
+use std::io::Cursor;
 
-mod asm;
-mod control_flow;
-mod data_flow;
-mod expr_tree;
-mod codegen;
-
-use std::path::PathBuf;
+use reki::{asm, control_flow, data_flow, expr_tree, codegen, disasm};
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: reki <compiled.hsaco>");
-        return;
+    if let Err(message) = run() {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let mut path: Option<String> = None;
+    let mut offload_target: Option<String> = None;
+    let mut mcpu: Option<String> = None;
+    let mut listing = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--offload-target" | "--mcpu" => match args.next() {
+                Some(value) if arg == "--mcpu" && !value.starts_with("--") => mcpu = Some(value),
+                Some(value) if !value.starts_with("--") => offload_target = Some(value),
+                /* A missing value is a usage error */
+                _ => { path = None; break; }
+            },
+            "disasm" if path.is_none() && !listing => listing = true,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => { path = None; break; }
+        }
+    }
+    let path = match path {
+        Some(path) => path,
+        None => return Err("Usage: reki [disasm] [--offload-target <triple|gfxNNN>] [--mcpu <gfxNNN>] <compiled.hsaco|bundle|host executable>".to_string())
+    };
+
+    let input = std::fs::read(&path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let entries = asm::offload_bundle::bundle_entries(&input).map_err(|e| format!("Malformed offload bundle: {}", e))?;
+    let code_object = if entries.is_empty() {
+        input
     }
-    let hsaco = elf::File::open_path(&PathBuf::from(&args[1])).unwrap();
-    let (kcode, kernel_args, instructions) = asm::disassemble(hsaco).unwrap();
+    else {
+        println!("Offload bundle entries:");
+        for entry in &entries {
+            println!("  {} ({} bytes)", entry.triple, entry.code_object.len());
+        }
+        let entry = asm::offload_bundle::select_entry(entries, offload_target.as_deref())
+            .map_err(|e| format!("Cannot select a bundle entry: {}", e))?;
+        println!("Selected: {}", entry.triple);
+        entry.code_object
+    };
+
+    let hsaco = elf::File::open_stream(&mut Cursor::new(&code_object))
+        .map_err(|e| format!("Malformed code object: {:?}", e))?;
+    let target = match mcpu {
+        Some(mcpu) => asm::target::Target::from_processor(&mcpu)
+            .ok_or_else(|| format!("Unknown processor {}", mcpu))?,
+        None => asm::target::detect(&code_object, &hsaco)
+            .map_err(|e| format!("Cannot detect the target, specify it with --mcpu: {}", e))?
+    };
+    println!("Target: {}", target);
+
+    let kernels = asm::disassemble(hsaco, &target).map_err(|e| format!("Cannot disassemble: {}", e))?;
 
-    let cf_map = control_flow::build_map(&instructions);
+    for asm::Disassembly { name, kcode, metadata, instructions, code } in kernels {
+        println!("Kernel: {}", name);
 
-    println!("{:#?}", kcode);
-    println!("Args: {:#?}", kernel_args);
-    println!("Control flow map: {:?}", cf_map);
+        let dialect = asm::dialect::Dialect::new(&target, &kcode);
+        if listing {
+            let state = data_flow::exec_state::ExecState::new(kcode, target.clone());
+            disasm::print_listing(&code, &instructions, &dialect, &state, &metadata.args);
+            continue;
+        }
+        let instructions = dialect.normalize(instructions);
 
-    let mut state = data_flow::exec_state::ExecState::from(kcode);
-    let program = data_flow::analyze(&mut state, instructions.as_slice(), &cf_map);
+        let cfg = control_flow::build_graph(&instructions)
+            .map_err(|e| format!("Cannot analyze {}: {}", name, e))?
+            .without_exec_skips();
+        let dominators = control_flow::dominators::DominatorTree::dominators(&cfg);
+        let post_dominators = control_flow::dominators::DominatorTree::post_dominators(&cfg);
+        let loops = control_flow::loops::LoopForest::find(&cfg, &dominators);
 
-    println!("State: {:?}", state);
+        println!("{:#?}", kcode);
+        println!("Metadata: {:#?}", metadata);
+        let kernel_args = metadata.args;
+        println!("Control flow graph: {:?}", cfg);
+        println!("Loops: {:?}", loops);
 
-    let tree = expr_tree::build(&kernel_args, state, program);
+        let mut state = data_flow::exec_state::ExecState::new(kcode, target.clone());
+        let program = data_flow::analyze(&mut state, instructions.as_slice(), &cfg)
+            .map_err(|e| format!("Cannot analyze {}: {}", name, e))?;
+        let program = data_flow::out_of_ssa::destruct(&mut state, &cfg, program)
+            .map_err(|e| format!("Cannot analyze {}: {}", name, e))?;
 
-    let code = codegen::emit_c(tree, &kernel_args).unwrap();
-    println!("Code:\n{}", code);
+        println!("State: {:?}", state);
+
+        let tree = expr_tree::build(&kernel_args, state, program, &cfg, &loops, &post_dominators);
+
+        let code = codegen::emit_c(tree, &kernel_args).map_err(|e| format!("Cannot emit code: {}", e))?;
+        println!("Code:\n{}", code);
+    }
+    Ok(())
 }